@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../src/main.rs"]
+#[allow(dead_code, unused_imports)]
+mod usm;
+
+fuzz_target!(|data: &[u8]| {
+    // Any error is fine; panics are not. A successful parse must also serialize.
+    if let Ok(entry) = usm::parse_desktop_file_from_bytes(data) {
+        let _ = entry.to_desktop_string();
+    }
+});
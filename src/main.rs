@@ -2,1285 +2,13911 @@
 //! Loads XDG autostart `.desktop` files, lets you add user entries, toggle enablement,
 //! and delete user-owned entries. System entries are read-only.
 
+// `file-dialog` builds against GTK 4.10, which deprecates `Dialog`, `EntryCompletion`,
+// `ListStore` and `show()` that the dialogs here still use for older GTK releases.
+#![cfg_attr(feature = "file-dialog", allow(deprecated))]
+
 use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
 
 use anyhow::{bail, Context, Result};
 use gtk4::prelude::*;
 use gtk4::{
     AccessibleRole, Application, ApplicationWindow, Box as GtkBox, Button, CheckButton, Dialog,
-    Entry, HeaderBar, Label, ListBox, ListBoxRow, Orientation, ResponseType, ScrolledWindow,
-    SelectionMode,
+    Entry, EntryCompletion, FileChooserAction, FileChooserDialog, HeaderBar, Image, Label, ListBox,
+    ListBoxRow, MenuButton, Orientation, ResponseType, ScrolledWindow, SelectionMode, TextView,
 };
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use tempfile::NamedTempFile;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Serialized as its `source_label`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[allow(dead_code)]
 enum StartupSource {
+    #[serde(rename = "user")]
     UserAutostart,
+    #[serde(rename = "system")]
     SystemAutostart,
+    #[serde(rename = "shell")]
     ShellProfile,
+    #[serde(rename = "environment.d")]
+    EnvironmentD,
+    #[serde(rename = "openbox")]
+    OpenboxAutostart,
+    #[serde(rename = "systemd timer")]
+    SystemdTimer,
+    #[serde(rename = "runit")]
+    Runit,
+    #[serde(rename = "sway")]
+    SwayConfig,
+    #[serde(rename = "i3")]
+    I3Config,
+    #[serde(rename = "user dirs")]
+    XdgUserDirs,
+    #[serde(rename = "xfce")]
+    XfceAutostart,
+    #[serde(rename = "hyprland")]
+    Hyprland,
+    #[serde(rename = "plasma env")]
+    KdePlasmaEnv,
+    #[serde(rename = "unknown")]
     Unknown,
 }
 
-#[derive(Debug, Clone)]
-struct StartupEntry {
+impl StartupSource {
+    const ALL: [StartupSource; 14] = [
+        StartupSource::UserAutostart,
+        StartupSource::SystemAutostart,
+        StartupSource::ShellProfile,
+        StartupSource::EnvironmentD,
+        StartupSource::OpenboxAutostart,
+        StartupSource::SystemdTimer,
+        StartupSource::Runit,
+        StartupSource::SwayConfig,
+        StartupSource::I3Config,
+        StartupSource::XdgUserDirs,
+        StartupSource::XfceAutostart,
+        StartupSource::Hyprland,
+        StartupSource::KdePlasmaEnv,
+        StartupSource::Unknown,
+    ];
+}
+
+/// Serialized in the shape `generate_entry_json_schema` describes; see `export_entries_to_json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct StartupEntry {
     name: String,
-    command: String,
+    command: String,               // Exec= without any leading `env VAR=value` prefix
+    #[serde(serialize_with = "serialize_key_value_pairs", deserialize_with = "deserialize_key_value_pairs")]
+    env_vars: Vec<(String, String)>, // the stripped `env` assignments, in order
     enabled: bool,
+    locked: bool, // X-USM-Locked=true; blocks toggle/edit/delete
+    #[serde(default)]
+    xfce_override: bool, // X-XFCE-Autostart-Override=true
+    #[serde(default)]
+    generic_name: Option<String>, // GenericName=, as written
+    #[serde(default)]
+    comment: Option<String>, // Comment=, as written
+    #[serde(default)]
+    icon: Option<String>, // Icon=, as written
+    #[serde(default)]
+    try_exec: Option<String>, // TryExec=, as written
+    #[serde(default)]
+    terminal: bool, // Terminal=; always written
+    #[serde(default)]
+    delay_seconds: Option<u32>, // X-GNOME-Autostart-Delay=, when it is a valid number
+    #[serde(default)]
+    only_show_in: Vec<String>, // OnlyShowIn= desktops, in order
+    #[serde(default)]
+    not_show_in: Vec<String>, // NotShowIn= desktops, in order
     source: StartupSource,
     path: Option<PathBuf>,
+    #[serde(serialize_with = "serialize_key_value_pairs", deserialize_with = "deserialize_key_value_pairs")]
     extra: Vec<(String, String)>, // preserve additional keys in Desktop Entry group
+    #[serde(serialize_with = "serialize_locale_value_pairs", deserialize_with = "deserialize_locale_value_pairs")]
     localized_names: Vec<(String, String)>, // locale -> name
     entry_comments: Vec<String>,            // comments/blank lines inside Desktop Entry
     preamble: Vec<String>,                  // lines before first group
     other_groups: Vec<Vec<String>>,         // raw lines for non-Desktop Entry groups
 }
 
-#[derive(Clone)]
-struct AppState {
-    entries: Rc<RefCell<Vec<StartupEntry>>>,
-    visible_indices: Rc<RefCell<Vec<usize>>>,
-    filter: Rc<RefCell<FilterState>>,
-    sort: Rc<Cell<SortKey>>,
-    selected: Rc<Cell<Option<usize>>>,
-    list_box: ListBox,
-    detail_name: Label,
-    detail_command: Label,
-    detail_source: Label,
-    detail_status: Label,
-    status_bar: Label,
-    toggle_button: Button,
-    delete_button: Button,
-    edit_button: Button,
-}
+impl StartupEntry {
+    /// Serializes the entry exactly as `write_desktop_entry` would write it.
+    pub(crate) fn to_desktop_string(&self) -> String {
+        write_desktop_entry_to_string(self)
+    }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct FilterState {
-    show_enabled: bool,
-    show_disabled: bool,
-    show_user: bool,
-    show_system: bool,
-}
+    /// Full `Exec=` value: the `env` prefix rebuilt from `env_vars`, then `command`.
+    fn exec_line(&self) -> String {
+        if self.env_vars.is_empty() {
+            return self.command.clone();
+        }
+        let assignments: Vec<String> = self
+            .env_vars
+            .iter()
+            .map(|(key, value)| format!("{key}={}", quote_exec_arg(value)))
+            .collect();
+        format!("env {} {}", assignments.join(" "), self.command)
+    }
 
-impl Default for FilterState {
-    fn default() -> Self {
-        Self {
-            show_enabled: true,
-            show_disabled: true,
-            show_user: true,
-            show_system: true,
+    /// Multi-line breakdown of `Exec=` for the detail panel: binary, full path, arguments,
+    /// and field codes.
+    fn describe_command_verbosely(&self) -> String {
+        let parts = parse_exec_into_parts(&self.command);
+        if parts.binary.is_empty() {
+            return "No command".to_string();
+        }
+        let basename = Path::new(&parts.binary)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| parts.binary.clone());
+        let full_path = if parts.binary.contains('/') {
+            parts.binary.clone()
+        } else {
+            find_in_path(&parts.binary)
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|| format!("{} (not found in PATH)", parts.binary))
+        };
+        let mut lines = vec![format!("Binary: {basename}"), format!("Path: {full_path}")];
+        if parts.args.is_empty() {
+            lines.push("Arguments: none".to_string());
+        } else {
+            lines.push("Arguments:".to_string());
+            lines.extend(parts.args.iter().map(|a| format!("  {a}")));
         }
+        if parts.field_codes.is_empty() {
+            lines.push("Field codes: none".to_string());
+        } else {
+            lines.push(format!("Field codes: {}", parts.field_codes.join(" ")));
+        }
+        if !self.env_vars.is_empty() {
+            lines.push("Environment:".to_string());
+            lines.extend(self.env_vars.iter().map(|(k, v)| format!("  {k}={v}")));
+        }
+        lines.join("\n")
     }
-}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum SortKey {
-    NameAsc,
-    NameDesc,
-    StatusEnabledFirst,
-    SourceUserFirst,
-    SourceSystemFirst,
-}
+    /// `Exec=` split into argv, respecting quoting (see `tokenize_command`).
+    fn command_argv(&self) -> Vec<String> {
+        tokenize_command(&self.command)
+    }
 
-fn main() -> Result<()> {
-    let app = Application::builder()
-        .application_id("com.example.universal-startup-manager")
-        .build();
+    /// Checks whether the entry could launch: the binary resolves to an executable file,
+    /// environment variable names are valid, and field codes make sense without files.
+    fn try_launch_dry_run(&self) -> LaunchDryRunResult {
+        let mut result = LaunchDryRunResult {
+            args_valid: true,
+            env_vars_valid: true,
+            ..Default::default()
+        };
+        let mut argv = self.command_argv().into_iter().peekable();
+        // An `env` prefix parse_env_prefix left alone, e.g. one with an invalid name.
+        if argv.peek().map(String::as_str) == Some("env") {
+            argv.next();
+            while let Some((key, _)) = argv.peek().and_then(|word| word.split_once('=')) {
+                if !is_env_var_name(key) {
+                    result.env_vars_valid = false;
+                    result.warnings.push(format!("Invalid environment variable name {key:?}"));
+                }
+                argv.next();
+            }
+        }
+        for (key, _) in &self.env_vars {
+            if !is_env_var_name(key) {
+                result.env_vars_valid = false;
+                result.warnings.push(format!("Invalid environment variable name {key:?}"));
+            }
+        }
 
-    app.connect_activate(|app| {
-        if let Err(err) = build_ui(app) {
-            eprintln!("Failed to build UI: {err:?}");
+        let Some(binary) = argv.next() else {
+            result.args_valid = false;
+            result.warnings.push("No command to run".to_string());
+            return result;
+        };
+        let resolved = if binary.contains('/') {
+            Some(PathBuf::from(&binary)).filter(|p| p.is_file())
+        } else {
+            find_in_path(&binary)
+        };
+        match resolved {
+            Some(path) => {
+                result.binary_found = true;
+                result.binary_executable = is_executable(&path);
+                if !result.binary_executable {
+                    result.warnings.push(format!("{} is not executable", path.display()));
+                }
+            }
+            None => result.warnings.push(format!("{binary} not found")),
         }
-    });
 
-    app.run();
-    Ok(())
-}
+        for arg in argv {
+            if is_field_code(&arg) {
+                if "fFuU".contains(&arg[1..]) {
+                    result
+                        .warnings
+                        .push(format!("{arg} expands to nothing at login; no file is passed"));
+                }
+                continue;
+            }
+            let mut chars = arg.char_indices();
+            let mut embedded = None;
+            while let Some((i, c)) = chars.next() {
+                if c != '%' {
+                    continue;
+                }
+                let Some((_, next)) = chars.next() else {
+                    break;
+                };
+                let code = &arg[i..i + 1 + next.len_utf8()];
+                if is_field_code(code) {
+                    embedded = Some(code);
+                    break;
+                }
+            }
+            if let Some(code) = embedded {
+                result.args_valid = false;
+                result
+                    .warnings
+                    .push(format!("{code} inside {arg:?} must be an argument on its own"));
+            }
+        }
+        result
+    }
 
-fn build_ui(app: &Application) -> Result<()> {
-    let entries = load_entries().unwrap_or_else(|err| {
-        eprintln!("Failed to load entries: {err:?}");
-        Vec::new()
-    });
+    /// Source with its location, for the detail panel; `source_label` is the short form.
+    fn human_readable_source(&self) -> String {
+        match self.source {
+            StartupSource::UserAutostart => "User autostart (~/.config/autostart/)",
+            StartupSource::SystemAutostart => "System autostart (/etc/xdg/autostart/)",
+            StartupSource::ShellProfile => "Shell profile (~/.profile, ~/.bash_profile)",
+            StartupSource::EnvironmentD => "Session environment (/etc/environment.d/)",
+            StartupSource::OpenboxAutostart => "Openbox autostart (~/.config/openbox/autostart)",
+            StartupSource::SystemdTimer => "systemd user session (~/.config/systemd/user/)",
+            StartupSource::Runit => "runit user service (~/.config/service/)",
+            StartupSource::SwayConfig => "Sway config (~/.config/sway/config)",
+            StartupSource::I3Config => "i3 config (~/.config/i3/config)",
+            StartupSource::XdgUserDirs => "XDG user directories (~/.config/user-dirs.dirs)",
+            StartupSource::XfceAutostart => "Xfce session override (~/.config/autostart/)",
+            StartupSource::Hyprland => "Hyprland config (~/.config/hypr/hyprland.conf)",
+            StartupSource::KdePlasmaEnv => "Plasma environment script (~/.config/plasma-workspace/env/)",
+            StartupSource::Unknown => "Unknown source",
+        }
+        .to_string()
+    }
 
-    let list_box = ListBox::new();
-    list_box.set_accessible_role(AccessibleRole::List);
-    list_box.set_selection_mode(SelectionMode::Single);
+    /// Longer explanation of the source for the detail panel's tooltip.
+    fn source_tooltip(&self) -> String {
+        match self.source {
+            StartupSource::UserAutostart => {
+                "A .desktop file you own. It can be edited, toggled, locked or deleted here, and it \
+                 overrides a system entry with the same file name."
+            }
+            StartupSource::SystemAutostart => {
+                "Installed by a package for all users. Read-only here; to change it for yourself, \
+                 create a user entry with the same file name."
+            }
+            StartupSource::ShellProfile => {
+                "A command run by your shell's startup file when a login shell starts. Read-only here."
+            }
+            StartupSource::EnvironmentD => {
+                "A variable set for the whole session by systemd's environment.d. Read-only here."
+            }
+            StartupSource::OpenboxAutostart => {
+                "A line in Openbox's autostart script, run when an Openbox session starts. Read-only here."
+            }
+            StartupSource::SystemdTimer => {
+                "A systemd user timer created by this app; systemd runs the command on its schedule. \
+                 Read-only here."
+            }
+            StartupSource::Runit => {
+                "A runit user service; runsvdir starts it unless the service has a down file. Read-only here."
+            }
+            StartupSource::SwayConfig => {
+                "An exec or exec_always line in Sway's config, run when Sway starts (exec_always also \
+                 on reload). Read-only here."
+            }
+            StartupSource::I3Config => {
+                "An exec or exec_always line in i3's config, run when i3 starts (exec_always also \
+                 on restart). Read-only here."
+            }
+            StartupSource::XdgUserDirs => {
+                "xdg-user-dirs-update runs at session start and keeps the Desktop, Downloads and \
+                 other folders listed in user-dirs.dirs up to date. Read-only here."
+            }
+            StartupSource::XfceAutostart => {
+                "A .desktop file the Xfce session manager wrote with X-XFCE-Autostart-Override=true, \
+                 which Xfce applies in place of the system entry of the same name. Read-only here."
+            }
+            StartupSource::Hyprland => {
+                "An exec-once line in Hyprland's config, run once when Hyprland starts. Read-only here."
+            }
+            StartupSource::KdePlasmaEnv => {
+                "A variable exported by a script that KDE Plasma sources before the session starts. \
+                 Read-only here."
+            }
+            StartupSource::Unknown => "Where this entry came from could not be determined.",
+        }
+        .to_string()
+    }
 
-    let detail_name = Label::new(Some("-"));
-    let detail_command = Label::new(Some("-"));
-    let detail_source = Label::new(Some("-"));
-    let detail_status = Label::new(Some("-"));
-    let status_bar = Label::new(None);
-    status_bar.set_wrap(true);
+    /// `lint_entry` without the informational notes.
+    fn validate(&self) -> ValidationResult {
+        ValidationResult {
+            warnings: lint_entry(self)
+                .into_iter()
+                .filter(|w| w.severity >= LintSeverity::Warning)
+                .collect(),
+        }
+    }
 
-    let toggle_button = Button::with_label("Enable/Disable");
-    let delete_button = Button::with_label("Delete");
-    let edit_button = Button::with_label("Edit");
-    let sort_button = Button::with_label("Sort");
-    let about_button = Button::with_label("About");
-    toggle_button.set_sensitive(false);
-    delete_button.set_sensitive(false);
-    edit_button.set_sensitive(false);
+    /// Rewrites `command` from its argv so every argument is quoted the way `quote_exec_arg`
+    /// would: paths with spaces end up double-quoted, already-correct commands are unchanged.
+    fn normalize_command_quoting(&mut self) {
+        let argv = self.command_argv();
+        if argv.is_empty() {
+            return;
+        }
+        self.command = argv.iter().map(|arg| quote_exec_arg(arg)).collect::<Vec<_>>().join(" ");
+    }
 
-    let state = AppState {
-        entries: Rc::new(RefCell::new(entries)),
-        visible_indices: Rc::new(RefCell::new(Vec::new())),
-        filter: Rc::new(RefCell::new(FilterState::default())),
-        sort: Rc::new(Cell::new(SortKey::NameAsc)),
-        selected: Rc::new(Cell::new(None)),
-        list_box: list_box.clone(),
-        detail_name,
-        detail_command,
-        detail_source,
-        detail_status,
-        status_bar: status_bar.clone(),
-        toggle_button: toggle_button.clone(),
-        delete_button: delete_button.clone(),
-        edit_button: edit_button.clone(),
-    };
+    /// First argv element. Owned, since argv is rebuilt from `command` on each call.
+    fn exec_binary(&self) -> Option<String> {
+        self.command_argv().into_iter().next()
+    }
 
-    rebuild_list(&state);
+    /// The `TryExec=` value, if set and not blank.
+    fn try_exec(&self) -> Option<&str> {
+        self.try_exec.as_deref().map(str::trim).filter(|v| !v.is_empty())
+    }
 
-    let refresh_button = Button::with_label("Refresh");
-    refresh_button.set_accessible_role(AccessibleRole::Button);
-    refresh_button.set_tooltip_text(Some("Refresh entries"));
-    let add_button = Button::with_label("Add");
-    add_button.set_accessible_role(AccessibleRole::Button);
-    add_button.set_tooltip_text(Some("Add autostart entry"));
-    let filter_button = Button::with_label("Filter");
-    filter_button.set_accessible_role(AccessibleRole::Button);
-    filter_button.set_tooltip_text(Some("Filter visible entries"));
-    about_button.set_accessible_role(AccessibleRole::Button);
-    about_button.set_tooltip_text(Some("About this app"));
+    /// The trimmed value of `key` in `extra`; `None` when missing or blank.
+    fn extra_value(&self, key: &str) -> Option<&str> {
+        self.extra
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.trim())
+            .filter(|v| !v.is_empty())
+    }
 
-    {
-        let state = state.clone();
-        refresh_button.connect_clicked(move |_| {
-            if let Err(err) = refresh_entries(&state) {
-                state.status_bar.set_text(&format!("Refresh failed: {err:#}"));
-            }
-        });
+    /// The `only_show_in` or `not_show_in` desktops, by key; empty when the key is unset.
+    fn desktop_list(&self, key: &str) -> Vec<String> {
+        if key == ONLY_SHOW_IN_KEY { self.only_show_in.clone() } else { self.not_show_in.clone() }
     }
 
-    {
-        let state = state.clone();
-        add_button.connect_clicked(move |_| {
-            if let Err(err) = show_add_dialog(&state) {
-                state.status_bar.set_text(&format!("Add failed: {err:#}"));
-            }
-        });
+    fn desktop_list_mut(&mut self, key: &str) -> &mut Vec<String> {
+        if key == ONLY_SHOW_IN_KEY { &mut self.only_show_in } else { &mut self.not_show_in }
     }
 
-    {
-        let state = state.clone();
-        state.list_box.clone().connect_row_selected(move |_, row| {
-            let idx = row
-                .and_then(|r| usize::try_from(r.index()).ok())
-                .and_then(|visible_idx| state.visible_indices.borrow().get(visible_idx).copied());
-            state.selected.replace(idx);
-            update_detail(&state);
-        });
+    /// Appends `value` to the list under `key` (`ONLY_SHOW_IN_KEY` or `NOT_SHOW_IN_KEY`).
+    fn add_desktop_list_value(&mut self, key: &str, value: &str) -> Result<()> {
+        let value = value.trim();
+        if value.is_empty() {
+            bail!("The desktop name is empty");
+        }
+        if value.contains(';') || value.chars().any(char::is_whitespace) {
+            bail!("Invalid desktop name {value:?}; use a single word like GNOME or KDE");
+        }
+        let values = self.desktop_list_mut(key);
+        if !values.iter().any(|v| v == value) {
+            values.push(value.to_string());
+        }
+        Ok(())
     }
 
-    {
-        let state = state.clone();
-        filter_button.connect_clicked(move |_| {
-            if let Err(err) = show_filter_dialog(&state) {
-                state
-                    .status_bar
-                    .set_text(&format!("Filter dialog failed: {err:#}"));
-            }
-        });
+    /// Drops `value` from the list under `key`; the key is not written once the list is empty.
+    fn remove_desktop_list_value(&mut self, key: &str, value: &str) -> bool {
+        let values = self.desktop_list_mut(key);
+        let before = values.len();
+        values.retain(|v| v != value);
+        values.len() != before
     }
 
-    {
-        let state = state.clone();
-        sort_button.connect_clicked(move |_| {
-            if let Err(err) = show_sort_dialog(&state) {
-                state
-                    .status_bar
-                    .set_text(&format!("Sort dialog failed: {err:#}"));
-            }
-        });
+    /// The `Comment=` description, if set and not blank.
+    fn comment(&self) -> Option<&str> {
+        self.comment.as_deref().map(str::trim).filter(|v| !v.is_empty())
     }
 
-    {
-        let state = state.clone();
-        about_button.connect_clicked(move |_| {
-            if let Err(err) = show_about_dialog(&state) {
-                state
-                    .status_bar
-                    .set_text(&format!("About dialog failed: {err:#}"));
-            }
-        });
+    /// The `GenericName=` (e.g. "Web Browser"), if set and not blank.
+    fn generic_name(&self) -> Option<&str> {
+        self.generic_name.as_deref().map(str::trim).filter(|v| !v.is_empty())
     }
 
-    {
-        let state = state.clone();
-        toggle_button.connect_clicked(move |_| {
-            if let Err(err) = toggle_selected(&state) {
-                state.status_bar.set_text(&format!("Toggle failed: {err:#}"));
-            }
-        });
+    /// The program the entry needs installed: `TryExec=` when set, else `exec_binary`.
+    /// `None` for environment entries, which run nothing.
+    fn required_binary(&self) -> Option<String> {
+        if is_environment_source(&self.source) {
+            return None;
+        }
+        self.try_exec().map(str::to_string).or_else(|| self.exec_binary())
     }
 
-    {
-        let state = state.clone();
-        delete_button.connect_clicked(move |_| {
-            if let Err(err) = delete_selected(&state) {
-                state.status_bar.set_text(&format!("Delete failed: {err:#}"));
-            }
-        });
+    /// `exec_binary` as a path, looked up on `$PATH` when it has no `/`.
+    fn binary_path(&self) -> Option<PathBuf> {
+        let binary = self.exec_binary()?;
+        if binary.contains('/') {
+            Some(PathBuf::from(binary))
+        } else {
+            find_in_path(&binary)
+        }
     }
 
-    {
-        let state = state.clone();
-        edit_button.connect_clicked(move |_| {
-            if let Err(err) = show_edit_dialog(&state) {
-                state.status_bar.set_text(&format!("Edit failed: {err:#}"));
-            }
-        });
+    /// PowerShell fragment for a Chocolatey `chocolateyInstall.ps1` that registers the
+    /// command under the current user's `Run` key.
+    fn to_chocolatey_nupkg_snippet(&self) -> String {
+        let value = windows_command_line(&self.command_argv());
+        format!(
+            "# Auto-generated by Universal Startup Manager\n\
+             $ErrorActionPreference = 'Stop'\n\
+             $runKey = 'HKCU:\\Software\\Microsoft\\Windows\\CurrentVersion\\Run'\n\
+             New-ItemProperty -Path $runKey -Name {} -Value {} -PropertyType String -Force | Out-Null\n",
+            powershell_single_quote(&self.name),
+            powershell_single_quote(&value)
+        )
     }
 
-    let header = HeaderBar::builder()
-        .title_widget(&Label::new(Some("Universal Startup Manager")))
-        .show_title_buttons(true)
-        .build();
-    header.pack_start(&refresh_button);
-    header.pack_start(&filter_button);
-    header.pack_start(&sort_button);
-    header.pack_end(&add_button);
-    header.pack_end(&about_button);
+    /// Minimal winget singleton manifest naming the entry's program. winget has no notion of
+    /// autostart, so the original command line is kept as a comment.
+    fn to_winget_manifest_snippet(&self) -> String {
+        let argv = self.command_argv();
+        let binary = argv
+            .first()
+            .and_then(|b| Path::new(b).file_name())
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut identifier: String = self.name.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+        if identifier.is_empty() {
+            identifier = "Entry".to_string();
+        }
+        format!(
+            "# Auto-generated by Universal Startup Manager\n\
+             # Startup command: {}\n\
+             PackageIdentifier: Usm.{identifier}\n\
+             PackageVersion: 1.0.0\n\
+             PackageName: {}\n\
+             Commands:\n  - {}\n\
+             InstallerType: portable\n\
+             ManifestType: singleton\n\
+             ManifestVersion: 1.6.0\n",
+            windows_command_line(&argv).replace('\n', " "),
+            yaml_double_quote(&self.name),
+            yaml_double_quote(&binary)
+        )
+    }
 
-    let list_box_scrolled = ScrolledWindow::builder()
-        .child(&list_box)
-        .min_content_width(320)
-        .build();
+    /// The `X-USM-ID=` value stored in the file, if any.
+    fn entry_id(&self) -> Option<&str> {
+        self.extra
+            .iter()
+            .find(|(k, _)| k == USM_ID_KEY)
+            .map(|(_, v)| v.as_str())
+    }
 
-    let detail_box = GtkBox::new(Orientation::Vertical, 6);
-    detail_box.append(&label_row("Name:", &state.detail_name));
-    detail_box.append(&label_row("Command:", &state.detail_command));
-    detail_box.append(&label_row("Source:", &state.detail_source));
-    detail_box.append(&label_row("Status:", &state.detail_status));
+    /// `sha256:<hex>` of the entry's `.desktop` text, leaving out comments, the preamble and
+    /// `X-USM-ID=`, so copies of the same entry hash alike wherever they came from.
+    fn generate_desktop_file_hash_uri(&self) -> String {
+        let mut canonical = self.clone();
+        canonical.extra.retain(|(k, _)| k != USM_ID_KEY);
+        canonical.entry_comments.clear();
+        canonical.preamble.clear();
+        let digest = sha256(write_desktop_entry_to_string(&canonical).as_bytes());
+        let hex: String = digest.iter().map(|b| format!("{b:02x}")).collect();
+        format!("sha256:{hex}")
+    }
 
-    let action_row = GtkBox::new(Orientation::Horizontal, 6);
-    toggle_button.set_accessible_role(AccessibleRole::Button);
-    toggle_button.set_tooltip_text(Some("Toggle enabled state"));
-    delete_button.set_accessible_role(AccessibleRole::Button);
-    delete_button.set_tooltip_text(Some("Delete entry"));
-    edit_button.set_accessible_role(AccessibleRole::Button);
-    edit_button.set_tooltip_text(Some("Edit entry"));
-    action_row.append(&toggle_button);
-    action_row.append(&edit_button);
-    action_row.append(&delete_button);
-    detail_box.append(&action_row);
-    detail_box.append(&Label::new(Some("Status messages:")));
-    detail_box.append(&status_bar);
+    /// `X-GNOME-Autostart-Delay=` in seconds; 0 when missing or invalid.
+    fn delay_seconds(&self) -> u32 {
+        self.delay_seconds.unwrap_or(0)
+    }
 
-    let content = GtkBox::new(Orientation::Horizontal, 12);
-    content.append(&list_box_scrolled);
-    content.append(&detail_box);
+    /// Sets `X-GNOME-Autostart-Delay=`; 0 removes the key, since no delay is the default.
+    /// An invalid value kept in `extra` is replaced too.
+    fn set_delay_seconds(&mut self, seconds: u32) {
+        self.delay_seconds = Some(seconds).filter(|&s| s > 0);
+        self.extra.retain(|(k, _)| k != AUTOSTART_DELAY_KEY);
+    }
 
-    let root = GtkBox::new(Orientation::Vertical, 8);
-    root.append(&header);
-    root.append(&content);
+    /// AutoHotkey v2 script that runs the command, for a Windows Startup folder. `None` when
+    /// the binary lives under `/usr/` or `/bin/` and so has no Windows counterpart. v2's
+    /// `Run` takes the whole command line as its first argument (the second is the working
+    /// directory), so arguments are folded into that string.
+    fn to_autohotkey_script(&self) -> Option<String> {
+        let argv = self.command_argv();
+        let binary = argv.first()?;
+        if binary.starts_with("/usr/") || binary.starts_with("/bin/") {
+            return None;
+        }
+        let mut script = String::from(";Auto-generated by USM\n");
+        for (key, value) in &self.env_vars {
+            script.push_str(&format!("EnvSet({}, {})\n", ahk_quote(key), ahk_quote(value)));
+        }
+        script.push_str(&format!("Run({})\n", ahk_quote(&windows_command_line(&argv))));
+        Some(script)
+    }
 
-    let window = ApplicationWindow::builder()
-        .application(app)
-        .title("Universal Startup Manager")
-        .default_width(900)
-        .default_height(600)
-        .child(&root)
-        .build();
+    /// systemd user `.service` unit running the command once. Field codes are dropped and
+    /// `env_vars` become `Environment=` lines.
+    fn to_systemd_service_string(&self) -> String {
+        let parts = parse_exec_into_parts(&self.command);
+        let argv: Vec<String> = std::iter::once(parts.binary)
+            .chain(parts.args)
+            .map(|arg| systemd_quote(&arg))
+            .collect();
+        let mut unit = format!(
+            "# Auto-generated by Universal Startup Manager\n\
+             [Unit]\n\
+             Description={}\n\
+             \n\
+             [Service]\n\
+             Type=oneshot\n",
+            systemd_escape_specifiers(&self.name)
+        );
+        for (key, value) in &self.env_vars {
+            unit.push_str(&format!("Environment={}\n", systemd_quote(&format!("{key}={value}"))));
+        }
+        unit.push_str(&format!("ExecStart={}\n", argv.join(" ")));
+        unit
+    }
 
-    window.present();
-    Ok(())
-}
+    /// systemd user `.timer` unit that starts the matching service on `schedule`
+    /// (an `OnCalendar=` expression such as `daily`).
+    fn to_systemd_timer_string(&self, schedule: &str) -> String {
+        format!(
+            "# Auto-generated by Universal Startup Manager\n\
+             [Unit]\n\
+             Description=Run {} on a schedule\n\
+             \n\
+             [Timer]\n\
+             OnCalendar={schedule}\n\
+             Persistent=true\n\
+             \n\
+             [Install]\n\
+             WantedBy=timers.target\n",
+            systemd_escape_specifiers(&self.name)
+        )
+    }
 
-fn label_row(label: &str, value: &Label) -> GtkBox {
-    let row = GtkBox::new(Orientation::Horizontal, 6);
-    let lab = Label::new(Some(label));
-    lab.set_mnemonic_widget(Some(value));
-    row.append(&lab);
-    row.append(value);
-    row
-}
+    /// runit `run` script for `~/.config/service/<name>/run`. Field codes are dropped,
+    /// since nothing substitutes them outside a desktop launcher.
+    fn to_runit_sv(&self) -> String {
+        let parts = parse_exec_into_parts(&self.command);
+        let command = if parts.field_codes.is_empty() {
+            self.command.clone()
+        } else {
+            std::iter::once(parts.binary)
+                .chain(parts.args)
+                .map(|arg| quote_exec_arg(&arg))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let mut stripped = self.clone();
+        stripped.command = command;
+        format!("#!/bin/sh\nexec {}\n", stripped.exec_line())
+    }
 
-fn apply_filter(entries: &[StartupEntry], filter: &FilterState) -> Vec<usize> {
-    entries
-        .iter()
-        .enumerate()
-        .filter(|(_, entry)| {
-            let state_ok = (filter.show_enabled && entry.enabled)
-                || (filter.show_disabled && !entry.enabled)
-                || (!filter.show_enabled && !filter.show_disabled);
-            let source_ok = (filter.show_user && matches!(entry.source, StartupSource::UserAutostart))
-                || (filter.show_system && matches!(entry.source, StartupSource::SystemAutostart))
-                || (!filter.show_user && !filter.show_system);
-            state_ok && source_ok
-        })
-        .map(|(idx, _)| idx)
-        .collect()
-}
+    /// macOS LaunchAgent for `~/Library/LaunchAgents/<Label>.plist`; see `to_launchd_agent_dict`.
+    fn to_launchd_agent_plist(&self) -> String {
+        let mut out = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+             <plist version=\"1.0\">\n",
+        );
+        self.to_launchd_agent_dict().write_xml(0, &mut out);
+        out.push_str("</plist>\n");
+        out
+    }
 
-fn sort_indices(entries: &[StartupEntry], mut indices: Vec<usize>, sort: SortKey) -> Vec<usize> {
-    indices.sort_by(|&a, &b| {
-        let ea = &entries[a];
-        let eb = &entries[b];
-        match sort {
-            SortKey::NameAsc => ea.name.to_lowercase().cmp(&eb.name.to_lowercase()),
-            SortKey::NameDesc => eb.name.to_lowercase().cmp(&ea.name.to_lowercase()),
-            SortKey::StatusEnabledFirst => {
-                eb.enabled.cmp(&ea.enabled).then_with(|| ea.name.to_lowercase().cmp(&eb.name.to_lowercase()))
-            }
-            SortKey::SourceUserFirst => {
-                let sa = matches!(ea.source, StartupSource::UserAutostart);
-                let sb = matches!(eb.source, StartupSource::UserAutostart);
-                sb.cmp(&sa).then_with(|| ea.name.to_lowercase().cmp(&eb.name.to_lowercase()))
-            }
-            SortKey::SourceSystemFirst => {
-                let sa = matches!(ea.source, StartupSource::SystemAutostart);
-                let sb = matches!(eb.source, StartupSource::SystemAutostart);
-                sb.cmp(&sa).then_with(|| ea.name.to_lowercase().cmp(&eb.name.to_lowercase()))
-            }
+    /// LaunchAgent keys: `Label` (`local.<slug>`), `ProgramArguments` without field codes,
+    /// `EnvironmentVariables` from the `env` prefix, `RunAtLoad`, `Disabled` for a disabled
+    /// entry, `KeepAlive` (false: autostart runs once), and stdout/stderr going to
+    /// `launchd_log_path`.
+    fn to_launchd_agent_dict(&self) -> PlistValue {
+        let slug = slugify(&self.name);
+        let parts = parse_exec_into_parts(&self.command);
+        let arguments = std::iter::once(parts.binary)
+            .chain(parts.args)
+            .map(PlistValue::String)
+            .collect();
+        let log = PlistValue::String(launchd_log_path(&slug));
+        let mut dict = vec![
+            ("Label".to_string(), PlistValue::String(format!("local.{slug}"))),
+            ("ProgramArguments".to_string(), PlistValue::Array(arguments)),
+        ];
+        if !self.env_vars.is_empty() {
+            let vars = self
+                .env_vars
+                .iter()
+                .map(|(k, v)| (k.clone(), PlistValue::String(v.clone())))
+                .collect();
+            dict.push(("EnvironmentVariables".to_string(), PlistValue::Dict(vars)));
         }
-    });
-    indices
-}
+        dict.push(("RunAtLoad".to_string(), PlistValue::Bool(true)));
+        if !self.enabled {
+            dict.push(("Disabled".to_string(), PlistValue::Bool(true)));
+        }
+        dict.push(("KeepAlive".to_string(), PlistValue::Bool(false)));
+        dict.push(("StandardOutPath".to_string(), log.clone()));
+        dict.push(("StandardErrorPath".to_string(), log));
+        PlistValue::Dict(dict)
+    }
 
-fn rebuild_list(state: &AppState) {
-    while let Some(child) = state.list_box.first_child() {
-        state.list_box.remove(&child);
+    /// Names of the groups after `[Desktop Entry]`, in file order, duplicates included.
+    fn entry_group_headers(&self) -> Vec<String> {
+        self.other_groups
+            .iter()
+            .filter_map(|group| {
+                let header = group.iter().map(|l| l.trim()).find(|l| !l.is_empty())?;
+                header.strip_prefix('[')?.strip_suffix(']').map(str::to_string)
+            })
+            .collect()
     }
-    let filtered = apply_filter(&state.entries.borrow(), &state.filter.borrow());
-    let sorted = sort_indices(&state.entries.borrow(), filtered, state.sort.get());
-    state.visible_indices.replace(sorted.clone());
-    state.selected.replace(None);
-    if sorted.is_empty() {
-        let row = ListBoxRow::new();
-        row.set_accessible_role(AccessibleRole::ListItem);
-        row.set_child(Some(&Label::new(Some("No entries to show"))));
-        state.list_box.append(&row);
-        state.status_bar.set_text("No entries match the current filter");
-        return;
+
+    fn has_desktop_actions(&self) -> bool {
+        self.entry_group_headers()
+            .iter()
+            .any(|h| h.starts_with("Desktop Action "))
     }
-    for idx in sorted {
-        let entry = &state.entries.borrow()[idx];
-        let text = format!(
-            "{} — {} [{}] {}",
-            entry.name,
-            entry.command,
-            source_label(&entry.source),
-            if entry.enabled { "enabled" } else { "disabled" }
-        );
-        let row = ListBoxRow::new();
-        row.set_accessible_role(AccessibleRole::ListItem);
-        row.set_child(Some(&Label::new(Some(&text))));
-        state.list_box.append(&row);
+
+    /// JSON object for GNOME's autostart handler. `id` is the desktop file id without
+    /// `.desktop`, falling back to the name's slug for entries without a file.
+    fn to_gnome_autostart_handler_json(&self) -> String {
+        let id = self
+            .path
+            .as_ref()
+            .and_then(|p| p.file_stem())
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| slugify(&self.name));
+        let handler = GnomeAutostartHandler {
+            id,
+            name: self.name.clone(),
+            description: self.comment.clone().unwrap_or_default(),
+            command_line: self.exec_line(),
+            autostart: self.enabled,
+            delay_seconds: self.delay_seconds(),
+        };
+        // Serializing a struct of strings, a bool and a number cannot fail.
+        serde_json::to_string_pretty(&handler).unwrap_or_default() + "\n"
     }
-}
 
-fn refresh_entries(state: &AppState) -> Result<()> {
-    let new_entries = load_entries()?;
-    state.entries.replace(new_entries);
-    state.selected.replace(None);
-    rebuild_list(state);
-    update_detail(state);
-    state.status_bar.set_text("Refreshed");
-    Ok(())
-}
+    /// Nix attribute set for a Home Manager configuration:
+    /// `services.startup.<slug> = { enable = …; name = "…"; command = "…"; };`
+    fn to_nix_home_manager_module(&self) -> String {
+        let slug = slugify(&self.name);
+        let attr = if slug.starts_with(|c: char| c.is_ascii_digit()) {
+            format!("\"{slug}\"")
+        } else {
+            slug
+        };
+        format!(
+            "services.startup.{attr} = {{\n  enable = {};\n  name = {};\n  command = {};\n}};\n",
+            self.enabled,
+            nix_quote(&self.name),
+            nix_quote(&self.exec_line())
+        )
+    }
 
-fn update_detail(state: &AppState) {
-    if let Some(idx) = state.selected.get() {
-        if let Some(entry) = state.entries.borrow().get(idx) {
-            state.detail_name.set_text(&entry.name);
-            state.detail_command.set_text(&entry.command);
-            state.detail_source.set_text(source_label(&entry.source));
-            state
-                .detail_status
-                .set_text(if entry.enabled { "enabled" } else { "disabled" });
-            let user_owned = matches!(entry.source, StartupSource::UserAutostart)
-                && entry
-                    .path
-                    .as_ref()
-                    .map(|p| is_user_owned_path(p))
-                    .unwrap_or(false);
-            state.toggle_button.set_sensitive(user_owned);
-            state.delete_button.set_sensitive(user_owned);
-            state.edit_button.set_sensitive(user_owned);
-            return;
+    /// `X-GNOME-Autostart-Phase=`; unknown or missing values mean the Applications phase.
+    fn gnome_autostart_phase(&self) -> AutostartPhase {
+        self.extra_value(AUTOSTART_PHASE_KEY)
+            .and_then(AutostartPhase::from_key_value)
+            .unwrap_or(AutostartPhase::Applications)
+    }
+
+    /// Line for `~/.config/openbox/autostart`: `cmd &`, or `(sleep N && cmd) &` when delayed.
+    /// Openbox has no disabled state, so `enabled` is not reflected.
+    fn to_openbox_autostart_entry(&self) -> String {
+        match self.delay_seconds() {
+            0 => format!("{} &", self.exec_line()),
+            delay => format!("(sleep {delay} && {}) &", self.exec_line()),
         }
     }
-    state.detail_name.set_text("-");
-    state.detail_command.set_text("-");
-    state.detail_source.set_text("-");
-    state.detail_status.set_text("-");
-    state.toggle_button.set_sensitive(false);
-    state.delete_button.set_sensitive(false);
-    state.edit_button.set_sensitive(false);
-}
 
-fn toggle_selected(state: &AppState) -> Result<()> {
-    let idx = state.selected.get().context("No item selected")?;
-    let mut entries = state.entries.borrow_mut();
-    let entry = entries.get_mut(idx).context("Invalid selection")?;
-    if entry.source != StartupSource::UserAutostart {
-        bail!("Only user autostart entries can be toggled");
+    /// Sets `Name[locale]=value`, replacing an existing value for the same locale.
+    fn add_localized_name(&mut self, locale: &str, value: &str) -> Result<()> {
+        if !is_valid_locale_tag(locale) {
+            bail!("Invalid locale {locale:?}; use a form like de, pt_BR or sr@latin");
+        }
+        if value.trim().is_empty() {
+            bail!("The name for {locale} is empty");
+        }
+        if value.contains('\n') {
+            bail!("The name for {locale} must be a single line");
+        }
+        match self.localized_names.iter_mut().find(|(l, _)| l == locale) {
+            Some((_, existing)) => *existing = value.to_string(),
+            None => self.localized_names.push((locale.to_string(), value.to_string())),
+        }
+        Ok(())
     }
-    let path = entry
-        .path
-        .clone()
-        .unwrap_or_else(|| user_autostart_dir().join(format!("{}.desktop", slugify(&entry.name))));
-    let path = validate_user_entry_path(&path)?;
-    entry.enabled = !entry.enabled;
-    write_desktop_entry(entry, &path)?;
-    state
-        .status_bar
-        .set_text(if entry.enabled { "Enabled" } else { "Disabled" });
-    refresh_entries(state)?;
-    Ok(())
-}
 
-fn delete_selected(state: &AppState) -> Result<()> {
-    let idx = state.selected.get().context("No item selected")?;
-    let entries = state.entries.borrow();
-    let entry = entries.get(idx).context("Invalid selection")?;
-    if entry.source != StartupSource::UserAutostart {
-        bail!("Only user autostart entries can be deleted");
+    /// Drops `Name[locale]`; returns whether there was one.
+    fn remove_localized_name(&mut self, locale: &str) -> bool {
+        let before = self.localized_names.len();
+        self.localized_names.retain(|(l, _)| l != locale);
+        self.localized_names.len() != before
     }
-    let path = entry
-        .path
-        .as_ref()
-        .context("Entry has no associated file path")?;
-    let path = validate_user_entry_path(path)?;
-    fs::remove_file(&path).with_context(|| format!("Removing {:?}", path))?;
-    drop(entries);
-    state.status_bar.set_text("Deleted entry");
-    refresh_entries(state)?;
-    Ok(())
-}
 
-fn show_add_dialog(state: &AppState) -> Result<()> {
-    let parent = state
-        .list_box
-        .root()
-        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
-    let dialog = Dialog::with_buttons(
-        Some("Add autostart entry"),
-        parent.as_ref(),
-        gtk4::DialogFlags::MODAL,
-        &[("Cancel", ResponseType::Cancel), ("Add", ResponseType::Ok)],
-    );
+    /// `Name[xx]` for `locale`, matched the Desktop Entry spec way: `lang_COUNTRY@MODIFIER`,
+    /// then `lang_COUNTRY`, `lang@MODIFIER` and `lang`. The encoding part is ignored.
+    fn localized_name_for_locale(&self, locale: &str) -> Option<&str> {
+        let (rest, modifier) = match locale.split_once('@') {
+            Some((rest, modifier)) => (rest, Some(modifier)),
+            None => (locale, None),
+        };
+        let rest = rest.split('.').next().unwrap_or(rest);
+        let (lang, country) = match rest.split_once('_') {
+            Some((lang, country)) => (lang, Some(country)),
+            None => (rest, None),
+        };
+        let mut candidates = Vec::new();
+        if let (Some(country), Some(modifier)) = (country, modifier) {
+            candidates.push(format!("{lang}_{country}@{modifier}"));
+        }
+        if let Some(country) = country {
+            candidates.push(format!("{lang}_{country}"));
+        }
+        if let Some(modifier) = modifier {
+            candidates.push(format!("{lang}@{modifier}"));
+        }
+        candidates.push(lang.to_string());
+        candidates.iter().find_map(|tag| {
+            self.localized_names
+                .iter()
+                .find(|(l, value)| l == tag && !value.trim().is_empty())
+                .map(|(_, value)| value.as_str())
+        })
+    }
 
-    let content = dialog.content_area();
-    content.set_spacing(6);
-    let name_label = Label::new(Some("Name:"));
-    let name_entry = Entry::new();
-    name_entry.set_placeholder_text(Some("Name"));
-    name_entry.set_accessible_role(AccessibleRole::TextBox);
-    name_label.set_mnemonic_widget(Some(&name_entry));
+    /// The `Icon=` value: a theme icon name or an absolute image path. `None` when unset or blank.
+    fn icon(&self) -> Option<&str> {
+        self.icon.as_deref().map(str::trim).filter(|v| !v.is_empty())
+    }
 
-    let cmd_label = Label::new(Some("Command:"));
-    let cmd_entry = Entry::new();
-    cmd_entry.set_placeholder_text(Some("Command"));
-    cmd_entry.set_accessible_role(AccessibleRole::TextBox);
-    cmd_label.set_mnemonic_widget(Some(&cmd_entry));
+    /// Guess at a background service rather than an app: at least two of no `Icon=`, no
+    /// `GenericName=`, and a command word such as `daemon`, `agent`, `server` or `service`
+    /// (`gnome-keyring-daemon`, `polkit-gnome-authentication-agent-1`).
+    fn is_daemon(&self) -> bool {
+        const DAEMON_WORDS: [&str; 4] = ["daemon", "agent", "server", "service"];
+        let daemon_word = self
+            .command
+            .to_lowercase()
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|word| DAEMON_WORDS.contains(&word));
+        [self.icon().is_none(), self.generic_name().is_none(), daemon_word]
+            .into_iter()
+            .filter(|&signal| signal)
+            .count()
+            >= 2
+    }
 
-    content.append(&name_label);
-    content.append(&name_entry);
-    content.append(&cmd_label);
-    content.append(&cmd_entry);
+    /// Whether Sway or i3 should rerun the command on every reload (`exec_always`).
+    fn always_restart(&self) -> bool {
+        self.extra.iter().any(|(k, v)| k == EXEC_ALWAYS_KEY && v == "true")
+    }
 
-    dialog.connect_response({
-        let state = state.clone();
-        move |dlg, resp| {
-            if resp == ResponseType::Ok {
-                let name = name_entry.text().to_string();
-                let cmd = cmd_entry.text().to_string();
-                if let Err(err) = create_user_entry(&name, &cmd) {
-                    state
-                        .status_bar
-                        .set_text(&format!("Failed to add entry: {err:#}"));
-                } else if let Err(err) = refresh_entries(&state) {
-                    state
-                        .status_bar
-                        .set_text(&format!("Failed to refresh after add: {err:#}"));
-                } else {
-                    state.status_bar.set_text("Added entry");
-                }
-            }
-            dlg.close();
-        }
-    });
+    /// The entry as a `.desktop` file with `X-XFCE-Autostart-Override=true`, so Xfce's Session
+    /// Manager uses it in place of a system entry of the same file name.
+    fn to_xfce4_autostart_entry(&self) -> String {
+        let xfce = StartupEntry { xfce_override: true, ..self.clone() };
+        write_desktop_entry_to_string(&xfce)
+    }
 
-    dialog.show();
-    Ok(())
-}
+    /// `StartupNotify=` as a bool; `None` when missing or not `true`/`false`.
+    fn startup_notify(&self) -> Option<bool> {
+        self.extra
+            .iter()
+            .find(|(k, _)| k == "StartupNotify")
+            .and_then(|(_, v)| v.trim().parse().ok())
+    }
 
-fn show_edit_dialog(state: &AppState) -> Result<()> {
-    let idx = state.selected.get().context("No item selected")?;
-    let entry = {
-        let entries = state.entries.borrow();
-        entries.get(idx).cloned().context("Invalid selection")?
-    };
-    if entry.source != StartupSource::UserAutostart {
-        bail!("Only user entries can be edited");
+    /// Line for i3's config: `exec --no-startup-id cmd` when the entry turns startup
+    /// notification off (`StartupNotify=false`), plain `exec cmd` otherwise. i3 has no disabled
+    /// state or delay, so neither is reflected.
+    fn to_i3_exec_line(&self) -> String {
+        let directive = if self.always_restart() { "exec_always" } else { "exec" };
+        match self.startup_notify() {
+            Some(false) => format!("{directive} --no-startup-id {}", self.exec_line()),
+            _ => format!("{directive} {}", self.exec_line()),
+        }
     }
 
-    let parent = state
-        .list_box
-        .root()
-        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
-    let dialog = Dialog::with_buttons(
-        Some("Edit autostart entry"),
-        parent.as_ref(),
-        gtk4::DialogFlags::MODAL,
-        &[("Cancel", ResponseType::Cancel), ("Save", ResponseType::Ok)],
-    );
+    /// For KDE Plasma: an entry from an environment source becomes a script for
+    /// `~/.config/plasma-workspace/env/`, which Plasma sources before the session starts, with
+    /// one `export` per variable it sets; anything else becomes a `.desktop` file for
+    /// `~/.config/autostart/`.
+    fn to_kde_plasma_autostart_entry(&self) -> String {
+        let assignments = if is_environment_source(&self.source) {
+            parse_environment_assignments(&self.command)
+        } else {
+            Vec::new()
+        };
+        if assignments.is_empty() {
+            return write_desktop_entry_to_string(self);
+        }
+        let mut script = String::from("#!/bin/sh\n");
+        for (key, value) in assignments {
+            script.push_str(&format!("export {key}={}\n", quote_exec_arg(&value)));
+        }
+        script
+    }
 
-    let content = dialog.content_area();
-    content.set_spacing(6);
-    let name_label = Label::new(Some("Name:"));
-    let name_entry = Entry::new();
+    /// `ENV KEY=value` for an `environment.d` or Plasma env entry that sets exactly one
+    /// variable (`export KEY=value`); `None` for entries that run a program.
+    fn to_dockerfile_env_instruction(&self) -> Option<String> {
+        if !is_environment_source(&self.source) {
+            return None;
+        }
+        let assignments = parse_environment_assignments(&self.command);
+        let [(key, value)] = assignments.as_slice() else {
+            return None;
+        };
+        let plain = !value.is_empty()
+            && !value.chars().any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | '$'));
+        if plain {
+            Some(format!("ENV {key}={value}"))
+        } else {
+            let escaped = value.replace('\\', "\\\\").replace('"', "\\\"").replace('$', "\\$");
+            Some(format!("ENV {key}=\"{escaped}\""))
+        }
+    }
+
+    /// Exec-form `ENTRYPOINT ["binary", "arg", …]` for an entry that runs a program, with
+    /// field codes such as `%U` dropped; `None` for environment entries or an empty command.
+    fn to_dockerfile_entrypoint_instruction(&self) -> Option<String> {
+        if is_environment_source(&self.source) {
+            return None;
+        }
+        let argv: Vec<String> = self
+            .command_argv()
+            .iter()
+            .filter(|arg| !is_field_code(arg))
+            .map(|arg| serde_json::to_string(arg).unwrap_or_default())
+            .collect();
+        if argv.is_empty() {
+            return None;
+        }
+        Some(format!("ENTRYPOINT [{}]", argv.join(", ")))
+    }
+
+    /// Line for Hyprland's config: `exec-once = cmd`. Hyprland has no disabled state or delay,
+    /// so neither is reflected.
+    fn to_hyprland_exec_once(&self) -> String {
+        format!("exec-once = {}", self.exec_line())
+    }
+
+    /// Line for Sway's config: `exec cmd`, or `exec_always cmd` when `always_restart`. Sway has
+    /// neither a disabled state nor a delay, so `enabled` and the autostart delay are dropped.
+    fn to_swayconfig_exec(&self) -> String {
+        let directive = if self.always_restart() { "exec_always" } else { "exec" };
+        format!("{directive} {}", self.exec_line())
+    }
+
+    /// Shareable `xdg-autostart://entry?name=…&command=…&enabled=…` link for this entry.
+    fn to_xdg_autostart_url_scheme(&self) -> String {
+        format!(
+            "{XDG_AUTOSTART_URL_PREFIX}?name={}&command={}&enabled={}",
+            percent_encode(&self.name),
+            percent_encode(&self.exec_line()),
+            self.enabled
+        )
+    }
+
+    /// Stamps user entries with a stable ID so they can be tracked across renames.
+    fn ensure_entry_id(&mut self) {
+        if self.source == StartupSource::UserAutostart && self.entry_id().is_none() {
+            let id = generate_entry_id(self);
+            self.extra.push((USM_ID_KEY.to_string(), id));
+        }
+    }
+
+    /// Size of the backing file in bytes, or 0 when it has none or cannot be read.
+    fn size_on_disk(&self) -> u64 {
+        self.path
+            .as_ref()
+            .and_then(|p| fs::metadata(p).ok())
+            .map(|m| m.len())
+            .unwrap_or(0)
+    }
+
+    /// Hash of the entry's content, ignoring its file name, so renamed copies compare equal.
+    fn compute_content_fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.name.hash(&mut hasher);
+        self.command.hash(&mut hasher);
+        self.env_vars.hash(&mut hasher);
+        self.enabled.hash(&mut hasher);
+        let mut extra = self.extra.clone();
+        extra.sort();
+        extra.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Whether `comment_out_entry` turned the file into comments.
+    fn is_commented_out(&self) -> bool {
+        self.preamble.first().map(String::as_str) == Some(COMMENTED_OUT_MARKER)
+    }
+
+    /// Rewrites the whole file as comments (`#` before every non-blank line) under a marker
+    /// line, so parsers find no `[Desktop Entry]` group at all. Unlike `Hidden=true`, which
+    /// session managers read and honour, the entry stops existing for them.
+    fn comment_out_entry(&mut self) {
+        if self.is_commented_out() {
+            return;
+        }
+        let text = write_desktop_entry_to_string(self);
+        self.preamble = std::iter::once(COMMENTED_OUT_MARKER.to_string())
+            .chain(text.lines().map(|line| {
+                if line.trim().is_empty() {
+                    line.to_string()
+                } else {
+                    format!("#{line}")
+                }
+            }))
+            .collect();
+        self.entry_comments.clear();
+        self.other_groups.clear();
+    }
+
+    /// Undoes `comment_out_entry`, keeping the entry's source and path.
+    fn uncomment_entry(&mut self) {
+        if !self.is_commented_out() {
+            return;
+        }
+        let text: String = self.preamble[1..]
+            .iter()
+            .map(|line| format!("{}\n", line.strip_prefix('#').unwrap_or(line)))
+            .collect();
+        if let Ok(parsed) = parse_desktop_file_from_bytes(text.as_bytes()) {
+            *self = StartupEntry {
+                source: self.source.clone(),
+                path: self.path.take(),
+                ..parsed
+            };
+        }
+    }
+
+    /// "enabled", "disabled", or "commented out" for list rows and the detail panel.
+    fn status_label(&self) -> &'static str {
+        if self.is_commented_out() {
+            "commented out"
+        } else if self.enabled {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    }
+}
+
+const USM_ID_KEY: &str = "X-USM-ID";
+const AUTOSTART_DELAY_KEY: &str = "X-GNOME-Autostart-Delay";
+const AUTOSTART_PHASE_KEY: &str = "X-GNOME-Autostart-Phase";
+const LOCKED_KEY: &str = "X-USM-Locked";
+/// Marks an entry read from Sway's or i3's `exec_always`; see `StartupEntry::always_restart`.
+const EXEC_ALWAYS_KEY: &str = "X-USM-Exec-Always";
+const ONLY_SHOW_IN_KEY: &str = "OnlyShowIn";
+const NOT_SHOW_IN_KEY: &str = "NotShowIn";
+/// Desktops offered in the edit dialog's OnlyShowIn/NotShowIn pickers, as registered in the
+/// freedesktop menu spec.
+const COMMON_DESKTOP_NAMES: [&str; 6] = ["GNOME", "KDE", "XFCE", "LXDE", "X-Cinnamon", "MATE"];
+/// Set by Xfce's Session Manager on a user file that stands in for a system entry.
+const XFCE_AUTOSTART_OVERRIDE_KEY: &str = "X-XFCE-Autostart-Override";
+/// `true` runs the command in a terminal window; read into `StartupEntry::terminal`.
+const TERMINAL_KEY: &str = "Terminal";
+/// First line of a file written by `StartupEntry::comment_out_entry`.
+const COMMENTED_OUT_MARKER: &str = "# Commented out by Universal Startup Manager; remove the leading # to restore";
+const XDG_AUTOSTART_URL_PREFIX: &str = "xdg-autostart://entry";
+const SYSTEMD_TIMER_PREFIX: &str = "usm-";
+
+/// `Exec=` split into the program, its arguments, and any `%x` field codes it uses.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ExecParts {
+    binary: String,
+    args: Vec<String>,
+    field_codes: Vec<String>,
+}
+
+/// What `StartupEntry::try_launch_dry_run` found; nothing is executed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct LaunchDryRunResult {
+    binary_found: bool,
+    binary_executable: bool,
+    args_valid: bool,
+    env_vars_valid: bool,
+    warnings: Vec<String>,
+}
+
+impl LaunchDryRunResult {
+    fn is_ok(&self) -> bool {
+        self.binary_found && self.binary_executable && self.args_valid && self.env_vars_valid
+    }
+}
+
+/// One entry as GNOME's JSON autostart handler describes it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GnomeAutostartHandler {
+    id: String,
+    name: String,
+    #[serde(default)]
+    description: String,
+    command_line: String,
+    #[serde(default = "default_true")]
+    autostart: bool,
+    #[serde(default)]
+    delay_seconds: u32,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// gnome-session startup phases, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum AutostartPhase {
+    EarlyInitialization,
+    PreDisplayServer,
+    DisplayServer,
+    Initialization,
+    WindowManager,
+    Panel,
+    Desktop,
+    Applications,
+}
+
+impl AutostartPhase {
+    fn from_key_value(value: &str) -> Option<Self> {
+        Some(match value {
+            "EarlyInitialization" => AutostartPhase::EarlyInitialization,
+            "PreDisplayServer" => AutostartPhase::PreDisplayServer,
+            "DisplayServer" => AutostartPhase::DisplayServer,
+            "Initialization" => AutostartPhase::Initialization,
+            "WindowManager" => AutostartPhase::WindowManager,
+            "Panel" => AutostartPhase::Panel,
+            "Desktop" => AutostartPhase::Desktop,
+            "Applications" => AutostartPhase::Applications,
+            _ => return None,
+        })
+    }
+}
+
+/// Per-entry text conversions offered under "Copy Selected Entry As" in the main menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    Chocolatey,
+    Winget,
+    Openbox,
+    Link,
+    AutoHotkey,
+    Runit,
+    HomeManager,
+    GnomeJson,
+    Sway,
+    I3,
+    Dockerfile,
+    Xfce,
+    Hyprland,
+    Launchd,
+    KdePlasma,
+}
+
+impl ExportFormat {
+    const ALL: [ExportFormat; 15] = [
+        ExportFormat::Chocolatey,
+        ExportFormat::Winget,
+        ExportFormat::AutoHotkey,
+        ExportFormat::Launchd,
+        ExportFormat::Openbox,
+        ExportFormat::Sway,
+        ExportFormat::I3,
+        ExportFormat::Hyprland,
+        ExportFormat::Xfce,
+        ExportFormat::KdePlasma,
+        ExportFormat::Runit,
+        ExportFormat::HomeManager,
+        ExportFormat::GnomeJson,
+        ExportFormat::Dockerfile,
+        ExportFormat::Link,
+    ];
+
+    fn id(self) -> &'static str {
+        match self {
+            ExportFormat::Chocolatey => "chocolatey",
+            ExportFormat::Winget => "winget",
+            ExportFormat::Openbox => "openbox",
+            ExportFormat::Link => "link",
+            ExportFormat::AutoHotkey => "autohotkey",
+            ExportFormat::Runit => "runit",
+            ExportFormat::HomeManager => "home-manager",
+            ExportFormat::GnomeJson => "gnome-json",
+            ExportFormat::Sway => "sway",
+            ExportFormat::I3 => "i3",
+            ExportFormat::Dockerfile => "dockerfile",
+            ExportFormat::Xfce => "xfce",
+            ExportFormat::Hyprland => "hyprland",
+            ExportFormat::Launchd => "launchd",
+            ExportFormat::KdePlasma => "kde-plasma",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Chocolatey => "Chocolatey install script",
+            ExportFormat::Winget => "winget manifest",
+            ExportFormat::Openbox => "Openbox autostart line",
+            ExportFormat::Link => "xdg-autostart:// link",
+            ExportFormat::AutoHotkey => "AutoHotkey v2 script",
+            ExportFormat::Runit => "runit run script",
+            ExportFormat::HomeManager => "Home Manager (Nix) module",
+            ExportFormat::GnomeJson => "GNOME autostart handler JSON",
+            ExportFormat::Sway => "Sway config exec line",
+            ExportFormat::I3 => "i3 config exec line",
+            ExportFormat::Dockerfile => "Dockerfile ENV or ENTRYPOINT",
+            ExportFormat::Xfce => "Xfce autostart override file",
+            ExportFormat::Hyprland => "Hyprland exec-once line",
+            ExportFormat::Launchd => "macOS LaunchAgent plist",
+            ExportFormat::KdePlasma => "KDE Plasma autostart file or env script",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.id() == id)
+    }
+
+    fn render(self, entry: &StartupEntry) -> Option<String> {
+        match self {
+            ExportFormat::Chocolatey => Some(entry.to_chocolatey_nupkg_snippet()),
+            ExportFormat::Winget => Some(entry.to_winget_manifest_snippet()),
+            ExportFormat::Openbox => Some(entry.to_openbox_autostart_entry()),
+            ExportFormat::Link => Some(entry.to_xdg_autostart_url_scheme()),
+            ExportFormat::AutoHotkey => entry.to_autohotkey_script(),
+            ExportFormat::Runit => Some(entry.to_runit_sv()),
+            ExportFormat::HomeManager => Some(entry.to_nix_home_manager_module()),
+            ExportFormat::GnomeJson => Some(entry.to_gnome_autostart_handler_json()),
+            ExportFormat::Sway => Some(entry.to_swayconfig_exec()),
+            ExportFormat::I3 => Some(entry.to_i3_exec_line()),
+            ExportFormat::Xfce => Some(entry.to_xfce4_autostart_entry()),
+            ExportFormat::Hyprland => Some(entry.to_hyprland_exec_once()),
+            ExportFormat::Launchd => Some(entry.to_launchd_agent_plist()),
+            ExportFormat::KdePlasma => Some(entry.to_kde_plasma_autostart_entry()),
+            ExportFormat::Dockerfile => entry
+                .to_dockerfile_env_instruction()
+                .or_else(|| entry.to_dockerfile_entrypoint_instruction()),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct AppState {
+    entries: Rc<RefCell<Vec<StartupEntry>>>,
+    visible_indices: Rc<RefCell<Vec<usize>>>,
+    filter: Rc<RefCell<FilterState>>,
+    sort: Rc<Cell<SortKey>>,
+    view_mode: Rc<Cell<ViewMode>>,
+    /// Group header text keyed by list row position, read by the list box header func.
+    row_headers: Rc<RefCell<HashMap<usize, String>>>,
+    pending_warnings: Rc<RefCell<Vec<(usize, ValidationResult)>>>,
+    /// Entry index → pid for entries whose program was running at the last refresh; read by
+    /// the filter, the sort, the list's running dots and the detail panel.
+    running: Rc<RefCell<HashMap<usize, u32>>>,
+    /// `check_for_updates_from_system` as of the last refresh; read by the list's update
+    /// markers, the detail panel and Update from System.
+    system_updates: Rc<RefCell<Vec<UpdateAvailable>>>,
+    /// Untruncated `.desktop` count of each autostart dir at the last load, for
+    /// `with_truncation_notice`.
+    autostart_dir_totals: Rc<RefCell<AutostartDirTotals>>,
+    /// `session_search_path` as of the last refresh, for the missing-program checks.
+    search_path: Rc<RefCell<Vec<PathBuf>>>,
+    selected: Rc<Cell<Option<usize>>>,
+    config: Rc<RefCell<AppConfig>>,
+    list_box: ListBox,
+    list_scroller: ScrolledWindow,
+    alpha_strip: GtkBox,
+    detail_name: Label,
+    detail_command: Label,
+    detail_command_info: TextView,
+    detail_source: Label,
+    detail_status: Label,
+    detail_sections: Label,
+    detail_icon: Label,
+    detail_terminal: Label,
+    /// `Comment=` and `GenericName=`; their rows are hidden when empty.
+    detail_comment: Label,
+    detail_generic_name: Label,
+    /// `X-GNOME-Autostart-Delay=` as `Ns`; the row is hidden when there is no delay.
+    detail_delay: Label,
+    /// Every `extra` key and value of the selected entry, rebuilt by `update_detail`; the
+    /// expander around it is hidden when there are none.
+    detail_extra: gtk4::Grid,
+    detail_extra_expander: gtk4::Expander,
+    /// Locale, name and a Copy button per `Name[xx]=` of the selected entry, or "None".
+    detail_localized_names: gtk4::Grid,
+    /// Shown beside the command when `required_binary` is not installed.
+    detail_binary_warning: Image,
+    status_bar: Label,
+    count_label: Label,
+    toggle_button: Button,
+    delete_button: Button,
+    edit_button: Button,
+    lock_button: Button,
+    schedule_button: Button,
+    update_from_system_button: Button,
+    check_launch_button: Button,
+    warnings_button: Button,
+    /// Labelled "Filter (on)" while the filter differs from the default; see `rebuild_list`.
+    filter_button: Button,
+    wine_only_toggle: gtk4::ToggleButton,
+    daemons_only_toggle: gtk4::ToggleButton,
+    running_only_toggle: gtk4::ToggleButton,
+    /// Kept alive for as long as the window; see `watch_user_autostart_dir`.
+    user_dir_monitor: Rc<RefCell<Option<gio::FileMonitor>>>,
+    /// Kept alive for as long as the window; see `watch_system_autostart_dirs`.
+    system_dir_monitors: Rc<RefCell<Vec<gio::FileMonitor>>>,
+    /// The last `STATUS_HISTORY_LEN` status bar texts, oldest first, for debug dumps.
+    status_history: Rc<RefCell<VecDeque<String>>>,
+    /// Add, delete, edit and toggle changes that Ctrl+Z / Ctrl+Shift+Z can reverse.
+    undo_stack: Rc<RefCell<UndoStack>>,
+    /// `row.*` actions for the list's right-click menu; see `show_row_context_menu`.
+    row_actions: gio::SimpleActionGroup,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+struct FilterState {
+    show_enabled: bool,
+    show_disabled: bool,
+    show_user: bool,
+    show_system: bool,
+    wine_only: bool,
+    daemons_only: bool,
+    running_only: bool,
+    /// Live search bar text, matched case-insensitively against name and command.
+    search_text: String,
+}
+
+impl FilterState {
+    /// Reads `--filter=SPEC[,SPEC…]` (or `--filter SPEC…`) from CLI arguments; any other
+    /// argument is an error. Specifiers are `enabled`, `disabled`, `user` and `system`. Naming a status
+    /// or a source limits the list to the named ones; otherwise everything passes.
+    fn from_cli_args(args: &[String]) -> Result<FilterState> {
+        let mut specs = Vec::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if let Some(value) = arg.strip_prefix("--filter=") {
+                specs.push(value.as_ref());
+            } else if arg == "--filter" {
+                specs.push(iter.next().context("--filter needs a value such as user,enabled")?.as_str());
+            } else {
+                bail!("Unexpected argument {arg:?}; only --filter can follow --list");
+            }
+        }
+        let mut filter = FilterState::default();
+        if specs.is_empty() {
+            return Ok(filter);
+        }
+        let (mut enabled, mut disabled, mut user, mut system) = (false, false, false, false);
+        for spec in specs.into_iter().flat_map(|s| s.split(',')).map(str::trim) {
+            match spec {
+                "enabled" => enabled = true,
+                "disabled" => disabled = true,
+                "user" => user = true,
+                "system" => system = true,
+                other => bail!("Unknown filter {other:?}; use enabled, disabled, user or system"),
+            }
+        }
+        if enabled || disabled {
+            filter.show_enabled = enabled;
+            filter.show_disabled = disabled;
+        }
+        if user || system {
+            filter.show_user = user;
+            filter.show_system = system;
+        }
+        Ok(filter)
+    }
+
+    /// Whether every flag is at its `Default` value, i.e. nothing is hidden. The search
+    /// text is left out: the search bar shows it already.
+    fn is_default(&self) -> bool {
+        FilterState {
+            search_text: String::new(),
+            ..self.clone()
+        } == Self::default()
+    }
+}
+
+impl Default for FilterState {
+    fn default() -> Self {
+        Self {
+            show_enabled: true,
+            show_disabled: true,
+            show_user: true,
+            show_system: true,
+            wine_only: false,
+            daemons_only: false,
+            running_only: false,
+            search_text: String::new(),
+        }
+    }
+}
+
+/// How the list is laid out; grouped modes keep each group's rows together under a header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ViewMode {
+    Flat,
+    ByBinaryDir,
+    BySource,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum SortKey {
+    NameAsc,
+    NameDesc,
+    StatusEnabledFirst,
+    SourceUserFirst,
+    SourceSystemFirst,
+    Alternating,
+    WineFirst,
+    DaemonsFirst,
+    RunningFirst,
+}
+
+/// Filter, sort and window size, restored at the next launch. The quick filters and the
+/// live search are left out on purpose: they are meant for the moment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+struct ViewSettings {
+    show_enabled: bool,
+    show_disabled: bool,
+    show_user: bool,
+    show_system: bool,
+    sort: SortKey,
+    window_width: i32,
+    window_height: i32,
+}
+
+impl Default for ViewSettings {
+    fn default() -> Self {
+        Self {
+            show_enabled: true,
+            show_disabled: true,
+            show_user: true,
+            show_system: true,
+            sort: SortKey::NameAsc,
+            window_width: 900,
+            window_height: 600,
+        }
+    }
+}
+
+impl ViewSettings {
+    /// The saved filter, with everything that is not saved at its default.
+    fn filter_state(&self) -> FilterState {
+        FilterState {
+            show_enabled: self.show_enabled,
+            show_disabled: self.show_disabled,
+            show_user: self.show_user,
+            show_system: self.show_system,
+            ..FilterState::default()
+        }
+    }
+
+    fn record_filter(&mut self, filter: &FilterState) {
+        self.show_enabled = filter.show_enabled;
+        self.show_disabled = filter.show_disabled;
+        self.show_user = filter.show_user;
+        self.show_system = filter.show_system;
+    }
+}
+
+/// Persisted user preferences, stored as JSON under the user config dir.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct AppConfig {
+    search_history: SearchHistory,
+    sync_on_write: bool,
+    auto_disable_missing_binary: bool,
+    /// Cap on `.desktop` files read from each autostart dir; `None` reads them all.
+    max_entries_per_source: Option<usize>,
+    /// Pick files through the XDG desktop portal (needed inside Flatpak) instead of GTK's
+    /// in-process chooser.
+    use_xdg_portal_for_file_chooser: bool,
+    /// In grouped views, leave out groups the filter has emptied instead of showing them with
+    /// a "0 entries" header.
+    hide_empty_groups: bool,
+    /// Refresh the list when files in `~/.config/autostart` change outside the app.
+    refresh_on_external_change: bool,
+    /// Send a desktop notification when a system autostart dir gains entries, e.g. after a
+    /// package install.
+    notify_on_new_system_entry: bool,
+    /// System entry files seen on the last load, to tell which ones are new.
+    known_system_entry_paths: HashSet<PathBuf>,
+    /// Warn in the status bar when user autostart files take more bytes than this.
+    user_autostart_dir_size_limit: Option<u64>,
+    /// Warn in the status bar when there are more user autostart files than this.
+    autostart_entry_count_limit: Option<usize>,
+    view: ViewSettings,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            search_history: SearchHistory::default(),
+            sync_on_write: true,
+            auto_disable_missing_binary: false,
+            max_entries_per_source: None,
+            use_xdg_portal_for_file_chooser: Path::new("/.flatpak-info").exists(),
+            hide_empty_groups: true,
+            refresh_on_external_change: true,
+            notify_on_new_system_entry: false,
+            known_system_entry_paths: HashSet::new(),
+            user_autostart_dir_size_limit: None,
+            autostart_entry_count_limit: None,
+            view: ViewSettings::default(),
+        }
+    }
+}
+
+/// Mirrors `AppConfig::sync_on_write` for write paths that do not carry `AppState`.
+static SYNC_ON_WRITE: AtomicBool = AtomicBool::new(true);
+
+/// Most-recent-first list of search queries, capped at `max` items.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+struct SearchHistory {
+    queries: VecDeque<String>,
+    max: usize,
+}
+
+impl Default for SearchHistory {
+    fn default() -> Self {
+        Self {
+            queries: VecDeque::new(),
+            max: 10,
+        }
+    }
+}
+
+impl SearchHistory {
+    fn push(&mut self, query: &str) {
+        let query = query.trim();
+        if query.is_empty() || self.max == 0 {
+            return;
+        }
+        self.queries.retain(|q| q != query);
+        self.queries.push_front(query.to_string());
+        self.queries.truncate(self.max);
+        // Keep storage contiguous so `recent` can hand out a single slice.
+        self.queries.make_contiguous();
+    }
+
+    fn recent(&self) -> &[String] {
+        self.queries.as_slices().0
+    }
+
+    fn matches_prefix(&self, prefix: &str) -> Vec<&str> {
+        let prefix = prefix.to_lowercase();
+        self.queries
+            .iter()
+            .filter(|q| q.to_lowercase().starts_with(&prefix))
+            .map(|q| q.as_str())
+            .collect()
+    }
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(result) = run_cli(&args) {
+        print!("{}", result?);
+        return Ok(());
+    }
+
+    let app = Application::builder()
+        .application_id("com.example.universal-startup-manager")
+        .flags(gio::ApplicationFlags::HANDLES_OPEN)
+        .build();
+
+    app.connect_activate(|app| {
+        if let Err(err) = build_ui(app) {
+            eprintln!("Failed to build UI: {err:?}");
+        }
+    });
+
+    // Launched through the xdg-autostart:// handler: open the Add dialog prefilled.
+    app.connect_open(|app, files, _| {
+        let state = match build_ui(app) {
+            Ok(state) => state,
+            Err(err) => {
+                eprintln!("Failed to build UI: {err:?}");
+                return;
+            }
+        };
+        for file in files {
+            match from_xdg_autostart_url(&file.uri()) {
+                Ok(entry) => {
+                    let hash = entry.generate_desktop_file_hash_uri();
+                    if let Some(idx) = find_by_hash(&hash, &state.entries.borrow()) {
+                        let name = display_name_with_fallback(&state.entries.borrow()[idx], &current_locale());
+                        state
+                            .status_bar
+                            .set_text(&format!("Not importing: identical to the existing entry {name:?}"));
+                        continue;
+                    }
+                    if let Err(err) = show_add_dialog_with(&state, &entry.name, &entry.command) {
+                        state.status_bar.set_text(&format!("Add dialog failed: {err:#}"));
+                    }
+                }
+                Err(err) => state.status_bar.set_text(&format!("Invalid link: {err:#}")),
+            }
+        }
+    });
+
+    app.run();
+    Ok(())
+}
+
+fn build_ui(app: &Application) -> Result<AppState> {
+    let config = load_config().unwrap_or_else(|err| {
+        eprintln!("Failed to load config: {err:?}");
+        AppConfig::default()
+    });
+    SYNC_ON_WRITE.store(config.sync_on_write, Ordering::Relaxed);
+
+    let list_box = ListBox::new();
+    list_box.set_accessible_role(AccessibleRole::List);
+    list_box.set_selection_mode(SelectionMode::Single);
+    let list_box_scrolled = ScrolledWindow::builder()
+        .child(&list_box)
+        .min_content_width(320)
+        .vexpand(true)
+        .hexpand(true)
+        .build();
+    let alpha_strip = GtkBox::new(Orientation::Vertical, 0);
+    alpha_strip.update_property(&[gtk4::accessible::Property::Label("Jump to letter")]);
+
+    let detail_name = Label::new(Some("-"));
+    let detail_command = Label::new(Some("-"));
+    let detail_command_info = TextView::new();
+    detail_command_info.set_editable(false);
+    detail_command_info.set_cursor_visible(false);
+    detail_command_info.set_monospace(true);
+    detail_command_info.update_property(&[gtk4::accessible::Property::Label("Command details")]);
+    let detail_source = Label::new(Some("-"));
+    let detail_status = Label::new(Some("-"));
+    let detail_icon = Label::new(Some("-"));
+    let detail_terminal = Label::new(Some("-"));
+    let detail_comment = Label::new(None);
+    detail_comment.set_wrap(true);
+    detail_comment.set_xalign(0.0);
+    let detail_generic_name = Label::new(None);
+    let detail_delay = Label::new(None);
+    let detail_extra = gtk4::Grid::new();
+    detail_extra.set_row_spacing(4);
+    detail_extra.set_column_spacing(12);
+    let detail_extra_expander = gtk4::Expander::new(Some("Additional fields"));
+    detail_extra_expander.set_child(Some(&detail_extra));
+    detail_extra_expander.set_visible(false);
+    let detail_localized_names = gtk4::Grid::new();
+    detail_localized_names.set_row_spacing(4);
+    detail_localized_names.set_column_spacing(12);
+    let detail_localized_expander = gtk4::Expander::new(Some("Localized names"));
+    detail_localized_expander.set_child(Some(&detail_localized_names));
+    let detail_binary_warning = Image::from_icon_name("dialog-warning-symbolic");
+    detail_binary_warning.set_tooltip_text(Some("Binary not found in PATH"));
+    detail_binary_warning.update_property(&[gtk4::accessible::Property::Label("Binary not found in PATH")]);
+    detail_binary_warning.set_visible(false);
+    let detail_sections = Label::new(None);
+    detail_sections.set_wrap(true);
+    detail_sections.set_xalign(0.0);
+    detail_sections.set_visible(false);
+    let status_bar = Label::new(None);
+    status_bar.set_wrap(true);
+    let load_progress = gtk4::ProgressBar::new();
+    load_progress.set_text(Some("Loading entries…"));
+    load_progress.set_show_text(true);
+    let count_label = Label::new(None);
+    count_label.set_xalign(0.0);
+
+    let toggle_button = Button::with_label("Enable/Disable");
+    let delete_button = Button::with_label("Delete");
+    let edit_button = Button::with_label("Edit");
+    let lock_button = Button::with_label("Lock");
+    let schedule_button = Button::with_label("Schedule as Timer…");
+    let update_from_system_button = Button::with_label("Update from System");
+    let check_launch_button = Button::with_label("Check Launch");
+    let warnings_button = Button::new();
+    warnings_button.set_visible(false);
+    let sort_button = Button::with_label("Sort");
+    let batch_rename_button = Button::with_label("Batch Rename…");
+    let about_button = Button::with_label("About");
+    let preferences_button = Button::with_label("Preferences");
+    let filter_button = Button::with_label("Filter");
+    let wine_only_toggle = gtk4::ToggleButton::with_label("Wine Only");
+    let daemons_only_toggle = gtk4::ToggleButton::with_label("Daemons Only");
+    let running_only_toggle = gtk4::ToggleButton::with_label("Running Only");
+    toggle_button.set_sensitive(false);
+    delete_button.set_sensitive(false);
+    edit_button.set_sensitive(false);
+    lock_button.set_sensitive(false);
+    schedule_button.set_sensitive(false);
+    update_from_system_button.set_visible(false);
+    check_launch_button.set_sensitive(false);
+
+    let state = AppState {
+        entries: Rc::new(RefCell::new(Vec::new())),
+        visible_indices: Rc::new(RefCell::new(Vec::new())),
+        filter: Rc::new(RefCell::new(config.view.filter_state())),
+        sort: Rc::new(Cell::new(config.view.sort)),
+        view_mode: Rc::new(Cell::new(ViewMode::Flat)),
+        row_headers: Rc::new(RefCell::new(HashMap::new())),
+        pending_warnings: Rc::new(RefCell::new(Vec::new())),
+        running: Rc::new(RefCell::new(HashMap::new())),
+        system_updates: Rc::new(RefCell::new(Vec::new())),
+        autostart_dir_totals: Rc::new(RefCell::new(Vec::new())),
+        search_path: Rc::new(RefCell::new(Vec::new())),
+        selected: Rc::new(Cell::new(None)),
+        config: Rc::new(RefCell::new(config)),
+        list_box: list_box.clone(),
+        list_scroller: list_box_scrolled.clone(),
+        alpha_strip: alpha_strip.clone(),
+        detail_name,
+        detail_command,
+        detail_command_info,
+        detail_source,
+        detail_status,
+        detail_sections,
+        detail_icon,
+        detail_terminal,
+        detail_comment,
+        detail_generic_name,
+        detail_delay,
+        detail_extra,
+        detail_extra_expander,
+        detail_localized_names,
+        detail_binary_warning,
+        status_bar: status_bar.clone(),
+        count_label: count_label.clone(),
+        toggle_button: toggle_button.clone(),
+        delete_button: delete_button.clone(),
+        edit_button: edit_button.clone(),
+        lock_button: lock_button.clone(),
+        schedule_button: schedule_button.clone(),
+        update_from_system_button: update_from_system_button.clone(),
+        check_launch_button: check_launch_button.clone(),
+        warnings_button: warnings_button.clone(),
+        filter_button: filter_button.clone(),
+        wine_only_toggle: wine_only_toggle.clone(),
+        daemons_only_toggle: daemons_only_toggle.clone(),
+        running_only_toggle: running_only_toggle.clone(),
+        user_dir_monitor: Rc::new(RefCell::new(None)),
+        system_dir_monitors: Rc::new(RefCell::new(Vec::new())),
+        status_history: Rc::new(RefCell::new(VecDeque::new())),
+        undo_stack: Rc::new(RefCell::new(UndoStack::default())),
+        row_actions: gio::SimpleActionGroup::new(),
+    };
+    add_row_actions(&state);
+
+    status_bar.connect_label_notify({
+        let history = state.status_history.clone();
+        move |label| {
+            let text = label.text();
+            if text.is_empty() {
+                return;
+            }
+            let mut history = history.borrow_mut();
+            if history.len() == STATUS_HISTORY_LEN {
+                history.pop_front();
+            }
+            history.push_back(text.to_string());
+        }
+    });
+
+    {
+        let row_headers = state.row_headers.clone();
+        list_box.set_header_func(move |row, _before| {
+            let header = usize::try_from(row.index())
+                .ok()
+                .and_then(|pos| row_headers.borrow().get(&pos).cloned())
+                .map(|text| {
+                    let label = Label::new(Some(&text));
+                    label.set_xalign(0.0);
+                    label.add_css_class("heading");
+                    label
+                });
+            row.set_header(header.as_ref());
+        });
+    }
+
+    let refresh_button = Button::with_label("Refresh");
+    refresh_button.set_accessible_role(AccessibleRole::Button);
+    refresh_button.set_tooltip_text(Some("Refresh entries"));
+    let add_button = Button::with_label("Add");
+    add_button.set_accessible_role(AccessibleRole::Button);
+    add_button.set_tooltip_text(Some("Add autostart entry"));
+    filter_button.set_accessible_role(AccessibleRole::Button);
+    filter_button.set_tooltip_text(Some("Filter visible entries"));
+    about_button.set_accessible_role(AccessibleRole::Button);
+    about_button.set_tooltip_text(Some("About this app"));
+    preferences_button.set_accessible_role(AccessibleRole::Button);
+    preferences_button.set_tooltip_text(Some("Preferences and maintenance"));
+
+    wine_only_toggle.set_tooltip_text(Some("Show only entries that run under Wine"));
+    {
+        let state = state.clone();
+        wine_only_toggle.connect_toggled(move |toggle| {
+            state.filter.borrow_mut().wine_only = toggle.is_active();
+            rebuild_list(&state);
+        });
+    }
+    daemons_only_toggle.set_tooltip_text(Some("Show only entries that look like background daemons"));
+    {
+        let state = state.clone();
+        daemons_only_toggle.connect_toggled(move |toggle| {
+            state.filter.borrow_mut().daemons_only = toggle.is_active();
+            rebuild_list(&state);
+        });
+    }
+    running_only_toggle.set_tooltip_text(Some("Show only entries whose program is running now"));
+    {
+        let state = state.clone();
+        running_only_toggle.connect_toggled(move |toggle| {
+            state.filter.borrow_mut().running_only = toggle.is_active();
+            rebuild_list(&state);
+        });
+    }
+
+
+    {
+        let state = state.clone();
+        refresh_button.connect_clicked(move |_| {
+            if let Err(err) = refresh_entries(&state) {
+                state.status_bar.set_text(&format!("Refresh failed: {err:#}"));
+            }
+        });
+    }
+
+    {
+        let state = state.clone();
+        add_button.connect_clicked(move |_| {
+            if let Err(err) = show_add_dialog(&state) {
+                state.status_bar.set_text(&format!("Add failed: {err:#}"));
+            }
+        });
+    }
+
+    {
+        let state = state.clone();
+        state.list_box.clone().connect_row_selected(move |_, row| {
+            let idx = row
+                .and_then(|r| usize::try_from(r.index()).ok())
+                .and_then(|visible_idx| state.visible_indices.borrow().get(visible_idx).copied());
+            state.selected.replace(idx);
+            update_detail(&state);
+        });
+    }
+
+    {
+        let state = state.clone();
+        let double_click = gtk4::GestureClick::new();
+        double_click.connect_pressed(move |_, n_press, _, y| {
+            if n_press != 2 {
+                return;
+            }
+            let Some(row) = state.list_box.row_at_y(y as i32) else {
+                return;
+            };
+            let idx = usize::try_from(row.index())
+                .ok()
+                .and_then(|visible_idx| state.visible_indices.borrow().get(visible_idx).copied());
+            if let Some(idx) = idx {
+                open_entry_editor(&state, idx);
+            }
+        });
+        state.list_box.add_controller(double_click);
+    }
+
+    // One search input: filters as you type (debounced) and keeps queries confirmed with
+    // Enter in the search history it suggests from.
+    let search_entry = Entry::new();
+    search_entry.set_placeholder_text(Some("Filter by name or command"));
+    search_entry.set_accessible_role(AccessibleRole::SearchBox);
+    search_entry.set_tooltip_text(Some("Press Enter to keep a search; recent searches are suggested"));
+    search_entry.set_hexpand(true);
+    let completion_store = gtk4::ListStore::new(&[glib::Type::STRING]);
+    let completion = EntryCompletion::new();
+    completion.set_model(Some(&completion_store));
+    completion.set_text_column(0);
+    completion.set_minimum_key_length(0);
+    // The store is already narrowed by `SearchHistory::matches_prefix`.
+    completion.set_match_func(|_, _, _| true);
+    search_entry.set_completion(Some(&completion));
+    let live_search_bar = gtk4::SearchBar::new();
+    live_search_bar.set_child(Some(&search_entry));
+    live_search_bar.connect_entry(&search_entry);
+    live_search_bar.set_show_close_button(true);
+    let search_toggle = gtk4::ToggleButton::new();
+    search_toggle.set_icon_name("system-search-symbolic");
+    search_toggle.set_tooltip_text(Some("Search (Ctrl+F)"));
+    search_toggle.update_property(&[gtk4::accessible::Property::Label("Search")]);
+    search_toggle
+        .bind_property("active", &live_search_bar, "search-mode-enabled")
+        .bidirectional()
+        .build();
+
+    {
+        let state = state.clone();
+        let completion_store = completion_store.clone();
+        // Each change bumps the generation; only the newest timeout rebuilds the list.
+        let generation = Rc::new(Cell::new(0u64));
+        search_entry.connect_changed(move |entry| {
+            let text = entry.text().to_string();
+            refresh_search_completion(&completion_store, &state.config.borrow().search_history, &text);
+            let current = generation.get().wrapping_add(1);
+            generation.set(current);
+            let state = state.clone();
+            let generation = generation.clone();
+            glib::timeout_add_local_once(std::time::Duration::from_millis(SEARCH_DEBOUNCE_MS), move || {
+                if generation.get() != current || state.filter.borrow().search_text == text {
+                    return;
+                }
+                state.filter.borrow_mut().search_text = text;
+                rebuild_list(&state);
+                update_detail(&state);
+            });
+        });
+    }
+
+    {
+        let state = state.clone();
+        search_entry.connect_activate(move |entry| {
+            // Apply now rather than after the debounce; the pending timeout then sees no change.
+            let text = entry.text().to_string();
+            if state.filter.borrow().search_text != text {
+                state.filter.borrow_mut().search_text = text.clone();
+                rebuild_list(&state);
+                update_detail(&state);
+            }
+            let query = text.trim().to_string();
+            if query.is_empty() {
+                return;
+            }
+            let mut config = state.config.borrow_mut();
+            config.search_history.push(&query);
+            refresh_search_completion(&completion_store, &config.search_history, "");
+            if let Err(err) = save_config(&config) {
+                state
+                    .status_bar
+                    .set_text(&format!("Saving search history failed: {err:#}"));
+            }
+        });
+    }
+
+    {
+        let state = state.clone();
+        let search_entry = search_entry.clone();
+        live_search_bar.connect_search_mode_enabled_notify(move |bar| {
+            if bar.is_search_mode() {
+                return;
+            }
+            search_entry.set_text("");
+            if !state.filter.borrow().search_text.is_empty() {
+                state.filter.borrow_mut().search_text.clear();
+                rebuild_list(&state);
+                update_detail(&state);
+            }
+        });
+    }
+
+    {
+        let state = state.clone();
+        filter_button.connect_clicked(move |_| {
+            if let Err(err) = show_filter_dialog(&state) {
+                state
+                    .status_bar
+                    .set_text(&format!("Filter dialog failed: {err:#}"));
+            }
+        });
+    }
+
+    {
+        let state = state.clone();
+        sort_button.connect_clicked(move |_| {
+            if let Err(err) = show_sort_dialog(&state) {
+                state
+                    .status_bar
+                    .set_text(&format!("Sort dialog failed: {err:#}"));
+            }
+        });
+    }
+
+    {
+        let state = state.clone();
+        batch_rename_button.connect_clicked(move |_| {
+            if let Err(err) = show_batch_rename_dialog(&state) {
+                state
+                    .status_bar
+                    .set_text(&format!("Batch rename dialog failed: {err:#}"));
+            }
+        });
+    }
+
+    {
+        let state = state.clone();
+        about_button.connect_clicked(move |_| {
+            if let Err(err) = show_about_dialog(&state) {
+                state
+                    .status_bar
+                    .set_text(&format!("About dialog failed: {err:#}"));
+            }
+        });
+    }
+
+    let menu = gio::Menu::new();
+    menu.append(Some("Import File…"), Some("app.import-file"));
+    menu.append(Some("Export JSON…"), Some("app.export-json"));
+    menu.append(Some("Import JSON…"), Some("app.import-json"));
+    menu.append(Some("Export as SVG…"), Some("app.export-svg"));
+    menu.append(Some("Check Entries…"), Some("app.check-entries"));
+    menu.append(Some("Check All Entries for Missing Programs…"), Some("app.check-binaries"));
+    menu.append(Some("Preview Boot Order…"), Some("app.preview-boot-order"));
+    menu.append(Some("Normalize User Entries…"), Some("app.normalize"));
+    menu.append(Some("Comment Out or Restore Selected Entry"), Some("app.toggle-commented-out"));
+    menu.append(Some("Enable All Visible"), Some("app.enable-all-visible"));
+    menu.append(Some("Disable All Visible"), Some("app.disable-all-visible"));
+    menu.append(Some("Group Wine Entries First"), Some("app.group-wine-first"));
+    menu.append(Some("Group by Install Location"), Some("app.group-by-location"));
+    menu.append(Some("Group by Source"), Some("app.group-by-source"));
+    menu.append(Some("Reset Filter, Sort and Window Size"), Some("app.reset-preferences"));
+    let copy_as_section = gio::Menu::new();
+    for format in ExportFormat::ALL {
+        copy_as_section.append(Some(format.label()), Some(&format!("app.copy-as::{}", format.id())));
+    }
+    menu.append_section(Some("Copy Selected Entry As"), &copy_as_section);
+    let destructive_menu = gio::Menu::new();
+    destructive_menu.append(Some("Delete All User Entries…"), Some("app.clear-user-entries"));
+    menu.append_submenu(Some("Destructive Actions"), &destructive_menu);
+    let menu_button = MenuButton::new();
+    menu_button.set_icon_name("open-menu-symbolic");
+    menu_button.set_menu_model(Some(&menu));
+    menu_button.set_tooltip_text(Some("Main menu"));
+    menu_button.update_property(&[gtk4::accessible::Property::Label("Main menu")]);
+
+    let export_svg_action = gio::SimpleAction::new("export-svg", None);
+    {
+        let state = state.clone();
+        export_svg_action.connect_activate(move |_, _| {
+            if let Err(err) = show_export_svg_dialog(&state) {
+                state
+                    .status_bar
+                    .set_text(&format!("SVG export failed: {err:#}"));
+            }
+        });
+    }
+    app.add_action(&export_svg_action);
+
+    let import_file_action = gio::SimpleAction::new("import-file", None);
+    {
+        let state = state.clone();
+        import_file_action.connect_activate(move |_, _| {
+            if let Err(err) = show_import_file_dialog(&state) {
+                state.status_bar.set_text(&format!("Import failed: {err:#}"));
+            }
+        });
+    }
+    app.add_action(&import_file_action);
+
+    let export_json_action = gio::SimpleAction::new("export-json", None);
+    {
+        let state = state.clone();
+        export_json_action.connect_activate(move |_, _| {
+            if let Err(err) = show_export_json_dialog(&state) {
+                state.status_bar.set_text(&format!("JSON export failed: {err:#}"));
+            }
+        });
+    }
+    app.add_action(&export_json_action);
+
+    let import_json_action = gio::SimpleAction::new("import-json", None);
+    {
+        let state = state.clone();
+        import_json_action.connect_activate(move |_, _| {
+            if let Err(err) = show_import_json_dialog(&state) {
+                state.status_bar.set_text(&format!("JSON import failed: {err:#}"));
+            }
+        });
+    }
+    app.add_action(&import_json_action);
+
+    let check_binaries_action = gio::SimpleAction::new("check-binaries", None);
+    {
+        let state = state.clone();
+        check_binaries_action.connect_activate(move |_, _| show_missing_binaries_dialog(&state));
+    }
+    app.add_action(&check_binaries_action);
+
+    let check_entries_action = gio::SimpleAction::new("check-entries", None);
+    {
+        let state = state.clone();
+        check_entries_action.connect_activate(move |_, _| {
+            if let Err(err) = show_lint_dialog(&state) {
+                state
+                    .status_bar
+                    .set_text(&format!("Check failed: {err:#}"));
+            }
+        });
+    }
+    app.add_action(&check_entries_action);
+
+    let normalize_action = gio::SimpleAction::new("normalize", None);
+    {
+        let state = state.clone();
+        normalize_action.connect_activate(move |_, _| {
+            if let Err(err) = show_normalize_dialog(&state) {
+                state
+                    .status_bar
+                    .set_text(&format!("Normalize dialog failed: {err:#}"));
+            }
+        });
+    }
+    app.add_action(&normalize_action);
+
+    for (name, enabled) in [("enable-all-visible", true), ("disable-all-visible", false)] {
+        let action = gio::SimpleAction::new(name, None);
+        let state = state.clone();
+        action.connect_activate(move |_, _| {
+            if let Err(err) = toggle_all_visible(&state, Some(enabled)) {
+                state.status_bar.set_text(&format!("Toggle failed: {err:#}"));
+            }
+        });
+        app.add_action(&action);
+    }
+
+    let group_wine_action = gio::SimpleAction::new("group-wine-first", None);
+    {
+        let state = state.clone();
+        group_wine_action.connect_activate(move |_, _| group_wine_entries_first(&state));
+    }
+    app.add_action(&group_wine_action);
+
+    let boot_order_action = gio::SimpleAction::new("preview-boot-order", None);
+    {
+        let state = state.clone();
+        boot_order_action.connect_activate(move |_, _| {
+            if let Err(err) = show_autostart_sequence_dialog(&state) {
+                state.status_bar.set_text(&format!("Preview failed: {err:#}"));
+            }
+        });
+    }
+    app.add_action(&boot_order_action);
+
+    let commented_out_action = gio::SimpleAction::new("toggle-commented-out", None);
+    {
+        let state = state.clone();
+        commented_out_action.connect_activate(move |_, _| {
+            if let Err(err) = toggle_selected_commented_out(&state) {
+                state.status_bar.set_text(&format!("Comment out failed: {err:#}"));
+            }
+        });
+    }
+    app.add_action(&commented_out_action);
+
+    let clear_user_entries_action = gio::SimpleAction::new("clear-user-entries", None);
+    {
+        let state = state.clone();
+        clear_user_entries_action.connect_activate(move |_, _| {
+            if let Err(err) = show_clear_user_entries_dialog(&state) {
+                state.status_bar.set_text(&format!("Delete all failed: {err:#}"));
+            }
+        });
+    }
+    app.add_action(&clear_user_entries_action);
+
+    let toggle_search_action = gio::SimpleAction::new("toggle-search", None);
+    {
+        let live_search_bar = live_search_bar.clone();
+        toggle_search_action.connect_activate(move |_, _| {
+            live_search_bar.set_search_mode(!live_search_bar.is_search_mode());
+        });
+    }
+    app.add_action(&toggle_search_action);
+    app.set_accels_for_action("app.toggle-search", &["<Control>f"]);
+
+    let undo_action = gio::SimpleAction::new("undo", None);
+    {
+        let state = state.clone();
+        undo_action.connect_activate(move |_, _| {
+            if let Err(err) = undo(&state) {
+                state.status_bar.set_text(&format!("Undo failed: {err:#}"));
+            }
+        });
+    }
+    app.add_action(&undo_action);
+    app.set_accels_for_action("app.undo", &["<Control>z"]);
+
+    let redo_action = gio::SimpleAction::new("redo", None);
+    {
+        let state = state.clone();
+        redo_action.connect_activate(move |_, _| {
+            if let Err(err) = redo(&state) {
+                state.status_bar.set_text(&format!("Redo failed: {err:#}"));
+            }
+        });
+    }
+    app.add_action(&redo_action);
+    app.set_accels_for_action("app.redo", &["<Control><Shift>z"]);
+
+    // Keyboard access to header and detail panel buttons; each acts like a click, and does
+    // nothing while its button is insensitive.
+    for (name, accel, button) in [
+        ("add", "<Control>n", &add_button),
+        ("edit", "<Control>e", &edit_button),
+        ("refresh", "<Control>r", &refresh_button),
+    ] {
+        let action = gio::SimpleAction::new(name, None);
+        let button = button.clone();
+        action.connect_activate(move |_, _| {
+            if button.is_sensitive() {
+                button.emit_clicked();
+            }
+        });
+        app.add_action(&action);
+        app.set_accels_for_action(&format!("app.{name}"), &[accel]);
+    }
+    // Space and Delete without modifiers would swallow typing in text fields as app-wide
+    // accels, so they only apply while the list has focus.
+    let list_keys = gtk4::ShortcutController::new();
+    list_keys.set_propagation_phase(gtk4::PropagationPhase::Capture);
+    for (trigger, button) in [("space", &toggle_button), ("Delete", &delete_button)] {
+        let button = button.clone();
+        list_keys.add_shortcut(gtk4::Shortcut::new(
+            gtk4::ShortcutTrigger::parse_string(trigger),
+            Some(gtk4::CallbackAction::new(move |_, _| {
+                if button.is_sensitive() {
+                    button.emit_clicked();
+                }
+                glib::Propagation::Stop
+            })),
+        ));
+    }
+    list_box.add_controller(list_keys);
+
+    let shortcuts_action = gio::SimpleAction::new("shortcuts", None);
+    {
+        let list_box = list_box.clone();
+        shortcuts_action.connect_activate(move |_, _| {
+            let parent = list_box.root().and_then(|w| w.downcast::<ApplicationWindow>().ok());
+            show_shortcuts_window(parent.as_ref());
+        });
+    }
+    app.add_action(&shortcuts_action);
+    app.set_accels_for_action("app.shortcuts", &["<Control>question"]);
+
+    let group_by_location_action =
+        gio::SimpleAction::new_stateful("group-by-location", None, &false.to_variant());
+    let group_by_source_action =
+        gio::SimpleAction::new_stateful("group-by-source", None, &false.to_variant());
+    // The two groupings are exclusive: turning one on turns the other off.
+    for (action, other, mode) in [
+        (&group_by_location_action, &group_by_source_action, ViewMode::ByBinaryDir),
+        (&group_by_source_action, &group_by_location_action, ViewMode::BySource),
+    ] {
+        let state = state.clone();
+        let other = other.clone();
+        action.connect_activate(move |action, _| {
+            let grouped = !action.state().and_then(|v| v.get::<bool>()).unwrap_or(false);
+            action.set_state(&grouped.to_variant());
+            if grouped {
+                other.set_state(&false.to_variant());
+            }
+            state.view_mode.set(if grouped { mode } else { ViewMode::Flat });
+            rebuild_list(&state);
+        });
+    }
+    app.add_action(&group_by_location_action);
+    app.add_action(&group_by_source_action);
+
+    let copy_as_action = gio::SimpleAction::new("copy-as", Some(glib::VariantTy::STRING));
+    {
+        let state = state.clone();
+        copy_as_action.connect_activate(move |_, param| {
+            let format = param
+                .and_then(|p| p.get::<String>())
+                .and_then(|id| ExportFormat::from_id(&id));
+            let res = format
+                .context("Unknown export format")
+                .and_then(|format| copy_selected_as(&state, format));
+            if let Err(err) = res {
+                state.status_bar.set_text(&format!("Copy failed: {err:#}"));
+            }
+        });
+    }
+    app.add_action(&copy_as_action);
+
+    {
+        let state = state.clone();
+        preferences_button.connect_clicked(move |_| {
+            if let Err(err) = show_preferences_dialog(&state) {
+                state
+                    .status_bar
+                    .set_text(&format!("Preferences dialog failed: {err:#}"));
+            }
+        });
+    }
+
+    {
+        let state = state.clone();
+        toggle_button.connect_clicked(move |_| {
+            if let Err(err) = toggle_selected(&state) {
+                state.status_bar.set_text(&format!("Toggle failed: {err:#}"));
+            }
+        });
+    }
+
+    {
+        let state = state.clone();
+        delete_button.connect_clicked(move |_| {
+            if let Err(err) = delete_selected(&state) {
+                state.status_bar.set_text(&format!("Delete failed: {err:#}"));
+            }
+        });
+    }
+
+    {
+        let state = state.clone();
+        edit_button.connect_clicked(move |_| {
+            if let Err(err) = show_edit_dialog(&state) {
+                state.status_bar.set_text(&format!("Edit failed: {err:#}"));
+            }
+        });
+    }
+
+    {
+        let state = state.clone();
+        schedule_button.connect_clicked(move |_| {
+            if let Err(err) = show_schedule_timer_dialog(&state) {
+                state.status_bar.set_text(&format!("Schedule failed: {err:#}"));
+            }
+        });
+    }
+
+    {
+        let state = state.clone();
+        warnings_button.connect_clicked(move |_| {
+            if let Err(err) = show_lint_dialog(&state) {
+                state.status_bar.set_text(&format!("Check failed: {err:#}"));
+            }
+        });
+    }
+
+    {
+        let state = state.clone();
+        check_launch_button.connect_clicked(move |_| {
+            let result = state
+                .selected
+                .get()
+                .and_then(|idx| state.entries.borrow().get(idx).map(|e| e.try_launch_dry_run()));
+            let Some(result) = result else {
+                state.status_bar.set_text("No item selected");
+                return;
+            };
+            if result.is_ok() && result.warnings.is_empty() {
+                state.status_bar.set_text("Launch check passed");
+            } else {
+                state.status_bar.set_text(&format!(
+                    "Launch check {}: {}",
+                    if result.is_ok() { "passed with warnings" } else { "failed" },
+                    result.warnings.join("; ")
+                ));
+            }
+        });
+    }
+
+    {
+        let state = state.clone();
+        update_from_system_button.connect_clicked(move |_| {
+            let res = state
+                .selected
+                .get()
+                .context("No item selected")
+                .and_then(|idx| update_from_system(idx, &state));
+            if let Err(err) = res {
+                state.status_bar.set_text(&format!("Update failed: {err:#}"));
+            }
+        });
+    }
+
+    {
+        let state = state.clone();
+        lock_button.connect_clicked(move |_| {
+            let res = state.selected.get().context("No item selected").and_then(|idx| {
+                let locked = state.entries.borrow().get(idx).map(|e| e.locked).unwrap_or(false);
+                if locked {
+                    unlock_entry(idx, &state)
+                } else {
+                    lock_entry(idx, &state)
+                }
+            });
+            if let Err(err) = res {
+                state.status_bar.set_text(&format!("Lock failed: {err:#}"));
+            }
+        });
+    }
+
+    let header = HeaderBar::builder()
+        .title_widget(&Label::new(Some("Universal Startup Manager")))
+        .show_title_buttons(true)
+        .build();
+    header.pack_start(&refresh_button);
+    header.pack_start(&filter_button);
+    header.pack_start(&sort_button);
+    header.pack_start(&batch_rename_button);
+    header.pack_start(&search_toggle);
+    header.pack_start(&wine_only_toggle);
+    header.pack_start(&daemons_only_toggle);
+    header.pack_start(&running_only_toggle);
+    header.pack_end(&menu_button);
+    warnings_button.set_accessible_role(AccessibleRole::Button);
+    warnings_button.add_css_class("warning");
+    warnings_button.set_tooltip_text(Some("Open the entry check results"));
+    header.pack_end(&warnings_button);
+    header.pack_end(&add_button);
+    header.pack_end(&about_button);
+    header.pack_end(&preferences_button);
+    let shortcuts_button = Button::with_label("?");
+    shortcuts_button.set_action_name(Some("app.shortcuts"));
+    shortcuts_button.set_tooltip_text(Some("Keyboard shortcuts (Ctrl+?)"));
+    shortcuts_button.update_property(&[gtk4::accessible::Property::Label("Keyboard shortcuts")]);
+    header.pack_end(&shortcuts_button);
+
+    let list_row = GtkBox::new(Orientation::Horizontal, 2);
+    list_row.append(&list_box_scrolled);
+    list_row.append(&alpha_strip);
+    let list_column = GtkBox::new(Orientation::Vertical, 4);
+    list_column.append(&load_progress);
+    list_column.append(&live_search_bar);
+    list_column.append(&list_row);
+    list_column.append(&count_label);
+
+    let detail_box = GtkBox::new(Orientation::Vertical, 6);
+    detail_box.append(&label_row("Name:", &state.detail_name));
+    let comment_row = label_row("Description:", &state.detail_comment);
+    comment_row.set_visible(false);
+    detail_box.append(&comment_row);
+    let generic_name_row = label_row("Generic name:", &state.detail_generic_name);
+    generic_name_row.set_visible(false);
+    detail_box.append(&generic_name_row);
+    detail_box.append(&label_row("Icon:", &state.detail_icon));
+    let command_row = label_row("Command:", &state.detail_command);
+    command_row.append(&state.detail_binary_warning);
+    detail_box.append(&command_row);
+    detail_box.append(&state.detail_command_info);
+    detail_box.append(&label_row("Source:", &state.detail_source));
+    detail_box.append(&label_row("Status:", &state.detail_status));
+    detail_box.append(&state.detail_extra_expander);
+    detail_box.append(&detail_localized_expander);
+    detail_box.append(&label_row("Runs in terminal:", &state.detail_terminal));
+    let delay_row = label_row("Delay:", &state.detail_delay);
+    delay_row.set_visible(false);
+    detail_box.append(&delay_row);
+    detail_box.append(&state.detail_sections);
+
+    let action_row = GtkBox::new(Orientation::Horizontal, 6);
+    toggle_button.set_accessible_role(AccessibleRole::Button);
+    toggle_button.set_tooltip_text(Some("Toggle enabled state"));
+    delete_button.set_accessible_role(AccessibleRole::Button);
+    delete_button.set_tooltip_text(Some("Delete entry"));
+    edit_button.set_accessible_role(AccessibleRole::Button);
+    edit_button.set_tooltip_text(Some("Edit entry"));
+    lock_button.set_accessible_role(AccessibleRole::Button);
+    lock_button.set_tooltip_text(Some("Lock or unlock entry against changes"));
+    action_row.append(&toggle_button);
+    action_row.append(&edit_button);
+    action_row.append(&delete_button);
+    action_row.append(&lock_button);
+    check_launch_button.set_accessible_role(AccessibleRole::Button);
+    check_launch_button.set_tooltip_text(Some("Check that the command could start, without running it"));
+    action_row.append(&check_launch_button);
+    schedule_button.set_accessible_role(AccessibleRole::Button);
+    schedule_button.set_tooltip_text(Some("Run this command on a schedule with a systemd user timer"));
+
+    detail_box.append(&action_row);
+    detail_box.append(&schedule_button);
+    update_from_system_button.set_accessible_role(AccessibleRole::Button);
+    update_from_system_button.set_tooltip_text(Some("Replace this override with the newer system version"));
+    detail_box.append(&update_from_system_button);
+    detail_box.append(&Label::new(Some("Status messages:")));
+    detail_box.append(&status_bar);
+
+    let content = GtkBox::new(Orientation::Horizontal, 12);
+    content.append(&list_column);
+    content.append(&detail_box);
+
+    let root = GtkBox::new(Orientation::Vertical, 8);
+    root.append(&header);
+    root.append(&content);
+
+    let window = ApplicationWindow::builder()
+        .application(app)
+        .title("Universal Startup Manager")
+        .default_width(state.config.borrow().view.window_width)
+        .default_height(state.config.borrow().view.window_height)
+        .child(&root)
+        .build();
+
+    {
+        let state = state.clone();
+        // Saved once on close rather than on every resize step; a maximized window keeps the
+        // size it will return to.
+        window.connect_close_request(move |window| {
+            if !window.is_maximized() {
+                let (width, height) = window.default_size();
+                let mut config = state.config.borrow_mut();
+                config.view.window_width = width;
+                config.view.window_height = height;
+                if let Err(err) = save_config(&config) {
+                    eprintln!("Saving the window size failed: {err:#}");
+                }
+            }
+            glib::Propagation::Proceed
+        });
+    }
+
+    let reset_view_action = gio::SimpleAction::new("reset-preferences", None);
+    {
+        let state = state.clone();
+        let window = window.clone();
+        reset_view_action.connect_activate(move |_, _| {
+            let defaults = ViewSettings::default();
+            let search_text = std::mem::take(&mut state.filter.borrow_mut().search_text);
+            state.filter.replace(FilterState {
+                search_text,
+                ..defaults.filter_state()
+            });
+            state.sort.set(defaults.sort);
+            // The quick-filter toggles rebuild the list themselves when they change.
+            state.wine_only_toggle.set_active(false);
+            state.daemons_only_toggle.set_active(false);
+            state.running_only_toggle.set_active(false);
+            window.set_default_size(defaults.window_width, defaults.window_height);
+            state.config.borrow_mut().view = defaults;
+            rebuild_list(&state);
+            update_detail(&state);
+            state.status_bar.set_text("Filter, sort and window size reset");
+            if let Err(err) = save_config(&state.config.borrow()) {
+                state
+                    .status_bar
+                    .set_text(&format!("Saving preferences failed: {err:#}"));
+            }
+        });
+    }
+    app.add_action(&reset_view_action);
+
+    window.present();
+
+    // Load after presenting so the window is not blank; pump the main loop between files
+    // so the progress bar repaints. Until the entries and directory watchers are in place the
+    // window ignores input and the app's actions are off, so nothing runs re-entrantly.
+    window.set_sensitive(false);
+    let paused_actions: Vec<gio::SimpleAction> = app
+        .list_actions()
+        .iter()
+        .filter_map(|name| app.lookup_action(name)?.downcast::<gio::SimpleAction>().ok())
+        .filter(|action| action.is_enabled())
+        .collect();
+    for action in &paused_actions {
+        action.set_enabled(false);
+    }
+    let limit = state.config.borrow().max_entries_per_source;
+    let (entries, dir_totals) = load_entries_with_progress(limit, |fraction| {
+        load_progress.set_fraction(f64::from(fraction));
+        while glib::MainContext::default().iteration(false) {}
+    })
+    .unwrap_or_else(|err| {
+        eprintln!("Failed to load entries: {err:?}");
+        (Vec::new(), Vec::new())
+    });
+    load_progress.set_visible(false);
+    state.entries.replace(entries);
+    state.autostart_dir_totals.replace(dir_totals);
+    update_pending_warnings(&state);
+    update_running_entries(&state);
+    update_system_updates(&state);
+    update_session_search_path(&state);
+    notify_new_system_entries(&state);
+    rebuild_list(&state);
+    watch_system_autostart_dirs(&state);
+    watch_user_autostart_dir(&state);
+    for action in &paused_actions {
+        action.set_enabled(true);
+    }
+    window.set_sensitive(true);
+    state.status_bar.set_text(&with_dir_limit_warning(
+        &state,
+        with_truncation_notice(entries_summary(&state.entries.borrow()), limit, &state.autostart_dir_totals.borrow()),
+    ));
+    Ok(state)
+}
+
+/// Shows `text` in `value`, hiding its whole `label_row` when there is nothing to show.
+fn set_optional_detail_row(value: &Label, text: Option<&str>) {
+    value.set_text(text.unwrap_or_default());
+    if let Some(row) = value.parent() {
+        row.set_visible(text.is_some());
+    }
+}
+
+/// Fills the "Additional fields" grid with one row per key: the key in bold, the value
+/// selectable so it can be copied.
+fn fill_detail_extra(state: &AppState, extra: &[(String, String)]) {
+    while let Some(child) = state.detail_extra.first_child() {
+        state.detail_extra.remove(&child);
+    }
+    for (row, (key, value)) in extra.iter().enumerate() {
+        let key_label = Label::new(None);
+        key_label.set_markup(&format!("<b>{}</b>", glib::markup_escape_text(key)));
+        key_label.set_xalign(0.0);
+        key_label.set_yalign(0.0);
+        let value_label = Label::new(Some(value));
+        value_label.set_selectable(true);
+        value_label.set_wrap(true);
+        value_label.set_xalign(0.0);
+        key_label.set_mnemonic_widget(Some(&value_label));
+        state.detail_extra.attach(&key_label, 0, row as i32, 1, 1);
+        state.detail_extra.attach(&value_label, 1, row as i32, 1, 1);
+    }
+    state.detail_extra_expander.set_visible(!extra.is_empty());
+}
+
+/// Fills the "Localized names" grid with one row per `Name[xx]=`: the locale, the name, and a
+/// button copying the name to the clipboard. An entry without any shows "None".
+fn fill_detail_localized_names(state: &AppState, names: &[(String, String)]) {
+    let grid = &state.detail_localized_names;
+    while let Some(child) = grid.first_child() {
+        grid.remove(&child);
+    }
+    if names.is_empty() {
+        let none = Label::new(Some("None"));
+        none.set_xalign(0.0);
+        none.add_css_class("dim-label");
+        grid.attach(&none, 0, 0, 1, 1);
+        return;
+    }
+    for (row, (locale, name)) in names.iter().enumerate() {
+        let locale_label = Label::new(Some(locale));
+        locale_label.set_xalign(0.0);
+        let name_label = Label::new(Some(name));
+        name_label.set_selectable(true);
+        name_label.set_wrap(true);
+        name_label.set_xalign(0.0);
+        name_label.set_hexpand(true);
+        locale_label.set_mnemonic_widget(Some(&name_label));
+        let copy_button = Button::with_label("Copy");
+        copy_button.set_tooltip_text(Some(&format!("Copy Name[{locale}] to the clipboard")));
+        copy_button.update_property(&[gtk4::accessible::Property::Label(&format!("Copy Name[{locale}]"))]);
+        copy_button.connect_clicked({
+            let state = state.clone();
+            let (locale, name) = (locale.clone(), name.clone());
+            move |button| {
+                button.clipboard().set_text(&name);
+                state.status_bar.set_text(&format!("Copied Name[{locale}]"));
+            }
+        });
+        grid.attach(&locale_label, 0, row as i32, 1, 1);
+        grid.attach(&name_label, 1, row as i32, 1, 1);
+        grid.attach(&copy_button, 2, row as i32, 1, 1);
+    }
+}
+
+fn label_row(label: &str, value: &Label) -> GtkBox {
+    let row = GtkBox::new(Orientation::Horizontal, 6);
+    let lab = Label::new(Some(label));
+    lab.set_mnemonic_widget(Some(value));
+    row.append(&lab);
+    row.append(value);
+    row
+}
+
+fn apply_filter(entries: &[StartupEntry], filter: &FilterState) -> Vec<usize> {
+    let running = if filter.running_only { running_entries(entries) } else { HashMap::new() };
+    apply_filter_with_running(entries, filter, &running)
+}
+
+/// `apply_filter` with `running` (entry index → pid) already looked up, as `AppState`
+/// keeps it between refreshes.
+fn apply_filter_with_running(entries: &[StartupEntry], filter: &FilterState, running: &HashMap<usize, u32>) -> Vec<usize> {
+    let search = filter.search_text.to_lowercase();
+    let locale = current_locale();
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(idx, entry)| {
+            let state_ok = (filter.show_enabled && entry.enabled)
+                || (filter.show_disabled && !entry.enabled)
+                || (!filter.show_enabled && !filter.show_disabled);
+            let source_ok = (filter.show_user && is_user_side_source(&entry.source))
+                || (filter.show_system && is_system_side_source(&entry.source))
+                || (!filter.show_user && !filter.show_system);
+            let wine_ok = !filter.wine_only || is_wine_entry(entry);
+            let daemon_ok = !filter.daemons_only || entry.is_daemon();
+            let running_ok = !filter.running_only || running.contains_key(idx);
+            let search_ok = search.is_empty()
+                || display_name_with_fallback(entry, &locale).to_lowercase().contains(&search)
+                || entry.name.to_lowercase().contains(&search)
+                || entry.command.to_lowercase().contains(&search);
+            state_ok && source_ok && wine_ok && daemon_ok && running_ok && search_ok
+        })
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+fn sort_indices(entries: &[StartupEntry], indices: Vec<usize>, sort: SortKey) -> Vec<usize> {
+    let running = if sort == SortKey::RunningFirst { running_entries(entries) } else { HashMap::new() };
+    sort_indices_with_running(entries, indices, sort, &running)
+}
+
+/// `sort_indices` with `running` already looked up; see `apply_filter_with_running`.
+fn sort_indices_with_running(
+    entries: &[StartupEntry],
+    mut indices: Vec<usize>,
+    sort: SortKey,
+    running: &HashMap<usize, u32>,
+) -> Vec<usize> {
+    // Sorted by the name the list shows, not the raw `Name=`.
+    let locale = current_locale();
+    let names: Vec<String> = entries
+        .iter()
+        .map(|e| display_name_with_fallback(e, &locale).to_lowercase())
+        .collect();
+    indices.sort_by(|&a, &b| {
+        let ea = &entries[a];
+        let eb = &entries[b];
+        match sort {
+            SortKey::NameAsc | SortKey::Alternating => {
+                names[a].cmp(&names[b])
+            }
+            SortKey::NameDesc => names[b].cmp(&names[a]),
+            SortKey::StatusEnabledFirst => {
+                eb.enabled.cmp(&ea.enabled).then_with(|| names[a].cmp(&names[b]))
+            }
+            SortKey::SourceUserFirst => {
+                let sa = matches!(ea.source, StartupSource::UserAutostart);
+                let sb = matches!(eb.source, StartupSource::UserAutostart);
+                sb.cmp(&sa).then_with(|| names[a].cmp(&names[b]))
+            }
+            SortKey::SourceSystemFirst => {
+                let sa = matches!(ea.source, StartupSource::SystemAutostart);
+                let sb = matches!(eb.source, StartupSource::SystemAutostart);
+                sb.cmp(&sa).then_with(|| names[a].cmp(&names[b]))
+            }
+            SortKey::WineFirst => is_wine_entry(eb)
+                .cmp(&is_wine_entry(ea))
+                .then_with(|| names[a].cmp(&names[b])),
+            SortKey::DaemonsFirst => eb
+                .is_daemon()
+                .cmp(&ea.is_daemon())
+                .then_with(|| names[a].cmp(&names[b])),
+            SortKey::RunningFirst => running
+                .contains_key(&b)
+                .cmp(&running.contains_key(&a))
+                .then_with(|| names[a].cmp(&names[b])),
+        }
+    });
+    if sort == SortKey::Alternating {
+        let (enabled, disabled): (Vec<usize>, Vec<usize>) =
+            indices.into_iter().partition(|&idx| entries[idx].enabled);
+        return interleave(enabled, disabled);
+    }
+    indices
+}
+
+/// `(entry index, pid)` for entries whose binary some visible process is running.
+fn find_entries_matching_running_processes(entries: &[StartupEntry]) -> Vec<(usize, u32)> {
+    let running = running_executables();
+    if running.is_empty() {
+        return Vec::new();
+    }
+    entries
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, entry)| Some((idx, running_pid(&running, &entry.binary_path()?)?)))
+        .collect()
+}
+
+/// The pid in `running` (from `running_executables`) whose executable is `binary_path`.
+fn running_pid(running: &HashMap<PathBuf, u32>, binary_path: &Path) -> Option<u32> {
+    running.get(&binary_path.canonicalize().ok()?).copied()
+}
+
+/// The lowest pid running `binary_path`, from a fresh `/proc` scan. The list and the detail
+/// panel read the per-refresh `running_entries` instead of scanning once per entry.
+#[cfg(target_os = "linux")]
+#[allow(dead_code)]
+fn is_binary_running(binary_path: &Path) -> Option<u32> {
+    running_pid(&running_executables(), binary_path)
+}
+
+/// `find_entries_matching_running_processes` keyed by entry index.
+fn running_entries(entries: &[StartupEntry]) -> HashMap<usize, u32> {
+    find_entries_matching_running_processes(entries).into_iter().collect()
+}
+
+/// Each executable in `/proc/<pid>/exe` with the lowest pid running it. Processes of other
+/// users are usually unreadable and so missing. A binary replaced by an upgrade reads as
+/// `path (deleted)` and is matched by its old path.
+#[cfg(target_os = "linux")]
+fn running_executables() -> HashMap<PathBuf, u32> {
+    let mut running: HashMap<PathBuf, u32> = HashMap::new();
+    let Ok(dir) = fs::read_dir("/proc") else {
+        return running;
+    };
+    for proc_entry in dir.flatten() {
+        let Some(pid) = proc_entry.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) else {
+            continue;
+        };
+        let Ok(exe) = fs::read_link(proc_entry.path().join("exe")) else {
+            continue;
+        };
+        let exe = match exe.to_str().and_then(|e| e.strip_suffix(" (deleted)")) {
+            Some(old) => PathBuf::from(old),
+            None => exe,
+        };
+        running
+            .entry(exe)
+            .and_modify(|lowest| *lowest = (*lowest).min(pid))
+            .or_insert(pid);
+    }
+    running
+}
+
+#[cfg(not(target_os = "linux"))]
+fn running_executables() -> HashMap<PathBuf, u32> {
+    HashMap::new()
+}
+
+/// How long the live search waits after the last keystroke before filtering the list.
+const SEARCH_DEBOUNCE_MS: u64 = 200;
+
+/// How long the user autostart watcher waits for a burst of file events to settle.
+const USER_DIR_WATCH_DEBOUNCE_MS: u64 = 500;
+
+const WINE_BINARIES: [&str; 3] = ["wine", "wine64", "wineboot"];
+
+/// Whether the command runs Wine, looking past an `env` wrapper with options or
+/// assignments that `parse_env_prefix` left in place.
+fn is_wine_entry(entry: &StartupEntry) -> bool {
+    let argv = entry.command_argv();
+    let mut args = argv.iter().map(String::as_str);
+    let mut program = args.next();
+    if program.is_some_and(|p| Path::new(p).file_name().is_some_and(|n| n == "env")) {
+        program = None;
+        while let Some(arg) = args.next() {
+            if matches!(arg, "-u" | "--unset" | "-C" | "--chdir") {
+                args.next();
+            } else if !arg.starts_with('-') && !arg.contains('=') {
+                program = Some(arg);
+                break;
+            }
+        }
+    }
+    program
+        .and_then(|p| Path::new(p).file_name())
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| WINE_BINARIES.contains(&n))
+}
+
+fn detect_wine_entries(entries: &[StartupEntry]) -> Vec<usize> {
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| is_wine_entry(e))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Directory holding an entry's binary, with bare names resolved through `$PATH`.
+fn binary_dir_of(entry: &StartupEntry) -> String {
+    let Some(binary) = entry.exec_binary() else {
+        return "No command".to_string();
+    };
+    let path = if binary.contains('/') {
+        Some(PathBuf::from(&binary))
+    } else {
+        find_in_path(&binary)
+    };
+    path.and_then(|p| p.parent().map(|dir| dir.display().to_string()))
+        .filter(|dir| !dir.is_empty())
+        .unwrap_or_else(|| "Not found in PATH".to_string())
+}
+
+/// Entry indices keyed by `binary_dir_of`, in order of first appearance.
+fn group_entries_by_binary_dir(entries: &[StartupEntry]) -> IndexMap<String, Vec<usize>> {
+    let mut groups: IndexMap<String, Vec<usize>> = IndexMap::new();
+    for (idx, entry) in entries.iter().enumerate() {
+        groups.entry(binary_dir_of(entry)).or_default().push(idx);
+    }
+    groups
+}
+
+fn group_header(label: &str, count: usize) -> String {
+    format!("{label} ({count} {})", if count == 1 { "entry" } else { "entries" })
+}
+
+/// Reorders the visible indices so each binary directory's entries are contiguous, keeping
+/// the sort within and between groups, and returns header text keyed by each group's first
+/// row position.
+fn order_by_binary_dir(entries: &[StartupEntry], sorted: Vec<usize>) -> (Vec<usize>, HashMap<usize, String>) {
+    let position: HashMap<usize, usize> = sorted.iter().enumerate().map(|(pos, &idx)| (idx, pos)).collect();
+    let mut groups: Vec<(String, Vec<usize>)> = group_entries_by_binary_dir(entries)
+        .into_iter()
+        .filter_map(|(dir, idxs)| {
+            let mut idxs: Vec<usize> = idxs.into_iter().filter(|i| position.contains_key(i)).collect();
+            idxs.sort_by_key(|i| position[i]);
+            (!idxs.is_empty()).then_some((dir, idxs))
+        })
+        .collect();
+    groups.sort_by_key(|(_, idxs)| position[&idxs[0]]);
+    let mut ordered = Vec::with_capacity(sorted.len());
+    let mut headers = HashMap::new();
+    for (dir, idxs) in groups {
+        headers.insert(ordered.len(), group_header(&dir, idxs.len()));
+        ordered.extend(idxs);
+    }
+    (ordered, headers)
+}
+
+/// How many of the `visible` entry indices come from `group_source`.
+fn count_visible_in_group(group_source: &StartupSource, entries: &[StartupEntry], visible: &[usize]) -> usize {
+    visible
+        .iter()
+        .filter(|&&idx| entries.get(idx).is_some_and(|e| &e.source == group_source))
+        .count()
+}
+
+/// `order_by_binary_dir`, grouped by source instead. Every source with a loaded entry gets a
+/// group. Groups the filter left empty are dropped when `hide_empty` is set; otherwise their
+/// headers are keyed by positions past the last returned row, one per placeholder row the
+/// caller appends, so row positions still line up with the returned indices.
+fn order_by_source(
+    entries: &[StartupEntry],
+    sorted: Vec<usize>,
+    hide_empty: bool,
+) -> (Vec<usize>, HashMap<usize, String>) {
+    let position: HashMap<usize, usize> = sorted.iter().enumerate().map(|(pos, &idx)| (idx, pos)).collect();
+    let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+    let mut seen: Vec<&StartupSource> = Vec::new();
+    for entry in entries {
+        if seen.contains(&&entry.source) {
+            continue;
+        }
+        seen.push(&entry.source);
+        if hide_empty && count_visible_in_group(&entry.source, entries, &sorted) == 0 {
+            continue;
+        }
+        let idxs = sorted.iter().copied().filter(|&i| entries[i].source == entry.source).collect();
+        groups.push((entry.human_readable_source(), idxs));
+    }
+    // Empty groups sort last, keeping the order their sources were first seen in.
+    groups.sort_by_key(|(_, idxs)| idxs.first().map_or(usize::MAX, |i| position[i]));
+    let mut ordered = Vec::with_capacity(sorted.len());
+    let mut headers = HashMap::new();
+    let mut placeholders = 0;
+    for (label, idxs) in groups {
+        if idxs.is_empty() {
+            headers.insert(sorted.len() + placeholders, group_header(&label, 0));
+            placeholders += 1;
+            continue;
+        }
+        headers.insert(ordered.len(), group_header(&label, idxs.len()));
+        ordered.extend(idxs);
+    }
+    (ordered, headers)
+}
+
+/// Enabled `.desktop` entries in the order a session would start them: by phase, then
+/// delay, then name. System entries overridden by a user file of the same name are left out.
+fn build_autostart_sequence(entries: &[StartupEntry]) -> Vec<StartupEntry> {
+    let user_files: HashSet<&std::ffi::OsStr> = entries
+        .iter()
+        .filter(|e| e.source == StartupSource::UserAutostart)
+        .filter_map(|e| e.path.as_ref()?.file_name())
+        .collect();
+    let locale = current_locale();
+    let mut sequence: Vec<StartupEntry> = entries
+        .iter()
+        .filter(|e| e.enabled)
+        .filter(|e| match e.source {
+            StartupSource::UserAutostart => true,
+            StartupSource::SystemAutostart => !e
+                .path
+                .as_ref()
+                .and_then(|p| p.file_name())
+                .is_some_and(|name| user_files.contains(name)),
+            _ => false,
+        })
+        .cloned()
+        .collect();
+    sequence.sort_by(|a, b| {
+        a.gnome_autostart_phase()
+            .cmp(&b.gnome_autostart_phase())
+            .then_with(|| a.delay_seconds().cmp(&b.delay_seconds()))
+            .then_with(|| {
+                display_name_with_fallback(a, &locale)
+                    .to_lowercase()
+                    .cmp(&display_name_with_fallback(b, &locale).to_lowercase())
+            })
+    });
+    sequence
+}
+
+/// "1. polkit-agent (0s)" lines for the Preview Boot Order dialog.
+fn format_autostart_sequence(sequence: &[StartupEntry]) -> String {
+    let locale = current_locale();
+    sequence
+        .iter()
+        .enumerate()
+        .map(|(i, e)| format!("{}. {} ({}s)", i + 1, display_name_with_fallback(e, &locale), e.delay_seconds()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Merges two lists as a[0], b[0], a[1], b[1], …, appending whatever is left over.
+fn interleave(a: Vec<usize>, b: Vec<usize>) -> Vec<usize> {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let mut a = a.into_iter();
+    let mut b = b.into_iter();
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => break,
+            (x, y) => out.extend(x.into_iter().chain(y)),
+        }
+    }
+    out
+}
+
+fn rebuild_list(state: &AppState) {
+    // Removing the rows clears the selection, so note which entry it was first.
+    let keep = state.selected.get().and_then(|idx| state.entries.borrow().get(idx).cloned());
+    while let Some(child) = state.list_box.first_child() {
+        state.list_box.remove(&child);
+    }
+    let filtered = apply_filter_with_running(&state.entries.borrow(), &state.filter.borrow(), &state.running.borrow());
+    let sorted = sort_indices_with_running(&state.entries.borrow(), filtered, state.sort.get(), &state.running.borrow());
+    if state.filter.borrow().is_default() {
+        state.filter_button.set_label("Filter");
+        state.filter_button.remove_css_class("suggested-action");
+        state.filter_button.set_tooltip_text(Some("Filter visible entries"));
+    } else {
+        state.filter_button.set_label("Filter (on)");
+        state.filter_button.add_css_class("suggested-action");
+        state
+            .filter_button
+            .set_tooltip_text(Some("Some entries are hidden by the filter; open to change or reset it"));
+    }
+    let (sorted, headers) = match state.view_mode.get() {
+        ViewMode::Flat => (sorted, HashMap::new()),
+        ViewMode::ByBinaryDir => order_by_binary_dir(&state.entries.borrow(), sorted),
+        ViewMode::BySource => order_by_source(
+            &state.entries.borrow(),
+            sorted,
+            state.config.borrow().hide_empty_groups,
+        ),
+    };
+    let empty_groups = headers.keys().filter(|&&pos| pos >= sorted.len()).count();
+    state.row_headers.replace(headers);
+    state.visible_indices.replace(sorted.clone());
+    state.selected.replace(None);
+    {
+        let entries = state.entries.borrow();
+        state
+            .count_label
+            .set_text(&compute_count_display(entries.len(), sorted.len(), &entries));
+        rebuild_alpha_strip(state, &build_alpha_index(&sorted, &entries));
+    }
+    if sorted.is_empty() {
+        let row = ListBoxRow::new();
+        row.set_accessible_role(AccessibleRole::ListItem);
+        row.set_child(Some(&Label::new(Some("No entries to show"))));
+        state.list_box.append(&row);
+        state.status_bar.set_text("No entries match the current filter");
+        return;
+    }
+    let wine = detect_wine_entries(&state.entries.borrow());
+    let running = state.running.borrow().clone();
+    let updates = state.system_updates.borrow().clone();
+    let locale = current_locale();
+    for idx in sorted {
+        let entry = &state.entries.borrow()[idx];
+        let text = format!(
+            "{} — {} [{}] {}",
+            display_name_with_fallback(entry, &locale),
+            entry.command,
+            source_label(&entry.source),
+            entry.status_label()
+        );
+        let row_box = GtkBox::new(Orientation::Horizontal, 6);
+        row_box.append(&entry_icon_image(entry));
+        if entry.locked {
+            let padlock = Image::from_icon_name("changes-prevent-symbolic");
+            padlock.update_property(&[gtk4::accessible::Property::Label("Locked")]);
+            row_box.append(&padlock);
+        }
+        if wine.contains(&idx) {
+            let icon = gio::ThemedIcon::from_names(&["wine", "application-x-ms-dos-executable"]);
+            let badge = Image::from_gicon(&icon);
+            badge.set_tooltip_text(Some("Runs under Wine"));
+            badge.update_property(&[gtk4::accessible::Property::Label("Wine")]);
+            row_box.append(&badge);
+        }
+        if let Some(pid) = running.get(&idx) {
+            let dot = Label::new(Some("●"));
+            dot.add_css_class("success");
+            dot.set_tooltip_text(Some(&format!("Running (PID {pid})")));
+            dot.update_property(&[gtk4::accessible::Property::Label("Running")]);
+            row_box.append(&dot);
+        }
+        if entry.is_daemon() {
+            let badge = Image::from_icon_name("system-run-symbolic");
+            badge.set_tooltip_text(Some("Looks like a background daemon"));
+            badge.update_property(&[gtk4::accessible::Property::Label("Daemon")]);
+            row_box.append(&badge);
+        }
+        if entry.xfce_override {
+            let icon = gio::ThemedIcon::from_names(&["xfce4-logo", "preferences-desktop-symbolic"]);
+            let badge = Image::from_gicon(&icon);
+            badge.set_tooltip_text(Some("Xfce uses this file in place of the system entry"));
+            badge.update_property(&[gtk4::accessible::Property::Label("Xfce override")]);
+            row_box.append(&badge);
+        }
+        if entry.source == StartupSource::XdgUserDirs {
+            let badge = Image::from_icon_name("folder-symbolic");
+            badge.set_tooltip_text(Some("Session start task, not a .desktop file"));
+            badge.update_property(&[gtk4::accessible::Property::Label("Session task")]);
+            row_box.append(&badge);
+        }
+        if updates.iter().any(|u| u.user_idx == idx) {
+            let badge = Image::from_icon_name("software-update-available-symbolic");
+            badge.set_tooltip_text(Some("A newer system version is available"));
+            badge.update_property(&[gtk4::accessible::Property::Label("Update available")]);
+            row_box.append(&badge);
+        }
+        row_box.append(&Label::new(Some(&text)));
+        let row = ListBoxRow::new();
+        row.set_accessible_role(AccessibleRole::ListItem);
+        row.set_child(Some(&row_box));
+        let right_click = gtk4::GestureClick::new();
+        right_click.set_button(gtk4::gdk::BUTTON_SECONDARY);
+        right_click.connect_pressed({
+            let state = state.clone();
+            move |gesture, _, x, y| {
+                gesture.set_state(gtk4::EventSequenceState::Claimed);
+                if let Some(row) = gesture.widget().and_then(|w| w.downcast::<ListBoxRow>().ok()) {
+                    show_row_context_menu(&state, &row, x, y);
+                }
+            }
+        });
+        row.add_controller(right_click);
+        state.list_box.append(&row);
+    }
+    let position = keep.and_then(|keep| {
+        let entries = state.entries.borrow();
+        state.visible_indices.borrow().iter().position(|&idx| is_same_entry(&entries[idx], &keep))
+    });
+    if let Some(row) = position.and_then(|pos| state.list_box.row_at_index(pos as i32)) {
+        state.list_box.select_row(Some(&row));
+    }
+    for _ in 0..empty_groups {
+        let row = ListBoxRow::new();
+        row.set_accessible_role(AccessibleRole::ListItem);
+        row.set_selectable(false);
+        row.set_activatable(false);
+        let label = Label::new(Some("No entries match the current filter"));
+        label.add_css_class("dim-label");
+        row.set_child(Some(&label));
+        state.list_box.append(&row);
+    }
+}
+
+/// Double-click on a row: the edit dialog for a user entry, otherwise a notice that the
+/// entry is read-only, offering a user override for system entries.
+fn open_entry_editor(state: &AppState, idx: usize) {
+    let Some(source) = state.entries.borrow().get(idx).map(|e| e.source.clone()) else {
+        return;
+    };
+    state.selected.replace(Some(idx));
+    if source == StartupSource::UserAutostart {
+        if let Err(err) = show_edit_dialog(state) {
+            state.status_bar.set_text(&format!("Edit failed: {err:#}"));
+        }
+    } else {
+        show_read_only_notice(state, idx, source == StartupSource::SystemAutostart);
+    }
+}
+
+fn show_read_only_notice(state: &AppState, idx: usize, offer_override: bool) {
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let (message, buttons): (&str, &[(&str, ResponseType)]) = if offer_override {
+        (
+            "This is a system entry and cannot be edited. Use 'Override' to create a user copy.",
+            &[("Close", ResponseType::Close), ("Override", ResponseType::Accept)],
+        )
+    } else {
+        (
+            "This entry comes from another program's configuration and cannot be edited here.",
+            &[("Close", ResponseType::Close)],
+        )
+    };
+    let dialog = Dialog::with_buttons(Some("Read-only entry"), parent.as_ref(), gtk4::DialogFlags::MODAL, buttons);
+    dialog.set_accessible_role(AccessibleRole::AlertDialog);
+    dialog.set_default_response(ResponseType::Close);
+    let label = Label::new(Some(message));
+    label.set_wrap(true);
+    dialog.content_area().append(&label);
+    dialog.connect_response({
+        let state = state.clone();
+        move |dlg, resp| {
+            dlg.close();
+            if resp != ResponseType::Accept {
+                return;
+            }
+            if let Err(err) = override_system_entry(&state, idx) {
+                state.status_bar.set_text(&format!("Override failed: {err:#}"));
+            }
+        }
+    });
+    dialog.present();
+}
+
+/// Copies the system entry at `idx` into the user autostart dir under the same file name,
+/// which takes its place in the session, and opens the copy for editing.
+fn override_system_entry(state: &AppState, idx: usize) -> Result<()> {
+    let entry = state
+        .entries
+        .borrow()
+        .get(idx)
+        .cloned()
+        .context("Invalid selection")?;
+    let dir = user_autostart_dir();
+    fs::create_dir_all(&dir).with_context(|| format!("Creating dir {:?}", dir))?;
+    let path = override_entry_in(&dir, &entry)?;
+    push_undo(state, UndoEntry::Added { path: path.clone() });
+    refresh_entries(state)?;
+    set_status_with_undo_hint(state, "Created a user override");
+    let copy_idx = state
+        .entries
+        .borrow()
+        .iter()
+        .position(|e| e.path.as_deref() == Some(path.as_path()));
+    if let Some(copy_idx) = copy_idx {
+        state.selected.replace(Some(copy_idx));
+        show_edit_dialog(state)?;
+    }
+    Ok(())
+}
+
+/// Registers the `row.*` actions on the list box; each works on the selected entry.
+fn add_row_actions(state: &AppState) {
+    // Edit, Toggle and Delete behave exactly like the detail panel's buttons.
+    for (name, button) in [
+        ("edit", &state.edit_button),
+        ("toggle", &state.toggle_button),
+        ("delete", &state.delete_button),
+    ] {
+        let action = gio::SimpleAction::new(name, None);
+        let button = button.clone();
+        action.connect_activate(move |_, _| button.emit_clicked());
+        state.row_actions.add_action(&action);
+    }
+    let with_selected: [(&str, &str, fn(&AppState) -> Result<()>); 4] = [
+        ("duplicate", "Duplicate failed", duplicate_selected),
+        ("copy-path", "Copy failed", copy_selected_path),
+        ("show-in-folder", "Opening the folder failed", show_selected_in_file_manager),
+        ("export-desktop", "Export failed", export_selected_as_desktop_file),
+    ];
+    for (name, failure, run) in with_selected {
+        let action = gio::SimpleAction::new(name, None);
+        let state_for_action = state.clone();
+        action.connect_activate(move |_, _| {
+            if let Err(err) = run(&state_for_action) {
+                state_for_action.status_bar.set_text(&format!("{failure}: {err:#}"));
+            }
+        });
+        state.row_actions.add_action(&action);
+    }
+    state.list_box.insert_action_group("row", Some(&state.row_actions));
+}
+
+/// Selects `row` and pops up its context menu at (`x`, `y`). Items the entry does not
+/// support are greyed out, the same way as the detail panel's buttons.
+fn show_row_context_menu(state: &AppState, row: &ListBoxRow, x: f64, y: f64) {
+    state.list_box.select_row(Some(row));
+    let Some((has_path, copyable)) = state.selected.get().and_then(|idx| {
+        state
+            .entries
+            .borrow()
+            .get(idx)
+            .map(|e| (e.path.is_some(), !is_environment_source(&e.source)))
+    }) else {
+        return;
+    };
+    let enabled = [
+        ("edit", state.edit_button.is_sensitive()),
+        ("toggle", state.toggle_button.is_sensitive()),
+        ("delete", state.delete_button.is_sensitive()),
+        ("duplicate", copyable),
+        ("copy-path", has_path),
+        ("show-in-folder", has_path),
+        ("export-desktop", true),
+    ];
+    for (name, on) in enabled {
+        if let Some(action) = state
+            .row_actions
+            .lookup_action(name)
+            .and_then(|a| a.downcast::<gio::SimpleAction>().ok())
+        {
+            action.set_enabled(on);
+        }
+    }
+    let menu = gio::Menu::new();
+    menu.append(Some("Edit"), Some("row.edit"));
+    menu.append(Some("Toggle"), Some("row.toggle"));
+    menu.append(Some("Delete"), Some("row.delete"));
+    menu.append(Some("Duplicate"), Some("row.duplicate"));
+    menu.append(Some("Copy Path to Clipboard"), Some("row.copy-path"));
+    menu.append(Some("Show in File Manager"), Some("row.show-in-folder"));
+    menu.append(Some("Export as .desktop…"), Some("row.export-desktop"));
+    let popover = gtk4::PopoverMenu::from_model(Some(&menu));
+    popover.set_parent(row);
+    popover.set_has_arrow(false);
+    popover.set_pointing_to(Some(&gtk4::gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+    // Unparent once closed; doing it inside the signal would cut off the item's activation.
+    popover.connect_closed(|popover| {
+        let popover = popover.clone();
+        glib::idle_add_local_once(move || popover.unparent());
+    });
+    popover.popup();
+}
+
+/// Writes a copy of the selected entry as a new user entry named "… (copy)".
+fn duplicate_selected(state: &AppState) -> Result<()> {
+    let idx = state.selected.get().context("No item selected")?;
+    let entry = state
+        .entries
+        .borrow()
+        .get(idx)
+        .cloned()
+        .context("Invalid selection")?;
+    let dir = user_autostart_dir();
+    fs::create_dir_all(&dir).with_context(|| format!("Creating dir {:?}", dir))?;
+    let (path, copy) = duplicate_entry_in(&dir, &entry)?;
+    push_undo(state, UndoEntry::Added { path });
+    refresh_entries(state)?;
+    set_status_with_undo_hint(
+        state,
+        &format!("Duplicated as {}", display_name_with_fallback(&copy, &current_locale())),
+    );
+    Ok(())
+}
+
+fn copy_selected_path(state: &AppState) -> Result<()> {
+    let idx = state.selected.get().context("No item selected")?;
+    let path = state
+        .entries
+        .borrow()
+        .get(idx)
+        .context("Invalid selection")?
+        .path
+        .clone()
+        .context("Entry has no associated file path")?;
+    state.list_box.clipboard().set_text(&path.to_string_lossy());
+    state.status_bar.set_text(&format!("Copied {}", path.display()));
+    Ok(())
+}
+
+/// Opens the folder holding the selected entry's file in the default file manager.
+fn show_selected_in_file_manager(state: &AppState) -> Result<()> {
+    let idx = state.selected.get().context("No item selected")?;
+    let path = state
+        .entries
+        .borrow()
+        .get(idx)
+        .context("Invalid selection")?
+        .path
+        .clone()
+        .context("Entry has no associated file path")?;
+    let dir = path.parent().context("Entry path has no parent folder")?;
+    gio::AppInfo::launch_default_for_uri(&gio::File::for_path(dir).uri(), None::<&gio::AppLaunchContext>)
+        .with_context(|| format!("Opening {:?}", dir))?;
+    Ok(())
+}
+
+/// Saves the selected entry's `.desktop` text to a file of the user's choosing.
+fn export_selected_as_desktop_file(state: &AppState) -> Result<()> {
+    let idx = state.selected.get().context("No item selected")?;
+    let entry = state
+        .entries
+        .borrow()
+        .get(idx)
+        .cloned()
+        .context("Invalid selection")?;
+    let export_state = state.clone();
+    let initial_name = format!("{}.desktop", slugify(&entry.name));
+    choose_file(state, "Export as .desktop", FileDialogAction::Save, &initial_name, Some("*.desktop"), move |path| {
+        match write_text_atomic(&path, &write_desktop_entry_to_string(&entry)) {
+            Ok(()) => export_state
+                .status_bar
+                .set_text(&format!(
+                    "Exported {} to {}",
+                    display_name_with_fallback(&entry, &current_locale()),
+                    path.display()
+                )),
+            Err(err) => export_state
+                .status_bar
+                .set_text(&format!("Export failed: {err:#}")),
+        }
+    });
+    Ok(())
+}
+
+/// Groups and `(accelerator, title)` pairs for the shortcuts window, in GTK accelerator syntax.
+const KEYBOARD_SHORTCUTS: [(&str, &[(&str, &str)]); 3] = [
+    (
+        "Navigation",
+        &[
+            ("Up Down", "Move through the list"),
+            ("Tab <Shift>Tab", "Move between the list and the detail panel"),
+        ],
+    ),
+    (
+        "Actions",
+        &[
+            ("<Control>n", "Add an entry"),
+            ("<Control>e", "Edit the selected entry"),
+            ("space", "Enable or disable the selected entry"),
+            ("Delete", "Delete the selected entry"),
+            ("<Control>r", "Refresh the list"),
+            ("<Control>z", "Undo"),
+            ("<Control><Shift>z", "Redo"),
+            ("<Control>question", "Show keyboard shortcuts"),
+        ],
+    ),
+    (
+        "Search",
+        &[
+            ("<Control>f", "Show or hide the search bar"),
+            ("Escape", "Close the search bar"),
+        ],
+    ),
+];
+
+/// GtkBuilder XML for a `GtkShortcutsWindow` (id `shortcuts`) listing `KEYBOARD_SHORTCUTS`.
+/// Built from XML because adding sections from code needs GTK 4.14.
+fn shortcuts_window_xml() -> String {
+    let mut xml = String::from(
+        "<interface>\n  <object class=\"GtkShortcutsWindow\" id=\"shortcuts\">\n    <property name=\"modal\">1</property>\n    <child>\n      <object class=\"GtkShortcutsSection\">\n        <property name=\"section-name\">main</property>\n",
+    );
+    for (group, shortcuts) in KEYBOARD_SHORTCUTS {
+        xml.push_str(&format!(
+            "        <child>\n          <object class=\"GtkShortcutsGroup\">\n            <property name=\"title\">{}</property>\n",
+            xml_escape(group)
+        ));
+        for (accelerator, title) in shortcuts {
+            xml.push_str(&format!(
+                "            <child>\n              <object class=\"GtkShortcutsShortcut\">\n                <property name=\"accelerator\">{}</property>\n                <property name=\"title\">{}</property>\n              </object>\n            </child>\n",
+                xml_escape(accelerator),
+                xml_escape(title)
+            ));
+        }
+        xml.push_str("          </object>\n        </child>\n");
+    }
+    xml.push_str("      </object>\n    </child>\n  </object>\n</interface>\n");
+    xml
+}
+
+fn show_shortcuts_window(parent: Option<&ApplicationWindow>) {
+    let builder = gtk4::Builder::from_string(&shortcuts_window_xml());
+    let Some(window) = builder.object::<gtk4::ShortcutsWindow>("shortcuts") else {
+        eprintln!("The shortcuts window description is invalid");
+        return;
+    };
+    window.set_transient_for(parent);
+    window.present();
+}
+
+/// Shown in list rows for entries without a usable `Icon=`.
+const FALLBACK_ICON_NAME: &str = "application-x-executable-symbolic";
+
+/// The entry's `Icon=` at 16px for its list row: an image file for an absolute path, else
+/// the theme icon, falling back to `FALLBACK_ICON_NAME`.
+fn entry_icon_image(entry: &StartupEntry) -> Image {
+    let image = match entry.icon() {
+        Some(icon) if Path::new(icon).is_absolute() && Path::new(icon).is_file() => Image::from_file(icon),
+        Some(icon) => Image::from_gicon(&gio::ThemedIcon::from_names(&[icon, FALLBACK_ICON_NAME])),
+        None => Image::from_icon_name(FALLBACK_ICON_NAME),
+    };
+    image.set_pixel_size(16);
+    image.set_accessible_role(AccessibleRole::Presentation);
+    image
+}
+
+/// One button per letter plus `#`; letters with no visible entry are insensitive.
+fn rebuild_alpha_strip(state: &AppState, index: &[(char, usize)]) {
+    while let Some(child) = state.alpha_strip.first_child() {
+        state.alpha_strip.remove(&child);
+    }
+    for letter in ('A'..='Z').chain(std::iter::once('#')) {
+        let button = Button::with_label(&letter.to_string());
+        button.add_css_class("flat");
+        button.set_tooltip_text(Some(&format!("Jump to {letter}")));
+        match index.iter().find(|(c, _)| *c == letter) {
+            Some(&(_, pos)) => {
+                let list_box = state.list_box.clone();
+                let scroller = state.list_scroller.clone();
+                button.connect_clicked(move |_| {
+                    let Some(row) = list_box.row_at_index(pos as i32) else {
+                        return;
+                    };
+                    if let Some(bounds) = row.compute_bounds(&list_box) {
+                        scroller.vadjustment().set_value(bounds.y() as f64);
+                    }
+                });
+            }
+            None => button.set_sensitive(false),
+        }
+        state.alpha_strip.append(&button);
+    }
+}
+
+/// Maps each distinct first character of the visible names, upper-cased, to the list
+/// position where it first appears. Names not starting with an ASCII letter share `#`,
+/// matching the A–Z strip.
+fn build_alpha_index(visible: &[usize], entries: &[StartupEntry]) -> Vec<(char, usize)> {
+    let mut index: Vec<(char, usize)> = Vec::new();
+    let locale = current_locale();
+    for (pos, &idx) in visible.iter().enumerate() {
+        let Some(entry) = entries.get(idx) else {
+            continue;
+        };
+        let key = match display_name_with_fallback(entry, &locale).chars().next() {
+            Some(c) if c.is_ascii_alphabetic() => c.to_ascii_uppercase(),
+            _ => '#',
+        };
+        if !index.iter().any(|(c, _)| *c == key) {
+            index.push((key, pos));
+        }
+    }
+    index
+}
+
+/// "Showing M of N entries (P user, Q system)"; the user/system split covers all entries.
+fn compute_count_display(total: usize, visible: usize, entries: &[StartupEntry]) -> String {
+    let user = entries.iter().filter(|e| is_user_side_source(&e.source)).count();
+    let system = entries.iter().filter(|e| is_system_side_source(&e.source)).count();
+    format!("Showing {visible} of {total} entries ({user} user, {system} system)")
+}
+
+/// Counts shown in the About dialog's Statistics panel.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct EntryStatistics {
+    total: usize,
+    enabled: usize,
+    disabled: usize,
+    user: usize,
+    system: usize,
+    with_icon: usize,
+    with_description: usize,
+    average_command_length: f32,
+    longest_command: Option<String>,
+}
+
+impl EntryStatistics {
+    /// "42 total entries, 35 enabled, 7 disabled; 12 user / 30 system"
+    fn summary(&self) -> String {
+        format!(
+            "{} total entries, {} enabled, {} disabled; {} user / {} system",
+            self.total, self.enabled, self.disabled, self.user, self.system
+        )
+    }
+}
+
+fn collect_entry_statistics(entries: &[StartupEntry]) -> EntryStatistics {
+    let command_lengths: Vec<usize> = entries.iter().map(|e| e.command.chars().count()).collect();
+    let mut longest: Option<&StartupEntry> = None;
+    for entry in entries {
+        if longest.is_none_or(|l| entry.command.chars().count() > l.command.chars().count()) {
+            longest = Some(entry);
+        }
+    }
+    EntryStatistics {
+        total: entries.len(),
+        enabled: entries.iter().filter(|e| e.enabled).count(),
+        disabled: entries.iter().filter(|e| !e.enabled).count(),
+        user: entries.iter().filter(|e| is_user_side_source(&e.source)).count(),
+        system: entries.iter().filter(|e| is_system_side_source(&e.source)).count(),
+        with_icon: entries.iter().filter(|e| e.icon().is_some()).count(),
+        with_description: entries.iter().filter(|e| e.comment().is_some()).count(),
+        average_command_length: if entries.is_empty() {
+            0.0
+        } else {
+            command_lengths.iter().sum::<usize>() as f32 / entries.len() as f32
+        },
+        longest_command: longest.filter(|e| !e.command.is_empty()).map(|e| e.command.clone()),
+    }
+}
+
+fn refresh_search_completion(store: &gtk4::ListStore, history: &SearchHistory, prefix: &str) {
+    store.clear();
+    for query in history.matches_prefix(prefix) {
+        store.set(&store.append(), &[(0, &query)]);
+    }
+}
+
+/// Re-validates the loaded entries and updates the "N warnings" header badge.
+fn update_pending_warnings(state: &AppState) {
+    let pending = validate_all_entries_on_load(&state.entries.borrow());
+    let count: usize = pending.iter().map(|(_, result)| result.warnings.len()).sum();
+    state
+        .warnings_button
+        .set_label(&format!("{count} {}", if count == 1 { "warning" } else { "warnings" }));
+    state.warnings_button.set_visible(count > 0);
+    state.pending_warnings.replace(pending);
+}
+
+/// Looks up which loaded entries are running now, once per load rather than per redraw.
+fn update_running_entries(state: &AppState) {
+    let running = running_entries(&state.entries.borrow());
+    state.running.replace(running);
+}
+
+/// Compares user overrides with their system files, once per load rather than per redraw
+/// or selection change.
+fn update_system_updates(state: &AppState) {
+    let updates = {
+        let entries = state.entries.borrow();
+        check_for_updates_from_system(&entries, &entries)
+    };
+    state.system_updates.replace(updates);
+}
+
+/// Re-reads the session `$PATH` from `/etc/environment`, once per load rather than per
+/// selection change.
+fn update_session_search_path(state: &AppState) {
+    state.search_path.replace(session_search_path(Path::new("/etc/environment")));
+}
+
+/// Records the loaded system entry files and, if enabled, sends a desktop notification
+/// about the ones not seen before.
+fn notify_new_system_entries(state: &AppState) {
+    let (added, changed) = {
+        let mut config = state.config.borrow_mut();
+        let before = config.known_system_entry_paths.len();
+        let added = track_system_entry_paths(&mut config.known_system_entry_paths, &state.entries.borrow());
+        let changed = !added.is_empty() || config.known_system_entry_paths.len() != before;
+        (added, changed)
+    };
+    let saved = if changed { save_config(&state.config.borrow()) } else { Ok(()) };
+    if let Err(err) = saved {
+        eprintln!("Failed to save known system entries: {err:?}");
+    }
+    if added.is_empty() || !state.config.borrow().notify_on_new_system_entry {
+        return;
+    }
+    let app = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok())
+        .and_then(|w| w.application());
+    if let Some(app) = app {
+        let notification = gio::Notification::new("Universal Startup Manager");
+        notification.set_body(Some(&new_system_entries_message(added.len())));
+        app.send_notification(Some("new-system-entries"), &notification);
+    }
+}
+
+/// "N new system startup entries added"
+fn new_system_entries_message(count: usize) -> String {
+    format!(
+        "{count} new system startup {} added",
+        if count == 1 { "entry" } else { "entries" }
+    )
+}
+
+/// Replaces `known` with the system entry files in `entries` and returns the ones it did not
+/// contain. The first call (nothing known yet) only records them, so a fresh config does not
+/// report every existing entry as new.
+fn track_system_entry_paths(known: &mut HashSet<PathBuf>, entries: &[StartupEntry]) -> Vec<PathBuf> {
+    let current: Vec<PathBuf> = entries
+        .iter()
+        .filter(|e| e.source == StartupSource::SystemAutostart)
+        .filter_map(|e| e.path.clone())
+        .collect();
+    let mut old: Vec<PathBuf> = known.iter().cloned().collect();
+    old.sort();
+    let first_run = old.is_empty();
+    let (added, _removed) = diff_entry_sets(&old, &current);
+    *known = current.into_iter().collect();
+    if first_run { Vec::new() } else { added }
+}
+
+/// Paths in `new` but not `old`, and paths in `old` but not `new`, each in input order.
+fn diff_entry_sets(old: &[PathBuf], new: &[PathBuf]) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let old_set: HashSet<&PathBuf> = old.iter().collect();
+    let new_set: HashSet<&PathBuf> = new.iter().collect();
+    let added = new.iter().filter(|p| !old_set.contains(p)).cloned().collect();
+    let removed = old.iter().filter(|p| !new_set.contains(p)).cloned().collect();
+    (added, removed)
+}
+
+fn refresh_entries(state: &AppState) -> Result<()> {
+    let limit = state.config.borrow().max_entries_per_source;
+    let (mut new_entries, dir_totals) = load_entries_with_progress(limit, |_| {})?;
+    let auto_disabled = if state.config.borrow().auto_disable_missing_binary {
+        auto_disable_missing_binaries(&mut new_entries)
+    } else {
+        0
+    };
+    let selected = state.selected.get().and_then(|idx| state.entries.borrow().get(idx).cloned());
+    let selected = selected.and_then(|keep| find_reloaded_entry(&new_entries, &keep));
+    state.entries.replace(new_entries);
+    state.autostart_dir_totals.replace(dir_totals);
+    state.selected.replace(selected);
+    update_pending_warnings(state);
+    update_running_entries(state);
+    update_system_updates(state);
+    update_session_search_path(state);
+    notify_new_system_entries(state);
+    rebuild_list(state);
+    update_detail(state);
+    if auto_disabled > 0 {
+        state.status_bar.set_text(&format!(
+            "Auto-disabled {auto_disabled} {}: binaries not found",
+            if auto_disabled == 1 { "entry" } else { "entries" }
+        ));
+    } else {
+        state.status_bar.set_text(&with_dir_limit_warning(
+            state,
+            with_truncation_notice(
+                format!("Refreshed: {}", entries_summary(&state.entries.borrow())),
+                limit,
+                &state.autostart_dir_totals.borrow(),
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Status bar warning when `~/.config/autostart` is over the size or entry-count limit set in
+/// Preferences.
+fn check_autostart_dir_size(state: &AppState) -> Option<String> {
+    let config = state.config.borrow();
+    check_user_autostart_limits(
+        &state.entries.borrow(),
+        config.user_autostart_dir_size_limit,
+        config.autostart_entry_count_limit,
+    )
+}
+
+fn with_dir_limit_warning(state: &AppState, text: String) -> String {
+    match check_autostart_dir_size(state) {
+        Some(warning) => format!("{text} · {warning}"),
+        None => text,
+    }
+}
+
+/// Reloads system entries when files in a system autostart dir change, e.g. during a
+/// package install. An unwatchable directory is only logged.
+fn watch_system_autostart_dirs(state: &AppState) {
+    for dir in system_autostart_dirs() {
+        match gio::File::for_path(&dir).monitor_directory(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE) {
+            Ok(monitor) => watch_system_autostart_monitor(state, monitor),
+            Err(err) => eprintln!("Not watching {dir:?}: {err}"),
+        }
+    }
+}
+
+fn watch_system_autostart_monitor(state: &AppState, monitor: gio::FileMonitor) {
+    let state_for_events = state.clone();
+    monitor.connect_changed(move |_, _, _, event| {
+        // A new file reports Created and then ChangesDoneHint once it is fully written.
+        if !matches!(
+            event,
+            gio::FileMonitorEvent::ChangesDoneHint
+                | gio::FileMonitorEvent::Deleted
+                | gio::FileMonitorEvent::MovedIn
+                | gio::FileMonitorEvent::MovedOut
+                | gio::FileMonitorEvent::Renamed
+        ) {
+            return;
+        }
+        if let Err(err) = refresh_system_entries_only(&state_for_events) {
+            state_for_events
+                .status_bar
+                .set_text(&format!("Reloading system entries failed: {err:#}"));
+        }
+    });
+    state.system_dir_monitors.borrow_mut().push(monitor);
+}
+
+/// Refreshes the list when another program changes `~/.config/autostart`, once events have
+/// been quiet for `USER_DIR_WATCH_DEBOUNCE_MS`. Our own writes also fire events; those are
+/// skipped because the list already matches the files by then.
+fn watch_user_autostart_dir(state: &AppState) {
+    let dir = user_autostart_dir();
+    let monitor = match gio::File::for_path(&dir).monitor_directory(gio::FileMonitorFlags::NONE, gio::Cancellable::NONE) {
+        Ok(monitor) => monitor,
+        Err(err) => {
+            eprintln!("Not watching {dir:?}: {err}");
+            return;
+        }
+    };
+    let state_for_events = state.clone();
+    let generation = Rc::new(Cell::new(0u64));
+    monitor.connect_changed(move |_, _, _, _| {
+        if !state_for_events.config.borrow().refresh_on_external_change {
+            return;
+        }
+        let current = generation.get().wrapping_add(1);
+        generation.set(current);
+        let state = state_for_events.clone();
+        let generation = generation.clone();
+        let dir = dir.clone();
+        glib::timeout_add_local_once(std::time::Duration::from_millis(USER_DIR_WATCH_DEBOUNCE_MS), move || {
+            if generation.get() != current {
+                return;
+            }
+            let limit = state.config.borrow().max_entries_per_source;
+            let on_disk = load_autostart_dir_bounded(&dir, StartupSource::UserAutostart, limit).unwrap_or_default();
+            if !user_entries_differ(&state.entries.borrow(), &on_disk) {
+                return;
+            }
+            match refresh_entries(&state) {
+                Ok(()) => state.status_bar.set_text("Directory changed externally, refreshed"),
+                Err(err) => state
+                    .status_bar
+                    .set_text(&format!("Failed to refresh after an external change: {err:#}")),
+            }
+        });
+    });
+    state.user_dir_monitor.replace(Some(monitor));
+}
+
+/// Whether the `UserAutostart` entries in `entries` differ from `on_disk` in which files
+/// exist or what they contain.
+fn user_entries_differ(entries: &[StartupEntry], on_disk: &[StartupEntry]) -> bool {
+    let fingerprint = |list: Vec<&StartupEntry>| {
+        let mut files: Vec<_> = list.into_iter().map(|e| (e.path.clone(), e.to_desktop_string())).collect();
+        files.sort();
+        files
+    };
+    let current = entries.iter().filter(|e| e.source == StartupSource::UserAutostart).collect();
+    fingerprint(current) != fingerprint(on_disk.iter().collect())
+}
+
+/// Re-reads only the system autostart dirs, leaving user and other entries as they are and
+/// keeping the selected entry selected when it still exists.
+fn refresh_system_entries_only(state: &AppState) -> Result<()> {
+    let limit = state.config.borrow().max_entries_per_source;
+    let (selected, system_totals) = {
+        let mut entries = state.entries.borrow_mut();
+        reload_system_entries_from(&mut entries, &system_autostart_dirs(), limit, state.selected.get())?
+    };
+    state.selected.replace(selected);
+    {
+        let mut totals = state.autostart_dir_totals.borrow_mut();
+        totals.retain(|(source, _)| *source != StartupSource::SystemAutostart);
+        totals.extend(system_totals);
+    }
+    update_pending_warnings(state);
+    update_running_entries(state);
+    update_system_updates(state);
+    update_session_search_path(state);
+    notify_new_system_entries(state);
+    rebuild_list(state);
+    if state.list_box.selected_row().is_none() {
+        update_detail(state);
+    }
+    state.status_bar.set_text(&with_truncation_notice(
+        format!("System entries changed: {}", entries_summary(&state.entries.borrow())),
+        limit,
+        &state.autostart_dir_totals.borrow(),
+    ));
+    Ok(())
+}
+
+/// Whether `a` and `b` are the same entry across a reload or re-sort: same file and same
+/// name, since config-file sources such as Sway's list several entries under one path.
+fn is_same_entry(a: &StartupEntry, b: &StartupEntry) -> bool {
+    a.path == b.path && a.name == b.name
+}
+
+/// Where `keep` is in freshly loaded `entries`: by its stored `X-USM-ID=` first, so an
+/// entry renamed to a new file stays selected, then by `is_same_entry`.
+fn find_reloaded_entry(entries: &[StartupEntry], keep: &StartupEntry) -> Option<usize> {
+    keep.entry_id()
+        .and_then(|id| find_entry_by_id(id, entries))
+        .or_else(|| entries.iter().position(|e| is_same_entry(e, keep)))
+}
+
+/// Swaps the `SystemAutostart` entries in `entries` for a fresh read of `dirs`, in place of the
+/// old ones, and returns where `selected` ended up: the same entry for other sources, the
+/// entry with the same path for system ones, or `None` if that file is gone. The counts of
+/// `dirs` come back too, as from `load_autostart_dirs_with_progress`.
+fn reload_system_entries_from(
+    entries: &mut Vec<StartupEntry>,
+    dirs: &[PathBuf],
+    limit: Option<usize>,
+    selected: Option<usize>,
+) -> Result<(Option<usize>, AutostartDirTotals)> {
+    let dirs: Vec<_> = dirs.iter().map(|dir| (dir.clone(), StartupSource::SystemAutostart)).collect();
+    let (fresh, dir_totals) = load_autostart_dirs_with_progress(&dirs, limit, &|_| {})?;
+    let is_system = |e: &StartupEntry| e.source == StartupSource::SystemAutostart;
+    let selected_entry = selected.and_then(|idx| entries.get(idx).map(|e| (idx, e)));
+    let selected_path = selected_entry
+        .filter(|(_, e)| is_system(e))
+        .and_then(|(_, e)| e.path.clone());
+    // Rank of the selected entry among the entries that stay.
+    let selected_rank = selected_entry
+        .filter(|(_, e)| !is_system(e))
+        .map(|(idx, _)| entries[..idx].iter().filter(|e| !is_system(e)).count());
+    // Fresh entries go where the old ones were, or after the user entries if there were none.
+    let insert_at = entries
+        .iter()
+        .position(is_system)
+        .unwrap_or_else(|| entries.iter().take_while(|e| e.source == StartupSource::UserAutostart).count());
+    let mut kept: Vec<StartupEntry> = std::mem::take(entries).into_iter().filter(|e| !is_system(e)).collect();
+    let tail = kept.split_off(insert_at);
+    let fresh_len = fresh.len();
+    entries.extend(kept);
+    entries.extend(fresh);
+    entries.extend(tail);
+    let selected = match (selected_path, selected_rank) {
+        (Some(path), _) => entries[insert_at..insert_at + fresh_len]
+            .iter()
+            .position(|e| e.path.as_ref() == Some(&path))
+            .map(|i| insert_at + i),
+        (None, Some(rank)) if rank < insert_at => Some(rank),
+        (None, Some(rank)) => Some(rank + fresh_len),
+        (None, None) => None,
+    };
+    Ok((selected, dir_totals))
+}
+
+/// Disables enabled user entries whose binary is gone and writes them back.
+/// Returns how many were disabled; write failures are logged and skipped.
+fn auto_disable_missing_binaries(entries: &mut [StartupEntry]) -> usize {
+    let mut disabled = 0;
+    for idx in check_binaries_still_present(entries) {
+        let entry = &mut entries[idx];
+        if entry.source != StartupSource::UserAutostart || !entry.enabled {
+            continue;
+        }
+        let Some(path) = entry.path.clone() else {
+            continue;
+        };
+        let res = validate_user_entry_path(&path).and_then(|path| {
+            entry.enabled = false;
+            write_desktop_entry(entry, &path)
+        });
+        match res {
+            Ok(()) => disabled += 1,
+            Err(err) => {
+                entry.enabled = true;
+                eprintln!("Auto-disable of {:?} failed: {err:?}", path);
+            }
+        }
+    }
+    disabled
+}
+
+const CLI_USAGE: &str = "Usage: universal-startup-manager [--list [--filter=user,system,enabled,disabled] | --enable NAME | --disable NAME | --delete NAME\n\
+                                    | --copy NAME DIR... | --diff-snapshot DIR | --delete-all-user-entries --yes]\n\
+Without arguments the graphical interface starts.\n";
+
+/// Handles command-line actions. `None` when `args` holds no CLI action, so the GTK app
+/// starts instead; otherwise the text to print.
+fn run_cli(args: &[String]) -> Option<Result<String>> {
+    // `rest` holds the arguments after `value`.
+    let (flag, value, rest) = match args.first()?.split_once('=') {
+        Some((flag, value)) => (flag, Some(value.to_string()), &args[1..]),
+        None => (args[0].as_str(), args.get(1).cloned(), args.get(2..).unwrap_or_default()),
+    };
+    let result = match flag {
+        "--help" | "-h" => Ok(CLI_USAGE.to_string()),
+        "--list" => FilterState::from_cli_args(&args[1..]).and_then(|filter| {
+            let entries = load_entries()?;
+            let visible: Vec<StartupEntry> = sort_indices(&entries, apply_filter(&entries, &filter), SortKey::NameAsc)
+                .into_iter()
+                .map(|idx| entries[idx].clone())
+                .collect();
+            Ok(cli_list(&visible))
+        }),
+        "--enable" | "--disable" | "--delete" => match value {
+            None => Err(anyhow::anyhow!("{flag} needs an entry name\n{CLI_USAGE}")),
+            Some(name) => load_entries().and_then(|entries| match flag {
+                "--enable" => cli_set_enabled(&entries, &name, true),
+                "--disable" => cli_set_enabled(&entries, &name, false),
+                _ => cli_delete(&entries, &name),
+            }),
+        },
+        "--copy" => match value {
+            None => Err(anyhow::anyhow!("--copy needs an entry name and at least one dir\n{CLI_USAGE}")),
+            Some(name) => load_entries().and_then(|entries| {
+                let dirs: Vec<PathBuf> = rest.iter().map(PathBuf::from).collect();
+                cli_copy(&entries, &name, &dirs)
+            }),
+        },
+        "--diff-snapshot" => match value {
+            None => Err(anyhow::anyhow!("--diff-snapshot needs a snapshot dir\n{CLI_USAGE}")),
+            Some(dir) => load_entries().and_then(|entries| cli_diff_snapshot(&entries, Path::new(&dir))),
+        },
+        // No dialogs without a display, so `--yes` stands in for the confirmation steps.
+        "--delete-all-user-entries" => match value.as_deref() {
+            Some("--yes") => clear_autostart_dir(&user_autostart_dir())
+                .map(|count| format!("Deleted {count} user {}\n", if count == 1 { "entry" } else { "entries" })),
+            _ => Err(anyhow::anyhow!(
+                "--delete-all-user-entries deletes every user entry and cannot be undone; \
+                 add --yes to confirm\n{CLI_USAGE}"
+            )),
+        },
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// One tab-separated line per entry: status, source, name, command.
+fn cli_list(entries: &[StartupEntry]) -> String {
+    entries
+        .iter()
+        .map(|e| {
+            format!(
+                "{}\t{}\t{}\t{}\n",
+                if e.enabled { "enabled" } else { "disabled" },
+                source_label(&e.source),
+                e.name,
+                e.exec_line()
+            )
+        })
+        .collect()
+}
+
+/// The user entry called `name`, with close matches suggested when there is none.
+fn cli_find_user_entry<'a>(entries: &'a [StartupEntry], name: &str) -> Result<&'a StartupEntry> {
+    let Some(idx) = find_entry_by_name(name, entries) else {
+        let suggestions: Vec<&str> = find_entries_by_name_fuzzy(name, entries)
+            .into_iter()
+            .take(3)
+            .map(|(idx, _)| entries[idx].name.as_str())
+            .collect();
+        if suggestions.is_empty() {
+            bail!("No entry named {name:?}");
+        }
+        bail!("No entry named {name:?}; did you mean: {}?", suggestions.join(", "));
+    };
+    let entry = &entries[idx];
+    if entry.source != StartupSource::UserAutostart {
+        bail!("{:?} is a {} entry; only user entries can be changed", entry.name, source_label(&entry.source));
+    }
+    if entry.locked {
+        bail!("{:?} is locked; unlock it first", entry.name);
+    }
+    Ok(entry)
+}
+
+fn cli_set_enabled(entries: &[StartupEntry], name: &str, enabled: bool) -> Result<String> {
+    let mut entry = cli_find_user_entry(entries, name)?.clone();
+    let path = validate_user_entry_path(entry.path.as_ref().context("Entry has no associated file path")?)?;
+    entry.enabled = enabled;
+    write_desktop_entry(&entry, &path)?;
+    Ok(format!("{} {}\n", if enabled { "Enabled" } else { "Disabled" }, entry.name))
+}
+
+fn cli_delete(entries: &[StartupEntry], name: &str) -> Result<String> {
+    let entry = cli_find_user_entry(entries, name)?;
+    let path = validate_user_entry_path(entry.path.as_ref().context("Entry has no associated file path")?)?;
+    fs::remove_file(&path).with_context(|| format!("Removing {:?}", path))?;
+    Ok(format!("Deleted {}\n", entry.name))
+}
+
+/// Writes the user entry `name` into each of `dirs` with `write_multiple_autostart_dirs`.
+fn cli_copy(entries: &[StartupEntry], name: &str, dirs: &[PathBuf]) -> Result<String> {
+    let entry = cli_find_user_entry(entries, name)?;
+    if dirs.is_empty() {
+        bail!("--copy needs at least one dir to copy {:?} into", entry.name);
+    }
+    let written = write_multiple_autostart_dirs(entry, dirs)?;
+    Ok(written.iter().map(|path| format!("Wrote {}\n", path.display())).collect())
+}
+
+/// What restoring the backup in `snapshot_dir` would change among the user entries, one
+/// tab-separated `added`, `removed` or `modified` line and file name per entry.
+fn cli_diff_snapshot(entries: &[StartupEntry], snapshot_dir: &Path) -> Result<String> {
+    let snapshot = load_entries_from_snapshot(snapshot_dir)?;
+    let current: Vec<StartupEntry> =
+        entries.iter().filter(|e| e.source == StartupSource::UserAutostart).cloned().collect();
+    let diff = diff_snapshots(&current, &snapshot);
+    let changes = diff
+        .added
+        .iter()
+        .map(|e| ("added", e))
+        .chain(diff.removed.iter().map(|e| ("removed", e)))
+        .chain(diff.modified.iter().map(|(cur, _)| ("modified", cur)));
+    let out: String = changes
+        .map(|(change, e)| format!("{change}\t{}\n", entry_file_name(e).unwrap_or_default()))
+        .collect();
+    Ok(if out.is_empty() { "No differences\n".to_string() } else { out })
+}
+
+/// Index of the first entry whose name equals `name`, ignoring case.
+fn find_entry_by_name(name: &str, entries: &[StartupEntry]) -> Option<usize> {
+    let name = name.to_lowercase();
+    entries.iter().position(|e| e.name.to_lowercase() == name)
+}
+
+/// Entries whose name fuzzily matches `name`, best score first (ties keep list order).
+fn find_entries_by_name_fuzzy(name: &str, entries: &[StartupEntry]) -> Vec<(usize, u32)> {
+    let mut matches: Vec<(usize, u32)> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, e)| fuzzy_score(name, &e.name).map(|score| (idx, score)))
+        .collect();
+    matches.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    matches
+}
+
+/// Case-insensitive subsequence match of `query` in `candidate`. Each matched character
+/// scores, with bonuses for runs of consecutive characters and for word starts; `None`
+/// when some character of `query` is missing.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<u32> {
+    let query: Vec<char> = query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    if query.is_empty() {
+        return None;
+    }
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut qi = 0;
+    let mut prev_match: Option<usize> = None;
+    for (ci, &c) in candidate.iter().enumerate() {
+        if qi == query.len() {
+            break;
+        }
+        if c != query[qi] {
+            continue;
+        }
+        score += 1;
+        if prev_match.is_some_and(|p| p + 1 == ci) {
+            score += 3;
+        }
+        if ci == 0 || !candidate[ci - 1].is_alphanumeric() {
+            score += 2;
+        }
+        prev_match = Some(ci);
+        qi += 1;
+    }
+    (qi == query.len()).then_some(score)
+}
+
+/// "N entries · X KB" for the status bar, counting the loaded user and system autostart files.
+fn entries_summary(entries: &[StartupEntry]) -> String {
+    let total = user_autostart_dir_total_size(entries) + system_autostart_dir_total_size(entries);
+    format!("{} entries · {}", entries.len(), format_size_human(total))
+}
+
+fn update_detail(state: &AppState) {
+    if let Some(idx) = state.selected.get() {
+        if let Some(entry) = state.entries.borrow().get(idx) {
+            state.detail_name.set_text(&display_name_with_fallback(entry, &current_locale()));
+            state.detail_icon.set_text(entry.icon().unwrap_or("None"));
+            set_optional_detail_row(&state.detail_comment, entry.comment());
+            set_optional_detail_row(&state.detail_generic_name, entry.generic_name());
+            state
+                .detail_terminal
+                .set_text(if entry.terminal { "yes" } else { "no" });
+            let delay = Some(entry.delay_seconds()).filter(|&s| s > 0).map(|s| format!("{s}s"));
+            set_optional_detail_row(&state.detail_delay, delay.as_deref());
+            fill_detail_extra(state, &strip_duplicate_suffix_on_write(entry));
+            fill_detail_localized_names(state, &entry.localized_names);
+            state.detail_command.set_text(&entry.command);
+            state.detail_binary_warning.set_visible(
+                entry
+                    .required_binary()
+                    .is_some_and(|binary| !binary_on_search_path(&binary, &state.search_path.borrow())),
+            );
+            state
+                .detail_command_info
+                .buffer()
+                .set_text(&entry.describe_command_verbosely());
+            state.detail_source.set_text(&entry.human_readable_source());
+            state
+                .detail_source
+                .set_tooltip_text(Some(&entry.source_tooltip()));
+            state
+                .detail_status
+                .set_text(&match state.running.borrow().get(&idx) {
+                    Some(pid) => format!("{} · running (PID {pid})", entry.status_label()),
+                    None => entry.status_label().to_string(),
+                });
+            let headers = entry.entry_group_headers();
+            state
+                .detail_sections
+                .set_text(&format!("Additional sections: [{}]", headers.join(", ")));
+            state.detail_sections.set_visible(!headers.is_empty());
+            let user_owned = matches!(entry.source, StartupSource::UserAutostart)
+                && entry
+                    .path
+                    .as_ref()
+                    .map(|p| is_user_owned_path(p))
+                    .unwrap_or(false);
+            state.toggle_button.set_sensitive(user_owned && !entry.locked);
+            state.delete_button.set_sensitive(user_owned && !entry.locked);
+            state.edit_button.set_sensitive(user_owned && !entry.locked);
+            state.lock_button.set_sensitive(user_owned);
+            let launchable = matches!(
+                entry.source,
+                StartupSource::UserAutostart
+                    | StartupSource::SystemAutostart
+                    | StartupSource::OpenboxAutostart
+                    | StartupSource::SwayConfig
+                    | StartupSource::I3Config
+                    | StartupSource::XfceAutostart
+                    | StartupSource::Hyprland
+                    | StartupSource::ShellProfile
+            );
+            state.schedule_button.set_sensitive(launchable);
+            state.check_launch_button.set_sensitive(launchable);
+            state
+                .lock_button
+                .set_label(if entry.locked { "Unlock" } else { "Lock" });
+            let has_update = state.system_updates.borrow().iter().any(|u| u.user_idx == idx);
+            state.update_from_system_button.set_visible(has_update);
+            state
+                .update_from_system_button
+                .set_sensitive(has_update && !entry.locked);
+            return;
+        }
+    }
+    state.detail_name.set_text("-");
+    state.detail_icon.set_text("-");
+    set_optional_detail_row(&state.detail_comment, None);
+    set_optional_detail_row(&state.detail_generic_name, None);
+    state.detail_terminal.set_text("-");
+    set_optional_detail_row(&state.detail_delay, None);
+    fill_detail_extra(state, &[]);
+    fill_detail_localized_names(state, &[]);
+    state.detail_command.set_text("-");
+    state.detail_binary_warning.set_visible(false);
+    state.detail_command_info.buffer().set_text("");
+    state.detail_source.set_text("-");
+    state.detail_source.set_tooltip_text(None);
+    state.detail_status.set_text("-");
+    state.detail_sections.set_visible(false);
+    state.toggle_button.set_sensitive(false);
+    state.delete_button.set_sensitive(false);
+    state.edit_button.set_sensitive(false);
+    state.lock_button.set_sensitive(false);
+    state.lock_button.set_label("Lock");
+    state.schedule_button.set_sensitive(false);
+    state.check_launch_button.set_sensitive(false);
+    state.update_from_system_button.set_visible(false);
+}
+
+fn toggle_selected(state: &AppState) -> Result<()> {
+    let idx = state.selected.get().context("No item selected")?;
+    let mut entries = state.entries.borrow_mut();
+    let entry = entries.get_mut(idx).context("Invalid selection")?;
+    let was_enabled = entry.enabled;
+    let path = write_entry_enabled_in(&user_autostart_dir(), entry, !was_enabled)?;
+    drop(entries);
+    push_undo(state, UndoEntry::Toggled { path, was_enabled });
+    set_status_with_undo_hint(state, if was_enabled { "Disabled" } else { "Enabled" });
+    refresh_entries(state)?;
+    Ok(())
+}
+
+/// Sets `enabled` on a user entry and writes it to its file under `dir`, returning the path.
+/// The entry is left as it was when the write fails.
+fn write_entry_enabled_in(dir: &Path, entry: &mut StartupEntry, enabled: bool) -> Result<PathBuf> {
+    if entry.source != StartupSource::UserAutostart {
+        bail!("Only user autostart entries can be toggled");
+    }
+    if entry.locked {
+        bail!("Entry is locked; unlock it first");
+    }
+    if entry.is_commented_out() {
+        bail!("Entry is commented out; restore it first");
+    }
+    let path = entry
+        .path
+        .clone()
+        .unwrap_or_else(|| dir.join(format!("{}.desktop", slugify(&entry.name))));
+    let path = validate_entry_path_in(dir, "user autostart dir", &path)?;
+    let was_enabled = entry.enabled;
+    entry.enabled = enabled;
+    if let Err(err) = write_atomic_with_rollback(entry, &path, &rollback_backup_path(&path)) {
+        entry.enabled = was_enabled;
+        return Err(err);
+    }
+    Ok(path)
+}
+
+/// Result of `set_entries_enabled_in`.
+#[derive(Debug, Default)]
+struct BulkToggle {
+    /// `(path, was_enabled)` for each file written, for the undo stack.
+    toggled: Vec<(PathBuf, bool)>,
+    /// "Name: reason" for each user entry that could not be changed.
+    errors: Vec<String>,
+}
+
+impl BulkToggle {
+    /// One undo step for every file written; `None` when nothing changed.
+    fn undo_entry(&self) -> Option<UndoEntry> {
+        let mut changes: Vec<UndoEntry> = self
+            .toggled
+            .iter()
+            .map(|(path, was_enabled)| UndoEntry::Toggled { path: path.clone(), was_enabled: *was_enabled })
+            .collect();
+        match changes.len() {
+            0 => None,
+            1 => changes.pop(),
+            _ => Some(UndoEntry::Batch(changes)),
+        }
+    }
+}
+
+/// Enables (`Some(true)`), disables (`Some(false)`) or inverts (`None`) the user entries among
+/// `indices`, writing each file under `dir`. Other sources and entries already in the wanted
+/// state are left alone; a failure is recorded and the rest still go ahead.
+fn set_entries_enabled_in(
+    dir: &Path,
+    entries: &mut [StartupEntry],
+    indices: &[usize],
+    force_enabled: Option<bool>,
+) -> BulkToggle {
+    let mut outcome = BulkToggle::default();
+    for &idx in indices {
+        let Some(entry) = entries.get_mut(idx).filter(|e| e.source == StartupSource::UserAutostart) else {
+            continue;
+        };
+        let was_enabled = entry.enabled;
+        let enabled = force_enabled.unwrap_or(!was_enabled);
+        if enabled == was_enabled {
+            continue;
+        }
+        match write_entry_enabled_in(dir, entry, enabled) {
+            Ok(path) => outcome.toggled.push((path, was_enabled)),
+            Err(err) => outcome
+                .errors
+                .push(format!("{}: {err:#}", display_name_with_fallback(entry, &current_locale()))),
+        }
+    }
+    outcome
+}
+
+/// `set_entries_enabled_in` for the user entries the list shows now. Entries that could not
+/// be changed are listed in a dialog afterwards.
+fn toggle_all_visible(state: &AppState, force_enabled: Option<bool>) -> Result<()> {
+    let indices = state.visible_indices.borrow().clone();
+    let outcome = set_entries_enabled_in(&user_autostart_dir(), &mut state.entries.borrow_mut(), &indices, force_enabled);
+    if let Some(change) = outcome.undo_entry() {
+        push_undo(state, change);
+    }
+    refresh_entries(state)?;
+    let count = outcome.toggled.len();
+    set_status_with_undo_hint(
+        state,
+        &format!("Toggled {count} {}", if count == 1 { "entry" } else { "entries" }),
+    );
+    if !outcome.errors.is_empty() {
+        show_report_dialog(state, "Some entries were not changed", &outcome.errors.join("\n"));
+    }
+    Ok(())
+}
+
+/// Asks before deleting the selected user entry; the file is removed once Delete is clicked.
+fn delete_selected(state: &AppState) -> Result<()> {
+    let idx = state.selected.get().context("No item selected")?;
+    let (name, path) = {
+        let entries = state.entries.borrow();
+        let entry = entries.get(idx).context("Invalid selection")?;
+        if entry.source != StartupSource::UserAutostart {
+            bail!("Only user autostart entries can be deleted");
+        }
+        if entry.locked {
+            bail!("Entry is locked; unlock it first");
+        }
+        let path = entry
+            .path
+            .clone()
+            .context("Entry has no associated file path")?;
+        (display_name_with_fallback(entry, &current_locale()), path)
+    };
+    let state_for_delete = state.clone();
+    show_confirm_delete_dialog(state, &name, move || {
+        if let Err(err) = delete_user_entry_file(&state_for_delete, &path) {
+            state_for_delete.status_bar.set_text(&format!("Delete failed: {err:#}"));
+        }
+    });
+    Ok(())
+}
+
+/// Deletes the user entry loaded from `path`, looked up again since the list may have been
+/// reloaded while the confirmation was open.
+fn delete_user_entry_file(state: &AppState, path: &Path) -> Result<()> {
+    let entries = state.entries.borrow();
+    let entry = entries
+        .iter()
+        .find(|e| e.source == StartupSource::UserAutostart && e.path.as_deref() == Some(path))
+        .context("The entry is no longer in the list")?;
+    let path = validate_user_entry_path(path)?;
+    fs::remove_file(&path).with_context(|| format!("Removing {:?}", path))?;
+    // Take the launcher installed alongside it, if any, but never someone else's.
+    let slug = slugify(&entry.name);
+    if is_application_copy_of(entry, &xdg_data_applications_dir().join(format!("{slug}.desktop"))) {
+        remove_xdg_data_home_desktop_file(&slug)?;
+    }
+    let deleted = entry.clone();
+    drop(entries);
+    push_undo(state, UndoEntry::Deleted { path, entry: deleted });
+    set_status_with_undo_hint(state, "Deleted entry");
+    refresh_entries(state)?;
+    Ok(())
+}
+
+/// Records a change for Ctrl+Z; anything previously undone can no longer be redone.
+fn push_undo(state: &AppState, entry: UndoEntry) {
+    state.undo_stack.borrow_mut().push(entry);
+}
+
+/// `message`, followed by what Ctrl+Z would undo, if anything.
+fn set_status_with_undo_hint(state: &AppState, message: &str) {
+    match state.undo_stack.borrow().next_undo() {
+        Some(action) => state
+            .status_bar
+            .set_text(&format!("{message} · Ctrl+Z undoes {}", action.label())),
+        None => state.status_bar.set_text(message),
+    }
+}
+
+/// Reverses the most recent add, delete, edit or toggle and reloads.
+fn undo(state: &AppState) -> Result<()> {
+    let action = state.undo_stack.borrow_mut().pop_undo().context("Nothing to undo")?;
+    let label = action.label();
+    match revert_checked(&action) {
+        Ok(inverse) => state.undo_stack.borrow_mut().push_redo(inverse),
+        Err(err) => {
+            state.undo_stack.borrow_mut().restore_undo(action);
+            return Err(err);
+        }
+    }
+    refresh_entries(state)?;
+    state.status_bar.set_text(&format!("Undid {label} · Ctrl+Shift+Z redoes it"));
+    Ok(())
+}
+
+/// Reapplies the change the last `undo` reversed and reloads.
+fn redo(state: &AppState) -> Result<()> {
+    let action = state.undo_stack.borrow_mut().pop_redo().context("Nothing to redo")?;
+    let label = action.label();
+    match revert_checked(&action) {
+        Ok(inverse) => state.undo_stack.borrow_mut().restore_undo(inverse),
+        Err(err) => {
+            state.undo_stack.borrow_mut().push_redo(action);
+            return Err(err);
+        }
+    }
+    refresh_entries(state)?;
+    set_status_with_undo_hint(state, &format!("Redid {label}"));
+    Ok(())
+}
+
+/// `UndoEntry::revert`, once every file it touches has passed `validate_user_entry_path`.
+fn revert_checked(action: &UndoEntry) -> Result<UndoEntry> {
+    for path in action.paths() {
+        validate_user_entry_path(path)?;
+    }
+    action.revert()
+}
+
+/// Deletes every unlocked `.desktop` file in `user_autostart_dir()` and reloads. Only called
+/// once the Delete All dialogs have been confirmed.
+fn clear_user_autostart_dir(state: &AppState) -> Result<usize> {
+    let count = clear_autostart_dir(&user_autostart_dir())?;
+    refresh_entries(state)?;
+    Ok(count)
+}
+
+/// Removes the `.desktop` files in `dir`, leaving locked entries alone. Returns how many
+/// were deleted.
+fn clear_autostart_dir(dir: &Path) -> Result<usize> {
+    let mut count = 0;
+    for path in desktop_files_in(dir)? {
+        let meta = fs::symlink_metadata(&path).with_context(|| format!("Reading {:?}", path))?;
+        if !meta.is_file() {
+            continue;
+        }
+        if parse_desktop_file(&path, StartupSource::UserAutostart).is_ok_and(|e| e.locked) {
+            continue;
+        }
+        fs::remove_file(&path).with_context(|| format!("Removing {:?}", path))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Second Delete All step: the typed text must be exactly `DELETE`, surrounding spaces aside.
+fn delete_all_confirmation_matches(typed: &str) -> bool {
+    typed.trim() == "DELETE"
+}
+
+/// Three confirmations before `clear_user_autostart_dir`: a count, typing DELETE, and a
+/// last chance. Cancelling any step stops the chain.
+fn show_clear_user_entries_dialog(state: &AppState) -> Result<()> {
+    let count = state
+        .entries
+        .borrow()
+        .iter()
+        .filter(|e| e.source == StartupSource::UserAutostart && !e.locked)
+        .count();
+    if count == 0 {
+        bail!("There are no unlocked user entries to delete");
+    }
+    let next = state.clone();
+    show_confirm_bulk_delete_dialog(state, count, move || {
+        let next_state = next.clone();
+        let message = "Locked user entries are kept. Type DELETE to confirm.";
+        show_confirm_step(&next, "Delete all user entries", message, "Continue", true, move || {
+            let state = next_state.clone();
+            show_confirm_step(&next_state, "Delete all user entries", "Last chance — this cannot be undone.", "Delete All", false, move || {
+                match clear_user_autostart_dir(&state) {
+                    Ok(deleted) => state
+                        .status_bar
+                        .set_text(&format!("Deleted {deleted} user entries")),
+                    Err(err) => state
+                        .status_bar
+                        .set_text(&format!("Delete all failed: {err:#}")),
+                }
+            })
+        })
+    });
+    Ok(())
+}
+
+/// "Delete '{name}'?" confirmation for a single entry; `on_confirm` runs only on Delete.
+fn show_confirm_delete_dialog(state: &AppState, entry_name: &str, on_confirm: impl Fn() + 'static) {
+    let message = format!("Delete '{entry_name}'? This will permanently remove the file.");
+    show_confirm_step(state, "Delete entry", &message, "Delete", false, on_confirm);
+}
+
+/// The same confirmation for deleting `count` entries at once; the first step of Delete All.
+fn show_confirm_bulk_delete_dialog(state: &AppState, count: usize, on_confirm: impl Fn() + 'static) {
+    let message = if count == 1 {
+        "Delete 1 entry? This will permanently remove its file.".to_string()
+    } else {
+        format!("Delete {count} entries? This will permanently remove their files.")
+    };
+    show_confirm_step(state, "Delete entries", &message, "Delete", false, on_confirm);
+}
+
+/// One modal confirmation, such as a step of the Delete All chain; `on_accept` runs after it
+/// closes. With `require_delete_text`, the accept button stays insensitive until DELETE is typed.
+fn show_confirm_step(
+    state: &AppState,
+    title: &str,
+    message: &str,
+    accept_label: &str,
+    require_delete_text: bool,
+    on_accept: impl Fn() + 'static,
+) {
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let dialog = Dialog::with_buttons(
+        Some(title),
+        parent.as_ref(),
+        gtk4::DialogFlags::MODAL,
+        &[("Cancel", ResponseType::Cancel), (accept_label, ResponseType::Accept)],
+    );
+    dialog.set_accessible_role(AccessibleRole::AlertDialog);
+    dialog.set_default_response(ResponseType::Cancel);
+    let content = dialog.content_area();
+    content.set_spacing(6);
+    let label = Label::new(Some(message));
+    label.set_wrap(true);
+    content.append(&label);
+    if let Some(accept) = dialog.widget_for_response(ResponseType::Accept) {
+        accept.add_css_class("destructive-action");
+        if require_delete_text {
+            accept.set_sensitive(false);
+            let entry = Entry::new();
+            entry.set_accessible_role(AccessibleRole::TextBox);
+            entry.update_property(&[gtk4::accessible::Property::Label("Type DELETE")]);
+            label.set_mnemonic_widget(Some(&entry));
+            entry.connect_changed(move |e| accept.set_sensitive(delete_all_confirmation_matches(&e.text())));
+            content.append(&entry);
+        }
+    }
+    dialog.connect_response(move |dlg, resp| {
+        dlg.close();
+        if resp == ResponseType::Accept {
+            on_accept();
+        }
+    });
+    dialog.present();
+}
+
+fn lock_entry(idx: usize, state: &AppState) -> Result<()> {
+    set_entry_locked(idx, state, true)?;
+    state.status_bar.set_text("Locked entry");
+    Ok(())
+}
+
+fn unlock_entry(idx: usize, state: &AppState) -> Result<()> {
+    set_entry_locked(idx, state, false)?;
+    state.status_bar.set_text("Unlocked entry");
+    Ok(())
+}
+
+fn set_entry_locked(idx: usize, state: &AppState, locked: bool) -> Result<()> {
+    {
+        let mut entries = state.entries.borrow_mut();
+        let entry = entries.get_mut(idx).context("Invalid selection")?;
+        if entry.source != StartupSource::UserAutostart {
+            bail!("Only user autostart entries can be locked");
+        }
+        if entry.is_commented_out() {
+            bail!("Entry is commented out; restore it first");
+        }
+        let path = entry
+            .path
+            .as_ref()
+            .context("Entry has no associated file path")?;
+        let path = validate_user_entry_path(path)?;
+        entry.locked = locked;
+        write_desktop_entry(entry, &path)?;
+    }
+    refresh_entries(state)
+}
+
+/// Comments out the selected user entry's file, or restores a commented-out one.
+fn toggle_selected_commented_out(state: &AppState) -> Result<()> {
+    let idx = state.selected.get().context("No item selected")?;
+    let restored = {
+        let mut entries = state.entries.borrow_mut();
+        let entry = entries.get_mut(idx).context("Invalid selection")?;
+        if entry.source != StartupSource::UserAutostart {
+            bail!("Only user autostart entries can be commented out");
+        }
+        if entry.locked {
+            bail!("Entry is locked; unlock it first");
+        }
+        let path = entry
+            .path
+            .as_ref()
+            .context("Entry has no associated file path")?;
+        let path = validate_user_entry_path(path)?;
+        let restored = entry.is_commented_out();
+        if restored {
+            entry.uncomment_entry();
+        } else {
+            entry.comment_out_entry();
+        }
+        write_desktop_entry(entry, &path)?;
+        restored
+    };
+    refresh_entries(state)?;
+    state
+        .status_bar
+        .set_text(if restored { "Restored entry" } else { "Commented out entry" });
+    Ok(())
+}
+
+/// A user override whose system counterpart (same file name) was modified after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct UpdateAvailable {
+    user_idx: usize,
+    system_idx: usize,
+    #[allow(dead_code)]
+    system_mtime: SystemTime,
+}
+
+/// Pairs user overrides with system entries of the same file name and reports those where
+/// the system file is newer. Indices refer to the two slices, which may be the same list;
+/// entries of other sources are ignored.
+fn check_for_updates_from_system(
+    user_entries: &[StartupEntry],
+    system_entries: &[StartupEntry],
+) -> Vec<UpdateAvailable> {
+    let mtime = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut updates = Vec::new();
+    for (user_idx, user) in user_entries.iter().enumerate() {
+        if user.source != StartupSource::UserAutostart {
+            continue;
+        }
+        let Some(user_path) = user.path.as_deref() else {
+            continue;
+        };
+        let Some(file_name) = user_path.file_name() else {
+            continue;
+        };
+        let Some((system_idx, system_path)) = system_entries.iter().enumerate().find_map(|(i, e)| {
+            let path = e.path.as_deref()?;
+            (e.source == StartupSource::SystemAutostart && path.file_name() == Some(file_name))
+                .then_some((i, path))
+        }) else {
+            continue;
+        };
+        if let (Some(user_mtime), Some(system_mtime)) = (mtime(user_path), mtime(system_path)) {
+            if system_mtime > user_mtime {
+                updates.push(UpdateAvailable {
+                    user_idx,
+                    system_idx,
+                    system_mtime,
+                });
+            }
+        }
+    }
+    updates
+}
+
+/// The system entry rewritten as the user's override: keeps the user's file, enabled state
+/// and entry id, takes everything else from the system version.
+fn merge_system_update(user: &StartupEntry, system: &StartupEntry) -> StartupEntry {
+    let mut merged = system.clone();
+    merged.source = StartupSource::UserAutostart;
+    merged.path = user.path.clone();
+    merged.enabled = user.enabled;
+    merged.extra.retain(|(k, _)| k != USM_ID_KEY);
+    if let Some(id) = user.entry_id() {
+        merged.extra.push((USM_ID_KEY.to_string(), id.to_string()));
+    }
+    merged
+}
+
+fn update_from_system(idx: usize, state: &AppState) -> Result<()> {
+    {
+        let entries = state.entries.borrow();
+        let update = state
+            .system_updates
+            .borrow()
+            .iter()
+            .find(|u| u.user_idx == idx)
+            .cloned()
+            .context("No newer system version for this entry")?;
+        let user = &entries[update.user_idx];
+        if user.locked {
+            bail!("Entry is locked; unlock it first");
+        }
+        let path = validate_user_entry_path(user.path.as_ref().context("Entry has no associated file path")?)?;
+        let merged = merge_system_update(user, &entries[update.system_idx]);
+        write_desktop_entry(&merged, &path)?;
+    }
+    refresh_entries(state)?;
+    state.status_bar.set_text("Updated entry from the system version");
+    Ok(())
+}
+
+fn show_add_dialog(state: &AppState) -> Result<()> {
+    show_add_dialog_with(state, "", "")
+}
+
+fn show_add_dialog_with(state: &AppState, name: &str, command: &str) -> Result<()> {
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let dialog = Dialog::with_buttons(
+        Some("Add autostart entry"),
+        parent.as_ref(),
+        gtk4::DialogFlags::MODAL,
+        &[("Cancel", ResponseType::Cancel), ("Add", ResponseType::Ok)],
+    );
+
+    let content = dialog.content_area();
+    content.set_spacing(6);
+    let name_label = Label::new(Some("Name:"));
+    let name_entry = Entry::new();
+    name_entry.set_placeholder_text(Some("Name"));
+    name_entry.set_text(name);
+    name_entry.set_accessible_role(AccessibleRole::TextBox);
+    name_label.set_mnemonic_widget(Some(&name_entry));
+
+    let cmd_label = Label::new(Some("Command:"));
+    let cmd_entry = Entry::new();
+    cmd_entry.set_placeholder_text(Some("Command"));
+    cmd_entry.set_text(command);
+    cmd_entry.set_accessible_role(AccessibleRole::TextBox);
+    cmd_label.set_mnemonic_widget(Some(&cmd_entry));
+
+    content.append(&name_label);
+    content.append(&name_entry);
+    content.append(&cmd_label);
+    content.append(&cmd_entry);
+    let terminal_cb = CheckButton::with_label("Run in terminal");
+    terminal_cb.set_tooltip_text(Some("Open the command in a terminal window (Terminal=true)"));
+    content.append(&terminal_cb);
+    let install_app_cb = CheckButton::with_label("Also install as application");
+    install_app_cb.set_tooltip_text(Some("Add a launcher to ~/.local/share/applications as well"));
+    content.append(&install_app_cb);
+
+    dialog.connect_response({
+        let state = state.clone();
+        move |dlg, resp| {
+            if resp == ResponseType::Ok {
+                let name = name_entry.text().to_string();
+                let cmd = cmd_entry.text().to_string();
+                let res = create_user_entry(&name, &cmd, terminal_cb.is_active()).and_then(|path| {
+                    push_undo(&state, UndoEntry::Added { path: path.clone() });
+                    if install_app_cb.is_active() {
+                        let entry = parse_desktop_file(&path, StartupSource::UserAutostart)?;
+                        generate_xdg_data_home_desktop_file(&entry)?;
+                    }
+                    Ok(())
+                });
+                if let Err(err) = res {
+                    state
+                        .status_bar
+                        .set_text(&format!("Failed to add entry: {err:#}"));
+                } else if let Err(err) = refresh_entries(&state) {
+                    state
+                        .status_bar
+                        .set_text(&format!("Failed to refresh after add: {err:#}"));
+                } else {
+                    set_status_with_undo_hint(&state, "Added entry");
+                }
+            }
+            dlg.close();
+        }
+    });
+
+    dialog.show();
+    Ok(())
+}
+
+fn show_batch_rename_dialog(state: &AppState) -> Result<()> {
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let dialog = Dialog::with_buttons(
+        Some("Batch rename user entries"),
+        parent.as_ref(),
+        gtk4::DialogFlags::MODAL,
+        &[("Cancel", ResponseType::Cancel), ("Rename", ResponseType::Ok)],
+    );
+
+    let content = dialog.content_area();
+    content.set_spacing(6);
+    content.append(&Label::new(Some(
+        "Use * to match any text; each * in the replacement inserts the matched text.",
+    )));
+    let find_label = Label::new(Some("Find:"));
+    let find_entry = Entry::new();
+    find_entry.set_placeholder_text(Some("My App *"));
+    find_entry.set_accessible_role(AccessibleRole::TextBox);
+    find_label.set_mnemonic_widget(Some(&find_entry));
+
+    let replace_label = Label::new(Some("Replace with:"));
+    let replace_entry = Entry::new();
+    replace_entry.set_placeholder_text(Some("App *"));
+    replace_entry.set_accessible_role(AccessibleRole::TextBox);
+    replace_label.set_mnemonic_widget(Some(&replace_entry));
+
+    content.append(&find_label);
+    content.append(&find_entry);
+    content.append(&replace_label);
+    content.append(&replace_entry);
+
+    dialog.connect_response({
+        let state = state.clone();
+        move |dlg, resp| {
+            if resp == ResponseType::Ok {
+                let pattern = find_entry.text().to_string();
+                let replacement = replace_entry.text().to_string();
+                match batch_rename_user_entries(&state, &pattern, &replacement) {
+                    Err(err) => state
+                        .status_bar
+                        .set_text(&format!("Batch rename failed: {err:#}")),
+                    Ok(count) => {
+                        if let Err(err) = refresh_entries(&state) {
+                            state
+                                .status_bar
+                                .set_text(&format!("Failed to refresh after rename: {err:#}"));
+                        } else {
+                            state.status_bar.set_text(&format!("Renamed {count} entries"));
+                        }
+                    }
+                }
+            }
+            dlg.close();
+        }
+    });
+
+    dialog.show();
+    Ok(())
+}
+
+/// Renames in memory, then writes each renamed entry to its new file and removes the old one.
+fn batch_rename_user_entries(state: &AppState, pattern: &str, replacement: &str) -> Result<usize> {
+    let before = state.entries.borrow().clone();
+    let mut after = before.clone();
+    let count = batch_rename_entries(pattern, replacement, &mut after)?;
+    for (old, new) in before.iter().zip(after.iter()) {
+        if old.name == new.name {
+            continue;
+        }
+        let new_path = validate_user_entry_path(new.path.as_ref().context("Entry has no associated file path")?)?;
+        write_desktop_entry(new, &new_path)?;
+        if let Some(old_path) = old.path.as_ref() {
+            if old_path != &new_path {
+                let old_path = validate_user_entry_path(old_path)?;
+                fs::remove_file(&old_path).with_context(|| format!("Removing {:?}", old_path))?;
+            }
+        }
+    }
+    Ok(count)
+}
+
+fn show_edit_dialog(state: &AppState) -> Result<()> {
+    let idx = state.selected.get().context("No item selected")?;
+    let entry = {
+        let entries = state.entries.borrow();
+        entries.get(idx).cloned().context("Invalid selection")?
+    };
+    if entry.source != StartupSource::UserAutostart {
+        bail!("Only user entries can be edited");
+    }
+    if entry.locked {
+        bail!("Entry is locked; unlock it first");
+    }
+
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let title = format!("Edit {}", display_name_with_fallback(&entry, &current_locale()));
+    let dialog = Dialog::with_buttons(
+        Some(title.as_str()),
+        parent.as_ref(),
+        gtk4::DialogFlags::MODAL,
+        &[("Cancel", ResponseType::Cancel), ("Save", ResponseType::Ok)],
+    );
+
+    let content = dialog.content_area();
+    content.set_spacing(6);
+    let name_label = Label::new(Some("Name:"));
+    let name_entry = Entry::new();
     name_entry.set_placeholder_text(Some("Name"));
     name_entry.set_text(&entry.name);
     name_entry.set_accessible_role(AccessibleRole::TextBox);
     name_label.set_mnemonic_widget(Some(&name_entry));
 
-    let cmd_label = Label::new(Some("Command:"));
-    let cmd_entry = Entry::new();
-    cmd_entry.set_placeholder_text(Some("Command"));
-    cmd_entry.set_text(&entry.command);
-    cmd_entry.set_accessible_role(AccessibleRole::TextBox);
-    cmd_label.set_mnemonic_widget(Some(&cmd_entry));
+    let cmd_label = Label::new(Some("Command:"));
+    let cmd_entry = Entry::new();
+    cmd_entry.set_placeholder_text(Some("Command"));
+    cmd_entry.set_text(&entry.command);
+    cmd_entry.set_accessible_role(AccessibleRole::TextBox);
+    cmd_label.set_mnemonic_widget(Some(&cmd_entry));
+
+    let env_label = Label::new(Some("Environment Variables (one KEY=value per line):"));
+    let env_view = TextView::new();
+    env_view.set_monospace(true);
+    env_view.update_property(&[gtk4::accessible::Property::Label("Environment variables")]);
+    let env_text: Vec<String> = entry.env_vars.iter().map(|(k, v)| format!("{k}={v}")).collect();
+    env_view.buffer().set_text(&env_text.join("\n"));
+
+    let terminal_cb = CheckButton::with_label("Run in terminal");
+    terminal_cb.set_tooltip_text(Some("Open the command in a terminal window (Terminal=true)"));
+    terminal_cb.set_active(entry.terminal);
+
+    let comment_label = Label::new(Some("Description:"));
+    let comment_entry = Entry::new();
+    comment_entry.set_placeholder_text(Some("Comment shown in menus and tooltips"));
+    comment_entry.set_text(entry.comment().unwrap_or_default());
+    comment_entry.set_accessible_role(AccessibleRole::TextBox);
+    comment_label.set_mnemonic_widget(Some(&comment_entry));
+
+    let generic_label = Label::new(Some("Generic name:"));
+    let generic_entry = Entry::new();
+    generic_entry.set_placeholder_text(Some("e.g. Web Browser"));
+    generic_entry.set_text(entry.generic_name().unwrap_or_default());
+    generic_entry.set_accessible_role(AccessibleRole::TextBox);
+    generic_label.set_mnemonic_widget(Some(&generic_entry));
+
+    content.append(&name_label);
+    content.append(&name_entry);
+    content.append(&cmd_label);
+    content.append(&cmd_entry);
+    content.append(&comment_label);
+    content.append(&comment_entry);
+    content.append(&generic_label);
+    content.append(&generic_entry);
+    let delay_label = Label::new(Some("Startup delay (seconds):"));
+    let delay_spin = gtk4::SpinButton::with_range(0.0, 3600.0, 1.0);
+    delay_spin.set_value(f64::from(entry.delay_seconds()));
+    delay_spin.set_tooltip_text(Some("Seconds GNOME waits before starting the entry (X-GNOME-Autostart-Delay)"));
+    delay_label.set_mnemonic_widget(Some(&delay_spin));
+    let delay_row = GtkBox::new(Orientation::Horizontal, 6);
+    delay_row.append(&delay_label);
+    delay_row.append(&delay_spin);
+
+    content.append(&terminal_cb);
+    content.append(&delay_row);
+    content.append(&env_label);
+    content.append(&env_view);
+
+    // Localized names and desktop lists are edited on a working copy that the preview and
+    // Save read from.
+    let localized = Rc::new(RefCell::new(entry.clone()));
+    let localized_list = ListBox::new();
+    localized_list.set_selection_mode(SelectionMode::None);
+    localized_list.update_property(&[gtk4::accessible::Property::Label("Localized names")]);
+    fill_localized_names_list(&localized_list, &localized);
+    let locale_entry = Entry::new();
+    locale_entry.set_placeholder_text(Some("Locale, e.g. de"));
+    locale_entry.update_property(&[gtk4::accessible::Property::Label("Locale")]);
+    let localized_value_entry = Entry::new();
+    localized_value_entry.set_placeholder_text(Some("Translated name"));
+    localized_value_entry.set_hexpand(true);
+    localized_value_entry.update_property(&[gtk4::accessible::Property::Label("Translated name")]);
+    let add_localized_button = Button::with_label("Add");
+    add_localized_button.set_tooltip_text(Some("Add or replace the name for this locale"));
+    let localized_error = Label::new(None);
+    localized_error.set_xalign(0.0);
+    localized_error.set_wrap(true);
+    let add_localized_row = GtkBox::new(Orientation::Horizontal, 6);
+    add_localized_row.append(&locale_entry);
+    add_localized_row.append(&localized_value_entry);
+    add_localized_row.append(&add_localized_button);
+    let localized_box = GtkBox::new(Orientation::Vertical, 6);
+    localized_box.append(&localized_list);
+    localized_box.append(&add_localized_row);
+    localized_box.append(&localized_error);
+    let localized_expander = gtk4::Expander::new(Some("Localized Names"));
+    localized_expander.set_child(Some(&localized_box));
+    content.append(&localized_expander);
+    content.append(&desktop_list_expander("Only Show In", ONLY_SHOW_IN_KEY, &localized));
+    content.append(&desktop_list_expander("Not Show In", NOT_SHOW_IN_KEY, &localized));
+    add_localized_button.connect_clicked({
+        let localized = localized.clone();
+        let localized_list = localized_list.clone();
+        move |_| {
+            let res = localized
+                .borrow_mut()
+                .add_localized_name(locale_entry.text().trim(), &localized_value_entry.text());
+            match res {
+                Ok(()) => {
+                    locale_entry.set_text("");
+                    localized_value_entry.set_text("");
+                    localized_error.set_text("");
+                    fill_localized_names_list(&localized_list, &localized);
+                }
+                Err(err) => localized_error.set_text(&format!("{err:#}")),
+            }
+        }
+    });
+
+    // Everything but name and command, which go through `apply_edit`.
+    let with_fields = Rc::new({
+        let env_view = env_view.clone();
+        move |entry: &StartupEntry| {
+            let buffer = env_view.buffer();
+            let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+            let mut entry = entry.clone();
+            entry.env_vars = parse_environment_assignments(&text);
+            entry.terminal = terminal_cb.is_active();
+            // Untouched fields keep their exact original text.
+            if comment_entry.text().trim() != entry.comment().unwrap_or_default() {
+                entry.comment = Some(comment_entry.text().trim().to_string()).filter(|v| !v.is_empty());
+            }
+            if generic_entry.text().trim() != entry.generic_name().unwrap_or_default() {
+                entry.generic_name = Some(generic_entry.text().trim().to_string()).filter(|v| !v.is_empty());
+            }
+            let delay = delay_spin.value_as_int().max(0) as u32;
+            if delay != entry.delay_seconds() {
+                entry.set_delay_seconds(delay);
+            }
+            entry
+        }
+    });
+
+    let preview_button = Button::with_label("Preview");
+    preview_button.set_accessible_role(AccessibleRole::Button);
+    preview_button.set_tooltip_text(Some("Show the .desktop file that will be written"));
+    content.append(&preview_button);
+    preview_button.connect_clicked({
+        let localized = localized.clone();
+        let name_entry = name_entry.clone();
+        let cmd_entry = cmd_entry.clone();
+        let with_fields = with_fields.clone();
+        let dialog = dialog.clone();
+        move |_| {
+            let updated = apply_edit(
+                &with_fields(&localized.borrow()),
+                &name_entry.text(),
+                &cmd_entry.text(),
+            );
+            show_preview_dialog(&dialog, &preview_write_output(&updated));
+        }
+    });
+
+    dialog.connect_response({
+        let state = state.clone();
+        let original_path = entry.path.clone();
+        move |dlg, resp| {
+            if resp == ResponseType::Ok {
+                let new_name = name_entry.text().to_string();
+                let new_cmd = cmd_entry.text().to_string();
+                if new_name.trim().is_empty() || new_cmd.trim().is_empty() {
+                    state
+                        .status_bar
+                        .set_text("Name and command cannot be empty");
+                    dlg.close();
+                    return;
+                }
+                let edited = with_fields(&localized.borrow());
+                let res = edit_user_entry(&edited, &new_name, &new_cmd, original_path.as_ref()).map(|path| {
+                    push_undo(&state, UndoEntry::Edited { path: path.clone(), original: entry.clone() });
+                    rewrite_entry_path_on_rename(&state, idx, original_path.as_deref(), &new_name, path);
+                });
+                if let Err(err) = res {
+                    state
+                        .status_bar
+                        .set_text(&format!("Failed to save: {err:#}"));
+                } else if let Err(err) = refresh_entries(&state) {
+                    state
+                        .status_bar
+                        .set_text(&format!("Failed to refresh after edit: {err:#}"));
+                } else {
+                    set_status_with_undo_hint(&state, "Saved entry");
+                }
+            }
+            dlg.close();
+        }
+    });
+
+    dialog.show();
+    Ok(())
+}
+
+/// One row per `Name[locale]` with a Remove button, for the edit dialog's Localized Names.
+fn fill_localized_names_list(list: &ListBox, edited: &Rc<RefCell<StartupEntry>>) {
+    while let Some(child) = list.first_child() {
+        list.remove(&child);
+    }
+    let names = edited.borrow().localized_names.clone();
+    if names.is_empty() {
+        list.append(&Label::new(Some("No localized names")));
+        return;
+    }
+    for (locale, value) in names {
+        let row = GtkBox::new(Orientation::Horizontal, 6);
+        let label = Label::new(Some(&format!("{locale}: {value}")));
+        label.set_xalign(0.0);
+        label.set_hexpand(true);
+        let remove = Button::with_label("Remove");
+        remove.update_property(&[gtk4::accessible::Property::Label(&format!("Remove the {locale} name"))]);
+        remove.connect_clicked({
+            let list = list.clone();
+            let edited = edited.clone();
+            move |_| {
+                edited.borrow_mut().remove_localized_name(&locale);
+                fill_localized_names_list(&list, &edited);
+            }
+        });
+        row.append(&label);
+        row.append(&remove);
+        list.append(&row);
+    }
+}
+
+/// Edit dialog section for a desktop list key (`OnlyShowIn=`, `NotShowIn=`): one row per
+/// desktop with a Remove button, and a picker of common desktops or a typed name to add.
+fn desktop_list_expander(title: &str, key: &'static str, edited: &Rc<RefCell<StartupEntry>>) -> gtk4::Expander {
+    let list = ListBox::new();
+    list.set_selection_mode(SelectionMode::None);
+    list.update_property(&[gtk4::accessible::Property::Label(title)]);
+    fill_desktop_list(&list, key, edited);
+
+    let picker = gtk4::DropDown::from_strings(&COMMON_DESKTOP_NAMES);
+    picker.update_property(&[gtk4::accessible::Property::Label("Common desktops")]);
+    let name_entry = Entry::new();
+    name_entry.set_placeholder_text(Some("Desktop, e.g. GNOME"));
+    name_entry.set_hexpand(true);
+    name_entry.update_property(&[gtk4::accessible::Property::Label("Desktop name")]);
+    picker.connect_selected_notify({
+        let name_entry = name_entry.clone();
+        move |picker| {
+            if let Some(name) = COMMON_DESKTOP_NAMES.get(picker.selected() as usize) {
+                name_entry.set_text(name);
+            }
+        }
+    });
+    let add_button = Button::with_label("Add");
+    add_button.set_tooltip_text(Some("Add this desktop to the list"));
+    let error = Label::new(None);
+    error.set_xalign(0.0);
+    error.set_wrap(true);
+    let add_row = GtkBox::new(Orientation::Horizontal, 6);
+    add_row.append(&picker);
+    add_row.append(&name_entry);
+    add_row.append(&add_button);
+    add_button.connect_clicked({
+        let list = list.clone();
+        let edited = edited.clone();
+        let error = error.clone();
+        let picker = picker.clone();
+        move |_| {
+            // An empty entry adds whatever the picker shows.
+            let typed = name_entry.text();
+            let value = if typed.trim().is_empty() {
+                COMMON_DESKTOP_NAMES.get(picker.selected() as usize).copied().unwrap_or_default()
+            } else {
+                typed.as_str()
+            };
+            let res = edited.borrow_mut().add_desktop_list_value(key, value);
+            match res {
+                Ok(()) => {
+                    name_entry.set_text("");
+                    error.set_text("");
+                    fill_desktop_list(&list, key, &edited);
+                }
+                Err(err) => error.set_text(&format!("{err:#}")),
+            }
+        }
+    });
+
+    let section = GtkBox::new(Orientation::Vertical, 6);
+    section.append(&list);
+    section.append(&add_row);
+    section.append(&error);
+    let expander = gtk4::Expander::new(Some(title));
+    expander.set_child(Some(&section));
+    expander
+}
+
+fn fill_desktop_list(list: &ListBox, key: &'static str, edited: &Rc<RefCell<StartupEntry>>) {
+    while let Some(child) = list.first_child() {
+        list.remove(&child);
+    }
+    let values = edited.borrow().desktop_list(key);
+    if values.is_empty() {
+        list.append(&Label::new(Some("No desktops")));
+        return;
+    }
+    for value in values {
+        let row = GtkBox::new(Orientation::Horizontal, 6);
+        let label = Label::new(Some(&value));
+        label.set_xalign(0.0);
+        label.set_hexpand(true);
+        let remove = Button::with_label("Remove");
+        remove.update_property(&[gtk4::accessible::Property::Label(&format!("Remove {value}"))]);
+        remove.connect_clicked({
+            let list = list.clone();
+            let edited = edited.clone();
+            move |_| {
+                edited.borrow_mut().remove_desktop_list_value(key, &value);
+                fill_desktop_list(&list, key, &edited);
+            }
+        });
+        row.append(&label);
+        row.append(&remove);
+        list.append(&row);
+    }
+}
+
+fn show_schedule_timer_dialog(state: &AppState) -> Result<()> {
+    let idx = state.selected.get().context("No item selected")?;
+    let entry = state
+        .entries
+        .borrow()
+        .get(idx)
+        .cloned()
+        .context("Invalid selection")?;
+
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let dialog = Dialog::with_buttons(
+        Some("Schedule as systemd timer"),
+        parent.as_ref(),
+        gtk4::DialogFlags::MODAL,
+        &[("Cancel", ResponseType::Cancel), ("Create Timer", ResponseType::Ok)],
+    );
+
+    let content = dialog.content_area();
+    content.set_spacing(6);
+    let schedule_label = Label::new(Some("Schedule (OnCalendar=, e.g. daily, weekly, Mon *-*-* 09:00):"));
+    let schedule_entry = Entry::new();
+    schedule_entry.set_text("daily");
+    schedule_entry.set_accessible_role(AccessibleRole::TextBox);
+    schedule_label.set_mnemonic_widget(Some(&schedule_entry));
+    content.append(&schedule_label);
+    content.append(&schedule_entry);
+
+    dialog.connect_response({
+        let state = state.clone();
+        move |dlg, resp| {
+            if resp == ResponseType::Ok {
+                match create_systemd_user_timer(&entry, &schedule_entry.text()) {
+                    Err(err) => state
+                        .status_bar
+                        .set_text(&format!("Failed to create timer: {err:#}")),
+                    Ok(()) => {
+                        if let Err(err) = refresh_entries(&state) {
+                            state
+                                .status_bar
+                                .set_text(&format!("Failed to refresh after scheduling: {err:#}"));
+                        } else {
+                            state.status_bar.set_text(
+                                "Timer created; run `systemctl --user daemon-reload` or log in again to start it",
+                            );
+                        }
+                    }
+                }
+            }
+            dlg.close();
+        }
+    });
+
+    dialog.show();
+    Ok(())
+}
+
+fn show_normalize_dialog(state: &AppState) -> Result<()> {
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let dialog = Dialog::with_buttons(
+        Some("Normalize user entries"),
+        parent.as_ref(),
+        gtk4::DialogFlags::MODAL,
+        &[("Cancel", ResponseType::Cancel), ("Clean", ResponseType::Ok)],
+    );
+    dialog.set_accessible_role(AccessibleRole::Dialog);
+
+    let content = dialog.content_area();
+    content.set_spacing(6);
+    content.append(&Label::new(Some(
+        "Rewrites unlocked user entries in a consistent form and re-quotes their commands. Keys and values are kept.",
+    )));
+    let remove_comments_cb = CheckButton::with_label("Remove comments");
+    remove_comments_cb.set_tooltip_text(Some(
+        "Drop comment lines before and inside [Desktop Entry]",
+    ));
+    content.append(&remove_comments_cb);
+
+    dialog.connect_response({
+        let state = state.clone();
+        move |dlg, resp| {
+            if resp == ResponseType::Ok {
+                let options = NormalizeOptions {
+                    remove_comments: remove_comments_cb.is_active(),
+                };
+                let res = normalize_user_entries(&state.entries.borrow(), options);
+                match res {
+                    Err(err) => state
+                        .status_bar
+                        .set_text(&format!("Normalize failed: {err:#}")),
+                    Ok(count) => {
+                        if let Err(err) = refresh_entries(&state) {
+                            state
+                                .status_bar
+                                .set_text(&format!("Failed to refresh after normalize: {err:#}"));
+                        } else {
+                            state.status_bar.set_text(&format!("Normalized {count} entries"));
+                        }
+                    }
+                }
+            }
+            dlg.close();
+        }
+    });
+
+    dialog.present();
+    Ok(())
+}
+
+/// Runs the detail panel's PATH check over every entry and lists the ones that fail.
+fn show_missing_binaries_dialog(state: &AppState) {
+    let text = {
+        let entries = state.entries.borrow();
+        let missing = find_missing_binaries(&entries, &state.search_path.borrow());
+        missing_binaries_report(&entries, &missing)
+    };
+    show_report_dialog(state, "Missing programs", &text);
+}
+
+/// Read-only, scrollable text in a modal dialog with a Close button.
+fn show_report_dialog(state: &AppState, title: &str, text: &str) {
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let dialog = Dialog::with_buttons(
+        Some(title),
+        parent.as_ref(),
+        gtk4::DialogFlags::MODAL,
+        &[("Close", ResponseType::Close)],
+    );
+    dialog.set_accessible_role(AccessibleRole::Dialog);
+    let view = TextView::new();
+    view.set_editable(false);
+    view.set_cursor_visible(false);
+    view.set_wrap_mode(gtk4::WrapMode::WordChar);
+    view.buffer().set_text(text);
+    view.update_property(&[gtk4::accessible::Property::Label(title)]);
+    let scrolled = ScrolledWindow::builder()
+        .child(&view)
+        .min_content_width(480)
+        .min_content_height(240)
+        .build();
+    dialog.content_area().append(&scrolled);
+    dialog.connect_response(|dlg, _| {
+        dlg.close();
+    });
+    dialog.present();
+}
+
+fn show_lint_dialog(state: &AppState) -> Result<()> {
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let dialog = Dialog::with_buttons(
+        Some("Check entries"),
+        parent.as_ref(),
+        gtk4::DialogFlags::MODAL,
+        &[("Close", ResponseType::Close)],
+    );
+    dialog.set_accessible_role(AccessibleRole::Dialog);
+
+    let warnings = audit_all(&state.entries.borrow(), &state.search_path.borrow());
+    let mut text = if warnings.is_empty() {
+        "No problems found.".to_string()
+    } else {
+        warnings
+            .iter()
+            .map(|w| format!("[{}] {}", w.severity.label(), w.message))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    match find_orphaned_xdg_data_apps() {
+        Ok(orphans) if !orphans.is_empty() => {
+            text.push_str("\n\nUnregistered Autostart Apps (set to autostart but missing from ~/.config/autostart):");
+            for path in orphans {
+                text.push_str(&format!("\n{}", path.display()));
+            }
+        }
+        Ok(_) => {}
+        Err(err) => text.push_str(&format!("\n\nCould not scan applications: {err:#}")),
+    }
+    let view = TextView::new();
+    view.set_editable(false);
+    view.set_cursor_visible(false);
+    view.set_wrap_mode(gtk4::WrapMode::WordChar);
+    view.buffer().set_text(&text);
+    view.update_property(&[gtk4::accessible::Property::Label("Check results")]);
+    let scrolled = ScrolledWindow::builder()
+        .child(&view)
+        .min_content_width(480)
+        .min_content_height(240)
+        .build();
+    dialog.content_area().append(&scrolled);
+
+    dialog.connect_response(|dlg, _| {
+        dlg.close();
+    });
+    dialog.present();
+    Ok(())
+}
+
+fn show_autostart_sequence_dialog(state: &AppState) -> Result<()> {
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let dialog = Dialog::with_buttons(
+        Some("Autostart Sequence"),
+        parent.as_ref(),
+        gtk4::DialogFlags::MODAL,
+        &[("Close", ResponseType::Close)],
+    );
+    dialog.set_accessible_role(AccessibleRole::Dialog);
+
+    let sequence = build_autostart_sequence(&state.entries.borrow());
+    let text = if sequence.is_empty() {
+        "No enabled autostart entries.".to_string()
+    } else {
+        format_autostart_sequence(&sequence)
+    };
+    let view = TextView::new();
+    view.set_editable(false);
+    view.set_cursor_visible(false);
+    view.buffer().set_text(&text);
+    view.update_property(&[gtk4::accessible::Property::Label("Autostart sequence")]);
+    let scrolled = ScrolledWindow::builder()
+        .child(&view)
+        .min_content_width(360)
+        .min_content_height(240)
+        .build();
+    dialog.content_area().append(&scrolled);
+
+    dialog.connect_response(|dlg, _| {
+        dlg.close();
+    });
+    dialog.present();
+    Ok(())
+}
+
+fn show_preview_dialog(parent: &Dialog, text: &str) {
+    let dialog = Dialog::with_buttons(
+        Some("Preview .desktop file"),
+        Some(parent),
+        gtk4::DialogFlags::MODAL,
+        &[("Copy", ResponseType::Apply), ("Close", ResponseType::Close)],
+    );
+    dialog.set_accessible_role(AccessibleRole::Dialog);
+
+    let view = TextView::new();
+    view.set_editable(false);
+    view.set_cursor_visible(false);
+    view.set_monospace(true);
+    view.buffer().set_text(text);
+    view.update_property(&[gtk4::accessible::Property::Label("Preview of .desktop file")]);
+    let scrolled = ScrolledWindow::builder()
+        .child(&view)
+        .min_content_width(480)
+        .min_content_height(320)
+        .build();
+    dialog.content_area().append(&scrolled);
+
+    dialog.connect_response({
+        let text = text.to_string();
+        move |dlg, resp| {
+            if resp == ResponseType::Apply {
+                dlg.clipboard().set_text(&text);
+                return;
+            }
+            dlg.close();
+        }
+    });
+    dialog.present();
+}
+
+fn show_filter_dialog(state: &AppState) -> Result<()> {
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let dialog = Dialog::with_buttons(
+        Some("Filter entries"),
+        parent.as_ref(),
+        gtk4::DialogFlags::MODAL,
+        &[
+            ("Reset Filter", ResponseType::Reject),
+            ("Cancel", ResponseType::Cancel),
+            ("Apply", ResponseType::Ok),
+        ],
+    );
+    dialog.set_response_sensitive(ResponseType::Reject, !state.filter.borrow().is_default());
+
+    let content = dialog.content_area();
+    content.set_spacing(8);
+    let current = state.filter.borrow().clone();
+    let enabled_cb = CheckButton::with_label("Show enabled");
+    enabled_cb.set_active(current.show_enabled);
+    let disabled_cb = CheckButton::with_label("Show disabled");
+    disabled_cb.set_active(current.show_disabled);
+    let user_cb = CheckButton::with_label("Show user entries");
+    user_cb.set_active(current.show_user);
+    let system_cb = CheckButton::with_label("Show system entries");
+    system_cb.set_active(current.show_system);
+    let hide_empty_cb = CheckButton::with_label("Hide empty groups when grouped");
+    hide_empty_cb.set_active(state.config.borrow().hide_empty_groups);
+
+    content.append(&enabled_cb);
+    content.append(&disabled_cb);
+    content.append(&user_cb);
+    content.append(&system_cb);
+    content.append(&hide_empty_cb);
+
+    dialog.connect_response({
+        let state = state.clone();
+        move |dlg, resp| {
+            if resp == ResponseType::Ok {
+                let mut filter = state.filter.borrow_mut();
+                filter.show_enabled = enabled_cb.is_active();
+                filter.show_disabled = disabled_cb.is_active();
+                filter.show_user = user_cb.is_active();
+                filter.show_system = system_cb.is_active();
+                drop(filter);
+                state.config.borrow_mut().hide_empty_groups = hide_empty_cb.is_active();
+                rebuild_list(&state);
+                update_detail(&state);
+                state.status_bar.set_text("Filter applied");
+                remember_view(&state);
+            } else if resp == ResponseType::Reject {
+                let search_text = std::mem::take(&mut state.filter.borrow_mut().search_text);
+                state.filter.replace(FilterState {
+                    search_text,
+                    ..FilterState::default()
+                });
+                // The quick-filter toggles rebuild the list themselves when they change.
+                state.wine_only_toggle.set_active(false);
+                state.daemons_only_toggle.set_active(false);
+                state.running_only_toggle.set_active(false);
+                rebuild_list(&state);
+                update_detail(&state);
+                state.status_bar.set_text("Filter reset");
+                remember_view(&state);
+            }
+            dlg.close();
+        }
+    });
+
+    dialog.show();
+    Ok(())
+}
+
+fn show_sort_dialog(state: &AppState) -> Result<()> {
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let dialog = Dialog::with_buttons(
+        Some("Sort entries"),
+        parent.as_ref(),
+        gtk4::DialogFlags::MODAL,
+        &[("Cancel", ResponseType::Cancel), ("Apply", ResponseType::Ok)],
+    );
+
+    let content = dialog.content_area();
+    content.set_spacing(8);
+    let current = state.sort.get();
+
+    let name_asc = CheckButton::with_label("Name (A→Z)");
+    name_asc.set_group(None::<&CheckButton>);
+    name_asc.set_active(matches!(current, SortKey::NameAsc));
+
+    let name_desc = CheckButton::with_label("Name (Z→A)");
+    name_desc.set_group(Some(&name_asc));
+    name_desc.set_active(matches!(current, SortKey::NameDesc));
+
+    let status = CheckButton::with_label("Status (enabled first)");
+    status.set_group(Some(&name_asc));
+    status.set_active(matches!(current, SortKey::StatusEnabledFirst));
+
+    let source_user = CheckButton::with_label("Source (user first)");
+    source_user.set_group(Some(&name_asc));
+    source_user.set_active(matches!(current, SortKey::SourceUserFirst));
+
+    let source_system = CheckButton::with_label("Source (system first)");
+    source_system.set_group(Some(&name_asc));
+    source_system.set_active(matches!(current, SortKey::SourceSystemFirst));
+
+    let alternating = CheckButton::with_label("Alternate enabled/disabled");
+    alternating.set_group(Some(&name_asc));
+    alternating.set_active(matches!(current, SortKey::Alternating));
+
+    let wine_first = CheckButton::with_label("Wine entries first");
+    wine_first.set_group(Some(&name_asc));
+    wine_first.set_active(matches!(current, SortKey::WineFirst));
+
+    let daemons_first = CheckButton::with_label("Background daemons first");
+    daemons_first.set_group(Some(&name_asc));
+    daemons_first.set_active(matches!(current, SortKey::DaemonsFirst));
+
+    content.append(&name_asc);
+    content.append(&name_desc);
+    content.append(&status);
+    content.append(&source_user);
+    content.append(&source_system);
+    content.append(&alternating);
+    content.append(&wine_first);
+    let running_first = CheckButton::with_label("Running entries first");
+    running_first.set_group(Some(&name_asc));
+    running_first.set_active(matches!(current, SortKey::RunningFirst));
+
+    content.append(&daemons_first);
+    content.append(&running_first);
+
+    dialog.connect_response({
+        let state = state.clone();
+        move |dlg, resp| {
+            if resp == ResponseType::Ok {
+                let new_sort = if name_asc.is_active() {
+                    SortKey::NameAsc
+                } else if name_desc.is_active() {
+                    SortKey::NameDesc
+                } else if status.is_active() {
+                    SortKey::StatusEnabledFirst
+                } else if source_user.is_active() {
+                    SortKey::SourceUserFirst
+                } else if source_system.is_active() {
+                    SortKey::SourceSystemFirst
+                } else if alternating.is_active() {
+                    SortKey::Alternating
+                } else if wine_first.is_active() {
+                    SortKey::WineFirst
+                } else if daemons_first.is_active() {
+                    SortKey::DaemonsFirst
+                } else if running_first.is_active() {
+                    SortKey::RunningFirst
+                } else {
+                    state.sort.get()
+                };
+                state.sort.set(new_sort);
+                rebuild_list(&state);
+                state.status_bar.set_text("Sort applied");
+                remember_view(&state);
+            }
+            dlg.close();
+        }
+    });
+
+    dialog.show();
+    Ok(())
+}
+
+/// Custom sort action: Wine entries on top, then by name.
+fn group_wine_entries_first(state: &AppState) {
+    state.sort.set(SortKey::WineFirst);
+    rebuild_list(state);
+    state.status_bar.set_text("Wine entries grouped first");
+    remember_view(state);
+}
+
+/// Saves the current filter and sort for the next launch; see `ViewSettings`.
+fn remember_view(state: &AppState) {
+    let mut config = state.config.borrow_mut();
+    config.view.record_filter(&state.filter.borrow());
+    config.view.sort = state.sort.get();
+    if let Err(err) = save_config(&config) {
+        state
+            .status_bar
+            .set_text(&format!("Saving preferences failed: {err:#}"));
+    }
+}
+
+fn copy_selected_as(state: &AppState, format: ExportFormat) -> Result<()> {
+    let idx = state.selected.get().context("No item selected")?;
+    let text = {
+        let entries = state.entries.borrow();
+        let entry = entries.get(idx).context("Invalid selection")?;
+        format
+            .render(entry)
+            .with_context(|| format!("{} is not available for this entry", format.label()))?
+    };
+    state.list_box.clipboard().set_text(&text);
+    state
+        .status_bar
+        .set_text(&format!("Copied as {}", format.label()));
+    Ok(())
+}
+
+/// Status bar messages kept for `write_debug_dump`.
+const STATUS_HISTORY_LEN: usize = 20;
+
+/// What a debug dump reports; `render` turns it into the text file.
+struct DebugDump<'a> {
+    gtk_version: String,
+    os: String,
+    entries: &'a [StartupEntry],
+    filter: FilterState,
+    sort: SortKey,
+    pending_warnings: &'a [(usize, ValidationResult)],
+    status_history: &'a [String],
+}
+
+impl DebugDump<'_> {
+    /// Plain-text report. Commands can hold tokens or private paths, so only their length
+    /// is shown.
+    fn render(&self) -> String {
+        let mut lines = vec![
+            "Universal Startup Manager debug dump".to_string(),
+            format!("Version: {}", env!("CARGO_PKG_VERSION")),
+            format!("GTK: {}", self.gtk_version),
+            format!("OS: {}", self.os),
+            String::new(),
+            format!("Filter: {:?}", self.filter),
+            format!("Sort: {:?}", self.sort),
+            String::new(),
+            format!("Entries ({}):", self.entries.len()),
+        ];
+        for entry in self.entries {
+            let path = entry
+                .path
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|| "(no file)".to_string());
+            lines.push(format!(
+                "  [{}] {} | {} | {} | command: {} chars",
+                source_label(&entry.source),
+                entry.name,
+                path,
+                entry.status_label(),
+                entry.exec_line().chars().count()
+            ));
+        }
+        lines.push(String::new());
+        lines.push(format!("Pending warnings ({}):", self.pending_warnings.len()));
+        for (idx, result) in self.pending_warnings {
+            let name = self.entries.get(*idx).map(|e| e.name.as_str()).unwrap_or("?");
+            for warning in &result.warnings {
+                lines.push(format!("  {name}: [{}] {}", warning.severity.label(), warning.message));
+            }
+        }
+        lines.push(String::new());
+        lines.push(format!("Recent status messages ({}, oldest first):", self.status_history.len()));
+        lines.extend(self.status_history.iter().map(|message| format!("  {message}")));
+        lines.join("\n") + "\n"
+    }
+}
+
+/// `PRETTY_NAME` from `/etc/os-release` with the kernel family and architecture.
+fn os_description() -> String {
+    let pretty = parse_environment_file(Path::new("/etc/os-release"))
+        .unwrap_or_default()
+        .into_iter()
+        .find(|(key, _)| key == "PRETTY_NAME")
+        .map(|(_, value)| value)
+        .unwrap_or_else(|| "unknown".to_string());
+    format!("{pretty} ({} {})", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+/// Writes a `DebugDump` of the current window state to `path` for bug reports.
+fn write_debug_dump(state: &AppState, path: &Path) -> Result<()> {
+    let entries = state.entries.borrow();
+    let pending_warnings = state.pending_warnings.borrow();
+    let status_history: Vec<String> = state.status_history.borrow().iter().cloned().collect();
+    let dump = DebugDump {
+        gtk_version: format!(
+            "{}.{}.{}",
+            gtk4::major_version(),
+            gtk4::minor_version(),
+            gtk4::micro_version()
+        ),
+        os: os_description(),
+        entries: &entries,
+        filter: state.filter.borrow().clone(),
+        sort: state.sort.get(),
+        pending_warnings: &pending_warnings,
+        status_history: &status_history,
+    };
+    write_text_atomic(path, &dump.render())
+}
+
+fn show_export_svg_dialog(state: &AppState) -> Result<()> {
+    let export_state = state.clone();
+    choose_file(state, "Export as SVG", FileDialogAction::Save, "autostart-entries.svg", None, move |path| {
+        let state = &export_state;
+        let visible: Vec<StartupEntry> = {
+            let entries = state.entries.borrow();
+            state
+                .visible_indices
+                .borrow()
+                .iter()
+                .filter_map(|&idx| entries.get(idx).cloned())
+                .collect()
+        };
+        match write_text_atomic(&path, &render_list_as_svg(&visible)) {
+            Ok(()) => state
+                .status_bar
+                .set_text(&format!("Exported {} entries to {}", visible.len(), path.display())),
+            Err(err) => state
+                .status_bar
+                .set_text(&format!("SVG export failed: {err:#}")),
+        }
+    });
+    Ok(())
+}
+
+/// Picks a `.desktop` file and copies it into `user_autostart_dir()`, asking first when an
+/// entry with the same file name exists.
+fn show_import_file_dialog(state: &AppState) -> Result<()> {
+    let import_state = state.clone();
+    choose_file(state, "Import File", FileDialogAction::Open, "", Some("*.desktop;*.json;*.nix"), move |path| {
+        let state = &import_state;
+        let res = read_importable_file(&path).and_then(|entry| {
+            let dir = user_autostart_dir();
+            fs::create_dir_all(&dir).with_context(|| format!("Creating dir {:?}", dir))?;
+            let target = dir.join(format!("{}.desktop", slugify(&entry.name)));
+            if target.exists() {
+                show_import_conflict_dialog(state, entry, target);
+                Ok(())
+            } else {
+                write_imported_entry(state, entry, &target)
+            }
+        });
+        if let Err(err) = res {
+            state.status_bar.set_text(&format!("Import failed: {err:#}"));
+        }
+    });
+    Ok(())
+}
+
+/// Overwrite / Rename / Cancel for an import whose target file already exists. Rename picks
+/// the next free `<slug>-N.desktop`.
+fn show_import_conflict_dialog(state: &AppState, entry: StartupEntry, target: PathBuf) {
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let dialog = Dialog::with_buttons(
+        Some("Entry already exists"),
+        parent.as_ref(),
+        gtk4::DialogFlags::MODAL,
+        &[
+            ("Cancel", ResponseType::Cancel),
+            ("Rename", ResponseType::Apply),
+            ("Overwrite", ResponseType::Accept),
+        ],
+    );
+    dialog.set_accessible_role(AccessibleRole::AlertDialog);
+    dialog.set_default_response(ResponseType::Cancel);
+    if let Some(overwrite) = dialog.widget_for_response(ResponseType::Accept) {
+        overwrite.add_css_class("destructive-action");
+    }
+    let file_name = target.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let label = Label::new(Some(&format!(
+        "Your autostart folder already has {file_name}. Overwrite it, or import under a new file name?"
+    )));
+    label.set_wrap(true);
+    dialog.content_area().append(&label);
+    dialog.connect_response({
+        let state = state.clone();
+        move |dlg, resp| {
+            dlg.close();
+            let res = match resp {
+                ResponseType::Accept => write_imported_entry(&state, entry.clone(), &target),
+                ResponseType::Apply => {
+                    let dir = target.parent().unwrap_or_else(|| Path::new("."));
+                    write_imported_entry(&state, entry.clone(), &free_desktop_file_path(dir, &slugify(&entry.name)))
+                }
+                _ => return,
+            };
+            if let Err(err) = res {
+                state.status_bar.set_text(&format!("Import failed: {err:#}"));
+            }
+        }
+    });
+    dialog.present();
+}
+
+/// Writes an imported entry to `path` as a user entry, records it for undo and reloads.
+fn write_imported_entry(state: &AppState, entry: StartupEntry, path: &Path) -> Result<()> {
+    let name = display_name_with_fallback(&entry, &current_locale());
+    if let Some(change) = write_imported_entry_files(vec![(entry, path.to_path_buf())])? {
+        push_undo(state, change);
+    }
+    refresh_entries(state)?;
+    set_status_with_undo_hint(state, &format!("Imported {name}"));
+    Ok(())
+}
+
+/// Writes imported entries as user entries in one `write_desktop_entry_batch` and returns
+/// the undo step covering them; `None` when there was nothing to write.
+fn write_imported_entry_files(entries: Vec<(StartupEntry, PathBuf)>) -> Result<Option<UndoEntry>> {
+    let mut batch = Vec::with_capacity(entries.len());
+    let mut changes = Vec::with_capacity(entries.len());
+    for (mut entry, path) in entries {
+        let path = validate_user_entry_path(&path)?;
+        changes.push(if path.exists() {
+            let original = parse_desktop_file(&path, StartupSource::UserAutostart)?;
+            UndoEntry::Edited { path: path.clone(), original }
+        } else {
+            UndoEntry::Added { path: path.clone() }
+        });
+        entry.source = StartupSource::UserAutostart;
+        entry.path = Some(path.clone());
+        batch.push((entry, path));
+    }
+    write_desktop_entry_batch(&batch)?;
+    Ok(match changes.len() {
+        0 => None,
+        1 => changes.pop(),
+        _ => Some(UndoEntry::Batch(changes)),
+    })
+}
+
+fn show_export_json_dialog(state: &AppState) -> Result<()> {
+    let export_state = state.clone();
+    choose_file(state, "Export JSON", FileDialogAction::Save, "autostart-entries.json", Some("*.json"), move |path| {
+        let state = &export_state;
+        let entries = state.entries.borrow().clone();
+        match export_entries_json(&entries, &path) {
+            Ok(()) => state
+                .status_bar
+                .set_text(&format!("Exported {} entries to {}", entries.len(), path.display())),
+            Err(err) => state
+                .status_bar
+                .set_text(&format!("JSON export failed: {err:#}")),
+        }
+    });
+    Ok(())
+}
+
+/// Reads a JSON backup, lists the user entries it would write for confirmation, then writes
+/// them, asking Overwrite or Skip for each file that already exists.
+fn show_import_json_dialog(state: &AppState) -> Result<()> {
+    let import_state = state.clone();
+    choose_file(state, "Import JSON", FileDialogAction::Open, "", Some("*.json"), move |path| {
+        let state = &import_state;
+        let res = import_entries_json(&path).and_then(|entries| {
+            let plan = plan_json_import(entries, &user_autostart_dir());
+            if plan.is_empty() {
+                bail!("{} has no user entries", path.display());
+            }
+            show_import_json_confirm_dialog(state, plan);
+            Ok(())
+        });
+        if let Err(err) = res {
+            state.status_bar.set_text(&format!("JSON import failed: {err:#}"));
+        }
+    });
+    Ok(())
+}
+
+fn show_import_json_confirm_dialog(state: &AppState, plan: Vec<(StartupEntry, PathBuf)>) {
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let dialog = Dialog::with_buttons(
+        Some("Import entries"),
+        parent.as_ref(),
+        gtk4::DialogFlags::MODAL,
+        &[("Cancel", ResponseType::Cancel), ("Import", ResponseType::Accept)],
+    );
+    let content = dialog.content_area();
+    content.set_spacing(6);
+    content.append(&Label::new(Some(&format!(
+        "Write these {} entries to your autostart folder?",
+        plan.len()
+    ))));
+    let names: Vec<String> = plan
+        .iter()
+        .map(|(entry, path)| {
+            let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            format!("{} ({file_name})", display_name_with_fallback(entry, &current_locale()))
+        })
+        .collect();
+    let list = Label::new(Some(&names.join("\n")));
+    list.set_xalign(0.0);
+    list.set_selectable(true);
+    let scroller = ScrolledWindow::builder()
+        .child(&list)
+        .min_content_height(160)
+        .vexpand(true)
+        .build();
+    content.append(&scroller);
+    let plan = RefCell::new(Some(plan));
+    dialog.connect_response({
+        let state = state.clone();
+        move |dlg, resp| {
+            dlg.close();
+            if resp != ResponseType::Accept {
+                return;
+            }
+            if let Some(plan) = plan.borrow_mut().take() {
+                let dir = user_autostart_dir();
+                match fs::create_dir_all(&dir).with_context(|| format!("Creating dir {:?}", dir)) {
+                    Ok(()) => import_json_entries_from(&state, plan.into(), Vec::new(), 0),
+                    Err(err) => state.status_bar.set_text(&format!("JSON import failed: {err:#}")),
+                }
+            }
+        }
+    });
+    dialog.present();
+}
+
+/// Goes through the queued entries in order, stopping to ask Overwrite / Skip when a file
+/// exists, then writes the kept ones as one batch, reloads once and reports the counts.
+fn import_json_entries_from(
+    state: &AppState,
+    mut queue: VecDeque<(StartupEntry, PathBuf)>,
+    mut batch: Vec<(StartupEntry, PathBuf)>,
+    skipped: usize,
+) {
+    while let Some((entry, path)) = queue.pop_front() {
+        if path.exists() {
+            show_import_json_conflict_dialog(state, entry, path, queue, batch, skipped);
+            return;
+        }
+        batch.push((entry, path));
+    }
+    let written = batch.len();
+    match write_imported_entry_files(batch) {
+        Ok(Some(change)) => push_undo(state, change),
+        Ok(None) => {}
+        Err(err) => {
+            state.status_bar.set_text(&format!("JSON import failed: {err:#}"));
+            let _ = refresh_entries(state);
+            return;
+        }
+    }
+    if let Err(err) = refresh_entries(state) {
+        state.status_bar.set_text(&format!("Failed to refresh after import: {err:#}"));
+        return;
+    }
+    set_status_with_undo_hint(state, &format!("Imported {written} entries, skipped {skipped}"));
+}
+
+fn show_import_json_conflict_dialog(
+    state: &AppState,
+    entry: StartupEntry,
+    path: PathBuf,
+    queue: VecDeque<(StartupEntry, PathBuf)>,
+    batch: Vec<(StartupEntry, PathBuf)>,
+    skipped: usize,
+) {
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let dialog = Dialog::with_buttons(
+        Some("Entry already exists"),
+        parent.as_ref(),
+        gtk4::DialogFlags::MODAL,
+        &[("Skip", ResponseType::Reject), ("Overwrite", ResponseType::Accept)],
+    );
+    dialog.set_accessible_role(AccessibleRole::AlertDialog);
+    dialog.set_default_response(ResponseType::Reject);
+    if let Some(overwrite) = dialog.widget_for_response(ResponseType::Accept) {
+        overwrite.add_css_class("destructive-action");
+    }
+    let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let label = Label::new(Some(&format!(
+        "Your autostart folder already has {file_name}. Overwrite it with {:?} from the backup?",
+        display_name_with_fallback(&entry, &current_locale())
+    )));
+    label.set_wrap(true);
+    dialog.content_area().append(&label);
+    let pending = RefCell::new(Some((entry, path, queue, batch)));
+    dialog.connect_response({
+        let state = state.clone();
+        move |dlg, resp| {
+            dlg.close();
+            let Some((entry, path, queue, mut batch)) = pending.borrow_mut().take() else {
+                return;
+            };
+            if resp == ResponseType::Accept {
+                batch.push((entry, path));
+                import_json_entries_from(&state, queue, batch, skipped);
+            } else {
+                import_json_entries_from(&state, queue, batch, skipped + 1);
+            }
+        }
+    });
+    dialog.present();
+}
+
+/// Reads a file picked for import: a GNOME autostart handler when it ends in `.json`, a
+/// Home Manager fragment from `to_nix_home_manager_module` when it ends in `.nix`,
+/// otherwise a `.desktop` file. The entry needs a non-empty `Name=` and `Exec=`.
+fn read_importable_file(path: &Path) -> Result<StartupEntry> {
+    let entry = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => {
+            let json = fs::read_to_string(path).with_context(|| format!("Reading {:?}", path))?;
+            from_gnome_autostart_handler_json(&json)?
+        }
+        Some("nix") => {
+            let nix = fs::read_to_string(path).with_context(|| format!("Reading {:?}", path))?;
+            from_nix_home_manager_entry(&nix)?
+        }
+        _ => parse_desktop_file(path, StartupSource::UserAutostart)?,
+    };
+    if entry.name.trim().is_empty() {
+        bail!("{:?} has no Name=", path);
+    }
+    if entry.command.trim().is_empty() {
+        bail!("{:?} has no Exec=", path);
+    }
+    Ok(entry)
+}
+
+/// `<slug>-2.desktop`, `<slug>-3.desktop`, … in `dir`, whichever is free first.
+fn free_desktop_file_path(dir: &Path, slug: &str) -> PathBuf {
+    (2..)
+        .map(|n| dir.join(format!("{slug}-{n}.desktop")))
+        .find(|path| !path.exists())
+        .unwrap_or_else(|| dir.join(format!("{slug}.desktop")))
+}
+
+/// Whether a file dialog picks an existing file or a place to save one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileDialogAction {
+    Open,
+    Save,
+}
+
+impl FileDialogAction {
+    fn accept_label(self) -> &'static str {
+        match self {
+            FileDialogAction::Open => "Open",
+            FileDialogAction::Save => "Save",
+        }
+    }
+}
+
+fn file_chooser_action(action: FileDialogAction) -> FileChooserAction {
+    match action {
+        FileDialogAction::Open => FileChooserAction::Open,
+        FileDialogAction::Save => FileChooserAction::Save,
+    }
+}
+
+/// Portal-aware `gtk4::FileDialog` (GTK 4.10+, `file-dialog` cargo feature).
+#[cfg(feature = "file-dialog")]
+fn create_file_dialog(title: &str, action: FileDialogAction) -> gtk4::FileDialog {
+    gtk4::FileDialog::builder()
+        .title(title)
+        .modal(true)
+        .accept_label(action.accept_label())
+        .build()
+}
+
+/// Asks for a file and passes its path to `on_path`; cancelling does nothing. A
+/// `filter_pattern` such as `*.desktop`, or several joined by `;`, limits the files offered. With
+/// `use_xdg_portal_for_file_chooser` set this goes through the portal: `FileDialog` when
+/// available at build and run time, `FileChooserNative` otherwise. Without it the
+/// in-process `FileChooserDialog` is used.
+// The chooser widgets are deprecated once the `file-dialog` feature enables GTK 4.10 APIs.
+#[allow(deprecated)]
+fn choose_file(
+    state: &AppState,
+    title: &str,
+    action: FileDialogAction,
+    initial_name: &str,
+    filter_pattern: Option<&str>,
+    on_path: impl Fn(PathBuf) + 'static,
+) {
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let filter = filter_pattern.map(|pattern| {
+        let filter = gtk4::FileFilter::new();
+        filter.set_name(Some(pattern));
+        for pattern in pattern.split(';') {
+            filter.add_pattern(pattern);
+        }
+        filter
+    });
+    let on_path: Rc<dyn Fn(PathBuf)> = Rc::new(on_path);
+    if !state.config.borrow().use_xdg_portal_for_file_chooser {
+        let dialog = FileChooserDialog::new(
+            Some(title),
+            parent.as_ref(),
+            file_chooser_action(action),
+            &[("Cancel", ResponseType::Cancel), (action.accept_label(), ResponseType::Accept)],
+        );
+        dialog.set_modal(true);
+        if action == FileDialogAction::Save {
+            dialog.set_current_name(initial_name);
+        }
+        if let Some(filter) = &filter {
+            dialog.add_filter(filter);
+        }
+        dialog.connect_response(move |dlg, resp| {
+            if resp == ResponseType::Accept {
+                if let Some(path) = dlg.file().and_then(|f| f.path()) {
+                    on_path(path);
+                }
+            }
+            dlg.close();
+        });
+        dialog.show();
+        return;
+    }
+    if choose_file_with_file_dialog(parent.as_ref(), title, action, initial_name, filter.as_ref(), on_path.clone()) {
+        return;
+    }
+    let native = gtk4::FileChooserNative::new(
+        Some(title),
+        parent.as_ref(),
+        file_chooser_action(action),
+        Some(action.accept_label()),
+        None,
+    );
+    native.set_modal(true);
+    if action == FileDialogAction::Save {
+        native.set_current_name(initial_name);
+    }
+    if let Some(filter) = &filter {
+        native.add_filter(filter);
+    }
+    // A native dialog is not kept alive by GTK; hold it until it answers.
+    let keep_alive = Rc::new(RefCell::new(Some(native.clone())));
+    native.connect_response(move |dlg, resp| {
+        if resp == ResponseType::Accept {
+            if let Some(path) = dlg.file().and_then(|f| f.path()) {
+                on_path(path);
+            }
+        }
+        keep_alive.borrow_mut().take();
+    });
+    native.show();
+}
+
+/// `FileDialog` flow for `choose_file`; false when GTK at run time is older than 4.10.
+#[cfg(feature = "file-dialog")]
+fn choose_file_with_file_dialog(
+    parent: Option<&ApplicationWindow>,
+    title: &str,
+    action: FileDialogAction,
+    initial_name: &str,
+    filter: Option<&gtk4::FileFilter>,
+    on_path: Rc<dyn Fn(PathBuf)>,
+) -> bool {
+    if gtk4::check_version(4, 10, 0).is_some() {
+        return false;
+    }
+    let dialog = create_file_dialog(title, action);
+    dialog.set_default_filter(filter);
+    let callback = move |res: Result<gio::File, glib::Error>| {
+        if let Some(path) = res.ok().and_then(|f| f.path()) {
+            on_path(path);
+        }
+    };
+    match action {
+        FileDialogAction::Open => dialog.open(parent, gio::Cancellable::NONE, callback),
+        FileDialogAction::Save => {
+            dialog.set_initial_name(Some(initial_name));
+            dialog.save(parent, gio::Cancellable::NONE, callback)
+        }
+    }
+    true
+}
+
+#[cfg(not(feature = "file-dialog"))]
+fn choose_file_with_file_dialog(
+    _parent: Option<&ApplicationWindow>,
+    _title: &str,
+    _action: FileDialogAction,
+    _initial_name: &str,
+    _filter: Option<&gtk4::FileFilter>,
+    _on_path: Rc<dyn Fn(PathBuf)>,
+) -> bool {
+    false
+}
+
+fn show_about_dialog(state: &AppState) -> Result<()> {
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let dialog = Dialog::with_buttons(
+        Some("About Universal Startup Manager"),
+        parent.as_ref(),
+        gtk4::DialogFlags::MODAL,
+        &[("Close", ResponseType::Close)],
+    );
+    dialog.set_accessible_role(AccessibleRole::Dialog);
+    dialog.update_property(&[gtk4::accessible::Property::Label(
+        "About Universal Startup Manager",
+    )]);
+
+    let content = dialog.content_area();
+    content.set_spacing(6);
+    let description = Label::new(Some(&format!(
+        "Manage user autostart entries and view system startup items. Version {}",
+        env!("CARGO_PKG_VERSION")
+    )));
+    description.set_wrap(true);
+    content.append(&description);
+
+    let stats = collect_entry_statistics(&state.entries.borrow());
+    let mut lines = vec![
+        stats.summary(),
+        format!("With icon: {}", stats.with_icon),
+        format!("With description: {}", stats.with_description),
+        format!("Average command length: {:.1} characters", stats.average_command_length),
+    ];
+    if let Some(longest) = &stats.longest_command {
+        lines.push(format!("Longest command: {longest}"));
+    }
+    let stats_label = Label::new(Some(&lines.join("\n")));
+    stats_label.set_wrap(true);
+    stats_label.set_xalign(0.0);
+    stats_label.set_selectable(true);
+    let stats_expander = gtk4::Expander::new(Some("Statistics"));
+    stats_expander.set_child(Some(&stats_label));
+    content.append(&stats_expander);
+
+    let dump_button = Button::with_label("Save Debug Dump…");
+    dump_button.set_accessible_role(AccessibleRole::Button);
+    dump_button.set_tooltip_text(Some(
+        "Save versions, entry names and paths, and recent messages for a bug report; commands are left out",
+    ));
+    dump_button.connect_clicked({
+        let state = state.clone();
+        move |_| {
+            let dump_state = state.clone();
+            choose_file(&state, "Save Debug Dump", FileDialogAction::Save, "usm-debug.txt", None, move |path| {
+                match write_debug_dump(&dump_state, &path) {
+                    Ok(()) => dump_state
+                        .status_bar
+                        .set_text(&format!("Saved debug dump to {}", path.display())),
+                    Err(err) => dump_state
+                        .status_bar
+                        .set_text(&format!("Saving debug dump failed: {err:#}")),
+                }
+            });
+        }
+    });
+    content.append(&dump_button);
+
+    let close_button = dialog
+        .widget_for_response(ResponseType::Close)
+        .and_then(|w| w.downcast::<Button>().ok());
+    if let Some(close_button) = close_button {
+        close_button.update_property(&[gtk4::accessible::Property::Label(
+            "Close about dialog",
+        )]);
+    }
+    dialog.connect_response(|dlg, _| {
+        dlg.close();
+    });
+    dialog.present();
+    Ok(())
+}
+
+fn show_preferences_dialog(state: &AppState) -> Result<()> {
+    let parent = state
+        .list_box
+        .root()
+        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
+    let dialog = Dialog::with_buttons(
+        Some("Preferences"),
+        parent.as_ref(),
+        gtk4::DialogFlags::MODAL,
+        &[("Close", ResponseType::Close)],
+    );
+    dialog.set_accessible_role(AccessibleRole::Dialog);
+
+    let content = dialog.content_area();
+    content.set_spacing(8);
+    let maintenance_label = Label::new(Some("Maintenance"));
+    maintenance_label.set_xalign(0.0);
+    let migrate_button = Button::with_label("Migrate Legacy Format");
+    migrate_button.set_accessible_role(AccessibleRole::Button);
+    migrate_button.set_tooltip_text(Some(
+        "Add X-GNOME-Autostart-enabled to user entries that only use Hidden",
+    ));
+    let sync_cb = CheckButton::with_label("Sync directory after each write (safer on crash)");
+    sync_cb.set_active(state.config.borrow().sync_on_write);
+    content.append(&sync_cb);
+    let auto_disable_cb = CheckButton::with_label("Auto-disable entries whose binary is missing");
+    auto_disable_cb.set_active(state.config.borrow().auto_disable_missing_binary);
+    content.append(&auto_disable_cb);
+    let limit_row = GtkBox::new(Orientation::Horizontal, 6);
+    let limit_label = Label::new(Some("Max entries per source (0 = no limit):"));
+    let limit_spin = gtk4::SpinButton::with_range(0.0, 10_000.0, 10.0);
+    limit_spin.set_value(state.config.borrow().max_entries_per_source.unwrap_or(0) as f64);
+    limit_label.set_mnemonic_widget(Some(&limit_spin));
+    limit_row.append(&limit_label);
+    limit_row.append(&limit_spin);
+    content.append(&limit_row);
+    let size_limit_row = GtkBox::new(Orientation::Horizontal, 6);
+    let size_limit_label = Label::new(Some("Warn when user entries take more KB than (0 = never):"));
+    let size_limit_spin = gtk4::SpinButton::with_range(0.0, 1_048_576.0, 64.0);
+    size_limit_spin.set_value((state.config.borrow().user_autostart_dir_size_limit.unwrap_or(0) / 1024) as f64);
+    size_limit_label.set_mnemonic_widget(Some(&size_limit_spin));
+    size_limit_row.append(&size_limit_label);
+    size_limit_row.append(&size_limit_spin);
+    content.append(&size_limit_row);
+    let count_limit_row = GtkBox::new(Orientation::Horizontal, 6);
+    let count_limit_label = Label::new(Some("Warn when there are more user entries than (0 = never):"));
+    let count_limit_spin = gtk4::SpinButton::with_range(0.0, 10_000.0, 10.0);
+    count_limit_spin.set_value(state.config.borrow().autostart_entry_count_limit.unwrap_or(0) as f64);
+    count_limit_label.set_mnemonic_widget(Some(&count_limit_spin));
+    count_limit_row.append(&count_limit_label);
+    count_limit_row.append(&count_limit_spin);
+    content.append(&count_limit_row);
+    let portal_cb = CheckButton::with_label("Use the desktop portal for file dialogs (needed in Flatpak)");
+    portal_cb.set_active(state.config.borrow().use_xdg_portal_for_file_chooser);
+    content.append(&portal_cb);
+    let watch_cb = CheckButton::with_label("Refresh the list when ~/.config/autostart changes outside the app");
+    watch_cb.set_active(state.config.borrow().refresh_on_external_change);
+    content.append(&watch_cb);
+    let notify_cb = CheckButton::with_label("Notify when new system startup entries appear");
+    notify_cb.set_active(state.config.borrow().notify_on_new_system_entry);
+    content.append(&notify_cb);
+    let url_handler_button = Button::with_label("Register xdg-autostart:// Links");
+    url_handler_button.set_accessible_role(AccessibleRole::Button);
+    url_handler_button.set_tooltip_text(Some(
+        "Install a handler so shared xdg-autostart:// links open the Add dialog",
+    ));
+    content.append(&maintenance_label);
+    content.append(&migrate_button);
+    content.append(&url_handler_button);
+
+    auto_disable_cb.connect_toggled({
+        let state = state.clone();
+        move |cb| {
+            let mut config = state.config.borrow_mut();
+            config.auto_disable_missing_binary = cb.is_active();
+            if let Err(err) = save_config(&config) {
+                state
+                    .status_bar
+                    .set_text(&format!("Saving preferences failed: {err:#}"));
+            }
+        }
+    });
+
+    limit_spin.connect_value_changed({
+        let state = state.clone();
+        move |spin| {
+            let limit = spin.value_as_int().max(0) as usize;
+            {
+                let mut config = state.config.borrow_mut();
+                config.max_entries_per_source = (limit > 0).then_some(limit);
+                if let Err(err) = save_config(&config) {
+                    state
+                        .status_bar
+                        .set_text(&format!("Saving preferences failed: {err:#}"));
+                    return;
+                }
+            }
+            if let Err(err) = refresh_entries(&state) {
+                state.status_bar.set_text(&format!("Refresh failed: {err:#}"));
+            }
+        }
+    });
+
+    size_limit_spin.connect_value_changed({
+        let state = state.clone();
+        move |spin| {
+            let kib = spin.value_as_int().max(0) as u64;
+            let saved = {
+                let mut config = state.config.borrow_mut();
+                config.user_autostart_dir_size_limit = (kib > 0).then_some(kib * 1024);
+                save_config(&config)
+            };
+            match saved {
+                Err(err) => state.status_bar.set_text(&format!("Saving preferences failed: {err:#}")),
+                Ok(()) => {
+                    if let Some(warning) = check_autostart_dir_size(&state) {
+                        state.status_bar.set_text(&warning);
+                    }
+                }
+            }
+        }
+    });
+
+    count_limit_spin.connect_value_changed({
+        let state = state.clone();
+        move |spin| {
+            let limit = spin.value_as_int().max(0) as usize;
+            let saved = {
+                let mut config = state.config.borrow_mut();
+                config.autostart_entry_count_limit = (limit > 0).then_some(limit);
+                save_config(&config)
+            };
+            match saved {
+                Err(err) => state.status_bar.set_text(&format!("Saving preferences failed: {err:#}")),
+                Ok(()) => {
+                    if let Some(warning) = check_autostart_dir_size(&state) {
+                        state.status_bar.set_text(&warning);
+                    }
+                }
+            }
+        }
+    });
+
+    portal_cb.connect_toggled({
+        let state = state.clone();
+        move |cb| {
+            let mut config = state.config.borrow_mut();
+            config.use_xdg_portal_for_file_chooser = cb.is_active();
+            if let Err(err) = save_config(&config) {
+                state
+                    .status_bar
+                    .set_text(&format!("Saving preferences failed: {err:#}"));
+            }
+        }
+    });
+
+    watch_cb.connect_toggled({
+        let state = state.clone();
+        move |cb| {
+            let mut config = state.config.borrow_mut();
+            config.refresh_on_external_change = cb.is_active();
+            if let Err(err) = save_config(&config) {
+                state
+                    .status_bar
+                    .set_text(&format!("Saving preferences failed: {err:#}"));
+            }
+        }
+    });
+
+    notify_cb.connect_toggled({
+        let state = state.clone();
+        move |cb| {
+            let mut config = state.config.borrow_mut();
+            config.notify_on_new_system_entry = cb.is_active();
+            if let Err(err) = save_config(&config) {
+                state
+                    .status_bar
+                    .set_text(&format!("Saving preferences failed: {err:#}"));
+            }
+        }
+    });
+
+    sync_cb.connect_toggled({
+        let state = state.clone();
+        move |cb| {
+            let mut config = state.config.borrow_mut();
+            config.sync_on_write = cb.is_active();
+            SYNC_ON_WRITE.store(config.sync_on_write, Ordering::Relaxed);
+            if let Err(err) = save_config(&config) {
+                state
+                    .status_bar
+                    .set_text(&format!("Saving preferences failed: {err:#}"));
+            }
+        }
+    });
+
+    migrate_button.connect_clicked({
+        let state = state.clone();
+        move |_| match migrate_hidden_to_xgnome(&user_autostart_dir()) {
+            Ok(count) => {
+                if let Err(err) = refresh_entries(&state) {
+                    state
+                        .status_bar
+                        .set_text(&format!("Failed to refresh after migration: {err:#}"));
+                } else {
+                    state
+                        .status_bar
+                        .set_text(&format!("Migrated {count} legacy entries"));
+                }
+            }
+            Err(err) => state
+                .status_bar
+                .set_text(&format!("Migration failed: {err:#}")),
+        }
+    });
+
+    url_handler_button.connect_clicked({
+        let state = state.clone();
+        move |_| {
+            let path = url_handler_desktop_path();
+            match write_text_atomic(&path, &generate_url_handler_desktop_file()) {
+                Ok(()) => state
+                    .status_bar
+                    .set_text(&format!("Registered link handler at {}", path.display())),
+                Err(err) => state
+                    .status_bar
+                    .set_text(&format!("Registering link handler failed: {err:#}")),
+            }
+        }
+    });
+
+    dialog.connect_response(|dlg, _| {
+        dlg.close();
+    });
+    dialog.present();
+    Ok(())
+}
+
+fn load_entries() -> Result<Vec<StartupEntry>> {
+    load_entries_with_progress(None, |_| {}).map(|(entries, _)| entries)
+}
+
+/// `load_entries`, reading at most `limit` files per autostart dir and reporting
+/// `files_done / total_files_in_dirs` after each `.desktop` file. The last call is always 1.0.
+/// Also returns the autostart dir counts from `load_autostart_dirs_with_progress`.
+fn load_entries_with_progress(
+    limit: Option<usize>,
+    progress: impl Fn(f32),
+) -> Result<(Vec<StartupEntry>, AutostartDirTotals)> {
+    let mut dirs = vec![(user_autostart_dir(), StartupSource::UserAutostart)];
+    dirs.extend(system_autostart_dirs().into_iter().map(|dir| (dir, StartupSource::SystemAutostart)));
+    let (mut entries, dir_totals) = load_autostart_dirs_with_progress(&dirs, limit, &progress)?;
+    let environment_d = Path::new("/etc/environment.d");
+    entries.extend(skip_unreadable_source(environment_d, load_environment_d_entries(environment_d)));
+    let kde_env = kde_plasma_env_dir();
+    entries.extend(skip_unreadable_source(&kde_env, load_kde_plasma_env_entries(&kde_env)));
+    let openbox = openbox_autostart_path();
+    if openbox.is_file() {
+        entries.extend(skip_unreadable_source(&openbox, load_openbox_autostart(&openbox)));
+    }
+    let sway = sway_config_path();
+    if sway.is_file() {
+        entries.extend(skip_unreadable_source(&sway, load_sway_config_entries(&sway)));
+    }
+    let i3 = i3_config_path();
+    if i3.is_file() {
+        entries.extend(skip_unreadable_source(&i3, load_i3_config_entries(&i3)));
+    }
+    let hyprland = hyprland_config_path();
+    if hyprland.is_file() {
+        entries.extend(skip_unreadable_source(&hyprland, load_hyprland_entries(&hyprland)));
+    }
+    for profile in shell_profile_paths() {
+        if profile.is_file() {
+            entries.extend(skip_unreadable_source(&profile, load_shell_profile_entries(&profile)));
+        }
+    }
+    entries.extend(skip_unreadable_source(&xdg_user_dirs_path(), load_xdg_user_dirs_entries()));
+    let systemd = systemd_user_dir();
+    entries.extend(skip_unreadable_source(&systemd, load_systemd_timers(&systemd)));
+    let runit = runit_user_service_dir();
+    entries.extend(skip_unreadable_source(&runit, load_runit_user_services(&runit)));
+    Ok((entries, dir_totals))
+}
+
+/// The entries of a source besides the autostart dirs, or none when `source` cannot be read
+/// (unreadable, not UTF-8, …); the failure is logged so the rest of the list still loads.
+fn skip_unreadable_source(source: &Path, loaded: Result<Vec<StartupEntry>>) -> Vec<StartupEntry> {
+    loaded.unwrap_or_else(|err| {
+        eprintln!("Skipping {:?}: {err:#}", source);
+        Vec::new()
+    })
+}
+
+/// Source and untruncated `.desktop` file count of each autostart dir read by a load.
+type AutostartDirTotals = Vec<(StartupSource, usize)>;
+
+/// Loads the `.desktop` files of `dirs`, at most `limit` per dir, along with each dir's
+/// `AutostartDirTotals` entry in `dirs` order.
+fn load_autostart_dirs_with_progress(
+    dirs: &[(PathBuf, StartupSource)],
+    limit: Option<usize>,
+    progress: &impl Fn(f32),
+) -> Result<(Vec<StartupEntry>, AutostartDirTotals)> {
+    let mut files: Vec<(PathBuf, StartupSource)> = Vec::new();
+    let mut totals: AutostartDirTotals = Vec::with_capacity(dirs.len());
+    for (dir, source) in dirs {
+        let (paths, total) = bounded_desktop_files(dir, limit)?;
+        totals.push((source.clone(), total));
+        for path in paths {
+            // A file in a more important dir of the same source hides one of the same name.
+            let masked = files
+                .iter()
+                .any(|(seen, seen_source)| seen_source == source && seen.file_name() == path.file_name());
+            if !masked {
+                files.push((path, source.clone()));
+            }
+        }
+    }
+    progress(0.0);
+    let mut entries = Vec::new();
+    for (done, (path, source)) in files.iter().enumerate() {
+        match parse_desktop_file(path, source.clone()) {
+            Ok(item) => entries.push(item),
+            Err(err) => eprintln!("Skipping {:?}: {err:?}", path),
+        }
+        progress((done + 1) as f32 / files.len() as f32);
+    }
+    if files.is_empty() {
+        progress(1.0);
+    }
+    Ok((entries, totals))
+}
+
+fn openbox_autostart_path() -> PathBuf {
+    let mut base = dirs::config_dir().unwrap_or_else(|| PathBuf::from("~/.config"));
+    base.push("openbox");
+    base.push("autostart");
+    base
+}
+
+fn xdg_user_dirs_path() -> PathBuf {
+    let mut base = dirs::config_dir().unwrap_or_else(|| PathBuf::from("~/.config"));
+    base.push("user-dirs.dirs");
+    base
+}
+
+fn i3_config_path() -> PathBuf {
+    let mut base = dirs::config_dir().unwrap_or_else(|| PathBuf::from("~/.config"));
+    base.push("i3");
+    base.push("config");
+    base
+}
+
+fn kde_plasma_env_dir() -> PathBuf {
+    let mut base = dirs::config_dir().unwrap_or_else(|| PathBuf::from("~/.config"));
+    base.push("plasma-workspace");
+    base.push("env");
+    base
+}
+
+fn hyprland_config_path() -> PathBuf {
+    let mut base = dirs::config_dir().unwrap_or_else(|| PathBuf::from("~/.config"));
+    base.push("hypr");
+    base.push("hyprland.conf");
+    base
+}
+
+fn sway_config_path() -> PathBuf {
+    let mut base = dirs::config_dir().unwrap_or_else(|| PathBuf::from("~/.config"));
+    base.push("sway");
+    base.push("config");
+    base
+}
+
+/// `~/.profile` and `~/.bash_profile`, which login shells run at session start.
+fn shell_profile_paths() -> Vec<PathBuf> {
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("~"));
+    vec![home.join(".profile"), home.join(".bash_profile")]
+}
+
+fn user_autostart_dir() -> PathBuf {
+    let mut base = dirs::config_dir().unwrap_or_else(|| PathBuf::from("~/.config"));
+    base.push("autostart");
+    base
+}
+
+/// `autostart` under each `$XDG_CONFIG_DIRS` entry, most important first; always includes
+/// `/etc/xdg/autostart`.
+fn system_autostart_dirs() -> Vec<PathBuf> {
+    system_autostart_dirs_from(std::env::var("XDG_CONFIG_DIRS").ok().as_deref())
+}
+
+/// `system_autostart_dirs` for a given `$XDG_CONFIG_DIRS` value. Empty and relative
+/// components are skipped, as the base directory spec requires.
+fn system_autostart_dirs_from(config_dirs: Option<&str>) -> Vec<PathBuf> {
+    let listed = config_dirs
+        .unwrap_or_default()
+        .split(':')
+        .map(Path::new)
+        .filter(|dir| dir.is_absolute())
+        .map(|dir| dir.join("autostart"));
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for dir in listed.chain([PathBuf::from("/etc/xdg/autostart")]) {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+fn url_handler_desktop_path() -> PathBuf {
+    xdg_data_applications_dir().join("universal-startup-manager-url-handler.desktop")
+}
+
+/// `$XDG_DATA_HOME/applications`, where launchers shown in application menus live.
+fn xdg_data_applications_dir() -> PathBuf {
+    let mut base = dirs::data_dir().unwrap_or_else(|| PathBuf::from("~/.local/share"));
+    base.push("applications");
+    base
+}
+
+fn runit_user_service_dir() -> PathBuf {
+    let mut base = dirs::config_dir().unwrap_or_else(|| PathBuf::from("~/.config"));
+    base.push("service");
+    base
+}
+
+fn systemd_user_dir() -> PathBuf {
+    let mut base = dirs::config_dir().unwrap_or_else(|| PathBuf::from("~/.config"));
+    base.push("systemd");
+    base.push("user");
+    base
+}
+
+fn create_systemd_user_timer(entry: &StartupEntry, schedule: &str) -> Result<()> {
+    write_systemd_user_timer(&systemd_user_dir(), entry, schedule).map(|_| ())
+}
+
+/// Writes `usm-<slug>.service` and `.timer` into `dir` and enables the timer the way
+/// `systemctl --user enable` would, via a `timers.target.wants` symlink. Returns the
+/// timer path. Takes effect after `systemctl --user daemon-reload` or the next login.
+fn write_systemd_user_timer(dir: &Path, entry: &StartupEntry, schedule: &str) -> Result<PathBuf> {
+    let schedule = schedule.trim();
+    if schedule.is_empty() || schedule.chars().any(|c| c.is_control()) {
+        bail!("Schedule must be a single-line OnCalendar= value");
+    }
+    if entry.command.trim().is_empty() {
+        bail!("Entry has no command");
+    }
+    let unit = format!("{SYSTEMD_TIMER_PREFIX}{}", slugify(&entry.name));
+    let service_path = dir.join(format!("{unit}.service"));
+    let timer_path = dir.join(format!("{unit}.timer"));
+    write_text_atomic(&service_path, &entry.to_systemd_service_string())?;
+    write_text_atomic(&timer_path, &entry.to_systemd_timer_string(schedule))?;
+    let wants = dir.join("timers.target.wants");
+    fs::create_dir_all(&wants).with_context(|| format!("Creating dir {:?}", wants))?;
+    let link = wants.join(format!("{unit}.timer"));
+    if fs::symlink_metadata(&link).is_err() {
+        std::os::unix::fs::symlink(&timer_path, &link).with_context(|| format!("Linking {:?}", link))?;
+    }
+    Ok(timer_path)
+}
+
+/// Timers written by `write_systemd_user_timer`, shown read-only. Enabled when the
+/// `timers.target.wants` link exists.
+fn load_systemd_timers(dir: &Path) -> Result<Vec<StartupEntry>> {
+    let mut entries = Vec::new();
+    if !dir.is_dir() {
+        return Ok(entries);
+    }
+    let mut timers: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("reading dir {dir:?}"))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension().and_then(|s| s.to_str()) == Some("timer")
+                && p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(SYSTEMD_TIMER_PREFIX))
+        })
+        .collect();
+    timers.sort();
+    for timer_path in timers {
+        let service_path = timer_path.with_extension("service");
+        let (Ok(timer), Ok(service)) = (fs::read_to_string(&timer_path), fs::read_to_string(&service_path)) else {
+            eprintln!("Skipping {:?}: timer or service unreadable", timer_path);
+            continue;
+        };
+        let value_of = |content: &str, key: &str| {
+            content
+                .lines()
+                .find_map(|l| l.strip_prefix(key)?.strip_prefix('='))
+                .map(|v| v.replace("%%", "%"))
+        };
+        let Some(command) = value_of(&service, "ExecStart") else {
+            continue;
+        };
+        let name = value_of(&service, "Description").unwrap_or_else(|| {
+            timer_path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default()
+        });
+        let mut extra = Vec::new();
+        if let Some(schedule) = value_of(&timer, "OnCalendar") {
+            extra.push(("OnCalendar".to_string(), schedule));
+        }
+        let enabled = timer_path
+            .file_name()
+            .map(|n| dir.join("timers.target.wants").join(n))
+            .is_some_and(|link| fs::symlink_metadata(link).is_ok());
+        entries.push(StartupEntry {
+            name,
+            command: command.replace("$$", "$"),
+            env_vars: Vec::new(),
+            enabled,
+            locked: false,
+            xfce_override: false,
+            generic_name: None,
+            comment: None,
+            icon: None,
+            try_exec: None,
+            terminal: false,
+            delay_seconds: None,
+            only_show_in: Vec::new(),
+            not_show_in: Vec::new(),
+            source: StartupSource::SystemdTimer,
+            path: Some(timer_path),
+            extra,
+            localized_names: Vec::new(),
+            entry_comments: Vec::new(),
+            preamble: Vec::new(),
+            other_groups: Vec::new(),
+        });
+    }
+    Ok(entries)
+}
+
+fn config_path() -> PathBuf {
+    let mut base = dirs::config_dir().unwrap_or_else(|| PathBuf::from("~/.config"));
+    base.push("universal-startup-manager");
+    base.push("config.json");
+    base
+}
+
+fn load_config() -> Result<AppConfig> {
+    load_config_from(&config_path())
+}
+
+fn load_config_from(path: &Path) -> Result<AppConfig> {
+    if !path.exists() {
+        return Ok(AppConfig::default());
+    }
+    let content = fs::read_to_string(path).with_context(|| format!("Reading config {:?}", path))?;
+    serde_json::from_str(&content).with_context(|| format!("Parsing config {:?}", path))
+}
+
+fn save_config(config: &AppConfig) -> Result<()> {
+    save_config_to(config, &config_path())
+}
+
+fn save_config_to(config: &AppConfig, path: &Path) -> Result<()> {
+    let json = serde_json::to_string_pretty(config).context("Serializing config")?;
+    write_text_atomic(path, &(json + "\n"))
+}
+
+/// Writes arbitrary text (exports, config) with the same temp+rename pattern as entries.
+fn write_text_atomic(path: &Path, content: &str) -> Result<()> {
+    let mut dir = path.parent().unwrap_or_else(|| Path::new(".")).to_path_buf();
+    if dir.as_os_str().is_empty() {
+        dir = PathBuf::from(".");
+    }
+    fs::create_dir_all(&dir).with_context(|| format!("Creating dir {:?}", dir))?;
+    let mut tmp = NamedTempFile::new_in(&dir).with_context(|| format!("Creating temp file in {:?}", dir))?;
+    tmp.as_file_mut()
+        .write_all(content.as_bytes())
+        .with_context(|| format!("Writing {:?}", tmp.path()))?;
+    tmp.persist(path)
+        .with_context(|| format!("Replacing {:?}", path))?;
+    Ok(())
+}
+
+/// Bytes on disk of the user autostart `.desktop` files among the loaded `entries`.
+fn user_autostart_dir_total_size(entries: &[StartupEntry]) -> u64 {
+    autostart_source_total_size(entries, StartupSource::UserAutostart)
+}
+
+/// Bytes on disk of the system autostart `.desktop` files among the loaded `entries`.
+fn system_autostart_dir_total_size(entries: &[StartupEntry]) -> u64 {
+    autostart_source_total_size(entries, StartupSource::SystemAutostart)
+}
+
+fn autostart_source_total_size(entries: &[StartupEntry], source: StartupSource) -> u64 {
+    entries
+        .iter()
+        .filter(|e| e.source == source)
+        .map(StartupEntry::size_on_disk)
+        .sum()
+}
+
+/// Formats a byte count as "432 B", "1.2 KB", "3.4 MB", … (binary units, truncated to
+/// one decimal so a value never rounds up into the next unit's range).
+fn format_size_human(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KB", "MB", "GB", "TB", "PB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", (value * 10.0).floor() / 10.0, UNITS[unit])
+}
+
+/// "User autostart has 250 entries (limit 200)"-style warning when the user autostart entries
+/// among the loaded `entries` go over either limit; `None` when both are unset or within limits.
+fn check_user_autostart_limits(all: &[StartupEntry], size_limit: Option<u64>, count_limit: Option<usize>) -> Option<String> {
+    if size_limit.is_none() && count_limit.is_none() {
+        return None;
+    }
+    let entries: Vec<&StartupEntry> = all.iter().filter(|e| e.source == StartupSource::UserAutostart).collect();
+    let mut problems = Vec::new();
+    if let Some(limit) = count_limit.filter(|&limit| entries.len() > limit) {
+        problems.push(format!("has {} entries (limit {limit})", entries.len()));
+    }
+    let total = user_autostart_dir_total_size(all);
+    if let Some(limit) = size_limit.filter(|&limit| total > limit) {
+        problems.push(format!(
+            "takes {} (limit {})",
+            format_size_human(total),
+            format_size_human(limit)
+        ));
+    }
+    if problems.is_empty() {
+        None
+    } else {
+        Some(format!("User autostart {}", problems.join(" and ")))
+    }
+}
+
+fn load_autostart_dir(dir: &Path, source: StartupSource) -> Result<Vec<StartupEntry>> {
+    load_autostart_dir_bounded(dir, source, None)
+}
+
+/// Parses at most `limit` `.desktop` files from `dir`, taken in file-name order.
+fn load_autostart_dir_bounded(dir: &Path, source: StartupSource, limit: Option<usize>) -> Result<Vec<StartupEntry>> {
+    let mut entries = Vec::new();
+    let (paths, _) = bounded_desktop_files(dir, limit)?;
+    for path in paths {
+        match parse_desktop_file(&path, source.clone()) {
+            Ok(item) => entries.push(item),
+            Err(err) => eprintln!("Skipping {:?}: {err:?}", path),
+        }
+    }
+    Ok(entries)
+}
+
+/// `desktop_files_in` sorted by file name and cut to `limit`, with the untruncated count.
+fn bounded_desktop_files(dir: &Path, limit: Option<usize>) -> Result<(Vec<PathBuf>, usize)> {
+    let mut paths = desktop_files_in(dir)?;
+    paths.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+    let total = paths.len();
+    if let Some(limit) = limit {
+        paths.truncate(limit);
+    }
+    Ok((paths, total))
+}
+
+/// Appends "Showing first N of M … entries" for each autostart dir that `limit` cut short,
+/// going by the `dir_totals` counted during the load.
+fn with_truncation_notice(text: String, limit: Option<usize>, dir_totals: &[(StartupSource, usize)]) -> String {
+    let Some(limit) = limit else {
+        return text;
+    };
+    let mut out = text;
+    for (source, total) in dir_totals {
+        let kind = if *source == StartupSource::UserAutostart { "user" } else { "system" };
+        if let Some(notice) = format_truncation_notice(limit, *total, kind) {
+            out.push_str(" · ");
+            out.push_str(&notice);
+        }
+    }
+    out
+}
+
+fn format_truncation_notice(limit: usize, total: usize, kind: &str) -> Option<String> {
+    (total > limit).then(|| format!("Showing first {limit} of {total} {kind} entries"))
+}
+
+/// `*.desktop` paths in `dir`, in directory order; empty when `dir` does not exist.
+fn desktop_files_in(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    if !dir.exists() {
+        return Ok(paths);
+    }
+    for entry in fs::read_dir(dir).with_context(|| format!("reading dir {dir:?}"))? {
+        let path = entry?.path();
+        if path.extension().and_then(|s| s.to_str()) == Some("desktop") {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+/// Loads a backup snapshot directory as if it were the user autostart dir; entry paths
+/// point into the snapshot so nothing is touched until a restore is confirmed.
+fn load_entries_from_snapshot(snapshot_dir: &Path) -> Result<Vec<StartupEntry>> {
+    if !snapshot_dir.is_dir() {
+        bail!("Snapshot {:?} is not a directory", snapshot_dir);
+    }
+    load_autostart_dir(snapshot_dir, StartupSource::UserAutostart)
+}
+
+/// Changes a restore would make, relative to the current entries.
+#[derive(Debug, Default)]
+struct SnapshotDiff {
+    added: Vec<StartupEntry>,                   // only in the snapshot
+    removed: Vec<StartupEntry>,                 // only in the current set
+    modified: Vec<(StartupEntry, StartupEntry)>, // (current, snapshot) with differing content
+}
+
+fn entry_file_name(entry: &StartupEntry) -> Option<String> {
+    entry
+        .path
+        .as_ref()
+        .and_then(|p| p.file_name())
+        .map(|n| n.to_string_lossy().into_owned())
+}
+
+/// Compares entries by file name, and matching files by their serialized content.
+fn diff_snapshots(current: &[StartupEntry], snapshot: &[StartupEntry]) -> SnapshotDiff {
+    let mut diff = SnapshotDiff::default();
+    for snap in snapshot {
+        let name = entry_file_name(snap);
+        match current.iter().find(|cur| entry_file_name(cur) == name) {
+            Some(cur) => {
+                if cur.to_desktop_string() != snap.to_desktop_string() {
+                    diff.modified.push((cur.clone(), snap.clone()));
+                }
+            }
+            None => diff.added.push(snap.clone()),
+        }
+    }
+    for cur in current {
+        let name = entry_file_name(cur);
+        if !snapshot.iter().any(|snap| entry_file_name(snap) == name) {
+            diff.removed.push(cur.clone());
+        }
+    }
+    diff
+}
+
+/// Reads systemd `environment.d` `*.conf` files; each `KEY=VALUE` assignment becomes a
+/// read-only entry shown as `export KEY=VALUE`.
+fn load_environment_d_entries(dir: &Path) -> Result<Vec<StartupEntry>> {
+    load_environment_assignment_files(dir, "conf", StartupSource::EnvironmentD)
+}
+
+/// Reads the `*.sh` scripts Plasma sources from `~/.config/plasma-workspace/env/`; each
+/// `export KEY=VALUE` becomes a read-only entry. Other shell lines are not evaluated.
+fn load_kde_plasma_env_entries(dir: &Path) -> Result<Vec<StartupEntry>> {
+    load_environment_assignment_files(dir, "sh", StartupSource::KdePlasmaEnv)
+}
+
+fn load_environment_assignment_files(dir: &Path, extension: &str, source: StartupSource) -> Result<Vec<StartupEntry>> {
+    let mut entries = Vec::new();
+    if !dir.exists() {
+        return Ok(entries);
+    }
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("reading dir {dir:?}"))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().and_then(|s| s.to_str()) == Some(extension))
+        .collect();
+    // environment.d and Plasma both apply files in lexical order.
+    paths.sort();
+    for path in paths {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => {
+                eprintln!("Skipping {:?}: {err:?}", path);
+                continue;
+            }
+        };
+        for (key, value) in parse_environment_assignments(&content) {
+            entries.push(StartupEntry {
+                name: key.clone(),
+                command: format!("export {key}={value}"),
+                env_vars: Vec::new(),
+                enabled: true,
+                locked: false,
+                xfce_override: false,
+                generic_name: None,
+                comment: None,
+                icon: None,
+                try_exec: None,
+                terminal: false,
+                delay_seconds: None,
+                only_show_in: Vec::new(),
+                not_show_in: Vec::new(),
+                source: source.clone(),
+                path: Some(path.clone()),
+                extra: Vec::new(),
+                localized_names: Vec::new(),
+                entry_comments: Vec::new(),
+                preamble: Vec::new(),
+                other_groups: Vec::new(),
+            });
+        }
+    }
+    Ok(entries)
+}
+
+/// Reads an Openbox `autostart` script; each command line becomes a read-only entry.
+fn load_openbox_autostart(path: &Path) -> Result<Vec<StartupEntry>> {
+    let content = fs::read_to_string(path).with_context(|| format!("reading {path:?}"))?;
+    Ok(content
+        .lines()
+        .filter_map(from_openbox_autostart_line)
+        .map(|mut entry| {
+            entry.path = Some(path.to_path_buf());
+            entry
+        })
+        .collect())
+}
+
+/// Programs a shell profile starts, one read-only entry per line; see `from_shell_profile_line`.
+fn load_shell_profile_entries(path: &Path) -> Result<Vec<StartupEntry>> {
+    let content = fs::read_to_string(path).with_context(|| format!("reading {path:?}"))?;
+    Ok(content
+        .lines()
+        .filter_map(from_shell_profile_line)
+        .map(|mut entry| {
+            entry.path = Some(path.to_path_buf());
+            entry
+        })
+        .collect())
+}
+
+/// The session-start `xdg-user-dirs-update` run, shown when `~/.config/user-dirs.dirs` exists.
+fn load_xdg_user_dirs_entries() -> Result<Vec<StartupEntry>> {
+    load_xdg_user_dirs_entries_from(&xdg_user_dirs_path())
+}
+
+/// One read-only entry for `xdg-user-dirs-update`, with `path` as its source document; none
+/// when the file is missing.
+fn load_xdg_user_dirs_entries_from(path: &Path) -> Result<Vec<StartupEntry>> {
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).with_context(|| format!("reading {path:?}"))?;
+    let dirs: Vec<String> = parse_environment_assignments(&content)
+        .into_iter()
+        .filter_map(|(key, _)| {
+            key.strip_prefix("XDG_")
+                .and_then(|k| k.strip_suffix("_DIR"))
+                .map(str::to_string)
+        })
+        .collect();
+    let comment = if dirs.is_empty() {
+        "No user directories set".to_string()
+    } else {
+        format!("Keeps {} up to date", dirs.join(", "))
+    };
+    Ok(vec![StartupEntry {
+        name: "xdg-user-dirs-update".to_string(),
+        command: "xdg-user-dirs-update".to_string(),
+        env_vars: Vec::new(),
+        enabled: true,
+        locked: false,
+        xfce_override: false,
+        generic_name: None,
+        comment: Some(comment),
+        icon: None,
+        try_exec: None,
+        terminal: false,
+        delay_seconds: None,
+        only_show_in: Vec::new(),
+        not_show_in: Vec::new(),
+        source: StartupSource::XdgUserDirs,
+        path: Some(path.to_path_buf()),
+        extra: Vec::new(),
+        localized_names: Vec::new(),
+        entry_comments: Vec::new(),
+        preamble: Vec::new(),
+        other_groups: Vec::new(),
+    }])
+}
+
+/// Reads a Sway config file; each `exec`/`exec_always` line becomes a read-only entry.
+fn load_sway_config_entries(path: &Path) -> Result<Vec<StartupEntry>> {
+    load_wm_config_entries(path, from_swayconfig_exec_lines)
+}
+
+/// Reads an i3 config file; each `exec`/`exec_always` line becomes a read-only entry.
+fn load_i3_config_entries(path: &Path) -> Result<Vec<StartupEntry>> {
+    load_wm_config_entries(path, from_i3_config_exec_lines)
+}
+
+/// Reads a Hyprland config file; each `exec-once` line becomes a read-only entry.
+fn load_hyprland_entries(path: &Path) -> Result<Vec<StartupEntry>> {
+    load_wm_config_entries(path, from_hyprland_config_exec_once_lines)
+}
+
+fn load_wm_config_entries(path: &Path, parse: fn(&str) -> Vec<StartupEntry>) -> Result<Vec<StartupEntry>> {
+    let content = fs::read_to_string(path).with_context(|| format!("reading {path:?}"))?;
+    Ok(parse(&content)
+        .into_iter()
+        .map(|mut entry| {
+            entry.path = Some(path.to_path_buf());
+            entry
+        })
+        .collect())
+}
+
+/// Entries for the top-level `exec` and `exec_always` lines of a Sway config, in file order.
+fn from_swayconfig_exec_lines(config: &str) -> Vec<StartupEntry> {
+    wm_config_exec_lines(config, StartupSource::SwayConfig)
+}
+
+/// Entries for the top-level `exec` and `exec_always` lines of an i3 config, in file order.
+fn from_i3_config_exec_lines(config: &str) -> Vec<StartupEntry> {
+    wm_config_exec_lines(config, StartupSource::I3Config)
+}
+
+/// Entries for the `exec-once = cmd` lines of a Hyprland config, in file order. A leading
+/// `[workspace 2 silent]`-style rule is dropped, `#` starts a comment and `##` is a literal `#`.
+fn from_hyprland_config_exec_once_lines(config: &str) -> Vec<StartupEntry> {
+    config
+        .lines()
+        .filter_map(|line| {
+            let line = strip_hyprland_comment(line);
+            let (keyword, value) = line.split_once('=')?;
+            if keyword.trim() != "exec-once" {
+                return None;
+            }
+            let mut value = value.trim();
+            if value.starts_with('[') {
+                value = value.split_once(']')?.1.trim_start();
+            }
+            let (env_vars, command) = parse_env_prefix(value);
+            let binary = tokenize_command(&command).into_iter().next()?;
+            let name = Path::new(&binary)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or(binary);
+            Some(StartupEntry {
+                name,
+                command,
+                env_vars,
+                enabled: true,
+                locked: false,
+                xfce_override: false,
+                generic_name: None,
+                comment: None,
+                icon: None,
+                try_exec: None,
+                terminal: false,
+                delay_seconds: None,
+                only_show_in: Vec::new(),
+                not_show_in: Vec::new(),
+                source: StartupSource::Hyprland,
+                path: None,
+                extra: Vec::new(),
+                localized_names: Vec::new(),
+                entry_comments: Vec::new(),
+                preamble: Vec::new(),
+                other_groups: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+/// `line` up to its first lone `#`, with `##` unescaped to `#`.
+fn strip_hyprland_comment(line: &str) -> String {
+    let mut out = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '#' {
+            if chars.peek() != Some(&'#') {
+                break;
+            }
+            chars.next();
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Sway and i3 share the syntax: `--no-startup-id` becomes `StartupNotify=false` and
+/// `exec_always` sets `EXEC_ALWAYS_KEY`. `exec` inside `bindsym` and other commands is not
+/// autostart and is ignored.
+fn wm_config_exec_lines(config: &str, source: StartupSource) -> Vec<StartupEntry> {
+    config
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (always, rest) = if let Some(rest) = line.strip_prefix("exec_always ") {
+                (true, rest)
+            } else {
+                (false, line.strip_prefix("exec ")?)
+            };
+            let rest = rest.trim_start();
+            let no_startup_id = rest.strip_prefix("--no-startup-id").map(str::trim_start);
+            let (env_vars, command) = parse_env_prefix(no_startup_id.unwrap_or(rest));
+            let binary = tokenize_command(&command).into_iter().next()?;
+            let name = Path::new(&binary)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or(binary);
+            let mut extra = Vec::new();
+            if always {
+                extra.push((EXEC_ALWAYS_KEY.to_string(), "true".to_string()));
+            }
+            if no_startup_id.is_some() {
+                extra.push(("StartupNotify".to_string(), "false".to_string()));
+            }
+            Some(StartupEntry {
+                name,
+                command,
+                env_vars,
+                enabled: true,
+                locked: false,
+                xfce_override: false,
+                generic_name: None,
+                comment: None,
+                icon: None,
+                try_exec: None,
+                terminal: false,
+                delay_seconds: None,
+                only_show_in: Vec::new(),
+                not_show_in: Vec::new(),
+                source: source.clone(),
+                path: None,
+                extra,
+                localized_names: Vec::new(),
+                entry_comments: Vec::new(),
+                preamble: Vec::new(),
+                other_groups: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+/// Reads the command from a runit `run` script: the last line that is not a comment,
+/// blank, or a bare `exec` redirection like `exec 2>&1`, with any leading `exec ` removed.
+/// The name is the binary's file name; `load_runit_user_services` uses the service dir instead.
+fn from_runit_run_script(script: &str) -> Result<StartupEntry> {
+    let is_redirection = |l: &str| {
+        l.strip_prefix("exec").is_some_and(|rest| {
+            rest.is_empty() || rest.trim_start().starts_with(|c: char| c.is_ascii_digit() || c == '>' || c == '<')
+        })
+    };
+    let line = script
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with('#') && !is_redirection(l))
+        .context("run script has no command")?;
+    let command = line.strip_prefix("exec ").map(str::trim_start).unwrap_or(line);
+    let (env_vars, command) = parse_env_prefix(command);
+    let binary = tokenize_command(&command)
+        .into_iter()
+        .next()
+        .context("run script has no command")?;
+    let name = Path::new(&binary)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or(binary);
+    Ok(StartupEntry {
+        name,
+        command,
+        env_vars,
+        enabled: true,
+        locked: false,
+        xfce_override: false,
+        generic_name: None,
+        comment: None,
+        icon: None,
+        try_exec: None,
+        terminal: false,
+        delay_seconds: None,
+        only_show_in: Vec::new(),
+        not_show_in: Vec::new(),
+        source: StartupSource::Runit,
+        path: None,
+        extra: Vec::new(),
+        localized_names: Vec::new(),
+        entry_comments: Vec::new(),
+        preamble: Vec::new(),
+        other_groups: Vec::new(),
+    })
+}
+
+/// One read-only entry per `<dir>/<service>/run`; a `down` file marks it disabled, as
+/// runsv does.
+fn load_runit_user_services(dir: &Path) -> Result<Vec<StartupEntry>> {
+    let mut entries = Vec::new();
+    if !dir.is_dir() {
+        return Ok(entries);
+    }
+    let mut services: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("reading dir {dir:?}"))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.join("run").is_file())
+        .collect();
+    services.sort();
+    for service in services {
+        let run = service.join("run");
+        let parsed = fs::read_to_string(&run)
+            .with_context(|| format!("reading {run:?}"))
+            .and_then(|script| from_runit_run_script(&script));
+        match parsed {
+            Ok(mut entry) => {
+                if let Some(name) = service.file_name() {
+                    entry.name = name.to_string_lossy().into_owned();
+                }
+                entry.enabled = !service.join("down").exists();
+                entry.path = Some(run);
+                entries.push(entry);
+            }
+            Err(err) => eprintln!("Skipping {:?}: {err:?}", run),
+        }
+    }
+    Ok(entries)
+}
+
+/// Shell keywords and builtins; a profile line starting with one is shell logic, not a program.
+const SHELL_BUILTINS: &[&str] = &[
+    "if", "then", "else", "elif", "fi", "for", "while", "until", "do", "done", "case", "esac",
+    "function", "{", "}", "[", "[[", "test", ".", "source", "export", "alias", "unalias", "set",
+    "unset", "shopt", "umask", "ulimit", "cd", "echo", "printf", "return", "exit", "trap", "eval",
+    "exec", "readonly", "local", "declare", "typeset", "true", "false", ":", "command", "type",
+    "hash", "complete", "bind", "builtin",
+];
+
+/// A shell profile line that starts a program, such as `xbindkeys &` or
+/// `/usr/bin/syncthing serve`. Comments, `export`/`alias` lines, variable assignments and
+/// other shell logic are skipped, as are words that are neither a path nor on `$PATH`.
+fn from_shell_profile_line(line: &str) -> Option<StartupEntry> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let command = line.strip_suffix('&').map(str::trim_end).unwrap_or(line);
+    let binary = tokenize_command(command).into_iter().next()?;
+    let assignment = binary.split_once('=').is_some_and(|(key, _)| is_env_var_name(key));
+    if assignment || SHELL_BUILTINS.contains(&binary.as_str()) {
+        return None;
+    }
+    if !binary.contains('/') && find_in_path(&binary).is_none() {
+        return None;
+    }
+    let name = Path::new(&binary)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or(binary);
+    Some(StartupEntry {
+        name,
+        command: command.to_string(),
+        env_vars: Vec::new(),
+        enabled: true,
+        locked: false,
+        xfce_override: false,
+        generic_name: None,
+        comment: None,
+        icon: None,
+        try_exec: None,
+        terminal: false,
+        delay_seconds: None,
+        only_show_in: Vec::new(),
+        not_show_in: Vec::new(),
+        source: StartupSource::ShellProfile,
+        path: None,
+        extra: Vec::new(),
+        localized_names: Vec::new(),
+        entry_comments: Vec::new(),
+        preamble: Vec::new(),
+        other_groups: Vec::new(),
+    })
+}
+
+/// Parses `cmd &` or `(sleep N && cmd) &`; blank lines and comments yield `None`.
+fn from_openbox_autostart_line(line: &str) -> Option<StartupEntry> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let line = line.strip_suffix('&').map(str::trim_end).unwrap_or(line);
+    let line = line
+        .strip_prefix('(')
+        .and_then(|l| l.strip_suffix(')'))
+        .map(str::trim)
+        .unwrap_or(line);
+    let (delay, command) = match line
+        .strip_prefix("sleep ")
+        .and_then(|rest| rest.split_once("&&"))
+        .and_then(|(n, cmd)| n.trim().parse::<u32>().ok().map(|n| (n, cmd.trim())))
+    {
+        Some((n, cmd)) => (n, cmd),
+        None => (0, line),
+    };
+    let binary = tokenize_command(command).into_iter().next()?;
+    let name = Path::new(&binary)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or(binary);
+    Some(StartupEntry {
+        name,
+        command: command.to_string(),
+        env_vars: Vec::new(),
+        enabled: true,
+        locked: false,
+        xfce_override: false,
+        generic_name: None,
+        comment: None,
+        icon: None,
+        try_exec: None,
+        terminal: false,
+        delay_seconds: Some(delay).filter(|&d| d > 0),
+        only_show_in: Vec::new(),
+        not_show_in: Vec::new(),
+        source: StartupSource::OpenboxAutostart,
+        path: None,
+        extra: Vec::new(),
+        localized_names: Vec::new(),
+        entry_comments: Vec::new(),
+        preamble: Vec::new(),
+        other_groups: Vec::new(),
+    })
+}
+
+fn parse_environment_assignments(content: &str) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").map(str::trim_start).unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        if !is_env_var_name(key) {
+            continue;
+        }
+        let value = value.trim();
+        let value = if value.len() >= 2
+            && ((value.starts_with('"') && value.ends_with('"'))
+                || (value.starts_with('\'') && value.ends_with('\'')))
+        {
+            &value[1..value.len() - 1]
+        } else {
+            value
+        };
+        out.push((key.to_string(), value.to_string()));
+    }
+    out
+}
+
+/// Parses a link made by `to_xdg_autostart_url_scheme` into an unsaved user entry.
+fn from_xdg_autostart_url(url: &str) -> Result<StartupEntry> {
+    let rest = url
+        .strip_prefix(XDG_AUTOSTART_URL_PREFIX)
+        .with_context(|| format!("not an {XDG_AUTOSTART_URL_PREFIX} link"))?;
+    let query = rest.strip_prefix('/').unwrap_or(rest);
+    let query = query.strip_prefix('?').context("link has no query")?;
+    let mut name = None;
+    let mut command = None;
+    let mut enabled = true;
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(value).with_context(|| format!("decoding {key:?}"))?;
+        match key {
+            "name" => name = Some(value),
+            "command" => command = Some(value),
+            "enabled" => {
+                enabled = match value.as_str() {
+                    "true" => true,
+                    "false" => false,
+                    other => bail!("enabled must be true or false, got {other:?}"),
+                }
+            }
+            _ => {}
+        }
+    }
+    let name = name.filter(|n| !n.trim().is_empty()).context("link has no name")?;
+    let command = command.filter(|c| !c.trim().is_empty()).context("link has no command")?;
+    let (env_vars, command) = parse_env_prefix(&command);
+    Ok(StartupEntry {
+        name,
+        command,
+        env_vars,
+        enabled,
+        locked: false,
+        xfce_override: false,
+        generic_name: None,
+        comment: None,
+        icon: None,
+        try_exec: None,
+        terminal: false,
+        delay_seconds: None,
+        only_show_in: Vec::new(),
+        not_show_in: Vec::new(),
+        source: StartupSource::UserAutostart,
+        path: None,
+        extra: Vec::new(),
+        localized_names: Vec::new(),
+        entry_comments: Vec::new(),
+        preamble: Vec::new(),
+        other_groups: Vec::new(),
+    })
+}
+
+/// Installs `entry` as an application launcher at `$XDG_DATA_HOME/applications/<slug>.desktop`
+/// so it shows up in application menus.
+fn generate_xdg_data_home_desktop_file(entry: &StartupEntry) -> Result<PathBuf> {
+    generate_application_launcher_in(&xdg_data_applications_dir(), entry)
+}
+
+/// Writes the launcher copy of `entry` to `dir/<slug>.desktop`. An existing launcher there is
+/// only replaced when it is our own copy of `entry`; anything else is the user's and is kept.
+fn generate_application_launcher_in(dir: &Path, entry: &StartupEntry) -> Result<PathBuf> {
+    fs::create_dir_all(dir).with_context(|| format!("Creating dir {:?}", dir))?;
+    let path = validate_entry_path_in(dir, "applications dir", &dir.join(format!("{}.desktop", slugify(&entry.name))))?;
+    if path.exists() && !is_application_copy_of(entry, &path) {
+        bail!("{:?} already exists and was not installed by this app; not replacing it", path);
+    }
+    write_desktop_entry(&application_launcher_copy(entry), &path)?;
+    Ok(path)
+}
+
+/// Removes `$XDG_DATA_HOME/applications/<slug>.desktop`; a missing file is not an error.
+fn remove_xdg_data_home_desktop_file(slug: &str) -> Result<()> {
+    if slug.is_empty() || slugify(slug) != slug {
+        bail!("Invalid launcher name {slug:?}");
+    }
+    let path = validate_data_home_entry_path(&xdg_data_applications_dir().join(format!("{slug}.desktop")))?;
+    match fs::remove_file(&path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(err).with_context(|| format!("Removing {:?}", path))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The launcher written for an entry: `Hidden=true` would hide it from menus too, so the
+/// copy is always enabled.
+fn application_launcher_copy(entry: &StartupEntry) -> StartupEntry {
+    let mut launcher = entry.clone();
+    launcher.enabled = true;
+    launcher
+}
+
+/// Whether the launcher at `path` was installed for `entry`, judged by a shared `X-USM-ID`.
+fn is_application_copy_of(entry: &StartupEntry, path: &Path) -> bool {
+    let Some(id) = entry.entry_id() else {
+        return false;
+    };
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| parse_desktop_file_from_bytes(&bytes).ok())
+        .is_some_and(|launcher| launcher.entry_id() == Some(id))
+}
+
+/// Launchers in `$XDG_DATA_HOME/applications` that ask to autostart but have no user
+/// autostart file, so nothing actually starts them.
+fn find_orphaned_xdg_data_apps() -> Result<Vec<PathBuf>> {
+    find_orphaned_xdg_data_apps_in(&xdg_data_applications_dir(), &user_autostart_dir())
+}
+
+/// `.desktop` files in `apps_dir` with `X-GNOME-Autostart-enabled=true` and no file of the
+/// same name in `autostart_dir`, sorted. A missing `apps_dir` has none; unreadable files
+/// are skipped.
+fn find_orphaned_xdg_data_apps_in(apps_dir: &Path, autostart_dir: &Path) -> Result<Vec<PathBuf>> {
+    if !apps_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut orphans: Vec<PathBuf> = desktop_files_in(apps_dir)?
+        .into_iter()
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| !autostart_dir.join(name).exists())
+        })
+        .filter(|path| {
+            fs::read_to_string(path).is_ok_and(|content| {
+                content.lines().any(|line| {
+                    line.split_once('=').is_some_and(|(key, value)| {
+                        key.trim() == "X-GNOME-Autostart-enabled" && value.trim() == "true"
+                    })
+                })
+            })
+        })
+        .collect();
+    orphans.sort();
+    Ok(orphans)
+}
+
+/// Handler entry that makes `xdg-autostart://` links open this app.
+fn generate_url_handler_desktop_file() -> String {
+    "[Desktop Entry]\n\
+     Type=Application\n\
+     Name=Universal Startup Manager (link handler)\n\
+     Exec=universal-startup-manager %u\n\
+     Icon=universal-startup-manager\n\
+     Terminal=false\n\
+     NoDisplay=true\n\
+     MimeType=x-scheme-handler/xdg-autostart;\n"
+        .to_string()
+}
+
+/// RFC 3986 percent-encoding; everything but unreserved characters is escaped.
+fn percent_encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+/// Inverse of `percent_encode`; also accepts `+` for a space as browsers send in queries.
+fn percent_decode(value: &str) -> Result<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut iter = value.bytes();
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'%' => {
+                let hex = [iter.next(), iter.next()];
+                let [Some(hi), Some(lo)] = hex else {
+                    bail!("truncated %-escape");
+                };
+                let hex = std::str::from_utf8(&[hi, lo]).ok().and_then(|h| u8::from_str_radix(h, 16).ok());
+                bytes.push(hex.context("invalid %-escape")?);
+            }
+            b'+' => bytes.push(b' '),
+            other => bytes.push(other),
+        }
+    }
+    String::from_utf8(bytes).context("decoded text is not valid UTF-8")
+}
+
+fn is_env_var_name(key: &str) -> bool {
+    key.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Splits `env A=1 B=2 cmd …` into its assignments and the remaining command. Anything
+/// else, including `env` with options or without a command, is returned unchanged.
+fn parse_env_prefix(exec: &str) -> (Vec<(String, String)>, String) {
+    let unchanged = || (Vec::new(), exec.to_string());
+    let Some((first, mut rest)) = split_shell_word(exec) else {
+        return unchanged();
+    };
+    if first != "env" {
+        return unchanged();
+    }
+    let mut vars = Vec::new();
+    while let Some((word, remaining)) = split_shell_word(rest) {
+        match word.split_once('=') {
+            Some((key, value)) if is_env_var_name(key) => {
+                vars.push((key.to_string(), value.to_string()));
+                rest = remaining;
+            }
+            _ => break,
+        }
+    }
+    if vars.is_empty() || rest.trim().is_empty() {
+        return unchanged();
+    }
+    (vars, rest.trim_start().to_string())
+}
+
+/// Double-quotes an `Exec=` argument when it contains whitespace or characters that
+/// would otherwise be interpreted.
+fn quote_exec_arg(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | '$' | '`'));
+    if !needs_quotes {
+        return value.to_string();
+    }
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '$' | '`') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
+
+/// Reads a `.desktop` file as an Xfce entry, with `xfce_override` set from its override key.
+#[allow(dead_code)]
+fn from_xfce4_autostart_entry(path: &Path) -> Result<StartupEntry> {
+    parse_desktop_file(path, StartupSource::XfceAutostart)
+}
+
+fn parse_desktop_file(path: &Path, source: StartupSource) -> Result<StartupEntry> {
+    let bytes = fs::read(path).with_context(|| format!("reading desktop file {path:?}"))?;
+    let mut entry = parse_desktop_file_from_bytes(&bytes)
+        .with_context(|| format!("parsing desktop file {path:?}"))?;
+    entry.source = source;
+    entry.path = Some(path.to_path_buf());
+    Ok(entry)
+}
+
+/// Parses `.desktop` content that is not tied to a file; source is `Unknown` and path is
+/// `None`. Visible to the fuzz target, which includes this file as a module.
+pub(crate) fn parse_desktop_file_from_bytes(bytes: &[u8]) -> Result<StartupEntry> {
+    let content = std::str::from_utf8(bytes).context("desktop file is not valid UTF-8")?;
+
+    let mut name = String::from("Unnamed");
+    let mut command = String::new();
+    let mut enabled = true;
+    let mut locked = false;
+    let mut xfce_override = false;
+    let mut generic_name = None;
+    let mut comment = None;
+    let mut icon = None;
+    let mut try_exec = None;
+    let mut terminal = false;
+    let mut delay_seconds = None;
+    let mut only_show_in = Vec::new();
+    let mut not_show_in = Vec::new();
+    let mut extra = Vec::new();
+    let mut localized_names = Vec::new();
+    let mut entry_comments = Vec::new();
+    let mut preamble = Vec::new();
+    let mut other_groups: Vec<Vec<String>> = Vec::new();
+
+    let mut current_group: Option<String> = None;
+    let mut current_other: Vec<String> = Vec::new();
+
+    for raw_line in content.lines() {
+        let trimmed = raw_line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            // close previous non-entry group buffer
+            if let Some(group) = current_group.take() {
+                if group != "Desktop Entry" && !current_other.is_empty() {
+                    other_groups.push(current_other.clone());
+                } else if group == "Desktop Entry" {
+                    // drop, we rebuild entry
+                }
+                current_other.clear();
+            } else {
+                // preamble ends here
+                if !current_other.is_empty() {
+                    preamble.append(&mut current_other);
+                }
+            }
+
+            let group_name = trimmed.trim_matches(&['[', ']'][..]).to_string();
+            let in_entry_group = group_name == "Desktop Entry";
+            current_group = Some(group_name.clone());
+            if !in_entry_group {
+                current_other.push(raw_line.to_string());
+            }
+            continue;
+        }
+
+        if let Some(group) = &current_group {
+            if group == "Desktop Entry" {
+                if trimmed.starts_with('#') || trimmed.is_empty() {
+                    entry_comments.push(raw_line.to_string());
+                    continue;
+                }
+                let (key, value) = match raw_line.split_once('=') {
+                    Some(pair) => pair,
+                    None => continue,
+                };
+                let key = key.trim();
+                let value = value.trim();
+                if key == "Name" {
+                    name = value.to_string();
+                } else if let Some(locale) = key.strip_prefix("Name[") {
+                    if let Some(locale_key) = locale.strip_suffix(']') {
+                        localized_names.push((locale_key.to_string(), value.to_string()));
+                    }
+                } else if key == "Exec" {
+                    command = value.to_string();
+                } else if key == "Hidden" {
+                    enabled = value != "true";
+                } else if key == "X-GNOME-Autostart-enabled" {
+                    enabled = value == "true";
+                } else if key == LOCKED_KEY {
+                    locked = value == "true";
+                } else if key == XFCE_AUTOSTART_OVERRIDE_KEY {
+                    xfce_override = value == "true";
+                } else if key == "GenericName" {
+                    generic_name = Some(value.to_string());
+                } else if key == "Comment" {
+                    comment = Some(value.to_string());
+                } else if key == "Icon" {
+                    icon = Some(value.to_string());
+                } else if key == "TryExec" {
+                    try_exec = Some(value.to_string());
+                } else if key == TERMINAL_KEY {
+                    terminal = value == "true";
+                } else if let Some(seconds) =
+                    (key == AUTOSTART_DELAY_KEY).then(|| value.trim().parse().ok()).flatten()
+                {
+                    delay_seconds = Some(seconds);
+                } else if key == ONLY_SHOW_IN_KEY {
+                    only_show_in = split_desktop_list(value);
+                } else if key == NOT_SHOW_IN_KEY {
+                    not_show_in = split_desktop_list(value);
+                } else {
+                    extra.push((key.to_string(), value.to_string()));
+                }
+            } else {
+                current_other.push(raw_line.to_string());
+            }
+        } else {
+            preamble.push(raw_line.to_string());
+        }
+    }
+
+    // Flush last group buffer if it is non-entry.
+    if let Some(group) = current_group {
+        if group != "Desktop Entry" && !current_other.is_empty() {
+            other_groups.push(current_other);
+        } else if group == "Desktop Entry" {
+            // drop, already parsed into fields
+        }
+    } else if !current_other.is_empty() {
+        preamble.extend(current_other);
+    }
+
+    let (env_vars, command) = parse_env_prefix(&command);
+    let mut entry = StartupEntry {
+        name,
+        command,
+        env_vars,
+        enabled,
+        locked,
+        xfce_override,
+        generic_name,
+        comment,
+        icon,
+        try_exec,
+        terminal,
+        delay_seconds,
+        only_show_in,
+        not_show_in,
+        source: StartupSource::Unknown,
+        path: None,
+        extra,
+        localized_names,
+        entry_comments,
+        preamble,
+        other_groups,
+    };
+    rename_duplicate_extra_keys_on_load(&mut entry);
+    if entry.is_commented_out() {
+        // Show what the commented lines describe, but keep writing them back as they are.
+        let commented = entry.preamble.clone();
+        entry.uncomment_entry();
+        entry.preamble = commented;
+        entry.entry_comments.clear();
+        entry.other_groups.clear();
+    }
+    Ok(entry)
+}
+
+/// Separates a repeated key from its number in memory. A key read from a file ends at the
+/// first `=`, so no real key contains one.
+const DUPLICATE_KEY_MARKER: char = '=';
+
+/// Makes repeated extra keys unique in memory: `X-Test`, `X-Test=2`, `X-Test=3`, …
+fn rename_duplicate_extra_keys_on_load(entry: &mut StartupEntry) {
+    let mut seen: HashSet<String> = HashSet::new();
+    for (key, _) in entry.extra.iter_mut() {
+        if seen.insert(key.clone()) {
+            continue;
+        }
+        let mut n = 2;
+        while seen.contains(&format!("{key}{DUPLICATE_KEY_MARKER}{n}")) {
+            n += 1;
+        }
+        *key = format!("{key}{DUPLICATE_KEY_MARKER}{n}");
+        seen.insert(key.clone());
+    }
+}
+
+/// Undoes `rename_duplicate_extra_keys_on_load` so the file keeps its original keys.
+fn strip_duplicate_suffix_on_write(entry: &StartupEntry) -> Vec<(String, String)> {
+    entry
+        .extra
+        .iter()
+        .map(|(key, value)| {
+            let restored = key.split_once(DUPLICATE_KEY_MARKER).map_or(key.as_str(), |(base, _)| base);
+            (restored.to_string(), value.clone())
+        })
+        .collect()
+}
+
+fn desktop_entry_has_key(content: &str, key: &str) -> bool {
+    let mut in_entry_group = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            in_entry_group = trimmed == "[Desktop Entry]";
+            continue;
+        }
+        if in_entry_group {
+            if let Some((k, _)) = trimmed.split_once('=') {
+                if k.trim() == key {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Adds `X-GNOME-Autostart-enabled=` to entries in `dir` that only carry `Hidden=`.
+/// Returns how many files were rewritten.
+fn migrate_hidden_to_xgnome(dir: &Path) -> Result<usize> {
+    let mut migrated = 0;
+    if !dir.exists() {
+        return Ok(migrated);
+    }
+    for dir_entry in fs::read_dir(dir).with_context(|| format!("reading dir {dir:?}"))? {
+        let path = dir_entry?.path();
+        if path.extension().and_then(|s| s.to_str()) != Some("desktop") {
+            continue;
+        }
+        match fs::symlink_metadata(&path) {
+            Ok(meta) if meta.is_file() && !meta.file_type().is_symlink() => {}
+            _ => continue,
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("reading desktop file {path:?}"))?;
+        if !desktop_entry_has_key(&content, "Hidden")
+            || desktop_entry_has_key(&content, "X-GNOME-Autostart-enabled")
+        {
+            continue;
+        }
+        let entry = parse_desktop_file(&path, StartupSource::UserAutostart)?;
+        write_desktop_entry(&entry, &path)?;
+        migrated += 1;
+    }
+    Ok(migrated)
+}
+
+/// Desktops in a `;`-separated list value such as `OnlyShowIn=GNOME;XFCE;`.
+fn split_desktop_list(value: &str) -> Vec<String> {
+    value.split(';').map(str::trim).filter(|d| !d.is_empty()).map(str::to_string).collect()
+}
+
+/// The exact `.desktop` text `write_desktop_entry` puts on disk; pure, so previews,
+/// clipboard copies and exports can share it.
+fn write_desktop_entry_to_string(entry: &StartupEntry) -> String {
+    if entry.is_commented_out() {
+        return entry.preamble.join("\n") + "\n";
+    }
+    let mut lines = Vec::new();
+    lines.extend(entry.preamble.clone());
+    if entry.preamble.last().map(|s| !s.is_empty()).unwrap_or(false) {
+        lines.push(String::new());
+    }
+
+    lines.push("[Desktop Entry]".to_string());
+    lines.extend(entry.entry_comments.clone());
+    lines.push("Type=Application".to_string());
+    lines.push(format!("Name={}", entry.name));
+    for (locale, value) in entry.localized_names.iter() {
+        lines.push(format!("Name[{locale}]={value}"));
+    }
+    if let Some(generic_name) = &entry.generic_name {
+        lines.push(format!("GenericName={generic_name}"));
+    }
+    if let Some(comment) = &entry.comment {
+        lines.push(format!("Comment={comment}"));
+    }
+    if let Some(icon) = &entry.icon {
+        lines.push(format!("Icon={icon}"));
+    }
+    if let Some(try_exec) = &entry.try_exec {
+        lines.push(format!("TryExec={try_exec}"));
+    }
+    lines.push(format!("Exec={}", entry.exec_line()));
+    lines.push(format!("{TERMINAL_KEY}={}", entry.terminal));
+    lines.push(format!(
+        "X-GNOME-Autostart-enabled={}",
+        if entry.enabled { "true" } else { "false" }
+    ));
+    lines.push(format!(
+        "Hidden={}",
+        if entry.enabled { "false" } else { "true" }
+    ));
+    if let Some(seconds) = entry.delay_seconds {
+        lines.push(format!("{AUTOSTART_DELAY_KEY}={seconds}"));
+    }
+    if entry.locked {
+        lines.push(format!("{LOCKED_KEY}=true"));
+    }
+    if entry.xfce_override {
+        lines.push(format!("{XFCE_AUTOSTART_OVERRIDE_KEY}=true"));
+    }
+    for (key, values) in [(ONLY_SHOW_IN_KEY, &entry.only_show_in), (NOT_SHOW_IN_KEY, &entry.not_show_in)] {
+        if !values.is_empty() {
+            lines.push(format!("{key}={};", values.join(";")));
+        }
+    }
+    let known = [
+        "Name",
+        "Exec",
+        "Hidden",
+        "X-GNOME-Autostart-enabled",
+        "Type",
+        LOCKED_KEY,
+        XFCE_AUTOSTART_OVERRIDE_KEY,
+        "GenericName",
+        "Comment",
+        "Icon",
+        "TryExec",
+        TERMINAL_KEY,
+        ONLY_SHOW_IN_KEY,
+        NOT_SHOW_IN_KEY,
+    ];
+    for (k, v) in strip_duplicate_suffix_on_write(entry).iter() {
+        if known.contains(&k.as_str()) || k.starts_with("Name[") {
+            continue;
+        }
+        lines.push(format!("{k}={v}"));
+    }
+
+    if !entry.other_groups.is_empty() && !lines.last().map(|s| s.is_empty()).unwrap_or(true) {
+        lines.push(String::new());
+    }
+    for (i, group) in entry.other_groups.iter().enumerate() {
+        lines.extend(group.clone());
+        if i + 1 != entry.other_groups.len() && !group.last().map(|s| s.is_empty()).unwrap_or(true) {
+            lines.push(String::new());
+        }
+    }
+
+    if lines.last().map(|l| l.is_empty()).unwrap_or(false) {
+        lines.join("\n")
+    } else {
+        lines.join("\n") + "\n"
+    }
+}
+
+fn write_desktop_entry(entry: &StartupEntry, path: &Path) -> Result<()> {
+    if SYNC_ON_WRITE.load(Ordering::Relaxed) {
+        write_desktop_entry_atomic_with_fsync(entry, path)
+    } else {
+        write_desktop_entry_unsynced_dir(entry, path)
+    }
+}
+
+/// Writes several entries all-or-nothing, as far as the filesystem allows: every entry goes
+/// to a temp file beside its target first, and only then are they renamed into place. If a
+/// rename fails, the files already replaced get their old content back (or are removed if
+/// they are new) and the remaining temp files are deleted. Returns the written paths.
+fn write_desktop_entry_batch(entries: &[(StartupEntry, PathBuf)]) -> Result<Vec<PathBuf>> {
+    let mut staged = Vec::with_capacity(entries.len());
+    for (entry, path) in entries {
+        let mut dir = path.parent().unwrap_or_else(|| Path::new(".")).to_path_buf();
+        if dir.as_os_str().is_empty() {
+            dir = PathBuf::from(".");
+        }
+        fs::create_dir_all(&dir).with_context(|| format!("Creating dir {:?}", dir))?;
+        let mut tmp = NamedTempFile::new_in(&dir).with_context(|| format!("Creating temp file in {:?}", dir))?;
+        let tmp_path = tmp.path().to_path_buf();
+        let file = tmp.as_file_mut();
+        file.write_all(write_desktop_entry_to_string(entry).as_bytes())
+            .with_context(|| format!("Writing {:?}", tmp_path))?;
+        let _ = file.sync_all();
+        let previous = fs::read(path).ok();
+        staged.push((tmp, path.clone(), previous, dir));
+    }
+    let mut written: Vec<(PathBuf, Option<Vec<u8>>)> = Vec::with_capacity(staged.len());
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    // Temp files left in `staged` are deleted when it is dropped.
+    for (tmp, path, previous, dir) in staged {
+        if let Err(err) = tmp.persist(&path) {
+            restore_previous_contents(&written);
+            return Err(err.error)
+                .with_context(|| format!("Replacing {:?}; the rest of the batch was undone", path));
+        }
+        written.push((path, previous));
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    if SYNC_ON_WRITE.load(Ordering::Relaxed) {
+        for dir in dirs {
+            fs::File::open(&dir)
+                .and_then(|d| d.sync_all())
+                .with_context(|| format!("Syncing dir {:?}", dir))?;
+        }
+    }
+    Ok(written.into_iter().map(|(path, _)| path).collect())
+}
+
+/// Undoes a partly written batch, newest first: each file gets its previous content back,
+/// or is removed if it did not exist before. Best effort, as the batch has already failed.
+fn restore_previous_contents(written: &[(PathBuf, Option<Vec<u8>>)]) {
+    for (path, previous) in written.iter().rev() {
+        let _ = match previous {
+            Some(content) => fs::write(path, content),
+            None => fs::remove_file(path),
+        };
+    }
+}
+
+/// Top-level dirs owned by the system; autostart files are never written below them.
+const SYSTEM_PATH_PREFIXES: &[&str] = &["/etc", "/usr", "/lib", "/lib64", "/bin", "/sbin", "/opt", "/var", "/boot"];
+/// Home directories on image-based systems such as Fedora Silverblue, where `/home`
+/// links to `/var/home`.
+const USER_PATH_PREFIXES_UNDER_SYSTEM: &[&str] = &["/var/home"];
+
+/// Whether `path` (already resolved) lies below a system-owned top-level dir.
+/// Prefixes match whole components, so `/etcetera` is not under `/etc`.
+fn is_system_path(path: &Path) -> bool {
+    SYSTEM_PATH_PREFIXES.iter().any(|prefix| path.starts_with(prefix))
+        && !USER_PATH_PREFIXES_UNDER_SYSTEM.iter().any(|prefix| path.starts_with(prefix))
+}
+
+/// `dir` must exist, be writable by this user, and not resolve into a system location.
+fn validate_user_writable_dir(dir: &Path) -> Result<PathBuf> {
+    let canon = dir
+        .canonicalize()
+        .with_context(|| format!("Resolving {:?}", dir))?;
+    if !canon.is_dir() {
+        bail!("{:?} is not a directory", dir);
+    }
+    if is_system_path(&canon) {
+        bail!("{:?} is a system path", dir);
+    }
+    NamedTempFile::new_in(&canon).with_context(|| format!("{:?} is not writable", dir))?;
+    Ok(canon)
+}
+
+/// Writes `entry` into each of `dirs`, for setups with more than one user autostart dir,
+/// and returns the paths written. Every dir is checked before anything is written; if a
+/// write still fails, the files already written get their old content back (or are removed).
+fn write_multiple_autostart_dirs(entry: &StartupEntry, dirs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let file_name = entry_file_name(entry).unwrap_or_else(|| format!("{}.desktop", slugify(&entry.name)));
+    let mut targets = Vec::with_capacity(dirs.len());
+    for dir in dirs {
+        let dir = validate_user_writable_dir(dir)?;
+        let path = validate_entry_path_in(&dir, "autostart dir", &dir.join(&file_name))?;
+        if !targets.contains(&path) {
+            targets.push(path);
+        }
+    }
+    let mut written: Vec<(PathBuf, Option<Vec<u8>>)> = Vec::with_capacity(targets.len());
+    for path in targets {
+        let previous = fs::read(&path).ok();
+        if let Err(err) = write_desktop_entry(entry, &path) {
+            restore_previous_contents(&written);
+            return Err(err.context(format!("Writing {:?}; earlier copies were undone", path)));
+        }
+        written.push((path, previous));
+    }
+    Ok(written.into_iter().map(|(path, _)| path).collect())
+}
+
+/// Like `write_desktop_entry_unsynced_dir`, but also fsyncs the parent directory so the
+/// rename survives a crash.
+fn write_desktop_entry_atomic_with_fsync(entry: &StartupEntry, path: &Path) -> Result<()> {
+    write_desktop_entry_unsynced_dir(entry, path)?;
+    let mut dir = path.parent().unwrap_or_else(|| Path::new(".")).to_path_buf();
+    if dir.as_os_str().is_empty() {
+        dir = PathBuf::from(".");
+    }
+    fs::File::open(&dir)
+        .and_then(|d| d.sync_all())
+        .with_context(|| format!("Syncing dir {:?}", dir))?;
+    Ok(())
+}
+
+fn write_desktop_entry_unsynced_dir(entry: &StartupEntry, path: &Path) -> Result<()> {
+    let mut dir = path.parent().unwrap_or_else(|| Path::new(".")).to_path_buf();
+    if dir.as_os_str().is_empty() {
+        dir = PathBuf::from(".");
+    }
+    fs::create_dir_all(&dir).with_context(|| format!("Creating dir {:?}", dir))?;
+    let mut tmp = NamedTempFile::new_in(&dir).with_context(|| format!("Creating temp file in {:?}", dir))?;
+    let tmp_path = tmp.path().to_path_buf();
+    let file = tmp.as_file_mut();
+    let content = write_desktop_entry_to_string(entry);
+    file.write_all(content.as_bytes())
+        .with_context(|| format!("Writing {:?}", tmp_path))?;
+    let _ = file.sync_all();
+    tmp.persist(path)
+        .with_context(|| format!("Replacing {:?}", path))?;
+    Ok(())
+}
+
+/// `write_desktop_entry`, first copying any existing file at `path` to `backup_path` and
+/// copying it back if the write fails. The backup is removed after a successful write.
+fn write_atomic_with_rollback(entry: &StartupEntry, path: &Path, backup_path: &Path) -> Result<()> {
+    with_rollback(path, backup_path, || write_desktop_entry(entry, path))
+}
+
+fn with_rollback(path: &Path, backup_path: &Path, write: impl FnOnce() -> Result<()>) -> Result<()> {
+    let backed_up = path.exists();
+    if backed_up {
+        if let Some(dir) = backup_path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Creating dir {:?}", dir))?;
+        }
+        fs::copy(path, backup_path).with_context(|| format!("Backing up {:?}", path))?;
+    }
+    match write() {
+        Ok(()) => {
+            if backed_up {
+                let _ = fs::remove_file(backup_path);
+            }
+            Ok(())
+        }
+        Err(err) if backed_up => match rollback_from_backup(backup_path, path) {
+            Ok(()) => Err(err),
+            Err(rollback_err) => Err(err.context(format!("{rollback_err:#}"))),
+        },
+        Err(err) => Err(err),
+    }
+}
+
+/// Puts the copy at `backup_path` back in place of `target_path`, then removes the copy.
+fn rollback_from_backup(backup_path: &Path, target_path: &Path) -> Result<()> {
+    fs::copy(backup_path, target_path)
+        .with_context(|| format!("Restoring {:?} from {:?}", target_path, backup_path))?;
+    let _ = fs::remove_file(backup_path);
+    Ok(())
+}
+
+/// Where `write_atomic_with_rollback` keeps the previous version of `path` while writing:
+/// the user cache dir, so a leftover copy never shows up as an autostart entry.
+fn rollback_backup_path(path: &Path) -> PathBuf {
+    let mut base = dirs::cache_dir().unwrap_or_else(std::env::temp_dir);
+    base.push("universal-startup-manager");
+    base.push("rollback");
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    base.push(format!("{name}.bak"));
+    base
+}
+
+/// How many changes `UndoStack` remembers in each direction.
+const UNDO_STACK_LEN: usize = 20;
+
+/// A change to a user entry file, holding what is needed to reverse it.
+#[derive(Debug, Clone)]
+enum UndoEntry {
+    Added { path: PathBuf },
+    Deleted { path: PathBuf, entry: StartupEntry },
+    /// `path` is where the edit was written; `original.path` is where it was before a rename.
+    Edited { path: PathBuf, original: StartupEntry },
+    Toggled { path: PathBuf, was_enabled: bool },
+    /// Changes made together, such as Enable All Visible, undone and redone as one step.
+    Batch(Vec<UndoEntry>),
+}
+
+impl UndoEntry {
+    fn label(&self) -> &'static str {
+        match self {
+            UndoEntry::Added { .. } => "add",
+            UndoEntry::Deleted { .. } => "delete",
+            UndoEntry::Edited { .. } => "edit",
+            UndoEntry::Toggled { .. } => "toggle",
+            UndoEntry::Batch(_) => "bulk change",
+        }
+    }
+
+    /// Every file `revert` writes or removes.
+    fn paths(&self) -> Vec<&Path> {
+        match self {
+            UndoEntry::Edited { path, original } => {
+                let mut paths = vec![path.as_path()];
+                paths.extend(original.path.as_deref());
+                paths
+            }
+            UndoEntry::Added { path } | UndoEntry::Deleted { path, .. } | UndoEntry::Toggled { path, .. } => {
+                vec![path.as_path()]
+            }
+            UndoEntry::Batch(changes) => changes.iter().flat_map(UndoEntry::paths).collect(),
+        }
+    }
+
+    /// Puts the files back as they were before the change and returns the change that
+    /// would redo it.
+    fn revert(&self) -> Result<UndoEntry> {
+        match self {
+            UndoEntry::Added { path } => {
+                let entry = parse_desktop_file(path, StartupSource::UserAutostart)?;
+                fs::remove_file(path).with_context(|| format!("Removing {:?}", path))?;
+                Ok(UndoEntry::Deleted { path: path.clone(), entry })
+            }
+            UndoEntry::Deleted { path, entry } => {
+                if path.exists() {
+                    bail!("{:?} exists again; not overwriting it", path);
+                }
+                write_desktop_entry(entry, path)?;
+                Ok(UndoEntry::Added { path: path.clone() })
+            }
+            UndoEntry::Edited { path, original } => {
+                let edited = parse_desktop_file(path, StartupSource::UserAutostart)?;
+                let original_path = original.path.clone().unwrap_or_else(|| path.clone());
+                if &original_path != path && original_path.exists() {
+                    bail!("{:?} exists again; not overwriting it", original_path);
+                }
+                write_atomic_with_rollback(original, &original_path, &rollback_backup_path(&original_path))?;
+                if &original_path != path {
+                    fs::remove_file(path).with_context(|| format!("Removing {:?}", path))?;
+                }
+                Ok(UndoEntry::Edited { path: original_path, original: edited })
+            }
+            UndoEntry::Toggled { path, was_enabled } => {
+                let mut entry = parse_desktop_file(path, StartupSource::UserAutostart)?;
+                let now_enabled = entry.enabled;
+                entry.enabled = *was_enabled;
+                write_atomic_with_rollback(&entry, path, &rollback_backup_path(path))?;
+                Ok(UndoEntry::Toggled { path: path.clone(), was_enabled: now_enabled })
+            }
+            UndoEntry::Batch(changes) => {
+                // Newest first; if one fails, the ones already reverted are reapplied so the
+                // batch stays all-or-nothing.
+                let mut inverses = Vec::with_capacity(changes.len());
+                for change in changes.iter().rev() {
+                    match change.revert() {
+                        Ok(inverse) => inverses.push(inverse),
+                        Err(err) => {
+                            for inverse in inverses.iter().rev() {
+                                if let Err(again) = inverse.revert() {
+                                    eprintln!("Reapplying {} after a failed undo: {again:#}", inverse.label());
+                                }
+                            }
+                            return Err(err);
+                        }
+                    }
+                }
+                Ok(UndoEntry::Batch(inverses))
+            }
+        }
+    }
+}
+
+/// Changes that can be undone, newest last, and changes undone that can be redone. Each side
+/// keeps the last `UNDO_STACK_LEN`.
+#[derive(Debug, Default)]
+struct UndoStack {
+    done: VecDeque<UndoEntry>,
+    undone: VecDeque<UndoEntry>,
+}
+
+impl UndoStack {
+    /// A new change; it replaces anything waiting to be redone.
+    fn push(&mut self, entry: UndoEntry) {
+        self.undone.clear();
+        self.restore_undo(entry);
+    }
+
+    /// Puts `entry` on the undo side without touching the redo side.
+    fn restore_undo(&mut self, entry: UndoEntry) {
+        self.done.push_back(entry);
+        if self.done.len() > UNDO_STACK_LEN {
+            self.done.pop_front();
+        }
+    }
+
+    fn push_redo(&mut self, entry: UndoEntry) {
+        self.undone.push_back(entry);
+        if self.undone.len() > UNDO_STACK_LEN {
+            self.undone.pop_front();
+        }
+    }
+
+    fn pop_undo(&mut self) -> Option<UndoEntry> {
+        self.done.pop_back()
+    }
+
+    fn pop_redo(&mut self) -> Option<UndoEntry> {
+        self.undone.pop_back()
+    }
+
+    fn next_undo(&self) -> Option<&UndoEntry> {
+        self.done.back()
+    }
+}
+
+fn apply_edit(original: &StartupEntry, new_name: &str, new_cmd: &str) -> StartupEntry {
+    let mut updated = original.clone();
+    updated.ensure_entry_id();
+    updated.name = new_name.to_string();
+    // An `env` prefix typed into the command field joins the entry's environment variables,
+    // replacing the value of a variable the entry already sets.
+    let (env_vars, command) = parse_env_prefix(new_cmd);
+    for (key, value) in env_vars {
+        match updated.env_vars.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, existing)) => *existing = value,
+            None => updated.env_vars.push((key, value)),
+        }
+    }
+    updated.command = command;
+    updated
+}
+
+/// Content the edit dialog's preview shows; identical to what gets written to disk.
+fn preview_write_output(entry: &StartupEntry) -> String {
+    write_desktop_entry_to_string(entry)
+}
+
+/// Writes the edit and returns the file it went to, which differs from `original_path` after
+/// a rename.
+fn edit_user_entry(original: &StartupEntry, new_name: &str, new_cmd: &str, original_path: Option<&PathBuf>) -> Result<PathBuf> {
+    if original.locked {
+        bail!("Entry is locked; unlock it first");
+    }
+    if original.is_commented_out() {
+        bail!("Entry is commented out; restore it first");
+    }
+    let updated = apply_edit(original, new_name, new_cmd);
+    let target_path = if let Some(p) = original_path {
+        renamed_entry_path(p, &original.name, new_name)
+    } else {
+        user_autostart_dir().join(format!("{}.desktop", slugify(new_name)))
+    };
+    if original_path.is_some_and(|p| p != &target_path) && target_path.exists() {
+        bail!("Another entry already uses {:?}", target_path);
+    }
+    let target_path = validate_user_entry_path(&target_path)?;
+    write_atomic_with_rollback(&updated, &target_path, &rollback_backup_path(&target_path))?;
+    // If slug/name changed, remove old file to avoid duplicates.
+    if let Some(old_path) = original_path {
+        if old_path != &target_path {
+            if let Ok(old_path) = validate_user_entry_path(old_path) {
+                let _ = fs::remove_file(old_path);
+            }
+        }
+    }
+    Ok(target_path)
+}
+
+/// Where an entry's file lives after renaming it from `old_name` to `new_name`. Only files we
+/// named (stem equal to the old slug) follow the new slug; anything else, such as an override
+/// sharing a system entry's file name, keeps its path.
+fn renamed_entry_path(old_path: &Path, old_name: &str, new_name: &str) -> PathBuf {
+    let old_slug = slugify(old_name);
+    let new_slug = slugify(new_name);
+    if old_slug == new_slug || old_path.file_stem().and_then(|s| s.to_str()) != Some(old_slug.as_str()) {
+        return old_path.to_path_buf();
+    }
+    old_path.with_file_name(format!("{new_slug}.desktop"))
+}
+
+/// Points the edited entry at `idx` at the file `edit_user_entry` wrote, so nothing acts on
+/// the stale path before the next `refresh_entries`. The entry keeps its index, so the
+/// selection still refers to it.
+fn rewrite_entry_path_on_rename(state: &AppState, idx: usize, old_path: Option<&Path>, new_name: &str, new_path: PathBuf) {
+    if point_entry_at_renamed_file(&mut state.entries.borrow_mut(), idx, old_path, new_name, new_path) {
+        update_detail(state);
+    }
+}
+
+/// Renames `entries[idx]` and sets its path to `new_path`, unless the entry there no longer
+/// has `old_path` (the list was reloaded meanwhile). Returns whether it was updated.
+fn point_entry_at_renamed_file(
+    entries: &mut [StartupEntry],
+    idx: usize,
+    old_path: Option<&Path>,
+    new_name: &str,
+    new_path: PathBuf,
+) -> bool {
+    let Some(entry) = entries.get_mut(idx).filter(|e| e.path.as_deref() == old_path) else {
+        return false;
+    };
+    entry.name = new_name.to_string();
+    entry.path = Some(new_path);
+    true
+}
+
+/// Renames unlocked user entries whose name matches `pattern`, where `*` matches any text and
+/// each `*` in `replacement` takes the next captured text. Renamed entries get a new file path
+/// whose slug is unique among all entries. Returns the number renamed.
+///
+/// Glob-style rather than regex: the `regex` crate is not among the vendored dependencies.
+fn batch_rename_entries(pattern: &str, replacement: &str, entries: &mut [StartupEntry]) -> Result<usize> {
+    if pattern.is_empty() {
+        bail!("Pattern is empty");
+    }
+    if replacement.matches('*').count() > pattern.matches('*').count() {
+        bail!("Replacement has more * than the pattern");
+    }
+    if replacement.trim().is_empty() {
+        bail!("Replacement is empty");
+    }
+    let mut taken: HashSet<String> = entries
+        .iter()
+        .filter_map(|e| e.path.as_ref()?.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .collect();
+    let mut count = 0;
+    for entry in entries.iter_mut() {
+        if entry.source != StartupSource::UserAutostart || entry.locked {
+            continue;
+        }
+        let Some(path) = entry.path.clone() else {
+            continue;
+        };
+        let Some(captures) = match_wildcard(pattern, &entry.name) else {
+            continue;
+        };
+        let new_name = fill_wildcards(replacement, &captures);
+        if new_name == entry.name || new_name.trim().is_empty() {
+            continue;
+        }
+        let old_stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        // Never reuse another entry's current file name, even one being renamed away from,
+        // so writing the new files cannot clobber a file that is still to be removed.
+        let slug = if slugify(&new_name) == old_stem {
+            old_stem
+        } else {
+            generate_unique_slug(&new_name, &taken)
+        };
+        taken.insert(slug.clone());
+        entry.ensure_entry_id();
+        entry.name = new_name;
+        entry.path = Some(path.with_file_name(format!("{slug}.desktop")));
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// `slugify(name)`, with `-2`, `-3`, … appended until it is not in `taken`.
+fn generate_unique_slug(name: &str, taken: &HashSet<String>) -> String {
+    let base = slugify(name);
+    if !taken.contains(&base) {
+        return base;
+    }
+    let mut n = 2;
+    loop {
+        let candidate = format!("{base}-{n}");
+        if !taken.contains(&candidate) {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+/// Matches `text` against a pattern where `*` matches any (possibly empty) text.
+/// Returns the text matched by each `*`, shortest match first.
+fn match_wildcard<'a>(pattern: &str, text: &'a str) -> Option<Vec<&'a str>> {
+    let literals: Vec<&str> = pattern.split('*').collect();
+    let (first, rest) = literals.split_first()?;
+    let mut remaining = text.strip_prefix(first)?;
+    let mut captures = Vec::new();
+    for (i, literal) in rest.iter().enumerate() {
+        let is_last = i + 1 == rest.len();
+        let pos = if is_last {
+            if !remaining.ends_with(literal) {
+                return None;
+            }
+            remaining.len() - literal.len()
+        } else {
+            remaining.find(literal)?
+        };
+        captures.push(&remaining[..pos]);
+        remaining = &remaining[pos + literal.len()..];
+    }
+    if rest.is_empty() && !remaining.is_empty() {
+        return None;
+    }
+    Some(captures)
+}
+
+fn fill_wildcards(replacement: &str, captures: &[&str]) -> String {
+    let mut out = String::new();
+    let mut captures = captures.iter();
+    for (i, part) in replacement.split('*').enumerate() {
+        if i > 0 {
+            out.push_str(captures.next().copied().unwrap_or(""));
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+fn create_user_entry(name: &str, command: &str, terminal: bool) -> Result<PathBuf> {
+    if name.trim().is_empty() || command.trim().is_empty() {
+        bail!("Name and command are required");
+    }
+    let dir = user_autostart_dir();
+    fs::create_dir_all(&dir).with_context(|| format!("Creating dir {:?}", dir))?;
+    let file_name = format!("{}.desktop", slugify(name));
+    let path = dir.join(file_name);
+    let path = validate_user_entry_path(&path)?;
+    let mut entry = StartupEntry {
+        name: name.to_string(),
+        command: command.to_string(),
+        env_vars: Vec::new(),
+        enabled: true,
+        locked: false,
+        xfce_override: false,
+        generic_name: None,
+        comment: None,
+        icon: None,
+        try_exec: None,
+        terminal,
+        delay_seconds: None,
+        only_show_in: Vec::new(),
+        not_show_in: Vec::new(),
+        source: StartupSource::UserAutostart,
+        path: Some(path.clone()),
+        extra: Vec::new(),
+        localized_names: Vec::new(),
+        entry_comments: Vec::new(),
+        preamble: Vec::new(),
+        other_groups: Vec::new(),
+    };
+    entry.ensure_entry_id();
+    write_desktop_entry(&entry, &path)?;
+    Ok(path)
+}
+
+/// Writes `entry` to a fresh file in `dir` as an unlocked user entry named "… (copy)", with
+/// its own `X-USM-ID=`. `Name[xx]` values are dropped so the copy is listed under its new
+/// name in every locale. Returns the new path and entry.
+fn duplicate_entry_in(dir: &Path, entry: &StartupEntry) -> Result<(PathBuf, StartupEntry)> {
+    if is_environment_source(&entry.source) {
+        bail!("Environment variables cannot be duplicated as startup entries");
+    }
+    let mut copy = entry.clone();
+    copy.name = format!("{} (copy)", entry.name);
+    copy.source = StartupSource::UserAutostart;
+    copy.locked = false;
+    copy.localized_names.clear();
+    copy.extra.retain(|(k, _)| k != USM_ID_KEY);
+    let slug = slugify(&copy.name);
+    let path = dir.join(format!("{slug}.desktop"));
+    let path = if path.exists() { free_desktop_file_path(dir, &slug) } else { path };
+    let path = validate_entry_path_in(dir, "user autostart dir", &path)?;
+    copy.path = Some(path.clone());
+    copy.ensure_entry_id();
+    write_desktop_entry(&copy, &path)?;
+    Ok((path, copy))
+}
+
+/// Writes a system entry to `dir` under its own file name, the XDG way to override it for
+/// this user. Refuses when a user file of that name already exists.
+fn override_entry_in(dir: &Path, entry: &StartupEntry) -> Result<PathBuf> {
+    if entry.source != StartupSource::SystemAutostart {
+        bail!("Only system autostart entries can be overridden");
+    }
+    let file_name = entry
+        .path
+        .as_deref()
+        .and_then(Path::file_name)
+        .context("Entry has no associated file path")?;
+    let path = dir.join(file_name);
+    if path.exists() {
+        bail!("{} already overrides this entry", path.display());
+    }
+    let path = validate_entry_path_in(dir, "user autostart dir", &path)?;
+    let mut copy = entry.clone();
+    copy.source = StartupSource::UserAutostart;
+    copy.path = Some(path.clone());
+    copy.ensure_entry_id();
+    write_desktop_entry(&copy, &path)?;
+    Ok(path)
+}
+
+/// Choices from the Normalize dialog.
+#[derive(Debug, Clone, Copy, Default)]
+struct NormalizeOptions {
+    remove_comments: bool,
+}
+
+/// The "Clean" normalization pipeline. Returns whether the serialized entry changed.
+fn normalize_entry(entry: &mut StartupEntry, options: NormalizeOptions) -> bool {
+    let before = entry.to_desktop_string();
+    entry.normalize_command_quoting();
+    if options.remove_comments {
+        strip_preamble(entry);
+        strip_entry_comments(entry);
+    }
+    entry.to_desktop_string() != before
+}
+
+fn strip_preamble(entry: &mut StartupEntry) {
+    entry.preamble.clear();
+}
+
+fn strip_entry_comments(entry: &mut StartupEntry) {
+    entry.entry_comments.clear();
+}
+
+/// Runs `normalize_entry` over unlocked user entries and rewrites the ones that changed,
+/// as one `write_desktop_entry_batch`.
+fn normalize_user_entries(entries: &[StartupEntry], options: NormalizeOptions) -> Result<usize> {
+    let mut batch = Vec::new();
+    for entry in entries {
+        if entry.source != StartupSource::UserAutostart || entry.locked {
+            continue;
+        }
+        let Some(path) = entry.path.as_ref() else {
+            continue;
+        };
+        let mut normalized = entry.clone();
+        if normalize_entry(&mut normalized, options) {
+            let path = validate_user_entry_path(path)?;
+            batch.push((normalized, path));
+        }
+    }
+    Ok(write_desktop_entry_batch(&batch)?.len())
+}
+
+/// Keeps one entry per content fingerprint, preferring user entries over system ones.
+fn dedup_by_content(entries: Vec<StartupEntry>) -> Vec<StartupEntry> {
+    let mut seen: HashMap<u64, usize> = HashMap::new();
+    let mut out: Vec<StartupEntry> = Vec::new();
+    for entry in entries {
+        let fingerprint = entry.compute_content_fingerprint();
+        match seen.get(&fingerprint) {
+            Some(&pos) => {
+                if out[pos].source != StartupSource::UserAutostart
+                    && entry.source == StartupSource::UserAutostart
+                {
+                    out[pos] = entry;
+                }
+            }
+            None => {
+                seen.insert(fingerprint, out.len());
+                out.push(entry);
+            }
+        }
+    }
+    out
+}
+
+/// Joins argv into a Windows command line, double-quoting arguments that need it.
+fn windows_command_line(argv: &[String]) -> String {
+    argv.iter()
+        .map(|arg| {
+            if !arg.is_empty() && !arg.chars().any(|c| c.is_whitespace() || c == '"') {
+                arg.clone()
+            } else {
+                format!("\"{}\"", arg.replace('"', "\\\""))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum LintSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl LintSeverity {
+    fn label(self) -> &'static str {
+        match self {
+            LintSeverity::Info => "info",
+            LintSeverity::Warning => "warning",
+            LintSeverity::Error => "error",
+        }
+    }
+}
+
+/// One finding from the entry checks (main menu → Check Entries…).
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LintWarning {
+    severity: LintSeverity,
+    message: String,
+}
+
+impl LintWarning {
+    fn new(severity: LintSeverity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+/// Checks that look at a single entry.
+fn lint_entry(entry: &StartupEntry) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+    if entry.name.trim().is_empty() {
+        warnings.push(LintWarning::new(LintSeverity::Error, "has no name"));
+    }
+    if entry.command.trim().is_empty() {
+        warnings.push(LintWarning::new(LintSeverity::Error, "has no command"));
+    }
+    warnings.extend(lint_section_names(entry));
+    warnings.extend(find_malformed_locales(entry).into_iter().map(|(_, warning)| warning));
+    if entry.has_desktop_actions() {
+        warnings.push(LintWarning::new(
+            LintSeverity::Info,
+            "has desktop actions; autostart only runs the main Exec= command",
+        ));
+    }
+    warnings
+}
+
+/// The spec only allows `Desktop Action <id>` and `X-` extension groups after
+/// `[Desktop Entry]`, each at most once.
+fn lint_section_names(entry: &StartupEntry) -> Vec<LintWarning> {
+    let headers = entry.entry_group_headers();
+    let mut warnings = Vec::new();
+    for (i, header) in headers.iter().enumerate() {
+        if headers[..i].contains(header) {
+            continue;
+        }
+        if !(header.starts_with("Desktop Action ") || header.starts_with("X-")) {
+            warnings.push(LintWarning::new(
+                LintSeverity::Warning,
+                format!("unusual section [{header}]; extension sections should start with X-"),
+            ));
+        }
+        let count = headers.iter().filter(|h| *h == header).count();
+        if count > 1 {
+            warnings.push(LintWarning::new(
+                LintSeverity::Warning,
+                format!("section [{header}] appears {count} times"),
+            ));
+        }
+    }
+    warnings
+}
+
+/// The message locale from `LC_ALL`, `LC_MESSAGES` or `LANG`, in that order; `C` if unset.
+fn current_locale() -> String {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_else(|| "C".to_string())
+}
+
+/// What the UI calls an entry: its `Name[xx]` for `locale`, else `GenericName=`, else
+/// `Name=`, else the file name without `.desktop`.
+fn display_name_with_fallback(entry: &StartupEntry, locale: &str) -> String {
+    if let Some(localized) = entry.localized_name_for_locale(locale) {
+        return localized.to_string();
+    }
+    if let Some(generic) = entry.generic_name() {
+        return generic.to_string();
+    }
+    if !entry.name.trim().is_empty() {
+        return entry.name.clone();
+    }
+    entry
+        .path
+        .as_ref()
+        .and_then(|p| p.file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// `lang_COUNTRY.ENCODING@MODIFIER` from the Desktop Entry spec, with everything but `lang`
+/// optional: `de`, `pt_BR`, `es_419`, `sr@latin`, `en_US.UTF-8`.
+fn is_valid_locale_tag(tag: &str) -> bool {
+    let (rest, modifier) = tag.split_once('@').unwrap_or((tag, "x"));
+    let (rest, encoding) = rest.split_once('.').unwrap_or((rest, "x"));
+    let (lang, country) = rest.split_once('_').unwrap_or((rest, "XX"));
+    (2..=3).contains(&lang.len())
+        && lang.chars().all(|c| c.is_ascii_lowercase())
+        && (2..=3).contains(&country.len())
+        && country.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        && !encoding.is_empty()
+        && encoding.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        && !modifier.is_empty()
+        && modifier.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// `Name[xx]` keys with an empty locale tag, English ones that just repeat `Name=`, and
+/// other-language ones left as the English name (`Name=` or the `Name[en…]` value).
+fn find_malformed_locales(entry: &StartupEntry) -> Vec<(String, LintWarning)> {
+    let is_english = |tag: &str| tag.split(['_', '.', '@']).next() == Some("en");
+    let english_names: Vec<&str> = std::iter::once(entry.name.as_str())
+        .chain(
+            entry
+                .localized_names
+                .iter()
+                .filter(|(tag, _)| is_english(tag))
+                .map(|(_, value)| value.as_str()),
+        )
+        .collect();
+    let mut found = Vec::new();
+    for (tag, value) in &entry.localized_names {
+        let warning = if tag.trim().is_empty() {
+            LintWarning::new(LintSeverity::Warning, "has a Name[] key with an empty locale")
+        } else if is_english(tag) && *value == entry.name {
+            LintWarning::new(LintSeverity::Info, format!("Name[{tag}] repeats Name= and can be removed"))
+        } else if !is_english(tag) && english_names.contains(&value.as_str()) {
+            LintWarning::new(
+                LintSeverity::Info,
+                format!("Name[{tag}] is the untranslated English name {value:?}"),
+            )
+        } else {
+            continue;
+        };
+        found.push((tag.clone(), warning));
+    }
+    found
+}
+
+/// Outcome of `StartupEntry::validate`: the warnings and errors from `lint_entry`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ValidationResult {
+    warnings: Vec<LintWarning>,
+}
+
+impl ValidationResult {
+    fn is_valid(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// Runs `validate` on every entry; only entries with findings are returned, with their index.
+fn validate_all_entries_on_load(entries: &[StartupEntry]) -> Vec<(usize, ValidationResult)> {
+    entries
+        .iter()
+        .enumerate()
+        .map(|(idx, entry)| (idx, entry.validate()))
+        .filter(|(_, result)| !result.is_valid())
+        .collect()
+}
+
+/// One warning per group of entries whose names match case-insensitively, naming each
+/// entry and its source. Groups are in order of first appearance.
+fn lint_duplicate_names(entries: &[StartupEntry]) -> Vec<LintWarning> {
+    let mut groups: IndexMap<String, Vec<&StartupEntry>> = IndexMap::new();
+    for entry in entries {
+        groups.entry(entry.name.to_lowercase()).or_default().push(entry);
+    }
+    groups
+        .values()
+        .filter(|group| group.len() > 1)
+        .map(|group| {
+            let members: Vec<String> = group
+                .iter()
+                .map(|e| format!("{} ({})", e.name, source_label(&e.source)))
+                .collect();
+            LintWarning::new(
+                LintSeverity::Warning,
+                format!("{} entries share a name: {}", group.len(), members.join(", ")),
+            )
+        })
+        .collect()
+}
+
+/// All checks over all entries, most severe first; messages name the entry. `TryExec=`
+/// binaries are looked up on `search_path` (`session_search_path`).
+fn audit_all(entries: &[StartupEntry], search_path: &[PathBuf]) -> Vec<LintWarning> {
+    let locale = current_locale();
+    let about = |entry: &StartupEntry, w: LintWarning| LintWarning {
+        message: format!(
+            "{} ({}): {}",
+            display_name_with_fallback(entry, &locale),
+            source_label(&entry.source),
+            w.message
+        ),
+        ..w
+    };
+    let mut warnings: Vec<LintWarning> = entries
+        .iter()
+        .flat_map(|entry| lint_entry(entry).into_iter().map(|w| about(entry, w)))
+        .collect();
+    warnings.extend(lint_duplicate_names(entries));
+    warnings.extend(entries.iter().filter_map(|entry| audit_try_exec(entry, search_path).map(|w| about(entry, w))));
+    warnings.sort_by_key(|w| std::cmp::Reverse(w.severity));
+    warnings
+}
+
+/// `%` starts a specifier in unit files, so it is doubled.
+fn systemd_escape_specifiers(text: &str) -> String {
+    text.replace('%', "%%")
+}
+
+/// One `ExecStart=`/`Environment=` word: double-quoted when needed, with `%` and `$`
+/// doubled so systemd does not expand them.
+fn systemd_quote(arg: &str) -> String {
+    let escaped = systemd_escape_specifiers(arg).replace('$', "$$");
+    if !escaped.is_empty() && !escaped.chars().any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | ';')) {
+        return escaped;
+    }
+    let mut out = String::from("\"");
+    for c in escaped.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Reverse of `StartupEntry::to_gnome_autostart_handler_json`; the entry has no file yet.
+fn from_gnome_autostart_handler_json(json: &str) -> Result<StartupEntry> {
+    let handler: GnomeAutostartHandler =
+        serde_json::from_str(json).context("parsing GNOME autostart handler JSON")?;
+    if handler.name.trim().is_empty() || handler.command_line.trim().is_empty() {
+        bail!("name and commandLine must not be empty");
+    }
+    let comment = Some(handler.description).filter(|d| !d.is_empty());
+    let (env_vars, command) = parse_env_prefix(&handler.command_line);
+    Ok(StartupEntry {
+        name: handler.name,
+        command,
+        env_vars,
+        enabled: handler.autostart,
+        locked: false,
+        xfce_override: false,
+        generic_name: None,
+        comment,
+        icon: None,
+        try_exec: None,
+        terminal: false,
+        delay_seconds: Some(handler.delay_seconds).filter(|&d| d > 0),
+        only_show_in: Vec::new(),
+        not_show_in: Vec::new(),
+        source: StartupSource::Unknown,
+        path: None,
+        extra: Vec::new(),
+        localized_names: Vec::new(),
+        entry_comments: Vec::new(),
+        preamble: Vec::new(),
+        other_groups: Vec::new(),
+    })
+}
+
+/// A property list value; `to_launchd_agent_dict` builds these before they become XML.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PlistValue {
+    String(String),
+    Bool(bool),
+    Array(Vec<PlistValue>),
+    /// Keys in output order.
+    Dict(Vec<(String, PlistValue)>),
+}
+
+impl PlistValue {
+    /// Appends the value's XML, one element per line, indented by tabs as Apple's tools do.
+    fn write_xml(&self, depth: usize, out: &mut String) {
+        let indent = "\t".repeat(depth);
+        match self {
+            PlistValue::String(text) => out.push_str(&format!("{indent}<string>{}</string>\n", xml_escape(text))),
+            PlistValue::Bool(value) => out.push_str(&format!("{indent}<{value}/>\n")),
+            PlistValue::Array(items) => {
+                out.push_str(&format!("{indent}<array>\n"));
+                for item in items {
+                    item.write_xml(depth + 1, out);
+                }
+                out.push_str(&format!("{indent}</array>\n"));
+            }
+            PlistValue::Dict(members) => {
+                out.push_str(&format!("{indent}<dict>\n"));
+                for (key, value) in members {
+                    out.push_str(&format!("{indent}\t<key>{}</key>\n", xml_escape(key)));
+                    value.write_xml(depth + 1, out);
+                }
+                out.push_str(&format!("{indent}</dict>\n"));
+            }
+        }
+    }
+}
+
+/// `/Users/<login>/Library/Logs/<slug>.log`; launchd does not expand `~`, so the path is
+/// spelled out with this user's login (`$USER`).
+fn launchd_log_path(slug: &str) -> String {
+    let user = std::env::var("USER").ok().filter(|u| !u.is_empty()).unwrap_or_else(|| "user".to_string());
+    format!("/Users/{user}/Library/Logs/{slug}.log")
+}
+
+/// Shape of one member of an entry object in `export_entries_to_json`.
+#[derive(Debug, Clone, Copy)]
+enum JsonFieldKind {
+    String,
+    Boolean,
+    /// A string, or `null` when unset.
+    OptionalString,
+    /// A non-negative integer, or `null` when unset.
+    OptionalInteger,
+    /// One of the `source_label` values.
+    Source,
+    /// Array of strings.
+    Lines,
+    /// Array of arrays of strings.
+    LineGroups,
+    /// Array of objects with exactly these two string members.
+    Pairs(&'static str, &'static str),
+}
+
+/// Every member of an exported entry, with its schema description.
+const ENTRY_JSON_FIELDS: [(&str, JsonFieldKind, &str); 21] = [
+    ("name", JsonFieldKind::String, "Name= value"),
+    ("command", JsonFieldKind::String, "Exec= without a leading env prefix"),
+    ("env_vars", JsonFieldKind::Pairs("key", "value"), "Variables from the Exec= env prefix, in order"),
+    ("enabled", JsonFieldKind::Boolean, "Whether the session starts the entry"),
+    ("locked", JsonFieldKind::Boolean, "X-USM-Locked=true"),
+    ("xfce_override", JsonFieldKind::Boolean, "X-XFCE-Autostart-Override=true"),
+    ("generic_name", JsonFieldKind::OptionalString, "GenericName= value, if any"),
+    ("comment", JsonFieldKind::OptionalString, "Comment= value, if any"),
+    ("icon", JsonFieldKind::OptionalString, "Icon= value, if any"),
+    ("try_exec", JsonFieldKind::OptionalString, "TryExec= value, if any"),
+    ("terminal", JsonFieldKind::Boolean, "Terminal=true"),
+    ("delay_seconds", JsonFieldKind::OptionalInteger, "X-GNOME-Autostart-Delay= in seconds, if set"),
+    ("only_show_in", JsonFieldKind::Lines, "OnlyShowIn= desktops"),
+    ("not_show_in", JsonFieldKind::Lines, "NotShowIn= desktops"),
+    ("source", JsonFieldKind::Source, "Where the entry was read from"),
+    ("path", JsonFieldKind::OptionalString, "File the entry was read from, if any; relative to the user autostart dir for user entries"),
+    ("extra", JsonFieldKind::Pairs("key", "value"), "Other [Desktop Entry] keys, in file order"),
+    ("localized_names", JsonFieldKind::Pairs("locale", "value"), "Name[xx] values"),
+    ("entry_comments", JsonFieldKind::Lines, "Comments and blank lines inside [Desktop Entry]"),
+    ("preamble", JsonFieldKind::Lines, "Lines before the first group"),
+    ("other_groups", JsonFieldKind::LineGroups, "Raw lines of each group other than [Desktop Entry]"),
+];
+
+/// Members added after the first export format; older backups lack them, so they are optional.
+const LATER_ENTRY_JSON_FIELDS: [&str; 9] = [
+    "xfce_override",
+    "generic_name",
+    "comment",
+    "icon",
+    "try_exec",
+    "terminal",
+    "delay_seconds",
+    "only_show_in",
+    "not_show_in",
+];
+
+fn json_pair(first: &str, first_value: &str, second: &str, second_value: &str) -> Value {
+    let mut object = Map::new();
+    object.insert(first.to_string(), Value::from(first_value));
+    object.insert(second.to_string(), Value::from(second_value));
+    Value::Object(object)
+}
+
+fn json_field_schema(kind: JsonFieldKind, description: &str) -> Value {
+    let strings = || serde_json::json!({ "type": "array", "items": { "type": "string" } });
+    let mut schema = match kind {
+        JsonFieldKind::String => serde_json::json!({ "type": "string" }),
+        JsonFieldKind::Boolean => serde_json::json!({ "type": "boolean" }),
+        JsonFieldKind::OptionalString => serde_json::json!({ "type": ["string", "null"] }),
+        JsonFieldKind::OptionalInteger => serde_json::json!({ "type": ["integer", "null"] }),
+        JsonFieldKind::Source => serde_json::json!({
+            "type": "string",
+            "enum": StartupSource::ALL.iter().map(source_label).collect::<Vec<_>>(),
+        }),
+        JsonFieldKind::Lines => strings(),
+        JsonFieldKind::LineGroups => serde_json::json!({ "type": "array", "items": strings() }),
+        JsonFieldKind::Pairs(first, second) => {
+            let mut properties = Map::new();
+            properties.insert(first.to_string(), serde_json::json!({ "type": "string" }));
+            properties.insert(second.to_string(), serde_json::json!({ "type": "string" }));
+            serde_json::json!({
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": properties,
+                    "required": [first, second],
+                    "additionalProperties": false,
+                },
+            })
+        }
+    };
+    schema["description"] = Value::from(description);
+    schema
+}
+
+/// Draft-07 JSON Schema for `export_entries_to_json` output, built from `ENTRY_JSON_FIELDS`.
+fn generate_entry_json_schema() -> String {
+    let properties: Map<String, Value> = ENTRY_JSON_FIELDS
+        .iter()
+        .map(|(name, kind, description)| (name.to_string(), json_field_schema(*kind, description)))
+        .collect();
+    let required: Vec<&str> = ENTRY_JSON_FIELDS
+        .iter()
+        .map(|(name, _, _)| *name)
+        .filter(|name| !LATER_ENTRY_JSON_FIELDS.contains(name))
+        .collect();
+    let schema = serde_json::json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "Universal Startup Manager entries",
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        },
+    });
+    serde_json::to_string_pretty(&schema).unwrap_or_default() + "\n"
+}
+
+/// Writes `(String, String)` pairs as objects with the members `first` and `second`.
+fn serialize_pairs<S: serde::Serializer>(
+    pairs: &[(String, String)],
+    first: &str,
+    second: &str,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_seq(pairs.iter().map(|(a, b)| json_pair(first, a, second, b)))
+}
+
+/// Reverse of `serialize_pairs`.
+fn deserialize_pairs<'de, D: serde::Deserializer<'de>>(
+    first: &'static str,
+    second: &'static str,
+    deserializer: D,
+) -> std::result::Result<Vec<(String, String)>, D::Error> {
+    Vec::<Map<String, Value>>::deserialize(deserializer)?
+        .iter()
+        .map(|object| {
+            let member = |name: &'static str| {
+                object
+                    .get(name)
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .ok_or_else(|| serde::de::Error::missing_field(name))
+            };
+            Ok((member(first)?, member(second)?))
+        })
+        .collect()
+}
+
+fn serialize_key_value_pairs<S: serde::Serializer>(
+    pairs: &[(String, String)],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serialize_pairs(pairs, "key", "value", serializer)
+}
+
+fn deserialize_key_value_pairs<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Vec<(String, String)>, D::Error> {
+    deserialize_pairs("key", "value", deserializer)
+}
+
+fn serialize_locale_value_pairs<S: serde::Serializer>(
+    pairs: &[(String, String)],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serialize_pairs(pairs, "locale", "value", serializer)
+}
+
+fn deserialize_locale_value_pairs<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Vec<(String, String)>, D::Error> {
+    deserialize_pairs("locale", "value", deserializer)
+}
+
+/// User entry paths are written relative to `user_autostart_dir()` so a backup restores
+/// under another home directory.
+fn json_entry_path(entry: &StartupEntry, path: &Path) -> String {
+    let dir = user_autostart_dir();
+    let relative = match entry.source {
+        StartupSource::UserAutostart => path.strip_prefix(&dir).unwrap_or(path),
+        _ => path,
+    };
+    relative.to_string_lossy().into_owned()
+}
+
+/// All of `entries` as a JSON array; `generate_entry_json_schema` describes the format.
+fn export_entries_to_json(entries: &[StartupEntry]) -> String {
+    let entries: Vec<StartupEntry> = entries
+        .iter()
+        .map(|entry| StartupEntry {
+            path: entry.path.as_ref().map(|p| PathBuf::from(json_entry_path(entry, p))),
+            ..entry.clone()
+        })
+        .collect();
+    serde_json::to_string_pretty(&entries).unwrap_or_default() + "\n"
+}
+
+/// Reverse of `export_entries_to_json`; the file is checked against the schema first, and
+/// user entry paths must pass `validate_path_traversal_safe`. Other sources are read-only, so
+/// their paths are only shown.
+fn import_entries_from_json(json: &str) -> Result<Vec<StartupEntry>> {
+    validate_json_against_schema(json, &generate_entry_json_schema())?;
+    let mut entries: Vec<StartupEntry> = serde_json::from_str(json).context("parsing entries JSON")?;
+    for entry in &mut entries {
+        if let (StartupSource::UserAutostart, Some(path)) = (&entry.source, &entry.path) {
+            validate_path_traversal_safe(path).with_context(|| format!("Importing {:?}", entry.name))?;
+            entry.path = Some(user_autostart_dir().join(path));
+        }
+    }
+    Ok(entries)
+}
+
+/// Saves `entries` to `path` as a JSON backup (`export_entries_to_json`).
+fn export_entries_json(entries: &[StartupEntry], path: &Path) -> Result<()> {
+    write_text_atomic(path, &export_entries_to_json(entries))
+}
+
+/// Reads a backup written by `export_entries_json`.
+fn import_entries_json(path: &Path) -> Result<Vec<StartupEntry>> {
+    let json = fs::read_to_string(path).with_context(|| format!("reading {path:?}"))?;
+    import_entries_from_json(&json).with_context(|| format!("importing {path:?}"))
+}
+
+/// The backed-up entries that belong in `dir` (user autostart files, including Xfce
+/// overrides) with the file each is restored to: its old file name, or its slug. Entries with
+/// the same content are restored once (`dedup_by_content`).
+fn plan_json_import(entries: Vec<StartupEntry>, dir: &Path) -> Vec<(StartupEntry, PathBuf)> {
+    dedup_by_content(entries)
+        .into_iter()
+        .filter(|e| matches!(e.source, StartupSource::UserAutostart | StartupSource::XfceAutostart))
+        .map(|entry| {
+            let file_name = entry
+                .path
+                .as_deref()
+                .and_then(Path::file_name)
+                .map(|n| n.to_string_lossy().into_owned())
+                .filter(|n| n.ends_with(".desktop"))
+                .unwrap_or_else(|| format!("{}.desktop", slugify(&entry.name)));
+            let path = dir.join(file_name);
+            (entry, path)
+        })
+        .collect()
+}
+
+/// Rejects an imported `path` unless it stays inside `user_autostart_dir()` once resolved
+/// against it.
+fn validate_path_traversal_safe(path: &Path) -> Result<()> {
+    validate_path_traversal_safe_in(&user_autostart_dir(), path)
+}
+
+/// `path` may be relative to `dir` or absolute, but must not contain `..`, and after
+/// following symlinks it must name something inside `dir`.
+fn validate_path_traversal_safe_in(dir: &Path, path: &Path) -> Result<()> {
+    if path.components().any(|c| c == Component::ParentDir) {
+        bail!("Path {:?} contains '..'", path);
+    }
+    let joined = dir.join(path);
+    let dir_canon = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    // A file still to be created is judged by its parent; a dangling symlink is refused.
+    let resolved = match joined.canonicalize() {
+        Ok(resolved) => resolved,
+        Err(_) if fs::symlink_metadata(&joined).is_ok() => bail!("Cannot resolve {:?}", path),
+        Err(_) => match (joined.parent().and_then(|p| p.canonicalize().ok()), joined.file_name()) {
+            (Some(parent), Some(name)) => parent.join(name),
+            _ => joined.clone(),
+        },
+    };
+    if resolved == dir_canon || !resolved.starts_with(&dir_canon) {
+        bail!("Path {:?} is outside {:?}", path, dir);
+    }
+    Ok(())
+}
+
+/// Checks `json` against `schema`, supporting the draft-07 keywords the entry schema uses.
+fn validate_json_against_schema(json: &str, schema: &str) -> Result<()> {
+    let value: Value = serde_json::from_str(json).context("parsing JSON")?;
+    let schema: Value = serde_json::from_str(schema).context("parsing JSON schema")?;
+    validate_json_value(&value, &schema, "$")
+}
+
+fn json_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn json_type_matches(value: &Value, expected: &str) -> bool {
+    match expected {
+        "integer" => value.is_i64() || value.is_u64(),
+        expected => json_type_name(value) == expected,
+    }
+}
+
+/// `type`, `enum`, `properties`, `required`, `additionalProperties: false` and `items`;
+/// other keywords are ignored. `at` is the JSON path used in error messages.
+fn validate_json_value(value: &Value, schema: &Value, at: &str) -> Result<()> {
+    let allowed: Vec<&str> = match schema.get("type") {
+        None => Vec::new(),
+        Some(Value::String(expected)) => vec![expected.as_str()],
+        Some(Value::Array(expected)) => expected.iter().filter_map(Value::as_str).collect(),
+        Some(_) => bail!("{at}: schema \"type\" must be a string or an array"),
+    };
+    if !allowed.is_empty() && !allowed.iter().any(|expected| json_type_matches(value, expected)) {
+        bail!("{at}: expected {}, found {}", allowed.join(" or "), json_type_name(value));
+    }
+    if let Some(options) = schema.get("enum").and_then(Value::as_array).filter(|o| !o.contains(value)) {
+        let options: Vec<String> = options.iter().map(Value::to_string).collect();
+        bail!("{at}: {value} is not one of {}", options.join(", "));
+    }
+    if let Value::Object(members) = value {
+        let required = schema.get("required").and_then(Value::as_array).into_iter().flatten();
+        if let Some(missing) = required.filter_map(Value::as_str).find(|name| !members.contains_key(*name)) {
+            bail!("{at}: missing required member {missing:?}");
+        }
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let closed = schema.get("additionalProperties") == Some(&Value::Bool(false));
+        for (name, member) in members {
+            match properties.and_then(|p| p.get(name)) {
+                Some(member_schema) => validate_json_value(member, member_schema, &format!("{at}.{name}"))?,
+                None if closed => bail!("{at}: unexpected member {name:?}"),
+                None => {}
+            }
+        }
+    }
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            validate_json_value(item, item_schema, &format!("{at}[{i}]"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Nix double-quoted string; `${` would start an interpolation, so it is escaped too.
+fn nix_quote(text: &str) -> String {
+    let mut out = String::from("\"");
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '$' if chars.peek() == Some(&'{') => out.push_str("\\$"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Reads back the body of a Nix double-quoted string up to its closing quote.
+fn nix_unquote(text: &str) -> Result<String> {
+    let mut chars = text.strip_prefix('"').context("expected a Nix string")?.chars();
+    let mut out = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Ok(out),
+            '\\' => match chars.next().context("unterminated escape")? {
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                'r' => out.push('\r'),
+                other => out.push(other),
+            },
+            c => out.push(c),
+        }
+    }
+    bail!("unterminated Nix string")
+}
+
+/// Parses the output of `to_nix_home_manager_module` line by line; not a Nix parser, so
+/// each `key = value;` must be on its own line.
+fn from_nix_home_manager_entry(nix: &str) -> Result<StartupEntry> {
+    let mut name = None;
+    let mut command = None;
+    let mut enabled = true;
+    for line in nix.lines().map(str::trim) {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "enable" => {
+                enabled = match value.trim_end_matches(';').trim() {
+                    "true" => true,
+                    "false" => false,
+                    other => bail!("enable must be true or false, got {other:?}"),
+                }
+            }
+            "name" => name = Some(nix_unquote(value).context("reading name")?),
+            "command" => command = Some(nix_unquote(value).context("reading command")?),
+            _ => {}
+        }
+    }
+    let name = name.context("no name = \"…\"; line")?;
+    let command = command.context("no command = \"…\"; line")?;
+    let (env_vars, command) = parse_env_prefix(&command);
+    Ok(StartupEntry {
+        name,
+        command,
+        env_vars,
+        enabled,
+        locked: false,
+        xfce_override: false,
+        generic_name: None,
+        comment: None,
+        icon: None,
+        try_exec: None,
+        terminal: false,
+        delay_seconds: None,
+        only_show_in: Vec::new(),
+        not_show_in: Vec::new(),
+        source: StartupSource::Unknown,
+        path: None,
+        extra: Vec::new(),
+        localized_names: Vec::new(),
+        entry_comments: Vec::new(),
+        preamble: Vec::new(),
+        other_groups: Vec::new(),
+    })
+}
+
+/// AutoHotkey v2 double-quoted string; `` ` `` is the escape character.
+fn ahk_quote(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '`' => out.push_str("``"),
+            '"' => out.push_str("`\""),
+            '\n' => out.push_str("`n"),
+            '\r' => out.push_str("`r"),
+            '\t' => out.push_str("`t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// PowerShell single-quoted literal: nothing is expanded, quotes are doubled.
+fn powershell_single_quote(text: &str) -> String {
+    let mut out = String::from("'");
+    for c in text.chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+            out.push(c);
+        }
+        out.push(c);
+    }
+    out.push('\'');
+    out
+}
+
+fn yaml_double_quote(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Renders entries as a vertical list of rounded boxes: blue for user, grey for system,
+/// with disabled entries drawn in a paler shade.
+fn render_list_as_svg(entries: &[StartupEntry]) -> String {
+    const WIDTH: usize = 420;
+    const ROW: usize = 44;
+    const PAD: usize = 12;
+    let rows = entries.len().max(1);
+    let height = PAD * 2 + rows * ROW;
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{WIDTH}\" height=\"{height}\" viewBox=\"0 0 {WIDTH} {height}\">\n"
+    ));
+    out.push_str("  <title>Autostart entries</title>\n");
+    if entries.is_empty() {
+        out.push_str(&format!(
+            "  <text x=\"{PAD}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"14\" fill=\"#5e5c64\">No entries</text>\n",
+            PAD + ROW / 2
+        ));
+    }
+    let locale = current_locale();
+    for (i, entry) in entries.iter().enumerate() {
+        let user = entry.source == StartupSource::UserAutostart;
+        let (fill, text_fill) = match (user, entry.enabled) {
+            (true, true) => ("#1c71d8", "#ffffff"),
+            (true, false) => ("#99c1f1", "#241f31"),
+            (false, true) => ("#5e5c64", "#ffffff"),
+            (false, false) => ("#c0bfbc", "#241f31"),
+        };
+        let y = PAD + i * ROW;
+        let label = format!(
+            "{} ({}, {})",
+            display_name_with_fallback(entry, &locale),
+            source_label(&entry.source),
+            if entry.enabled { "enabled" } else { "disabled" }
+        );
+        out.push_str(&format!(
+            "  <rect x=\"{PAD}\" y=\"{y}\" width=\"{}\" height=\"{}\" rx=\"8\" ry=\"8\" fill=\"{fill}\"/>\n",
+            WIDTH - PAD * 2,
+            ROW - 8
+        ));
+        out.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"14\" fill=\"{text_fill}\">{}</text>\n",
+            PAD * 2,
+            y + (ROW - 8) / 2 + 5,
+            xml_escape(&label)
+        ));
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// Shell-like word splitting for `Exec=`: single quotes are literal, double quotes allow
+/// `\"`, `\\`, `\$` and `` \` `` escapes, and a backslash outside quotes escapes the next
+/// character. No expansion is performed. An unclosed quote runs to the end of the input.
+fn tokenize_command(exec: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut rest = exec;
+    while let Some((word, remaining)) = split_shell_word(rest) {
+        tokens.push(word);
+        rest = remaining;
+    }
+    tokens
+}
+
+/// Reads one shell-like word (see `tokenize_command`) and returns it unquoted together with
+/// the unconsumed remainder of the input. `None` once only whitespace is left.
+fn split_shell_word(input: &str) -> Option<(String, &str)> {
+    let input = input.trim_start();
+    if input.is_empty() {
+        return None;
+    }
+    let mut word = String::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' => {
+                for (_, q) in chars.by_ref() {
+                    if q == '\'' {
+                        break;
+                    }
+                    word.push(q);
+                }
+            }
+            '"' => {
+                while let Some((_, q)) = chars.next() {
+                    match q {
+                        '"' => break,
+                        '\\' => match chars.peek() {
+                            Some(&(_, next @ ('"' | '\\' | '$' | '`'))) => {
+                                word.push(next);
+                                chars.next();
+                            }
+                            _ => word.push('\\'),
+                        },
+                        _ => word.push(q),
+                    }
+                }
+            }
+            '\\' => word.push(chars.next().map(|(_, c)| c).unwrap_or('\\')),
+            c if c.is_whitespace() => return Some((word, &input[i..])),
+            c => word.push(c),
+        }
+    }
+    Some((word, ""))
+}
+
+fn is_field_code(token: &str) -> bool {
+    let mut chars = token.chars();
+    chars.next() == Some('%')
+        && chars.next().is_some_and(|c| "fFuUdDnNickvm".contains(c))
+        && chars.next().is_none()
+}
+
+fn parse_exec_into_parts(exec: &str) -> ExecParts {
+    let mut tokens = tokenize_command(exec).into_iter();
+    let binary = tokens.next().unwrap_or_default();
+    let mut parts = ExecParts {
+        binary,
+        ..Default::default()
+    };
+    for token in tokens {
+        let mut chars = token.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c != '%' {
+                continue;
+            }
+            if let Some(&(_, next)) = chars.peek() {
+                chars.next();
+                let code = &token[i..i + 1 + next.len_utf8()];
+                if next != '%' && is_field_code(code) && !parts.field_codes.iter().any(|c| c == code) {
+                    parts.field_codes.push(code.to_string());
+                }
+            }
+        }
+        if !is_field_code(&token) {
+            parts.args.push(token);
+        }
+    }
+    parts
+}
+
+/// Indices of `.desktop`-backed entries whose `Exec=` binary no longer exists.
+fn check_binaries_still_present(entries: &[StartupEntry]) -> Vec<usize> {
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| {
+            matches!(
+                entry.source,
+                StartupSource::UserAutostart | StartupSource::SystemAutostart
+            )
+        })
+        .filter(|(_, entry)| {
+            let Some(binary) = entry.exec_binary() else {
+                return false;
+            };
+            if binary.contains('/') {
+                !Path::new(&binary).is_file()
+            } else {
+                find_in_path(&binary).is_none()
+            }
+        })
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Walks `$PATH` looking for an executable file named `binary`.
+fn find_in_path(binary: &str) -> Option<PathBuf> {
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(binary))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Reads `KEY=VALUE` lines from a file such as `/etc/environment` or `~/.pam_environment`;
+/// comments and lines without a valid variable name are skipped.
+fn parse_environment_file(path: &Path) -> Result<Vec<(String, String)>> {
+    let content = fs::read_to_string(path).with_context(|| format!("reading {path:?}"))?;
+    Ok(parse_environment_assignments(&content))
+}
+
+/// The `$PATH` a session gets: a `PATH=` from `env_file` (with `$PATH` in it expanded
+/// from this process), then this process's own `$PATH`.
+fn session_search_path(env_file: &Path) -> Vec<PathBuf> {
+    let process_path = std::env::var("PATH").unwrap_or_default();
+    let mut dirs: Vec<PathBuf> = parse_environment_file(env_file)
+        .unwrap_or_default()
+        .into_iter()
+        .rev()
+        .find(|(key, _)| key == "PATH")
+        .map(|(_, value)| {
+            let value = value.replace("${PATH}", &process_path).replace("$PATH", &process_path);
+            std::env::split_paths(&value).collect()
+        })
+        .unwrap_or_default();
+    for dir in std::env::split_paths(&process_path) {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+/// Sessions skip an entry whose `TryExec=` binary is missing, so flag one that is not
+/// on `search_path` (or, given as a path, does not exist).
+fn audit_try_exec(entry: &StartupEntry, search_path: &[PathBuf]) -> Option<LintWarning> {
+    let binary = entry.try_exec()?;
+    if binary_on_search_path(binary, search_path) {
+        return None;
+    }
+    Some(LintWarning::new(
+        LintSeverity::Warning,
+        format!("TryExec binary {binary:?} is not installed, so sessions skip this entry"),
+    ))
+}
+
+/// Whether `binary` is installed: a path (`~/` expanded) must name a file, a bare name
+/// must be found in one of `search_path`'s dirs.
+fn binary_on_search_path(binary: &str, search_path: &[PathBuf]) -> bool {
+    if let Some(rest) = binary.strip_prefix("~/") {
+        return dirs::home_dir().is_some_and(|home| home.join(rest).is_file());
+    }
+    if binary.contains('/') {
+        return Path::new(binary).is_file();
+    }
+    search_path.iter().any(|dir| dir.join(binary).is_file())
+}
+
+/// Entries whose `required_binary` is not installed, as (index, binary).
+fn find_missing_binaries(entries: &[StartupEntry], search_path: &[PathBuf]) -> Vec<(usize, String)> {
+    entries
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, entry)| {
+            entry
+                .required_binary()
+                .filter(|binary| !binary_on_search_path(binary, search_path))
+                .map(|binary| (idx, binary))
+        })
+        .collect()
+}
+
+/// Summary for the Check Programs dialog.
+fn missing_binaries_report(entries: &[StartupEntry], missing: &[(usize, String)]) -> String {
+    if missing.is_empty() {
+        return format!("No entry needs a missing program ({} checked).", entries.len());
+    }
+    let locale = current_locale();
+    let mut lines = vec![format!(
+        "{} of {} entries need a program that is not in PATH:",
+        missing.len(),
+        entries.len()
+    )];
+    lines.extend(missing.iter().map(|(idx, binary)| {
+        let entry = &entries[*idx];
+        format!("{} ({}): {binary}", display_name_with_fallback(entry, &locale), source_label(&entry.source))
+    }));
+    lines.join("\n")
+}
+
+/// Stable identifier derived from name, command, and source: base32(SHA-256(...)).
+fn generate_entry_id(entry: &StartupEntry) -> String {
+    let mut input = Vec::new();
+    input.extend_from_slice(entry.name.as_bytes());
+    input.push(0);
+    input.extend_from_slice(entry.command.as_bytes());
+    input.push(0);
+    input.extend_from_slice(source_label(&entry.source).as_bytes());
+    base32_encode(&sha256(&input))
+}
+
+/// Finds an entry by its stored `X-USM-ID=`, falling back to the derived ID.
+fn find_entry_by_id(id: &str, entries: &[StartupEntry]) -> Option<usize> {
+    entries
+        .iter()
+        .position(|e| e.entry_id() == Some(id))
+        .or_else(|| {
+            entries
+                .iter()
+                .position(|e| e.entry_id().is_none() && generate_entry_id(e) == id)
+        })
+}
+
+/// Index of the entry whose `generate_desktop_file_hash_uri` is `hash`.
+fn find_by_hash(hash: &str, entries: &[StartupEntry]) -> Option<usize> {
+    entries.iter().position(|e| e.generate_desktop_file_hash_uri() == hash)
+}
+
+/// RFC 4648 base32 without padding.
+fn base32_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+    let mut out = String::with_capacity(data.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in data {
+        buffer = (buffer << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    out
+}
+
+/// Plain SHA-256 (FIPS 180-4); only used for IDs, so no streaming API.
+fn sha256(data: &[u8]) -> [u8; 32] {
+    const K: [u32; 64] = [
+        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+        0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+        0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+        0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+        0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+        0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+        0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+        0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+    ];
+    let mut h: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+    ];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64).wrapping_mul(8)).to_be_bytes());
+
+    for chunk in message.chunks_exact(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in chunk.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = hh
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut out = [0u8; 32];
+    for (i, word) in h.iter().enumerate() {
+        out[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+fn slugify(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c.to_ascii_lowercase());
+        } else if c.is_whitespace() || c == '-' || c == '_' {
+            if !out.ends_with('-') {
+                out.push('-');
+            }
+        }
+    }
+    if out.is_empty() {
+        "entry".into()
+    } else {
+        out
+    }
+}
+
+/// Sources living in the user's home, counted as "user" by filters and the count label.
+fn is_user_side_source(source: &StartupSource) -> bool {
+    matches!(
+        source,
+        StartupSource::UserAutostart
+            | StartupSource::OpenboxAutostart
+            | StartupSource::SystemdTimer
+            | StartupSource::Runit
+            | StartupSource::SwayConfig
+            | StartupSource::I3Config
+            | StartupSource::XdgUserDirs
+            | StartupSource::XfceAutostart
+            | StartupSource::Hyprland
+            | StartupSource::KdePlasmaEnv
+            | StartupSource::ShellProfile
+    )
+}
+
+/// Sources whose entries set a session variable (`export KEY=value`) rather than run a program.
+fn is_environment_source(source: &StartupSource) -> bool {
+    matches!(source, StartupSource::EnvironmentD | StartupSource::KdePlasmaEnv)
+}
+
+fn is_system_side_source(source: &StartupSource) -> bool {
+    matches!(source, StartupSource::SystemAutostart | StartupSource::EnvironmentD)
+}
+
+fn source_label(source: &StartupSource) -> &'static str {
+    match source {
+        StartupSource::UserAutostart => "user",
+        StartupSource::SystemAutostart => "system",
+        StartupSource::ShellProfile => "shell",
+        StartupSource::EnvironmentD => "environment.d",
+        StartupSource::OpenboxAutostart => "openbox",
+        StartupSource::SystemdTimer => "systemd timer",
+        StartupSource::Runit => "runit",
+        StartupSource::SwayConfig => "sway",
+        StartupSource::I3Config => "i3",
+        StartupSource::XdgUserDirs => "user dirs",
+        StartupSource::XfceAutostart => "xfce",
+        StartupSource::Hyprland => "hyprland",
+        StartupSource::KdePlasmaEnv => "plasma env",
+        StartupSource::Unknown => "unknown",
+    }
+}
+
+fn is_user_owned_path(path: &Path) -> bool {
+    let base = user_autostart_dir();
+    let base_canon = match base.canonicalize() {
+        Ok(path) => path,
+        Err(_) => return false,
+    };
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    let parent_canon = match parent.canonicalize() {
+        Ok(path) => path,
+        Err(_) => return false,
+    };
+    if parent_canon != base_canon {
+        return false;
+    }
+    match fs::symlink_metadata(path) {
+        Ok(meta) => meta.is_file() && !meta.file_type().is_symlink(),
+        Err(_) => false,
+    }
+}
+
+fn validate_user_entry_path(path: &Path) -> Result<PathBuf> {
+    validate_entry_path_in(&user_autostart_dir(), "user autostart dir", path)
+}
+
+/// `validate_user_entry_path` for files under `$XDG_DATA_HOME/applications`.
+fn validate_data_home_entry_path(path: &Path) -> Result<PathBuf> {
+    validate_entry_path_in(&xdg_data_applications_dir(), "applications dir", path)
+}
+
+/// `path` must sit directly in `base` (called `dir_label` in errors) and must not be a
+/// symlink or anything but a regular file if it exists.
+fn validate_entry_path_in(base: &Path, dir_label: &str, path: &Path) -> Result<PathBuf> {
+    let base_canon = base
+        .canonicalize()
+        .with_context(|| format!("Resolving {:?}", base))?;
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    let parent_canon = parent
+        .canonicalize()
+        .with_context(|| format!("Resolving {:?}", parent))?;
+    if parent_canon != base_canon {
+        bail!("Entry path is outside {dir_label}");
+    }
+    if let Ok(meta) = fs::symlink_metadata(path) {
+        if meta.file_type().is_symlink() {
+            bail!("Refusing to operate on symlinked entry");
+        }
+        if !meta.is_file() {
+            bail!("Entry path is not a regular file");
+        }
+    }
+    Ok(path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    use std::fs::read_to_string;
+
+    fn entry(name: &str, command: &str, enabled: bool, source: StartupSource) -> StartupEntry {
+        StartupEntry {
+            name: name.to_string(),
+            command: command.to_string(),
+            env_vars: Vec::new(),
+            enabled,
+            locked: false,
+            xfce_override: false,
+            generic_name: None,
+            comment: None,
+            icon: None,
+            try_exec: None,
+            terminal: false,
+            delay_seconds: None,
+            only_show_in: Vec::new(),
+            not_show_in: Vec::new(),
+            source,
+            path: None,
+            extra: Vec::new(),
+            localized_names: Vec::new(),
+            entry_comments: Vec::new(),
+            preamble: Vec::new(),
+            other_groups: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn slugify_basic() {
+        assert_eq!(slugify("My App"), "my-app");
+        assert_eq!(slugify("App_123"), "app-123");
+        assert_eq!(slugify("$$$"), "entry");
+    }
+
+    #[test]
+    fn filter_and_sort() {
+        let entries = vec![
+            entry("B", "/bin/true", true, StartupSource::UserAutostart),
+            entry("A", "/bin/false", false, StartupSource::SystemAutostart),
+            entry("C", "/bin/echo", true, StartupSource::UserAutostart),
+        ];
+        let filter = FilterState { show_enabled: true, show_disabled: false, show_user: true, show_system: true, wine_only: false, daemons_only: false, running_only: false, search_text: String::new() };
+        let filtered = apply_filter(&entries, &filter);
+        assert_eq!(filtered.len(), 2);
+        let sorted = sort_indices(&entries, filtered, SortKey::NameAsc);
+        let names: Vec<_> = sorted.iter().map(|i| entries[*i].name.as_str()).collect();
+        assert_eq!(names, vec!["B", "C"]);
+        let sorted_status = sort_indices(&entries, vec![0,1,2], SortKey::StatusEnabledFirst);
+        assert_eq!(sorted_status[0], 0); // enabled first
+    }
+
+    #[test]
+    fn filter_combined_user_enabled() {
+        let entries = vec![
+            entry("UserEnabled", "/bin/true", true, StartupSource::UserAutostart),
+            entry("UserDisabled", "/bin/true", false, StartupSource::UserAutostart),
+            entry("SystemEnabled", "/bin/true", true, StartupSource::SystemAutostart),
+        ];
+        let filter = FilterState { show_enabled: true, show_disabled: false, show_user: true, show_system: false, wine_only: false, daemons_only: false, running_only: false, search_text: String::new() };
+        let filtered = apply_filter(&entries, &filter);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(entries[filtered[0]].name, "UserEnabled");
+    }
+
+    #[test]
+    fn filter_search_text_matches_name_or_command() {
+        let entries = vec![
+            entry("Syncthing", "/usr/bin/syncthing serve", true, StartupSource::UserAutostart),
+            entry("Backup", "/opt/SYNC/run.sh", false, StartupSource::UserAutostart),
+            entry("Clock", "xclock", true, StartupSource::SystemAutostart),
+        ];
+        let search = FilterState { search_text: "sync".into(), ..FilterState::default() };
+        assert_eq!(apply_filter(&entries, &search), vec![0, 1]);
+        assert!(search.is_default());
+        let enabled_only = FilterState { show_disabled: false, ..search.clone() };
+        assert_eq!(apply_filter(&entries, &enabled_only), vec![0]);
+        let cleared = FilterState { search_text: String::new(), ..search };
+        assert_eq!(apply_filter(&entries, &cleared), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn search_and_name_sort_use_the_displayed_name() {
+        let mut zeta = entry("zeta-daemon", "/usr/bin/zd", true, StartupSource::UserAutostart);
+        zeta.generic_name = Some("Audio Mixer".into());
+        let bravo = entry("Bravo", "/usr/bin/bravo", true, StartupSource::UserAutostart);
+        let entries = vec![zeta, bravo];
+        assert_eq!(sort_indices(&entries, vec![0, 1], SortKey::NameAsc), vec![0, 1]);
+        assert_eq!(sort_indices(&entries, vec![0, 1], SortKey::NameDesc), vec![1, 0]);
+        let by_shown = FilterState { search_text: "mixer".into(), ..FilterState::default() };
+        assert_eq!(apply_filter(&entries, &by_shown), vec![0]);
+        let by_file_name = FilterState { search_text: "zeta".into(), ..FilterState::default() };
+        assert_eq!(apply_filter(&entries, &by_file_name), vec![0]);
+        assert_eq!(build_alpha_index(&[0, 1], &entries), vec![('A', 0), ('B', 1)]);
+    }
+
+    #[test]
+    fn sort_localized_names_uses_base_name() {
+        let mut a = entry("Äpple", "/bin/true", true, StartupSource::UserAutostart);
+        a.localized_names.push(("de".into(), "Äpfel".into()));
+        let b = entry("Banana", "/bin/true", true, StartupSource::UserAutostart);
+        let indices = vec![0usize, 1usize];
+        let sorted = sort_indices(&vec![a, b], indices, SortKey::NameAsc);
+        // ASCII compare puts Banana before Äpple; ensure stable deterministic ordering
+        assert_eq!(sorted, vec![1, 0]);
+    }
+
+    #[test]
+    fn parse_write_preserves_fields() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.desktop");
+        let content = "\
+# Preamble comment
+
+[Desktop Entry]
+# entry comment
+Type=Application
+Name=Sample
+Name[de]=Beispiel
+Exec=/bin/true
+X-GNOME-Autostart-enabled=true
+Hidden=false
+X-Test=1
+
+[Other]
+Foo=Bar
+";
+        std::fs::write(&path, content).unwrap();
+        let mut entry = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+        assert_eq!(entry.name, "Sample");
+        assert_eq!(entry.localized_names.len(), 1);
+        assert_eq!(entry.extra.iter().find(|(k, _)| k == "X-Test").map(|(_, v)| v.as_str()), Some("1"));
+        // Modify and write back
+        entry.name = "Sample2".into();
+        entry.command = "/bin/echo hi".into();
+        write_desktop_entry(&entry, &path).unwrap();
+        let written = read_to_string(&path).unwrap();
+        assert!(written.contains("Name=Sample2"));
+        assert!(written.contains("Name[de]=Beispiel"));
+        assert!(written.contains("X-Test=1"));
+        assert!(written.contains("[Other]"));
+        assert!(written.contains("Foo=Bar"));
+    }
+
+    #[test]
+    fn parse_ignores_non_entry_groups_for_fields() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.desktop");
+        let content = "\
+[NotDesktop]
+Name=ShouldNotUse
+
+[Desktop Entry]
+Name=Good
+Exec=/bin/true
+X-GNOME-Autostart-enabled=true
+Hidden=false
+";
+        std::fs::write(&path, content).unwrap();
+        let entry = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+        assert_eq!(entry.name, "Good");
+        assert_eq!(entry.command, "/bin/true");
+    }
+
+    #[test]
+    fn parse_preserves_duplicate_unknown_keys_order() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.desktop");
+        let content = "\
+[Desktop Entry]
+Name=Sample
+Exec=/bin/true
+X-GNOME-Autostart-enabled=true
+Hidden=false
+X-Test=1
+X-Test=2
+";
+        std::fs::write(&path, content).unwrap();
+        let entry = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+        // Duplicates get a numeric suffix in memory so keys stay unique
+        assert_eq!(entry.extra.iter().filter(|(k, _)| k == "X-Test").count(), 1);
+        assert_eq!(entry.extra.iter().find(|(k, _)| k == "X-Test=2").map(|(_, v)| v.as_str()), Some("2"));
+        // Writing back restores both original keys, in order
+        write_desktop_entry(&entry, &path).unwrap();
+        let written = read_to_string(&path).unwrap();
+        assert!(written.contains("X-Test=1\nX-Test=2\n"));
+        assert_eq!(written.matches("X-Test").count(), 2);
+    }
+
+    #[test]
+    fn parse_preserves_entry_comments_and_preamble() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.desktop");
+        let content = "\
+# Preamble line 1
+
+[Desktop Entry]
+# comment inside
+Name=Foo
+Exec=/bin/true
+Hidden=false
+X-GNOME-Autostart-enabled=true
+";
+        std::fs::write(&path, content).unwrap();
+        let entry = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+        assert!(entry.preamble.iter().any(|l| l.contains("Preamble line 1")));
+        assert!(entry.entry_comments.iter().any(|l| l.contains("comment inside")));
+        write_desktop_entry(&entry, &path).unwrap();
+        let written = read_to_string(&path).unwrap();
+        assert!(written.contains("Preamble line 1"));
+        assert!(written.contains("comment inside"));
+    }
+
+    #[test]
+    fn localized_name_roundtrip_edit() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.desktop");
+        let content = "\
+[Desktop Entry]
+Name=Base
+Name[fr]=BaseFr
+Exec=/bin/true
+X-GNOME-Autostart-enabled=true
+Hidden=false
+";
+        std::fs::write(&path, content).unwrap();
+        let mut entry = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+        assert_eq!(entry.localized_names.len(), 1);
+        entry.name = "NewBase".into();
+        // Simulate editing localized name:
+        entry.localized_names.retain(|(loc, _)| loc != "fr");
+        entry.localized_names.push(("fr".into(), "Nouveau".into()));
+        write_desktop_entry(&entry, &path).unwrap();
+        let written = read_to_string(&path).unwrap();
+        assert!(written.contains("Name=NewBase"));
+        assert!(written.contains("Name[fr]=Nouveau"));
+    }
+
+    #[test]
+    fn search_history_push_caps_and_dedups() {
+        let mut history = SearchHistory { max: 3, ..Default::default() };
+        for q in ["one", "two", "three", "four"] {
+            history.push(q);
+        }
+        assert_eq!(history.recent(), ["four", "three", "two"]);
+        history.push("two");
+        assert_eq!(history.recent(), ["two", "four", "three"]);
+        history.push("   ");
+        assert_eq!(history.recent().len(), 3);
+    }
+
+    #[test]
+    fn search_history_matches_prefix_case_insensitive() {
+        let mut history = SearchHistory::default();
+        history.push("Firefox");
+        history.push("flameshot");
+        history.push("Nextcloud");
+        assert_eq!(history.matches_prefix("f"), vec!["flameshot", "Firefox"]);
+        assert_eq!(history.matches_prefix("NEXT"), vec!["Nextcloud"]);
+        assert!(history.matches_prefix("zzz").is_empty());
+        assert_eq!(history.matches_prefix("").len(), 3);
+    }
+
+    #[test]
+    fn search_history_recent_default_cap_and_persist() {
+        let mut config = AppConfig::default();
+        for i in 0..15 {
+            config.search_history.push(&format!("query {i}"));
+        }
+        assert_eq!(config.search_history.recent().len(), 10);
+        assert_eq!(config.search_history.recent()[0], "query 14");
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        save_config_to(&config, &path).unwrap();
+        let loaded = load_config_from(&path).unwrap();
+        assert_eq!(loaded.search_history, config.search_history);
+        assert_eq!(loaded.search_history.recent(), config.search_history.recent());
+    }
+
+    #[test]
+    fn shortcuts_window_xml_lists_every_shortcut() {
+        let xml = shortcuts_window_xml();
+        assert_well_formed_xml(&xml);
+        assert_eq!(xml.matches("class=\"GtkShortcutsGroup\"").count(), KEYBOARD_SHORTCUTS.len());
+        let count: usize = KEYBOARD_SHORTCUTS.iter().map(|(_, shortcuts)| shortcuts.len()).sum();
+        assert_eq!(xml.matches("class=\"GtkShortcutsShortcut\"").count(), count);
+        assert!(xml.contains("<property name=\"accelerator\">&lt;Control&gt;&lt;Shift&gt;z</property>"));
+    }
+
+    #[test]
+    fn override_entry_in_keeps_the_system_file_name() {
+        let dir = tempdir().unwrap();
+        let mut system = entry("Clock", "clock", false, StartupSource::SystemAutostart);
+        system.path = Some(PathBuf::from("/etc/xdg/autostart/org.example.Clock.desktop"));
+        let path = override_entry_in(dir.path(), &system).unwrap();
+        assert_eq!(path.file_name().unwrap(), "org.example.Clock.desktop");
+        let back = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+        assert_eq!((back.name.as_str(), back.enabled), ("Clock", false));
+        assert!(back.entry_id().is_some());
+        assert!(override_entry_in(dir.path(), &system).is_err());
+        assert!(override_entry_in(dir.path(), &back).is_err());
+    }
+
+    #[test]
+    fn duplicate_entry_in_writes_an_unlocked_copy() {
+        let dir = tempdir().unwrap();
+        let mut original = entry("Tray", "tray --quiet", false, StartupSource::SystemAutostart);
+        original.locked = true;
+        original.icon = Some("tray".into());
+        original.ensure_entry_id();
+        let (path, copy) = duplicate_entry_in(dir.path(), &original).unwrap();
+        assert_eq!(path, dir.path().canonicalize().unwrap().join("tray-copy.desktop"));
+        let back = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+        assert_eq!((back.name.as_str(), back.command.as_str()), ("Tray (copy)", "tray --quiet"));
+        assert!(!back.enabled && !back.locked);
+        assert_eq!(back.icon(), Some("tray"));
+        assert!(copy.entry_id().is_some());
+
+        let (second, _) = duplicate_entry_in(dir.path(), &original).unwrap();
+        assert_eq!(second.file_name().unwrap(), "tray-copy-2.desktop");
+        let env = entry("PATH", "/usr/bin", true, StartupSource::EnvironmentD);
+        assert!(duplicate_entry_in(dir.path(), &env).is_err());
+    }
+
+    #[test]
+    fn set_entries_enabled_in_forces_or_inverts_user_entries() {
+        let dir = tempdir().unwrap();
+        let mut entries = Vec::new();
+        for (name, enabled) in [("on", true), ("off", false), ("held", false)] {
+            let mut e = entry(name, name, enabled, StartupSource::UserAutostart);
+            e.path = Some(dir.path().join(format!("{name}.desktop")));
+            write_desktop_entry(&e, e.path.as_ref().unwrap()).unwrap();
+            entries.push(e);
+        }
+        entries[2].locked = true;
+        entries.push(entry("System", "sys", false, StartupSource::SystemAutostart));
+        let all = [0, 1, 2, 3];
+
+        let outcome = set_entries_enabled_in(dir.path(), &mut entries, &all, Some(true));
+        assert_eq!(outcome.toggled, vec![(dir.path().join("off.desktop"), false)]);
+        assert_eq!(outcome.errors.len(), 1);
+        assert!(outcome.errors[0].starts_with("held: "), "{:?}", outcome.errors);
+        assert!(!entries[3].enabled);
+        let on_disk = parse_desktop_file(&dir.path().join("off.desktop"), StartupSource::UserAutostart).unwrap();
+        assert!(on_disk.enabled);
+
+        entries[2].locked = false;
+        let outcome = set_entries_enabled_in(dir.path(), &mut entries, &[0, 2], None);
+        assert_eq!(outcome.toggled.len(), 2);
+        assert!(outcome.errors.is_empty());
+        assert_eq!((entries[0].enabled, entries[2].enabled), (false, true));
+
+        // The whole bulk toggle is one undo step, and so is its redo.
+        let batch = outcome.undo_entry().unwrap();
+        assert_eq!(batch.label(), "bulk change");
+        assert_eq!(batch.paths().len(), 2);
+        let enabled_on_disk = |name: &str| {
+            parse_desktop_file(&dir.path().join(format!("{name}.desktop")), StartupSource::UserAutostart).unwrap().enabled
+        };
+        let reapply = batch.revert().unwrap();
+        assert_eq!((enabled_on_disk("on"), enabled_on_disk("held")), (true, false));
+        reapply.revert().unwrap();
+        assert_eq!((enabled_on_disk("on"), enabled_on_disk("held")), (false, true));
+
+        // A file that went missing fails the undo and leaves the others as they were.
+        fs::remove_file(dir.path().join("on.desktop")).unwrap();
+        assert!(batch.revert().is_err());
+        assert!(enabled_on_disk("held"));
+        assert!(BulkToggle::default().undo_entry().is_none());
+    }
+
+    #[test]
+    fn view_settings_persist_filter_and_sort() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        // Configs written before view settings existed load with the defaults.
+        fs::write(&path, "{\"sync_on_write\": false}\n").unwrap();
+        let mut config = load_config_from(&path).unwrap();
+        assert_eq!(config.view, ViewSettings::default());
+        assert_eq!(config.view.filter_state(), FilterState::default());
+
+        let filter = FilterState {
+            show_system: false,
+            wine_only: true,
+            search_text: "tray".to_string(),
+            ..FilterState::default()
+        };
+        config.view.record_filter(&filter);
+        config.view.sort = SortKey::DaemonsFirst;
+        config.view.window_width = 1200;
+        save_config_to(&config, &path).unwrap();
+        let loaded = load_config_from(&path).unwrap();
+        assert_eq!(loaded.view, config.view);
+        assert_eq!(
+            loaded.view.filter_state(),
+            FilterState {
+                show_system: false,
+                ..FilterState::default()
+            }
+        );
+    }
+
+    #[test]
+    fn fingerprint_ignores_path_and_extra_order() {
+        let mut a = entry("App", "/usr/bin/app", true, StartupSource::UserAutostart);
+        a.path = Some(PathBuf::from("/tmp/app.desktop"));
+        a.extra = vec![("X-A".into(), "1".into()), ("X-B".into(), "2".into())];
+        let mut b = a.clone();
+        b.path = Some(PathBuf::from("/tmp/renamed.desktop"));
+        b.extra.reverse();
+        assert_eq!(a.compute_content_fingerprint(), b.compute_content_fingerprint());
+        let mut c = a.clone();
+        c.enabled = false;
+        assert_ne!(a.compute_content_fingerprint(), c.compute_content_fingerprint());
+    }
+
+    #[test]
+    fn dedup_by_content_prefers_user_entries() {
+        let system = entry("App", "/usr/bin/app", true, StartupSource::SystemAutostart);
+        let mut user = entry("App", "/usr/bin/app", true, StartupSource::UserAutostart);
+        user.path = Some(PathBuf::from("/tmp/app-copy.desktop"));
+        let other = entry("Other", "/usr/bin/other", true, StartupSource::UserAutostart);
+        let deduped = dedup_by_content(vec![system, other.clone(), user]);
+        assert_eq!(deduped.len(), 2);
+        assert_eq!(deduped[0].source, StartupSource::UserAutostart);
+        assert_eq!(deduped[0].path, Some(PathBuf::from("/tmp/app-copy.desktop")));
+        assert_eq!(deduped[1].name, "Other");
+    }
+
+    #[test]
+    fn dedup_by_content_keeps_distinct_entries() {
+        let entries = vec![
+            entry("App", "/usr/bin/app", true, StartupSource::UserAutostart),
+            entry("App", "/usr/bin/app --tray", true, StartupSource::UserAutostart),
+            entry("App2", "/usr/bin/app", true, StartupSource::UserAutostart),
+        ];
+        assert_eq!(dedup_by_content(entries).len(), 3);
+    }
+
+    #[test]
+    fn migrate_hidden_to_xgnome_only_touches_legacy_entries() {
+        let dir = tempdir().unwrap();
+        let legacy_disabled = dir.path().join("legacy-off.desktop");
+        let legacy_enabled = dir.path().join("legacy-on.desktop");
+        let modern = dir.path().join("modern.desktop");
+        std::fs::write(&legacy_disabled, "[Desktop Entry]\nName=Old\nExec=/bin/true\nHidden=true\n").unwrap();
+        std::fs::write(&legacy_enabled, "[Desktop Entry]\nName=Old2\nExec=/bin/true\nHidden=false\n").unwrap();
+        let modern_content = "[Desktop Entry]\nName=New\nExec=/bin/true\nX-GNOME-Autostart-enabled=false\nHidden=true\n";
+        std::fs::write(&modern, modern_content).unwrap();
+
+        assert_eq!(migrate_hidden_to_xgnome(dir.path()).unwrap(), 2);
+        let written = read_to_string(&legacy_disabled).unwrap();
+        assert!(written.contains("X-GNOME-Autostart-enabled=false"));
+        assert!(written.contains("Hidden=true"));
+        assert!(read_to_string(&legacy_enabled).unwrap().contains("X-GNOME-Autostart-enabled=true"));
+        assert_eq!(read_to_string(&modern).unwrap(), modern_content);
+
+        // Running again is a no-op.
+        assert_eq!(migrate_hidden_to_xgnome(dir.path()).unwrap(), 0);
+    }
+
+    #[test]
+    fn desktop_entry_has_key_ignores_other_groups() {
+        let content = "[Desktop Entry]\nName=A\n\n[Other]\nHidden=true\n";
+        assert!(desktop_entry_has_key(content, "Name"));
+        assert!(!desktop_entry_has_key(content, "Hidden"));
+    }
+
+    #[test]
+    fn preview_matches_written_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.desktop");
+        let content = "\
+# Preamble
+
+[Desktop Entry]
+Name=Sample
+Name[de]=Beispiel
+Exec=/bin/true
+X-Test=1
+
+[Other]
+Foo=Bar
+";
+        std::fs::write(&path, content).unwrap();
+        let original = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+        let updated = apply_edit(&original, "Renamed", "/bin/echo hi");
+        let preview = preview_write_output(&updated);
+        assert_eq!(preview, updated.to_desktop_string());
+        write_desktop_entry(&updated, &path).unwrap();
+        assert_eq!(read_to_string(&path).unwrap(), preview);
+        assert!(preview.contains("Name=Renamed"));
+        assert!(preview.contains("Exec=/bin/echo hi"));
+    }
+
+    #[test]
+    fn alternating_sort_equal_lengths() {
+        let entries = vec![
+            entry("D", "/bin/true", false, StartupSource::UserAutostart),
+            entry("A", "/bin/true", true, StartupSource::UserAutostart),
+            entry("C", "/bin/true", true, StartupSource::UserAutostart),
+            entry("B", "/bin/true", false, StartupSource::UserAutostart),
+        ];
+        let sorted = sort_indices(&entries, vec![0, 1, 2, 3], SortKey::Alternating);
+        let names: Vec<_> = sorted.iter().map(|i| entries[*i].name.as_str()).collect();
+        assert_eq!(names, vec!["A", "B", "C", "D"]);
+    }
+
+    #[test]
+    fn alternating_sort_unequal_lengths_appends_rest() {
+        let entries = vec![
+            entry("E1", "/bin/true", true, StartupSource::UserAutostart),
+            entry("D1", "/bin/true", false, StartupSource::UserAutostart),
+            entry("E2", "/bin/true", true, StartupSource::UserAutostart),
+            entry("E3", "/bin/true", true, StartupSource::SystemAutostart),
+        ];
+        let sorted = sort_indices(&entries, vec![0, 1, 2, 3], SortKey::Alternating);
+        let names: Vec<_> = sorted.iter().map(|i| entries[*i].name.as_str()).collect();
+        assert_eq!(names, vec!["E1", "D1", "E2", "E3"]);
+    }
+
+    #[test]
+    fn alternating_sort_single_status() {
+        let all_enabled = vec![
+            entry("B", "/bin/true", true, StartupSource::UserAutostart),
+            entry("A", "/bin/true", true, StartupSource::UserAutostart),
+        ];
+        assert_eq!(sort_indices(&all_enabled, vec![0, 1], SortKey::Alternating), vec![1, 0]);
+        let all_disabled = vec![
+            entry("B", "/bin/true", false, StartupSource::UserAutostart),
+            entry("A", "/bin/true", false, StartupSource::UserAutostart),
+        ];
+        assert_eq!(sort_indices(&all_disabled, vec![0, 1], SortKey::Alternating), vec![1, 0]);
+        assert!(sort_indices(&all_disabled, Vec::new(), SortKey::Alternating).is_empty());
+    }
+
+    #[test]
+    fn load_entries_from_snapshot_points_into_snapshot() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("app.desktop");
+        std::fs::write(&path, "[Desktop Entry]\nName=App\nExec=/bin/true\n").unwrap();
+        let entries = load_entries_from_snapshot(dir.path()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].source, StartupSource::UserAutostart);
+        assert_eq!(entries[0].path.as_deref(), Some(path.as_path()));
+        assert!(load_entries_from_snapshot(&dir.path().join("missing")).is_err());
+    }
+
+    fn at_path(mut e: StartupEntry, file: &str) -> StartupEntry {
+        e.path = Some(PathBuf::from(file));
+        e
+    }
+
+    #[test]
+    fn diff_snapshots_detects_added() {
+        let current = vec![at_path(entry("A", "/bin/a", true, StartupSource::UserAutostart), "/cur/a.desktop")];
+        let snapshot = vec![
+            at_path(entry("A", "/bin/a", true, StartupSource::UserAutostart), "/snap/a.desktop"),
+            at_path(entry("B", "/bin/b", true, StartupSource::UserAutostart), "/snap/b.desktop"),
+        ];
+        let diff = diff_snapshots(&current, &snapshot);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].name, "B");
+        assert!(diff.removed.is_empty());
+        assert!(diff.modified.is_empty());
+    }
+
+    #[test]
+    fn diff_snapshots_detects_removed() {
+        let current = vec![
+            at_path(entry("A", "/bin/a", true, StartupSource::UserAutostart), "/cur/a.desktop"),
+            at_path(entry("C", "/bin/c", true, StartupSource::UserAutostart), "/cur/c.desktop"),
+        ];
+        let snapshot = vec![at_path(entry("A", "/bin/a", true, StartupSource::UserAutostart), "/snap/a.desktop")];
+        let diff = diff_snapshots(&current, &snapshot);
+        assert!(diff.added.is_empty());
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].name, "C");
+        assert!(diff.modified.is_empty());
+    }
+
+    #[test]
+    fn diff_snapshots_detects_modified() {
+        let current = vec![at_path(entry("A", "/bin/a", true, StartupSource::UserAutostart), "/cur/a.desktop")];
+        let snapshot = vec![at_path(entry("A", "/bin/a", false, StartupSource::UserAutostart), "/snap/a.desktop")];
+        let diff = diff_snapshots(&current, &snapshot);
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.modified.len(), 1);
+        assert!(diff.modified[0].0.enabled);
+        assert!(!diff.modified[0].1.enabled);
+    }
+
+    #[test]
+    fn cli_diff_snapshot_lists_each_change() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.desktop"), "[Desktop Entry]\nName=A\nExec=/bin/a\n").unwrap();
+        fs::write(dir.path().join("new.desktop"), "[Desktop Entry]\nName=New\nExec=/bin/new\n").unwrap();
+        let current = vec![
+            at_path(entry("A", "/bin/a --changed", true, StartupSource::UserAutostart), "/cur/a.desktop"),
+            at_path(entry("Gone", "/bin/gone", true, StartupSource::UserAutostart), "/cur/gone.desktop"),
+            at_path(entry("Clock", "/bin/clock", true, StartupSource::SystemAutostart), "/etc/xdg/autostart/clock.desktop"),
+        ];
+        assert_eq!(
+            cli_diff_snapshot(&current, dir.path()).unwrap(),
+            "added\tnew.desktop\nremoved\tgone.desktop\nmodified\ta.desktop\n"
+        );
+        let same = load_entries_from_snapshot(dir.path()).unwrap();
+        assert_eq!(cli_diff_snapshot(&same, dir.path()).unwrap(), "No differences\n");
+        assert!(cli_diff_snapshot(&current, &dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn write_with_fsync_on_tempdir() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("synced.desktop");
+        let e = entry("Synced", "/bin/true", true, StartupSource::UserAutostart);
+        write_desktop_entry_atomic_with_fsync(&e, &path).unwrap();
+        assert_eq!(read_to_string(&path).unwrap(), e.to_desktop_string());
+        assert!(AppConfig::default().sync_on_write);
+        let parsed: AppConfig = serde_json::from_str("{}").unwrap();
+        assert!(parsed.sync_on_write);
+    }
+
+    /// Minimal well-formedness check: balanced tags, quoted attributes, valid entities.
+    fn assert_well_formed_xml(xml: &str) {
+        fn check_text(text: &str) {
+            assert!(!text.contains('<'), "stray '<' in {text:?}");
+            let mut rest = text;
+            while let Some(pos) = rest.find('&') {
+                let tail = &rest[pos..];
+                let end = tail.find(';').expect("unterminated entity");
+                assert!(
+                    ["&amp;", "&lt;", "&gt;", "&quot;", "&apos;"].contains(&&tail[..=end]),
+                    "bad entity {}",
+                    &tail[..=end]
+                );
+                rest = &tail[end + 1..];
+            }
+        }
+        let mut stack: Vec<String> = Vec::new();
+        let mut roots = 0;
+        let mut rest = xml.trim();
+        if rest.starts_with("<?") {
+            rest = &rest[rest.find("?>").expect("unterminated prolog") + 2..];
+        }
+        while let Some(start) = rest.find('<') {
+            let text = &rest[..start];
+            assert!(!stack.is_empty() || text.trim().is_empty(), "text outside root");
+            check_text(text);
+            let end = rest[start..].find('>').expect("unterminated tag") + start;
+            let tag = &rest[start + 1..end];
+            rest = &rest[end + 1..];
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(stack.pop().as_deref(), Some(name.trim()), "mismatched close tag");
+                continue;
+            }
+            let self_closing = tag.ends_with('/');
+            let tag = tag.trim_end_matches('/');
+            let (name, mut attrs) = tag.split_once(' ').unwrap_or((tag, ""));
+            assert!(!name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == ':' || c == '-'));
+            loop {
+                attrs = attrs.trim_start();
+                if attrs.is_empty() {
+                    break;
+                }
+                let eq = attrs.find('=').expect("attribute without value");
+                assert!(attrs[eq + 1..].starts_with('"'), "unquoted attribute");
+                let close = attrs[eq + 2..].find('"').expect("unterminated attribute") + eq + 2;
+                check_text(&attrs[eq + 2..close]);
+                attrs = &attrs[close + 1..];
+            }
+            if stack.is_empty() {
+                roots += 1;
+            }
+            if !self_closing {
+                stack.push(name.to_string());
+            }
+        }
+        assert!(rest.trim().is_empty(), "trailing text after root");
+        assert!(stack.is_empty(), "unclosed tags: {stack:?}");
+        assert_eq!(roots, 1);
+    }
+
+    #[test]
+    fn render_list_as_svg_is_well_formed() {
+        let entries = vec![
+            entry("Tom & Jerry <\"quoted\">", "/bin/true", true, StartupSource::UserAutostart),
+            entry("Disabled user", "/bin/true", false, StartupSource::UserAutostart),
+            entry("System", "/bin/true", true, StartupSource::SystemAutostart),
+            entry("System off", "/bin/true", false, StartupSource::SystemAutostart),
+        ];
+        let svg = render_list_as_svg(&entries);
+        assert_well_formed_xml(&svg);
+        assert_eq!(svg.matches("<rect").count(), 4);
+        assert!(svg.contains("Tom &amp; Jerry &lt;&quot;quoted&quot;&gt;"));
+        assert!(svg.contains("#1c71d8") && svg.contains("#99c1f1"));
+        assert!(svg.contains("#5e5c64") && svg.contains("#c0bfbc"));
+        assert_well_formed_xml(&render_list_as_svg(&[]));
+    }
+
+    #[test]
+    fn environment_d_entries_from_conf_files() {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("20-paths.conf"),
+            "# comment\n\nPATH=/opt/bin:$PATH\nEDITOR=\"nvim\"\nQUOTED='single value'\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("10-first.conf"),
+            "export GTK_THEME=Adwaita:dark\n  SPACED = yes \nnot an assignment\n1BAD=x\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("ignored.txt"), "IGNORED=1\n").unwrap();
+
+        let entries = load_environment_d_entries(dir.path()).unwrap();
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["GTK_THEME", "SPACED", "PATH", "EDITOR", "QUOTED"]);
+        assert_eq!(entries[0].command, "export GTK_THEME=Adwaita:dark");
+        assert_eq!(entries[1].command, "export SPACED=yes");
+        assert_eq!(entries[3].command, "export EDITOR=nvim");
+        assert_eq!(entries[4].command, "export QUOTED=single value");
+        assert!(entries.iter().all(|e| e.source == StartupSource::EnvironmentD && e.enabled));
+        assert_eq!(entries[2].path.as_deref(), Some(dir.path().join("20-paths.conf").as_path()));
+        assert!(load_environment_d_entries(&dir.path().join("missing")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn environment_d_entries_follow_system_filter() {
+        let entries = vec![entry("EDITOR", "export EDITOR=vi", true, StartupSource::EnvironmentD)];
+        let only_user = FilterState { show_enabled: true, show_disabled: true, show_user: true, show_system: false, wine_only: false, daemons_only: false, running_only: false, search_text: String::new() };
+        assert!(apply_filter(&entries, &only_user).is_empty());
+        assert_eq!(apply_filter(&entries, &FilterState::default()), vec![0]);
+    }
+
+    #[test]
+    fn format_size_human_boundaries() {
+        assert_eq!(format_size_human(0), "0 B");
+        assert_eq!(format_size_human(432), "432 B");
+        assert_eq!(format_size_human(1023), "1023 B");
+        assert_eq!(format_size_human(1024), "1.0 KB");
+        assert_eq!(format_size_human(1229), "1.2 KB");
+        assert_eq!(format_size_human(1_048_575), "1023.9 KB");
+        assert_eq!(format_size_human(1_048_576), "1.0 MB");
+        assert_eq!(format_size_human(3_565_159), "3.4 MB");
+    }
+
+    #[test]
+    fn autostart_dir_total_size_sums_desktop_files() {
+        let dir = tempdir().unwrap();
+        let a = "[Desktop Entry]\nName=A\n";
+        let b = "[Desktop Entry]\nName=BB\n";
+        std::fs::write(dir.path().join("a.desktop"), a).unwrap();
+        std::fs::write(dir.path().join("b.desktop"), b).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+        let mut entries = load_autostart_dir(dir.path(), StartupSource::UserAutostart).unwrap();
+        let mut sway = entry("bar", "waybar", true, StartupSource::SwayConfig);
+        sway.path = Some(dir.path().join("notes.txt"));
+        entries.push(sway);
+        assert_eq!(user_autostart_dir_total_size(&entries), (a.len() + b.len()) as u64);
+        assert_eq!(system_autostart_dir_total_size(&entries), 0);
+        for e in entries.iter_mut().filter(|e| e.source == StartupSource::UserAutostart) {
+            e.source = StartupSource::SystemAutostart;
+        }
+        assert_eq!(system_autostart_dir_total_size(&entries), (a.len() + b.len()) as u64);
+    }
+
+    #[test]
+    fn parse_exec_into_parts_splits_binary_args_and_codes() {
+        let parts = parse_exec_into_parts("/usr/bin/app --name \"My Doc\" --open=%u %F");
+        assert_eq!(parts.binary, "/usr/bin/app");
+        assert_eq!(parts.args, vec!["--name", "My Doc", "--open=%u"]);
+        assert_eq!(parts.field_codes, vec!["%u", "%F"]);
+
+        let literal = parse_exec_into_parts("printf 100%% done");
+        assert_eq!(literal.args, vec!["100%%", "done"]);
+        assert!(literal.field_codes.is_empty());
+        assert_eq!(parse_exec_into_parts(""), ExecParts::default());
+    }
+
+    #[test]
+    fn describe_command_verbosely_lists_each_part() {
+        let e = entry("App", "/usr/bin/app --tray %U", true, StartupSource::UserAutostart);
+        assert_eq!(
+            e.describe_command_verbosely(),
+            "Binary: app\nPath: /usr/bin/app\nArguments:\n  --tray\nField codes: %U"
+        );
+        let bare = entry("Missing", "definitely-not-installed-usm", true, StartupSource::UserAutostart);
+        let text = bare.describe_command_verbosely();
+        assert!(text.contains("Path: definitely-not-installed-usm (not found in PATH)"));
+        assert!(text.contains("Arguments: none"));
+        assert!(text.contains("Field codes: none"));
+    }
+
+    #[test]
+    fn check_binaries_still_present_flags_deleted_binary() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempdir().unwrap();
+        let bin = dir.path().join("usm-test-app");
+        std::fs::write(&bin, "#!/bin/sh\nexit 0\n").unwrap();
+        std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let entries = vec![
+            entry("Temp", &format!("{} --flag", bin.display()), true, StartupSource::UserAutostart),
+            entry("Missing", "/nonexistent/usm-binary", true, StartupSource::SystemAutostart),
+            entry("Env", "export FOO=bar", true, StartupSource::EnvironmentD),
+            entry("Empty", "", true, StartupSource::UserAutostart),
+        ];
+        assert_eq!(check_binaries_still_present(&entries), vec![1]);
+        std::fs::remove_file(&bin).unwrap();
+        assert_eq!(check_binaries_still_present(&entries), vec![0, 1]);
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn sha256_and_base32_known_vectors() {
+        assert_eq!(
+            hex(&sha256(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hex(&sha256(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        let long = vec![b'a'; 1000];
+        assert_eq!(
+            hex(&sha256(&long)),
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+        );
+        assert_eq!(base32_encode(b""), "");
+        assert_eq!(base32_encode(b"f"), "MY");
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+    }
+
+    #[test]
+    fn desktop_file_hash_uri_tracks_content() {
+        let a = entry("App", "/usr/bin/app --tray", true, StartupSource::UserAutostart);
+        let hash = a.generate_desktop_file_hash_uri();
+        assert!(hash.starts_with("sha256:"), "{hash}");
+        assert_eq!(hash.len(), "sha256:".len() + 64);
+        let mut same = entry("App", "/usr/bin/app --tray", true, StartupSource::SystemAutostart);
+        same.extra.push((USM_ID_KEY.into(), "ABC".into()));
+        same.entry_comments.push("# note".into());
+        same.path = Some(PathBuf::from("/elsewhere/app.desktop"));
+        assert_eq!(same.generate_desktop_file_hash_uri(), hash);
+        let mut variants = vec![
+            entry("App2", "/usr/bin/app --tray", true, StartupSource::UserAutostart),
+            entry("App", "/usr/bin/app", true, StartupSource::UserAutostart),
+            entry("App", "/usr/bin/app --tray", false, StartupSource::UserAutostart),
+        ];
+        let mut localized = a.clone();
+        localized.localized_names.push(("de".into(), "Anwendung".into()));
+        variants.push(localized);
+        let mut delayed = a.clone();
+        delayed.set_delay_seconds(5);
+        variants.push(delayed);
+        for variant in &variants {
+            assert_ne!(variant.generate_desktop_file_hash_uri(), hash, "{variant:?}");
+        }
+        variants.push(same);
+        assert_eq!(find_by_hash(&hash, &variants), Some(5));
+        assert_eq!(find_by_hash("sha256:00", &variants), None);
+    }
+
+    #[test]
+    fn entry_id_is_stable_and_unique() {
+        let a = entry("App", "/usr/bin/app", true, StartupSource::UserAutostart);
+        let mut a_disabled = a.clone();
+        a_disabled.enabled = false;
+        assert_eq!(generate_entry_id(&a), generate_entry_id(&a_disabled));
+        assert_eq!(generate_entry_id(&a).len(), 52);
+        let b = entry("App", "/usr/bin/app --tray", true, StartupSource::UserAutostart);
+        let c = entry("App", "/usr/bin/app", true, StartupSource::SystemAutostart);
+        assert_ne!(generate_entry_id(&a), generate_entry_id(&b));
+        assert_ne!(generate_entry_id(&a), generate_entry_id(&c));
+    }
+
+    #[test]
+    fn stored_entry_id_survives_rename() {
+        let original = entry("App", "/usr/bin/app", true, StartupSource::UserAutostart);
+        let id = generate_entry_id(&original);
+        let renamed = apply_edit(&original, "Renamed App", "/usr/bin/app");
+        assert_eq!(renamed.entry_id(), Some(id.as_str()));
+        assert!(renamed.to_desktop_string().contains(&format!("X-USM-ID={id}")));
+        let entries = vec![
+            entry("Other", "/bin/other", true, StartupSource::SystemAutostart),
+            renamed,
+        ];
+        assert_eq!(find_entry_by_id(&id, &entries), Some(1));
+        let other_id = generate_entry_id(&entries[0]);
+        assert_eq!(find_entry_by_id(&other_id, &entries), Some(0));
+        assert_eq!(find_entry_by_id("NOPE", &entries), None);
+
+        let mut before = entries[1].clone();
+        before.name = "App".into();
+        before.path = Some(PathBuf::from("/home/u/.config/autostart/app.desktop"));
+        assert_eq!(find_reloaded_entry(&entries, &before), Some(1));
+        before.extra.clear();
+        assert_eq!(find_reloaded_entry(&entries, &before), None);
+        assert_eq!(find_reloaded_entry(&entries, &entries[0]), Some(0));
+    }
+
+    #[test]
+    fn parse_desktop_file_from_bytes_edge_cases() {
+        let empty = parse_desktop_file_from_bytes(b"").unwrap();
+        assert_eq!(empty.name, "Unnamed");
+        assert_eq!(empty.source, StartupSource::Unknown);
+        assert!(empty.path.is_none());
+        let headerless = parse_desktop_file_from_bytes(b"Name=Orphan\nExec=/bin/true\n").unwrap();
+        assert_eq!(headerless.name, "Unnamed");
+        assert_eq!(headerless.preamble.len(), 2);
+        let nul = parse_desktop_file_from_bytes(b"[Desktop Entry]\nName=A\0B\n").unwrap();
+        assert_eq!(nul.name, "A\0B");
+        let _ = nul.to_desktop_string();
+        assert!(parse_desktop_file_from_bytes(&[0xff, 0xfe, b'[']).is_err());
+    }
+
+    fn argv(command: &str) -> Vec<String> {
+        entry("T", command, true, StartupSource::UserAutostart).command_argv()
+    }
+
+    #[test]
+    fn command_argv_plain_and_whitespace() {
+        assert_eq!(argv("/usr/bin/app --flag value"), vec!["/usr/bin/app", "--flag", "value"]);
+        assert_eq!(argv("  app \t  -v  "), vec!["app", "-v"]);
+        assert!(argv("").is_empty());
+        assert!(argv("   ").is_empty());
+    }
+
+    #[test]
+    fn command_argv_double_quotes() {
+        assert_eq!(argv("/usr/bin/app \"arg with spaces\""), vec!["/usr/bin/app", "arg with spaces"]);
+        assert_eq!(argv("app \"say \\\"hi\\\"\""), vec!["app", "say \"hi\""]);
+        assert_eq!(argv("app \"a\\\\b\" \"\\$HOME\""), vec!["app", "a\\b", "$HOME"]);
+        assert_eq!(argv("app \"keep\\n\""), vec!["app", "keep\\n"]);
+        assert_eq!(argv("app \"\""), vec!["app", ""]);
+        assert_eq!(argv("app pre\"fix\"post"), vec!["app", "prefixpost"]);
+    }
+
+    #[test]
+    fn command_argv_single_quotes() {
+        assert_eq!(argv("app '--flag'"), vec!["app", "--flag"]);
+        assert_eq!(argv("app 'two words' 'no \\escape'"), vec!["app", "two words", "no \\escape"]);
+        assert_eq!(argv("app 'it'\\''s'"), vec!["app", "it's"]);
+        assert_eq!(argv("app '\"double\" inside'"), vec!["app", "\"double\" inside"]);
+    }
+
+    #[test]
+    fn command_argv_backslash_escapes() {
+        assert_eq!(argv("/opt/My\\ App/bin/app"), vec!["/opt/My App/bin/app"]);
+        assert_eq!(argv("app \\\"quoted\\\""), vec!["app", "\"quoted\""]);
+        assert_eq!(argv("app trailing\\"), vec!["app", "trailing\\"]);
+    }
+
+    #[test]
+    fn command_argv_unclosed_quotes_run_to_end() {
+        assert_eq!(argv("app \"unclosed arg"), vec!["app", "unclosed arg"]);
+        assert_eq!(argv("app 'unclosed single"), vec!["app", "unclosed single"]);
+    }
+
+    #[test]
+    fn exec_binary_is_first_argv_element() {
+        let e = entry("T", "\"/opt/my app/run\" --x", true, StartupSource::UserAutostart);
+        assert_eq!(e.exec_binary().as_deref(), Some("/opt/my app/run"));
+        let empty = entry("T", "", true, StartupSource::UserAutostart);
+        assert_eq!(empty.exec_binary(), None);
+    }
+
+    #[test]
+    fn chocolatey_snippet_quotes_powershell_specials() {
+        let e = entry("Bob's $Tool `x`", "/usr/bin/tool --name 'it''s'", true, StartupSource::UserAutostart);
+        let snippet = e.to_chocolatey_nupkg_snippet();
+        assert!(snippet.contains("$runKey = 'HKCU:\\Software\\Microsoft\\Windows\\CurrentVersion\\Run'"));
+        assert!(snippet.contains("-Name 'Bob''s $Tool `x`'"));
+        assert!(snippet.contains("-Value '/usr/bin/tool --name its'"));
+        assert!(snippet.ends_with("-PropertyType String -Force | Out-Null\n"));
+    }
+
+    #[test]
+    fn chocolatey_snippet_quotes_paths_with_spaces() {
+        let e = entry("App", "\"C:\\Program Files\\App\\app.exe\" --open \"my file\"", true, StartupSource::UserAutostart);
+        let snippet = e.to_chocolatey_nupkg_snippet();
+        assert!(snippet.contains("-Value '\"C:\\Program Files\\App\\app.exe\" --open \"my file\"'"));
+    }
+
+    #[test]
+    fn winget_snippet_escapes_yaml() {
+        let e = entry("My \"Quoted\" App", "\"/opt/my app/bin/run\" --tray", true, StartupSource::UserAutostart);
+        let snippet = e.to_winget_manifest_snippet();
+        assert!(snippet.contains("PackageIdentifier: Usm.MyQuotedApp\n"));
+        assert!(snippet.contains("PackageName: \"My \\\"Quoted\\\" App\"\n"));
+        assert!(snippet.contains("Commands:\n  - \"run\"\n"));
+        assert!(snippet.contains("# Startup command: \"/opt/my app/bin/run\" --tray\n"));
+        let odd = entry("$$$", "", true, StartupSource::UserAutostart);
+        assert!(odd.to_winget_manifest_snippet().contains("PackageIdentifier: Usm.Entry\n"));
+    }
+
+    #[test]
+    fn export_format_ids_roundtrip() {
+        for format in ExportFormat::ALL {
+            assert_eq!(ExportFormat::from_id(format.id()), Some(format));
+        }
+        assert_eq!(ExportFormat::from_id("nope"), None);
+    }
+
+    #[test]
+    fn rename_duplicate_extra_keys_numbers_each_repeat() {
+        let mut e = entry("A", "/bin/true", true, StartupSource::UserAutostart);
+        e.extra = vec![
+            ("X-Test".into(), "1".into()),
+            ("X-Other".into(), "a".into()),
+            ("X-Test".into(), "2".into()),
+            ("X-Test".into(), "3".into()),
+            ("X-Other".into(), "b".into()),
+        ];
+        rename_duplicate_extra_keys_on_load(&mut e);
+        let keys: Vec<_> = e.extra.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, vec!["X-Test", "X-Other", "X-Test=2", "X-Test=3", "X-Other=2"]);
+
+        let restored = strip_duplicate_suffix_on_write(&e);
+        let keys: Vec<_> = restored.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, vec!["X-Test", "X-Other", "X-Test", "X-Test", "X-Other"]);
+        let values: Vec<_> = restored.iter().map(|(_, v)| v.as_str()).collect();
+        assert_eq!(values, vec!["1", "a", "2", "3", "b"]);
+    }
+
+    #[test]
+    fn strip_duplicate_suffix_leaves_unrelated_keys() {
+        let mut e = entry("A", "/bin/true", true, StartupSource::UserAutostart);
+        e.extra = vec![
+            ("X-Lone_2".into(), "x".into()),
+            ("X-Test".into(), "1".into()),
+            ("X-Test_1".into(), "y".into()),
+            ("X-Test_v2".into(), "z".into()),
+        ];
+        assert_eq!(strip_duplicate_suffix_on_write(&e), e.extra);
+    }
+
+    #[test]
+    fn numbered_keys_in_the_file_round_trip_unchanged() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.desktop");
+        fs::write(&path, "[Desktop Entry]\nName=Sample\nExec=/bin/true\nX-Test=a\nX-Test_2=b\n").unwrap();
+        let entry = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+        assert_eq!(entry.extra_value("X-Test_2"), Some("b"));
+        write_desktop_entry(&entry, &path).unwrap();
+        let written = read_to_string(&path).unwrap();
+        assert!(written.contains("X-Test=a\nX-Test_2=b\n"), "{written}");
+        assert_eq!(written.matches("X-Test=").count(), 1);
+    }
+
+    #[test]
+    fn compute_count_display_reports_visible_total_and_sources() {
+        let entries = vec![
+            entry("A", "a", true, StartupSource::UserAutostart),
+            entry("B", "b", false, StartupSource::UserAutostart),
+            entry("C", "c", true, StartupSource::SystemAutostart),
+            entry("D", "d", true, StartupSource::EnvironmentD),
+        ];
+        assert_eq!(
+            compute_count_display(4, 4, &entries),
+            "Showing 4 of 4 entries (2 user, 2 system)"
+        );
+        assert_eq!(
+            compute_count_display(4, 1, &entries),
+            "Showing 1 of 4 entries (2 user, 2 system)"
+        );
+        assert_eq!(
+            compute_count_display(4, 0, &entries),
+            "Showing 0 of 4 entries (2 user, 2 system)"
+        );
+        assert_eq!(
+            compute_count_display(0, 0, &[]),
+            "Showing 0 of 0 entries (0 user, 0 system)"
+        );
+    }
+
+    #[test]
+    fn openbox_autostart_line_round_trips() {
+        let plain = from_openbox_autostart_line("  tint2 &").unwrap();
+        assert_eq!(plain.name, "tint2");
+        assert_eq!(plain.command, "tint2");
+        assert_eq!(plain.source, StartupSource::OpenboxAutostart);
+        assert_eq!(plain.delay_seconds(), 0);
+        assert_eq!(plain.to_openbox_autostart_entry(), "tint2 &");
+
+        let delayed = from_openbox_autostart_line("(sleep 3 && /usr/bin/nm-applet --indicator) &").unwrap();
+        assert_eq!(delayed.name, "nm-applet");
+        assert_eq!(delayed.command, "/usr/bin/nm-applet --indicator");
+        assert_eq!(delayed.delay_seconds(), 3);
+        assert_eq!(
+            delayed.to_openbox_autostart_entry(),
+            "(sleep 3 && /usr/bin/nm-applet --indicator) &"
+        );
+
+        assert!(from_openbox_autostart_line("").is_none());
+        assert!(from_openbox_autostart_line("# feh --bg-scale wall.png &").is_none());
+        assert!(from_openbox_autostart_line("&").is_none());
+    }
+
+    #[test]
+    fn to_openbox_autostart_entry_uses_autostart_delay() {
+        let mut e = entry("A", "picom -b", true, StartupSource::UserAutostart);
+        assert_eq!(e.to_openbox_autostart_entry(), "picom -b &");
+        e.set_delay_seconds(5);
+        assert_eq!(e.to_openbox_autostart_entry(), "(sleep 5 && picom -b) &");
+    }
+
+    #[test]
+    fn load_openbox_autostart_reads_each_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("autostart");
+        fs::write(&path, "# Openbox autostart\n\ntint2 &\n(sleep 2 && volumeicon) &\n").unwrap();
+        let entries = load_openbox_autostart(&path).unwrap();
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["tint2", "volumeicon"]);
+        assert!(entries.iter().all(|e| e.path.as_deref() == Some(path.as_path())));
+        assert_eq!(entries[1].delay_seconds(), 2);
+    }
+
+    #[test]
+    fn load_shell_profile_entries_reads_program_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".profile");
+        fs::write(
+            &path,
+            "# ~/.profile\n\
+             export PATH=\"$HOME/bin:$PATH\"\n\
+             EDITOR=vim\n\
+             alias ll='ls -l'\n\
+             if [ -f ~/.bashrc ]; then\n\
+             \t. ~/.bashrc\n\
+             fi\n\
+             /usr/bin/xbindkeys -f \"$HOME/.xbindkeysrc\" &\n\
+             ~/bin/start-tray\n\
+             no-such-program-usm --flag\n",
+        )
+        .unwrap();
+        let entries = load_shell_profile_entries(&path).unwrap();
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["xbindkeys", "start-tray"]);
+        assert_eq!(entries[0].command, "/usr/bin/xbindkeys -f \"$HOME/.xbindkeysrc\"");
+        assert!(entries.iter().all(|e| e.source == StartupSource::ShellProfile && e.enabled));
+        assert!(entries.iter().all(|e| e.path.as_deref() == Some(path.as_path())));
+        assert!(is_user_side_source(&StartupSource::ShellProfile));
+        assert!(entries[0].to_dockerfile_entrypoint_instruction().is_some());
+
+        // Latin-1 comments make the file unreadable as text; that profile is skipped, not fatal.
+        fs::write(&path, b"# Gr\xfc\xdfe\nxbindkeys &\n").unwrap();
+        assert!(load_shell_profile_entries(&path).is_err());
+        assert!(skip_unreadable_source(&path, load_shell_profile_entries(&path)).is_empty());
+    }
+
+    #[test]
+    fn sway_exec_lines_round_trip() {
+        let config = "# Sway config\n\
+                      set $mod Mod4\n\
+                      exec mako\n\
+                      \texec_always --no-startup-id /usr/bin/kanshi -c \"/home/u/kanshi config\"\n\
+                      exec env GTK_THEME=Adwaita nm-applet --indicator\n\
+                      bindsym $mod+Return exec foot\n\
+                      # exec swayidle\n\
+                      exec   \n";
+        let entries = from_swayconfig_exec_lines(config);
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["mako", "kanshi", "nm-applet"]);
+        assert!(entries.iter().all(|e| e.source == StartupSource::SwayConfig));
+        assert!(!entries[0].always_restart());
+        assert!(entries[1].always_restart());
+        assert_eq!(entries[0].to_swayconfig_exec(), "exec mako");
+        assert_eq!(
+            entries[1].to_swayconfig_exec(),
+            "exec_always /usr/bin/kanshi -c \"/home/u/kanshi config\""
+        );
+        assert_eq!(entries[2].env_vars, vec![("GTK_THEME".to_string(), "Adwaita".to_string())]);
+        assert_eq!(entries[2].to_swayconfig_exec(), "exec env GTK_THEME=Adwaita nm-applet --indicator");
+    }
+
+    #[test]
+    fn i3_exec_lines_round_trip() {
+        let config = "exec --no-startup-id nm-applet\n\
+                      exec i3-sensible-terminal\n\
+                      exec_always --no-startup-id $HOME/.config/polybar/launch.sh\n\
+                      bindsym $mod+d exec dmenu_run\n";
+        let entries = from_i3_config_exec_lines(config);
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["nm-applet", "i3-sensible-terminal", "launch.sh"]);
+        assert!(entries.iter().all(|e| e.source == StartupSource::I3Config));
+        assert_eq!(entries[0].startup_notify(), Some(false));
+        assert_eq!(entries[0].to_i3_exec_line(), "exec --no-startup-id nm-applet");
+        assert_eq!(entries[1].startup_notify(), None);
+        assert_eq!(entries[1].to_i3_exec_line(), "exec i3-sensible-terminal");
+        assert_eq!(
+            entries[2].to_i3_exec_line(),
+            "exec_always --no-startup-id $HOME/.config/polybar/launch.sh"
+        );
+        let mut notified = entry("Term", "xterm", true, StartupSource::UserAutostart);
+        notified.extra.push(("StartupNotify".into(), "true".into()));
+        assert_eq!(notified.to_i3_exec_line(), "exec xterm");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config");
+        fs::write(&path, config).unwrap();
+        let loaded = load_i3_config_entries(&path).unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert!(loaded.iter().all(|e| e.path.as_deref() == Some(path.as_path())));
+    }
+
+    #[test]
+    fn load_sway_config_entries_sets_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config");
+        fs::write(&path, "exec waybar\nexec_always autotiling\n").unwrap();
+        let entries = load_sway_config_entries(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.path.as_deref() == Some(path.as_path())));
+        assert!(load_sway_config_entries(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn load_xdg_user_dirs_entries_from_reads_user_dirs_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("user-dirs.dirs");
+        assert!(load_xdg_user_dirs_entries_from(&path).unwrap().is_empty());
+        fs::write(
+            &path,
+            "# This file is written by xdg-user-dirs-update\n\
+             XDG_DESKTOP_DIR=\"$HOME/Desktop\"\n\
+             XDG_DOWNLOAD_DIR=\"$HOME/Downloads\"\n",
+        )
+        .unwrap();
+        let entries = load_xdg_user_dirs_entries_from(&path).unwrap();
+        assert_eq!(entries.len(), 1);
+        let e = &entries[0];
+        assert_eq!(e.name, "xdg-user-dirs-update");
+        assert_eq!(e.source, StartupSource::XdgUserDirs);
+        assert_eq!(e.path.as_deref(), Some(path.as_path()));
+        assert_eq!(e.comment(), Some("Keeps DESKTOP, DOWNLOAD up to date"));
+        assert!(is_user_side_source(&e.source));
+    }
+
+    #[test]
+    fn locked_flag_round_trips_through_desktop_file() {
+        let parsed = parse_desktop_file_from_bytes(
+            b"[Desktop Entry]\nName=A\nExec=a\nX-USM-Locked=true\nX-Other=1\n",
+        )
+        .unwrap();
+        assert!(parsed.locked);
+        assert!(parsed.extra.iter().all(|(k, _)| k != LOCKED_KEY));
+        let written = parsed.to_desktop_string();
+        assert_eq!(written.matches("X-USM-Locked=true").count(), 1);
+
+        let mut unlocked = parsed.clone();
+        unlocked.locked = false;
+        assert!(!unlocked.to_desktop_string().contains(LOCKED_KEY));
+        let reparsed = parse_desktop_file_from_bytes(unlocked.to_desktop_string().as_bytes()).unwrap();
+        assert!(!reparsed.locked);
+    }
+
+    #[test]
+    fn edit_user_entry_refuses_locked_entry() {
+        let mut e = entry("A", "a", true, StartupSource::UserAutostart);
+        e.locked = true;
+        let err = edit_user_entry(&e, "B", "b", None).unwrap_err();
+        assert!(err.to_string().contains("locked"));
+    }
+
+    #[test]
+    fn match_wildcard_captures_each_star() {
+        assert_eq!(match_wildcard("My App *", "My App Beta"), Some(vec!["Beta"]));
+        assert_eq!(match_wildcard("*-*", "a-b-c"), Some(vec!["a", "b-c"]));
+        assert_eq!(match_wildcard("Exact", "Exact"), Some(vec![]));
+        assert_eq!(match_wildcard("Exact", "Exactly"), None);
+        assert_eq!(match_wildcard("* Tool", "Tools"), None);
+        assert_eq!(match_wildcard("a*b*c", "abc"), Some(vec!["", ""]));
+    }
+
+    #[test]
+    fn batch_rename_entries_renames_matching_user_entries() {
+        let mut entries = vec![
+            at_path(entry("My App One", "a", true, StartupSource::UserAutostart), "my-app-one.desktop"),
+            at_path(entry("My App Two", "b", true, StartupSource::UserAutostart), "my-app-two.desktop"),
+            at_path(entry("My App Sys", "c", true, StartupSource::SystemAutostart), "my-app-sys.desktop"),
+            at_path(entry("Other", "d", true, StartupSource::UserAutostart), "other.desktop"),
+        ];
+        entries[1].locked = true;
+        let count = batch_rename_entries("My App *", "App *", &mut entries).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(entries[0].name, "App One");
+        assert_eq!(entries[0].path.as_ref().unwrap().file_name().unwrap(), "app-one.desktop");
+        assert!(entries[0].entry_id().is_some());
+        assert_eq!(entries[1].name, "My App Two");
+        assert_eq!(entries[2].name, "My App Sys");
+        assert_eq!(entries[3].name, "Other");
+    }
+
+    #[test]
+    fn batch_rename_entries_avoids_duplicate_file_names() {
+        let mut entries = vec![
+            at_path(entry("Old A", "a", true, StartupSource::UserAutostart), "old-a.desktop"),
+            at_path(entry("Old B", "b", true, StartupSource::UserAutostart), "old-b.desktop"),
+            at_path(entry("New", "c", true, StartupSource::UserAutostart), "new.desktop"),
+        ];
+        let count = batch_rename_entries("Old *", "New", &mut entries).unwrap();
+        assert_eq!(count, 2);
+        let files: Vec<_> = entries
+            .iter()
+            .map(|e| e.path.as_ref().unwrap().file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(files, vec!["new-2.desktop", "new-3.desktop", "new.desktop"]);
+    }
+
+    #[test]
+    fn batch_rename_entries_rejects_bad_input() {
+        let mut entries = vec![entry("A", "a", true, StartupSource::UserAutostart)];
+        assert!(batch_rename_entries("", "B", &mut entries).is_err());
+        assert!(batch_rename_entries("A", "B *", &mut entries).is_err());
+        assert!(batch_rename_entries("*", " ", &mut entries).is_err());
+    }
+
+    #[test]
+    fn generate_unique_slug_appends_counter() {
+        let taken: HashSet<String> = ["my-app".to_string(), "my-app-2".to_string()].into_iter().collect();
+        assert_eq!(generate_unique_slug("My App", &taken), "my-app-3");
+        assert_eq!(generate_unique_slug("Fresh", &taken), "fresh");
+    }
+
+    #[test]
+    fn parse_env_prefix_strips_leading_assignments() {
+        assert_eq!(
+            parse_env_prefix("env FOO=bar GTK_THEME=\"Adwaita dark\" /usr/bin/app --tray"),
+            (
+                vec![
+                    ("FOO".to_string(), "bar".to_string()),
+                    ("GTK_THEME".to_string(), "Adwaita dark".to_string()),
+                ],
+                "/usr/bin/app --tray".to_string()
+            )
+        );
+        for unchanged in ["/usr/bin/app", "env -i app", "env FOO=bar", "environment X=1 app", ""] {
+            assert_eq!(parse_env_prefix(unchanged), (Vec::new(), unchanged.to_string()));
+        }
+    }
+
+    #[test]
+    fn env_vars_round_trip_through_desktop_file() {
+        let parsed = parse_desktop_file_from_bytes(
+            b"[Desktop Entry]\nName=A\nExec=env LANG=C QT_SCALE=\"1 5\" app %U\n",
+        )
+        .unwrap();
+        assert_eq!(parsed.command, "app %U");
+        assert_eq!(
+            parsed.env_vars,
+            vec![("LANG".to_string(), "C".to_string()), ("QT_SCALE".to_string(), "1 5".to_string())]
+        );
+        assert!(parsed.to_desktop_string().contains("Exec=env LANG=C QT_SCALE=\"1 5\" app %U\n"));
+        let reparsed = parse_desktop_file_from_bytes(parsed.to_desktop_string().as_bytes()).unwrap();
+        assert_eq!(reparsed.env_vars, parsed.env_vars);
+        assert_eq!(reparsed.command, parsed.command);
+
+        let mut e = entry("B", "b", true, StartupSource::UserAutostart);
+        e.env_vars = vec![("V".into(), "a\"$b".into())];
+        assert_eq!(e.exec_line(), "env V=\"a\\\"\\$b\" b");
+        assert_eq!(parse_env_prefix(&e.exec_line()).0, e.env_vars);
+    }
+
+    #[test]
+    fn apply_edit_moves_typed_env_prefix_into_env_vars() {
+        let mut original = entry("A", "a", true, StartupSource::UserAutostart);
+        original.env_vars = vec![("X".into(), "1".into())];
+        let updated = apply_edit(&original, "A", "env Y=2 b");
+        assert_eq!(updated.command, "b");
+        assert_eq!(updated.exec_line(), "env X=1 Y=2 b");
+
+        let updated = apply_edit(&updated, "A", "env X=3 b");
+        assert_eq!(updated.env_vars, vec![("X".into(), "3".into()), ("Y".into(), "2".into())]);
+        assert_eq!(updated.exec_line(), "env X=3 Y=2 b");
+    }
+
+    #[test]
+    fn xdg_autostart_url_round_trips_awkward_text() {
+        let mut e = entry("Mon App & Co = 100% ✓", "/opt/my app/run --x=\"a+b\" %U", false, StartupSource::UserAutostart);
+        e.env_vars = vec![("LANG".into(), "C".into())];
+        let url = e.to_xdg_autostart_url_scheme();
+        assert!(url.starts_with("xdg-autostart://entry?name=Mon%20App%20%26%20Co%20%3D%20100%25%20%E2%9C%93&command="));
+        assert!(url.ends_with("&enabled=false"));
+        assert!(!url.contains(' '));
+
+        let parsed = from_xdg_autostart_url(&url).unwrap();
+        assert_eq!(parsed.name, e.name);
+        assert_eq!(parsed.command, e.command);
+        assert_eq!(parsed.env_vars, e.env_vars);
+        assert!(!parsed.enabled);
+        assert_eq!(parsed.source, StartupSource::UserAutostart);
+        assert!(parsed.path.is_none());
+    }
+
+    #[test]
+    fn from_xdg_autostart_url_accepts_variants() {
+        let parsed = from_xdg_autostart_url("xdg-autostart://entry/?command=app+--tray&name=App&extra=1").unwrap();
+        assert_eq!(parsed.name, "App");
+        assert_eq!(parsed.command, "app --tray");
+        assert!(parsed.enabled);
+    }
+
+    #[test]
+    fn from_xdg_autostart_url_rejects_malformed_links() {
+        for bad in [
+            "https://example.com/?name=A&command=a",
+            "xdg-autostart://entry",
+            "xdg-autostart://entry?name=A",
+            "xdg-autostart://entry?name=&command=a",
+            "xdg-autostart://entry?name=A&command=a&enabled=yes",
+            "xdg-autostart://entry?name=%zz&command=a",
+            "xdg-autostart://entry?name=A%4&command=a",
+            "xdg-autostart://entry?name=%FF&command=a",
+        ] {
+            assert!(from_xdg_autostart_url(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn url_handler_desktop_file_claims_scheme() {
+        let parsed = parse_desktop_file_from_bytes(generate_url_handler_desktop_file().as_bytes()).unwrap();
+        assert_eq!(parsed.command, "universal-startup-manager %u");
+        assert!(parsed
+            .extra
+            .iter()
+            .any(|(k, v)| k == "MimeType" && v == "x-scheme-handler/xdg-autostart;"));
+    }
+
+    #[test]
+    fn build_alpha_index_marks_first_position_per_letter() {
+        let entries = vec![
+            entry("alpha", "a", true, StartupSource::UserAutostart),
+            entry("Beta", "b", true, StartupSource::UserAutostart),
+            entry("Apple", "c", true, StartupSource::UserAutostart),
+            entry("1password", "d", true, StartupSource::UserAutostart),
+            entry("bravo", "e", true, StartupSource::UserAutostart),
+            entry("", "f", true, StartupSource::UserAutostart),
+            entry("Échelle", "g", true, StartupSource::UserAutostart),
+        ];
+        let sorted = sort_indices(&entries, (0..entries.len()).collect(), SortKey::NameAsc);
+        let index = build_alpha_index(&sorted, &entries);
+        let names: Vec<_> = sorted.iter().map(|&i| entries[i].name.as_str()).collect();
+        for &(letter, pos) in &index {
+            let key = |n: &str| match n.chars().next() {
+                Some(c) if c.is_ascii_alphabetic() => c.to_ascii_uppercase(),
+                _ => '#',
+            };
+            assert_eq!(key(names[pos]), letter);
+            assert!(names[..pos].iter().all(|n| key(n) != letter));
+        }
+        let letters: Vec<char> = index.iter().map(|(c, _)| *c).collect();
+        assert_eq!(letters.len(), 3);
+        for c in ['A', 'B', '#'] {
+            assert!(letters.contains(&c), "{c}");
+        }
+    }
+
+    #[test]
+    fn build_alpha_index_handles_subsets_and_empty_lists() {
+        let entries = vec![
+            entry("Zed", "z", true, StartupSource::UserAutostart),
+            entry("Ack", "a", true, StartupSource::UserAutostart),
+            entry("Zip", "z", true, StartupSource::UserAutostart),
+        ];
+        assert_eq!(build_alpha_index(&[], &entries), vec![]);
+        assert_eq!(build_alpha_index(&[2, 0], &entries), vec![('Z', 0)]);
+        assert_eq!(build_alpha_index(&[1, 0, 2], &entries), vec![('A', 0), ('Z', 1)]);
+        assert_eq!(build_alpha_index(&[7], &entries), vec![]);
+    }
+
+    #[test]
+    fn find_entry_by_name_ignores_case() {
+        let entries = vec![
+            entry("Nextcloud", "a", true, StartupSource::UserAutostart),
+            entry("Dropbox", "b", true, StartupSource::UserAutostart),
+            entry("dropbox", "c", true, StartupSource::SystemAutostart),
+        ];
+        assert_eq!(find_entry_by_name("Nextcloud", &entries), Some(0));
+        assert_eq!(find_entry_by_name("NEXTCLOUD", &entries), Some(0));
+        assert_eq!(find_entry_by_name("dropbox", &entries), Some(1));
+        assert_eq!(find_entry_by_name("Drop", &entries), None);
+        assert_eq!(find_entry_by_name("", &entries), None);
+    }
+
+    #[test]
+    fn find_entries_by_name_fuzzy_ranks_closer_names_first() {
+        let entries = vec![
+            entry("Network Manager Applet", "a", true, StartupSource::UserAutostart),
+            entry("nm-applet", "b", true, StartupSource::UserAutostart),
+            entry("Redshift", "c", true, StartupSource::UserAutostart),
+        ];
+        let matches = find_entries_by_name_fuzzy("nmapplet", &entries);
+        let order: Vec<usize> = matches.iter().map(|(idx, _)| *idx).collect();
+        assert_eq!(order, vec![1, 0]);
+        assert!(matches[0].1 > matches[1].1);
+        assert!(find_entries_by_name_fuzzy("xyz", &entries).is_empty());
+        assert_eq!(fuzzy_score("", "anything"), None);
+        assert_eq!(fuzzy_score("ab", "ba"), None);
+    }
+
+    #[test]
+    fn cli_reports_unknown_and_read_only_entries() {
+        let entries = vec![
+            entry("Redshift", "redshift", true, StartupSource::UserAutostart),
+            entry("Tracker", "tracker", true, StartupSource::SystemAutostart),
+        ];
+        let err = cli_find_user_entry(&entries, "rdshft").unwrap_err().to_string();
+        assert_eq!(err, "No entry named \"rdshft\"; did you mean: Redshift?");
+        let err = cli_find_user_entry(&entries, "tracker").unwrap_err().to_string();
+        assert!(err.contains("only user entries"));
+        assert_eq!(cli_find_user_entry(&entries, "REDSHIFT").unwrap().name, "Redshift");
+        assert_eq!(cli_list(&entries[..1]), "enabled\tuser\tRedshift\tredshift\n");
+    }
+
+    #[test]
+    fn run_cli_leaves_gui_arguments_alone() {
+        assert!(run_cli(&[]).is_none());
+        assert!(run_cli(&["xdg-autostart://entry?name=A&command=a".to_string()]).is_none());
+        assert!(run_cli(&["--gapplication-service".to_string()]).is_none());
+        assert!(run_cli(&["--enable".to_string()]).unwrap().is_err());
+        assert!(run_cli(&["--help".to_string()]).unwrap().unwrap().starts_with("Usage:"));
+    }
+
+    #[test]
+    fn autohotkey_script_runs_portable_command() {
+        let e = entry("Syncthing", "syncthing --no-browser", true, StartupSource::UserAutostart);
+        assert_eq!(
+            e.to_autohotkey_script().unwrap(),
+            ";Auto-generated by USM\nRun(\"syncthing --no-browser\")\n"
+        );
+    }
+
+    #[test]
+    fn autohotkey_script_skips_linux_only_binaries() {
+        for cmd in ["/usr/bin/nm-applet", "/bin/sh -c 'xset s off'", ""] {
+            let e = entry("A", cmd, true, StartupSource::UserAutostart);
+            assert_eq!(e.to_autohotkey_script(), None, "{cmd}");
+        }
+    }
+
+    #[test]
+    fn autohotkey_script_escapes_quoted_arguments() {
+        let mut e = entry("A", "app \"two words\" 'it\"s' `tick`", true, StartupSource::UserAutostart);
+        e.env_vars = vec![("LANG".into(), "C".into())];
+        assert_eq!(
+            e.to_autohotkey_script().unwrap(),
+            ";Auto-generated by USM\n\
+             EnvSet(\"LANG\", \"C\")\n\
+             Run(\"app `\"two words`\" `\"it\\`\"s`\" ``tick``\")\n"
+        );
+    }
+
+    #[test]
+    fn systemd_units_escape_command_and_schedule() {
+        let mut e = entry("Backup 100%", "/usr/bin/rsync -a \"$HOME/my docs\" %U", true, StartupSource::UserAutostart);
+        e.env_vars = vec![("LANG".into(), "C".into())];
+        let service = e.to_systemd_service_string();
+        assert!(service.contains("Description=Backup 100%%\n"));
+        assert!(service.contains("Environment=LANG=C\n"));
+        assert!(service.contains("ExecStart=/usr/bin/rsync -a \"$$HOME/my docs\"\n"));
+        let timer = e.to_systemd_timer_string("weekly");
+        assert!(timer.contains("\n[Timer]\nOnCalendar=weekly\n"));
+        assert!(timer.contains("WantedBy=timers.target\n"));
+    }
+
+    #[test]
+    fn write_systemd_user_timer_creates_and_loads_units() {
+        let dir = tempfile::tempdir().unwrap();
+        let e = entry("Sync Notes", "notes-sync --quiet", true, StartupSource::UserAutostart);
+        let timer_path = write_systemd_user_timer(dir.path(), &e, " daily ").unwrap();
+        assert_eq!(timer_path, dir.path().join("usm-sync-notes.timer"));
+        let timer = read_to_string(&timer_path).unwrap();
+        assert!(timer.lines().any(|l| l == "OnCalendar=daily"));
+        assert!(dir.path().join("usm-sync-notes.service").is_file());
+        assert!(dir.path().join("timers.target.wants/usm-sync-notes.timer").exists());
+        // Rewriting keeps the existing enable link.
+        write_systemd_user_timer(dir.path(), &e, "weekly").unwrap();
+
+        let loaded = load_systemd_timers(dir.path()).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].name, "Sync Notes");
+        assert_eq!(loaded[0].command, "notes-sync --quiet");
+        assert_eq!(loaded[0].source, StartupSource::SystemdTimer);
+        assert!(loaded[0].enabled);
+        assert!(loaded[0].extra.contains(&("OnCalendar".to_string(), "weekly".to_string())));
+
+        assert!(write_systemd_user_timer(dir.path(), &e, "").is_err());
+        assert!(write_systemd_user_timer(dir.path(), &e, "daily\nExecStart=evil").is_err());
+    }
+
+    #[test]
+    fn load_progress_increases_to_one() {
+        let user = tempfile::tempdir().unwrap();
+        let system = tempfile::tempdir().unwrap();
+        for (dir, names) in [(&user, vec!["a", "b", "c"]), (&system, vec!["d"])] {
+            for name in names {
+                fs::write(
+                    dir.path().join(format!("{name}.desktop")),
+                    format!("[Desktop Entry]\nName={name}\nExec={name}\n"),
+                )
+                .unwrap();
+            }
+        }
+        fs::write(user.path().join("notes.txt"), "ignored").unwrap();
+        let dirs = [
+            (user.path().to_path_buf(), StartupSource::UserAutostart),
+            (system.path().to_path_buf(), StartupSource::SystemAutostart),
+            (user.path().join("missing"), StartupSource::SystemAutostart),
+        ];
+        let calls = RefCell::new(Vec::new());
+        let (entries, totals) = load_autostart_dirs_with_progress(&dirs, None, &|f| calls.borrow_mut().push(f)).unwrap();
+        assert_eq!(entries.len(), 4);
+        let counts: Vec<usize> = totals.iter().map(|(_, total)| *total).collect();
+        assert_eq!(counts, vec![3, 1, 0]);
+        let calls = calls.into_inner();
+        assert_eq!(calls, vec![0.0, 0.25, 0.5, 0.75, 1.0]);
+        assert!(calls.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn load_progress_finishes_when_dirs_are_empty() {
+        let empty = tempfile::tempdir().unwrap();
+        let calls = RefCell::new(Vec::new());
+        let dirs = [(empty.path().to_path_buf(), StartupSource::UserAutostart)];
+        let (entries, _) = load_autostart_dirs_with_progress(&dirs, None, &|f| calls.borrow_mut().push(f)).unwrap();
+        assert!(entries.is_empty());
+        assert_eq!(calls.into_inner().last().copied(), Some(1.0));
+    }
+
+    #[test]
+    fn runit_run_script_round_trips() {
+        let mut e = entry("Syncthing", "syncthing serve --no-browser %U", true, StartupSource::UserAutostart);
+        e.env_vars = vec![("STNODEFAULTFOLDER".into(), "1".into())];
+        let script = e.to_runit_sv();
+        assert_eq!(
+            script,
+            "#!/bin/sh\nexec env STNODEFAULTFOLDER=1 syncthing serve --no-browser\n"
+        );
+        let parsed = from_runit_run_script(&script).unwrap();
+        assert_eq!(parsed.name, "syncthing");
+        assert_eq!(parsed.command, "syncthing serve --no-browser");
+        assert_eq!(parsed.env_vars, e.env_vars);
+        assert_eq!(parsed.source, StartupSource::Runit);
+    }
+
+    #[test]
+    fn runit_run_script_with_and_without_exec() {
+        let with_exec = "#!/bin/sh\n# log to svlogd\nexec 2>&1\nexec /usr/bin/redshift -l 52:4\n";
+        assert_eq!(from_runit_run_script(with_exec).unwrap().command, "/usr/bin/redshift -l 52:4");
+        let bare = "#!/bin/sh\n\npipewire\n";
+        let parsed = from_runit_run_script(bare).unwrap();
+        assert_eq!(parsed.command, "pipewire");
+        assert_eq!(parsed.name, "pipewire");
+        assert!(from_runit_run_script("#!/bin/sh\nexec 2>&1\n").is_err());
+        assert!(from_runit_run_script("").is_err());
+    }
+
+    #[test]
+    fn load_runit_user_services_uses_dir_names_and_down_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, down) in [("pipewire", false), ("redshift", true)] {
+            let service = dir.path().join(name);
+            fs::create_dir(&service).unwrap();
+            fs::write(service.join("run"), format!("#!/bin/sh\nexec {name}\n")).unwrap();
+            if down {
+                fs::write(service.join("down"), "").unwrap();
+            }
+        }
+        fs::create_dir(dir.path().join("no-run")).unwrap();
+        let entries = load_runit_user_services(dir.path()).unwrap();
+        let summary: Vec<_> = entries.iter().map(|e| (e.name.as_str(), e.enabled)).collect();
+        assert_eq!(summary, vec![("pipewire", true), ("redshift", false)]);
+        assert_eq!(entries[0].path.as_deref(), Some(dir.path().join("pipewire/run").as_path()));
+    }
+
+    fn with_groups(headers: &[&str]) -> StartupEntry {
+        let mut e = entry("A", "a", true, StartupSource::UserAutostart);
+        e.other_groups = headers
+            .iter()
+            .map(|h| vec![format!("[{h}]"), "Name=x".to_string()])
+            .collect();
+        e
+    }
+
+    #[test]
+    fn entry_group_headers_lists_sections_in_order() {
+        let parsed = parse_desktop_file_from_bytes(
+            b"[Desktop Entry]\nName=A\nExec=a\n\n[Desktop Action New]\nExec=a --new\n\n[X-Ubuntu-Gettext-Domain]\nX=1\n",
+        )
+        .unwrap();
+        assert_eq!(parsed.entry_group_headers(), vec!["Desktop Action New", "X-Ubuntu-Gettext-Domain"]);
+        assert!(parsed.has_desktop_actions());
+        let lint = lint_entry(&parsed);
+        assert_eq!(lint.len(), 1);
+        assert_eq!(lint[0].severity, LintSeverity::Info);
+
+        let none = entry("B", "b", true, StartupSource::UserAutostart);
+        assert!(none.entry_group_headers().is_empty());
+        assert!(!none.has_desktop_actions());
+        assert!(lint_entry(&none).is_empty());
+    }
+
+    #[test]
+    fn lint_section_names_flags_unusual_and_duplicate_sections() {
+        let e = with_groups(&["X-Extra", "Settings", "X-Extra", "Desktop Action Quit"]);
+        assert_eq!(e.entry_group_headers(), vec!["X-Extra", "Settings", "X-Extra", "Desktop Action Quit"]);
+        let messages: Vec<String> = lint_section_names(&e).into_iter().map(|w| w.message).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "section [X-Extra] appears 2 times".to_string(),
+                "unusual section [Settings]; extension sections should start with X-".to_string(),
+            ]
+        );
+        let audit = audit_all(&[e], &[]);
+        assert!(audit.iter().all(|w| w.message.starts_with("A (user): ")));
+        let severities: Vec<_> = audit.iter().map(|w| w.severity).collect();
+        assert_eq!(severities, vec![LintSeverity::Warning, LintSeverity::Warning, LintSeverity::Info]);
+    }
+
+    #[test]
+    fn strip_comments_keeps_key_values() {
+        let mut parsed = parse_desktop_file_from_bytes(
+            b"# Generated by hand\n\n[Desktop Entry]\n# Tray app\nName=A\nExec=a --tray\nX-Extra=1\n\n[X-Other]\nKey=v\n",
+        )
+        .unwrap();
+        strip_preamble(&mut parsed);
+        strip_entry_comments(&mut parsed);
+        let written = parsed.to_desktop_string();
+        assert!(written.starts_with("[Desktop Entry]\n"));
+        assert!(!written.contains("# Generated by hand"));
+        assert!(!written.contains("# Tray app"));
+        for line in ["Name=A", "Exec=a --tray", "X-Extra=1", "[X-Other]", "Key=v"] {
+            assert!(written.lines().any(|l| l == line), "{line}");
+        }
+    }
+
+    #[test]
+    fn normalize_entry_reports_changes() {
+        let mut plain = entry("A", "a", true, StartupSource::UserAutostart);
+        assert!(!normalize_entry(&mut plain, NormalizeOptions { remove_comments: true }));
+        let mut commented = plain.clone();
+        commented.entry_comments = vec!["# note".into()];
+        assert!(!normalize_entry(&mut commented, NormalizeOptions::default()));
+        assert_eq!(commented.entry_comments.len(), 1);
+        assert!(normalize_entry(&mut commented, NormalizeOptions { remove_comments: true }));
+        assert!(commented.entry_comments.is_empty());
+    }
+
+    #[test]
+    fn detect_wine_entries_matches_common_invocations() {
+        let entries = vec![
+            entry("Plain", "wine notepad.exe", true, StartupSource::UserAutostart),
+            entry(
+                "Prefix",
+                "env WINEPREFIX=\"/home/u/.wine\" wine C:\\\\windows\\\\command\\\\start.exe /Unix app.lnk",
+                true,
+                StartupSource::UserAutostart,
+            ),
+            entry("Path64", "/usr/bin/wine64 app.exe", true, StartupSource::UserAutostart),
+            entry("Boot", "wineboot -u", true, StartupSource::UserAutostart),
+            entry("EnvOpt", "env -u DISPLAY WINEDEBUG=-all wine app.exe", true, StartupSource::UserAutostart),
+            entry("Native", "winetricks --gui", true, StartupSource::UserAutostart),
+            entry("Arg", "echo wine", true, StartupSource::UserAutostart),
+            entry("Empty", "", true, StartupSource::UserAutostart),
+        ];
+        let mut parsed_prefix = parse_desktop_file_from_bytes(
+            format!("[Desktop Entry]\nName=P\nExec={}\n", entries[1].command).as_bytes(),
+        )
+        .unwrap();
+        parsed_prefix.source = StartupSource::UserAutostart;
+        assert!(!parsed_prefix.env_vars.is_empty());
+        assert!(is_wine_entry(&parsed_prefix));
+        assert_eq!(detect_wine_entries(&entries), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn wine_filter_and_sort() {
+        let entries = vec![
+            entry("B native", "b", true, StartupSource::UserAutostart),
+            entry("Z wine", "wine z.exe", true, StartupSource::UserAutostart),
+            entry("A native", "a", true, StartupSource::SystemAutostart),
+        ];
+        let wine_only = FilterState { wine_only: true, ..FilterState::default() };
+        assert_eq!(apply_filter(&entries, &wine_only), vec![1]);
+        assert_eq!(sort_indices(&entries, vec![0, 1, 2], SortKey::WineFirst), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn home_manager_module_round_trips() {
+        let mut e = entry("Say \"hi\" ${USER}", "sh -c 'echo \\\"${HOME}\\\"'", false, StartupSource::UserAutostart);
+        e.env_vars = vec![("LANG".into(), "C".into())];
+        let nix = e.to_nix_home_manager_module();
+        assert!(nix.starts_with("services.startup.say-hi-user = {\n  enable = false;\n"));
+        assert!(nix.contains("name = \"Say \\\"hi\\\" \\${USER}\";"));
+        let parsed = from_nix_home_manager_entry(&nix).unwrap();
+        assert_eq!(parsed.name, e.name);
+        assert_eq!(parsed.command, e.command);
+        assert_eq!(parsed.env_vars, e.env_vars);
+        assert!(!parsed.enabled);
+    }
+
+    #[test]
+    fn home_manager_module_quotes_numeric_attr_names() {
+        let e = entry("1Password", "1password --silent", true, StartupSource::UserAutostart);
+        let nix = e.to_nix_home_manager_module();
+        assert!(nix.starts_with("services.startup.\"1password\" = {"));
+        let parsed = from_nix_home_manager_entry(&nix).unwrap();
+        assert_eq!(parsed.name, "1Password");
+        assert!(parsed.enabled);
+    }
+
+    #[test]
+    fn from_nix_home_manager_entry_rejects_incomplete_input() {
+        assert!(from_nix_home_manager_entry("").is_err());
+        assert!(from_nix_home_manager_entry("name = \"A\";").is_err());
+        assert!(from_nix_home_manager_entry("name = \"A;\ncommand = \"a\";").is_err());
+        assert!(from_nix_home_manager_entry("enable = maybe;\nname = \"A\";\ncommand = \"a\";").is_err());
+    }
+
+    #[test]
+    fn write_desktop_entry_to_string_orders_core_keys() {
+        let mut e = entry("Foo", "foo --bar", false, StartupSource::UserAutostart);
+        e.locked = true;
+        e.comment = Some("hi".into());
+        e.extra = vec![("Categories".into(), "Utility;".into())];
+        let text = write_desktop_entry_to_string(&e);
+        assert_eq!(
+            text,
+            "[Desktop Entry]\nType=Application\nName=Foo\nComment=hi\nExec=foo --bar\nTerminal=false\n\
+             X-GNOME-Autostart-enabled=false\nHidden=true\nX-USM-Locked=true\nCategories=Utility;\n"
+        );
+        assert_eq!(text, e.to_desktop_string());
+    }
+
+    #[test]
+    fn write_desktop_entry_to_string_keeps_preamble_and_other_groups() {
+        let mut e = entry("Foo", "foo", true, StartupSource::UserAutostart);
+        e.preamble = vec!["# generated".into()];
+        e.other_groups = vec![vec!["[Desktop Action New]".into(), "Exec=foo --new".into()]];
+        let text = write_desktop_entry_to_string(&e);
+        assert!(text.starts_with("# generated\n\n[Desktop Entry]\n"));
+        assert!(text.ends_with("Hidden=false\n\n[Desktop Action New]\nExec=foo --new\n"));
+    }
+
+    #[test]
+    fn check_for_updates_from_system_compares_mtimes() {
+        let dir = tempfile::tempdir().unwrap();
+        let user_dir = dir.path().join("user");
+        let system_dir = dir.path().join("system");
+        fs::create_dir_all(&user_dir).unwrap();
+        fs::create_dir_all(&system_dir).unwrap();
+        let old = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+        let new = old + std::time::Duration::from_secs(60);
+        let touch = |path: &Path, when: SystemTime| {
+            fs::write(path, "").unwrap();
+            fs::File::options().write(true).open(path).unwrap().set_modified(when).unwrap();
+        };
+        touch(&user_dir.join("a.desktop"), old);
+        touch(&system_dir.join("a.desktop"), new);
+        touch(&user_dir.join("b.desktop"), new);
+        touch(&system_dir.join("b.desktop"), old);
+        touch(&user_dir.join("c.desktop"), old);
+
+        let with_path = |name: &str, source: StartupSource, path: PathBuf| {
+            let mut e = entry(name, name, true, source);
+            e.path = Some(path);
+            e
+        };
+        let user = vec![
+            with_path("a", StartupSource::UserAutostart, user_dir.join("a.desktop")),
+            with_path("b", StartupSource::UserAutostart, user_dir.join("b.desktop")),
+            with_path("c", StartupSource::UserAutostart, user_dir.join("c.desktop")),
+        ];
+        let system = vec![
+            with_path("b", StartupSource::SystemAutostart, system_dir.join("b.desktop")),
+            with_path("a", StartupSource::SystemAutostart, system_dir.join("a.desktop")),
+        ];
+        assert_eq!(
+            check_for_updates_from_system(&user, &system),
+            vec![UpdateAvailable {
+                user_idx: 0,
+                system_idx: 1,
+                system_mtime: new,
+            }]
+        );
+    }
+
+    #[test]
+    fn merge_system_update_keeps_user_state() {
+        let mut user = entry("App", "app --old", false, StartupSource::UserAutostart);
+        user.path = Some(PathBuf::from("/home/u/.config/autostart/app.desktop"));
+        user.ensure_entry_id();
+        let mut system = entry("App", "app --new", true, StartupSource::SystemAutostart);
+        system.path = Some(PathBuf::from("/etc/xdg/autostart/app.desktop"));
+        system.comment = Some("new".into());
+        let merged = merge_system_update(&user, &system);
+        assert_eq!(merged.command, "app --new");
+        assert_eq!(merged.source, StartupSource::UserAutostart);
+        assert_eq!(merged.path, user.path);
+        assert!(!merged.enabled);
+        assert_eq!(merged.entry_id(), user.entry_id());
+        assert_eq!(merged.comment(), Some("new"));
+    }
+
+    #[test]
+    fn normalize_command_quoting_quotes_paths_with_spaces() {
+        let mut e = entry("A", "/opt/My\\ App/run --flag %U", true, StartupSource::UserAutostart);
+        e.normalize_command_quoting();
+        assert_eq!(e.command, "\"/opt/My App/run\" --flag %U");
+        assert_eq!(e.command_argv(), vec!["/opt/My App/run", "--flag", "%U"]);
+    }
+
+    #[test]
+    fn normalize_command_quoting_is_idempotent() {
+        for cmd in ["app --tray", "\"/opt/My App/run\" %F", "sh -c \"echo \\\"hi\\\" && sleep 1\""] {
+            let mut e = entry("A", cmd, true, StartupSource::UserAutostart);
+            e.normalize_command_quoting();
+            assert_eq!(e.command, cmd);
+        }
+    }
+
+    #[test]
+    fn normalize_command_quoting_unifies_mixed_styles() {
+        let mut e = entry("A", "sh -c 'echo \"hi\"' \"two words\"", true, StartupSource::UserAutostart);
+        e.normalize_command_quoting();
+        assert_eq!(e.command, "sh -c \"echo \\\"hi\\\"\" \"two words\"");
+        let once = e.command.clone();
+        e.normalize_command_quoting();
+        assert_eq!(e.command, once);
+        assert!(normalize_entry(&mut entry("A", "a  'b'", true, StartupSource::UserAutostart), NormalizeOptions::default()));
+    }
+
+    #[test]
+    fn load_autostart_dir_bounded_takes_first_files_by_name() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["e", "c", "a", "d", "b"] {
+            let entry = entry(&name.to_uppercase(), name, true, StartupSource::SystemAutostart);
+            fs::write(dir.path().join(format!("{name}.desktop")), entry.to_desktop_string()).unwrap();
+        }
+        let bounded = load_autostart_dir_bounded(dir.path(), StartupSource::SystemAutostart, Some(2)).unwrap();
+        let names: Vec<&str> = bounded.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["A", "B"]);
+        let all = load_autostart_dir_bounded(dir.path(), StartupSource::SystemAutostart, None).unwrap();
+        assert_eq!(all.len(), 5);
+        assert_eq!(bounded_desktop_files(dir.path(), Some(2)).unwrap().1, 5);
+    }
+
+    #[test]
+    fn format_truncation_notice_only_when_cut() {
+        assert_eq!(
+            format_truncation_notice(50, 120, "system").as_deref(),
+            Some("Showing first 50 of 120 system entries")
+        );
+        assert_eq!(format_truncation_notice(50, 50, "system"), None);
+        assert_eq!(with_truncation_notice("3 entries".into(), None, &[(StartupSource::UserAutostart, 9)]), "3 entries");
+        let totals = [(StartupSource::UserAutostart, 5), (StartupSource::SystemAutostart, 2), (StartupSource::SystemAutostart, 4)];
+        assert_eq!(
+            with_truncation_notice("9 entries".into(), Some(3), &totals),
+            "9 entries · Showing first 3 of 5 user entries · Showing first 3 of 4 system entries"
+        );
+    }
+
+    #[test]
+    fn renamed_entry_path_follows_our_slug_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let ours = dir.path().join("my-app.desktop");
+        assert_eq!(renamed_entry_path(&ours, "My App", "Other App"), dir.path().join("other-app.desktop"));
+        assert_eq!(renamed_entry_path(&ours, "My App", "my_app"), ours);
+        let overriding = dir.path().join("org.example.App.desktop");
+        assert_eq!(renamed_entry_path(&overriding, "My App", "Other App"), overriding);
+    }
+
+    #[test]
+    fn point_entry_at_renamed_file_updates_only_the_edited_entry() {
+        let dir = tempdir().unwrap();
+        for slug in ["app", "app-2"] {
+            fs::write(
+                dir.path().join(format!("{slug}.desktop")),
+                "[Desktop Entry]\nType=Application\nName=App\nExec=app\n",
+            )
+            .unwrap();
+        }
+        let mut entries = load_autostart_dir(dir.path(), StartupSource::UserAutostart).unwrap();
+        let idx = entries.iter().position(|e| e.path.as_deref() == Some(&*dir.path().join("app-2.desktop"))).unwrap();
+        let old_path = entries[idx].path.clone().unwrap();
+        let new_path = dir.path().join("renamed.desktop");
+
+        assert!(point_entry_at_renamed_file(&mut entries, idx, Some(&old_path), "Renamed", new_path.clone()));
+        assert_eq!(entries[idx].path.as_ref(), Some(&new_path));
+        assert_eq!(entries[idx].name, "Renamed");
+        let other = &entries[1 - idx];
+        assert_eq!((other.name.as_str(), other.path.clone()), ("App", Some(dir.path().join("app.desktop"))));
+
+        // A reload moved another entry into `idx`: leave it alone.
+        assert!(!point_entry_at_renamed_file(&mut entries, 1 - idx, Some(&old_path), "Renamed", new_path));
+        assert_eq!(entries[1 - idx].name, "App");
+        assert!(!point_entry_at_renamed_file(&mut entries, 5, Some(&old_path), "Renamed", dir.path().join("x.desktop")));
+    }
+
+    #[test]
+    fn edit_user_entry_refuses_to_rename_onto_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.desktop");
+        fs::write(&a, "").unwrap();
+        fs::write(dir.path().join("b.desktop"), "").unwrap();
+        let e = entry("A", "a", true, StartupSource::UserAutostart);
+        let err = edit_user_entry(&e, "B", "a", Some(&a)).unwrap_err();
+        assert!(err.to_string().contains("already uses"), "{err}");
+    }
+
+    #[test]
+    fn try_launch_dry_run_accepts_executable() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempdir().unwrap();
+        let bin = dir.path().join("usm-dry-run");
+        fs::write(&bin, "#!/bin/sh\nexit 0\n").unwrap();
+        fs::set_permissions(&bin, fs::Permissions::from_mode(0o755)).unwrap();
+        let mut e = entry("Ok", &format!("{} --tray %U", bin.display()), true, StartupSource::UserAutostart);
+        e.env_vars = vec![("LANG".into(), "C".into())];
+        let result = e.try_launch_dry_run();
+        assert!(result.is_ok(), "{result:?}");
+        assert!(result.binary_found && result.binary_executable);
+        assert_eq!(result.warnings.len(), 1, "{result:?}");
+        assert!(result.warnings[0].starts_with("%U"));
+    }
+
+    #[test]
+    fn try_launch_dry_run_flags_non_executable_file() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        fs::write(&file, "text").unwrap();
+        let result = entry("Txt", &file.display().to_string(), true, StartupSource::UserAutostart).try_launch_dry_run();
+        assert!(result.binary_found);
+        assert!(!result.binary_executable);
+        assert!(!result.is_ok());
+    }
+
+    #[test]
+    fn try_launch_dry_run_flags_missing_binary_and_bad_args() {
+        let result = entry(
+            "Missing",
+            "env 1BAD=x /nonexistent/usm-binary --open=%u 100%",
+            true,
+            StartupSource::UserAutostart,
+        )
+        .try_launch_dry_run();
+        assert!(!result.binary_found);
+        assert!(!result.binary_executable);
+        assert!(!result.env_vars_valid);
+        assert!(!result.args_valid);
+        assert_eq!(result.warnings.len(), 3, "{result:?}");
+        assert!(!entry("Empty", "", true, StartupSource::UserAutostart).try_launch_dry_run().args_valid);
+    }
+
+    #[test]
+    fn group_entries_by_binary_dir_uses_binary_parent() {
+        let entries = vec![
+            entry("A", "/usr/bin/a --tray", true, StartupSource::SystemAutostart),
+            entry("B", "/usr/local/bin/b", true, StartupSource::UserAutostart),
+            entry("C", "\"/home/u/.local/bin/my app\" %U", true, StartupSource::UserAutostart),
+            entry("D", "/usr/bin/d", true, StartupSource::UserAutostart),
+            entry("E", "definitely-not-installed-usm", true, StartupSource::UserAutostart),
+            entry("F", "", true, StartupSource::UserAutostart),
+        ];
+        let groups = group_entries_by_binary_dir(&entries);
+        let expected: Vec<(&str, Vec<usize>)> = vec![
+            ("/usr/bin", vec![0, 3]),
+            ("/usr/local/bin", vec![1]),
+            ("/home/u/.local/bin", vec![2]),
+            ("Not found in PATH", vec![4]),
+            ("No command", vec![5]),
+        ];
+        let actual: Vec<(&str, Vec<usize>)> = groups.iter().map(|(k, v)| (k.as_str(), v.clone())).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn order_by_binary_dir_keeps_groups_contiguous() {
+        let entries = vec![
+            entry("A", "/usr/bin/a", true, StartupSource::SystemAutostart),
+            entry("B", "/usr/local/bin/b", true, StartupSource::UserAutostart),
+            entry("C", "/usr/bin/c", true, StartupSource::UserAutostart),
+            entry("D", "/opt/d/run", true, StartupSource::UserAutostart),
+        ];
+        let (ordered, headers) = order_by_binary_dir(&entries, vec![1, 0, 2]);
+        assert_eq!(ordered, vec![1, 0, 2]);
+        assert_eq!(headers.get(&0).map(String::as_str), Some("/usr/local/bin (1 entry)"));
+        assert_eq!(headers.get(&1).map(String::as_str), Some("/usr/bin (2 entries)"));
+        assert_eq!(headers.len(), 2);
+        let (ordered, _) = order_by_binary_dir(&entries, vec![0, 1, 2, 3]);
+        assert_eq!(ordered, vec![0, 2, 1, 3]);
+    }
+
+    #[test]
+    fn order_by_source_hides_groups_emptied_by_filter() {
+        let entries = vec![
+            entry("A", "a", true, StartupSource::SystemAutostart),
+            entry("B", "b", false, StartupSource::UserAutostart),
+            entry("C", "c", true, StartupSource::UserAutostart),
+            entry("D", "d", false, StartupSource::Runit),
+        ];
+        let filter = FilterState {
+            show_disabled: false,
+            ..FilterState::default()
+        };
+        let visible = apply_filter(&entries, &filter);
+        assert_eq!(count_visible_in_group(&StartupSource::UserAutostart, &entries, &visible), 1);
+        assert_eq!(count_visible_in_group(&StartupSource::Runit, &entries, &visible), 0);
+        let (ordered, headers) = order_by_source(&entries, visible, true);
+        assert_eq!(ordered, vec![0, 2]);
+        assert_eq!(headers.get(&0).map(String::as_str), Some("System autostart (/etc/xdg/autostart/) (1 entry)"));
+        assert_eq!(headers.get(&1).map(String::as_str), Some("User autostart (~/.config/autostart/) (1 entry)"));
+        assert_eq!(headers.len(), 2);
+    }
+
+    #[test]
+    fn order_by_source_shows_empty_groups_after_the_rows() {
+        let entries = vec![
+            entry("A", "a", false, StartupSource::Runit),
+            entry("B", "b", true, StartupSource::UserAutostart),
+            entry("C", "c", true, StartupSource::UserAutostart),
+        ];
+        let visible = vec![2, 1];
+        let (hidden_rows, hidden) = order_by_source(&entries, visible.clone(), true);
+        let (shown_rows, shown) = order_by_source(&entries, visible, false);
+        assert_eq!(hidden_rows, shown_rows);
+        assert_eq!(shown_rows, vec![2, 1]);
+        assert_eq!(hidden.len(), 1);
+        assert_eq!(shown.len(), 2);
+        assert_eq!(shown.get(&0), hidden.get(&0));
+        assert_eq!(shown.get(&2).map(String::as_str), Some("runit user service (~/.config/service/) (0 entries)"));
+        let (rows, headers) = order_by_source(&entries, Vec::new(), false);
+        assert!(rows.is_empty());
+        assert_eq!(headers.len(), 2);
+        assert!(order_by_source(&entries, Vec::new(), true).1.is_empty());
+    }
+
+    #[test]
+    fn find_malformed_locales_flags_empty_redundant_and_untranslated() {
+        let mut e = entry("Clock", "clock", true, StartupSource::UserAutostart);
+        e.localized_names = vec![
+            (String::new(), "Uhr".into()),
+            ("en_GB".into(), "Clock".into()),
+            ("en_US".into(), "Wall Clock".into()),
+            ("de".into(), "Uhr".into()),
+            ("fr".into(), "Clock".into()),
+            ("nl".into(), "Wall Clock".into()),
+        ];
+        let found = find_malformed_locales(&e);
+        let tags: Vec<&str> = found.iter().map(|(tag, _)| tag.as_str()).collect();
+        assert_eq!(tags, vec!["", "en_GB", "fr", "nl"]);
+        assert_eq!(found[0].1.severity, LintSeverity::Warning);
+        assert!(found[1].1.message.contains("repeats Name="), "{:?}", found[1]);
+        assert!(found[2].1.message.contains("untranslated"), "{:?}", found[2]);
+        assert_eq!(found[3].1.severity, LintSeverity::Info);
+        let lints = lint_entry(&e);
+        assert_eq!(lints.len(), 4, "{lints:?}");
+        assert!(!e.validate().is_valid());
+    }
+
+    #[test]
+    fn diff_entry_sets_reports_added_and_removed() {
+        let p = |s: &str| PathBuf::from(s);
+        let old = vec![p("/etc/xdg/autostart/a.desktop"), p("/etc/xdg/autostart/b.desktop")];
+        let new = vec![
+            p("/etc/xdg/autostart/c.desktop"),
+            p("/etc/xdg/autostart/a.desktop"),
+            p("/etc/xdg/autostart/d.desktop"),
+        ];
+        let (added, removed) = diff_entry_sets(&old, &new);
+        assert_eq!(added, vec![p("/etc/xdg/autostart/c.desktop"), p("/etc/xdg/autostart/d.desktop")]);
+        assert_eq!(removed, vec![p("/etc/xdg/autostart/b.desktop")]);
+        assert_eq!(diff_entry_sets(&old, &old), (Vec::new(), Vec::new()));
+    }
+
+    #[test]
+    fn track_system_entry_paths_reports_only_later_additions() {
+        let with_path = |name: &str, source: StartupSource| {
+            let mut e = entry(name, name, true, source);
+            e.path = Some(PathBuf::from(format!("/etc/xdg/autostart/{name}.desktop")));
+            e
+        };
+        let mut known = HashSet::new();
+        let mut entries = vec![with_path("a", StartupSource::SystemAutostart)];
+        assert!(track_system_entry_paths(&mut known, &entries).is_empty());
+        assert_eq!(known.len(), 1);
+        entries.push(with_path("b", StartupSource::SystemAutostart));
+        entries.push(with_path("c", StartupSource::SystemAutostart));
+        entries.push(with_path("mine", StartupSource::UserAutostart));
+        let added = track_system_entry_paths(&mut known, &entries);
+        assert_eq!(added.len(), 2);
+        assert_eq!(new_system_entries_message(added.len()), "2 new system startup entries added");
+        assert_eq!(new_system_entries_message(1), "1 new system startup entry added");
+        assert!(track_system_entry_paths(&mut known, &entries).is_empty());
+        assert_eq!(known.len(), 3);
+    }
+
+    #[test]
+    fn undo_entries_revert_and_redo_file_changes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("app.desktop");
+        let mut original = entry("App", "app", true, StartupSource::UserAutostart);
+        original.path = Some(path.clone());
+        write_desktop_entry(&original, &path).unwrap();
+        let read = || parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+
+        let mut disabled = original.clone();
+        disabled.enabled = false;
+        write_desktop_entry(&disabled, &path).unwrap();
+        let inverse = UndoEntry::Toggled { path: path.clone(), was_enabled: true }.revert().unwrap();
+        assert!(read().enabled);
+        inverse.revert().unwrap();
+        assert!(!read().enabled);
+
+        let renamed = dir.path().join("tool.desktop");
+        write_desktop_entry(&entry("Tool", "tool", false, StartupSource::UserAutostart), &renamed).unwrap();
+        fs::remove_file(&path).unwrap();
+        let edited = UndoEntry::Edited { path: renamed.clone(), original: original.clone() };
+        assert_eq!(edited.paths(), vec![renamed.as_path(), path.as_path()]);
+        let inverse = edited.revert().unwrap();
+        assert!(!renamed.exists());
+        assert_eq!((read().name, read().command), ("App".to_string(), "app".to_string()));
+        inverse.revert().unwrap();
+        assert!(!path.exists());
+        assert_eq!(parse_desktop_file(&renamed, StartupSource::UserAutostart).unwrap().name, "Tool");
+
+        let inverse = UndoEntry::Added { path: renamed.clone() }.revert().unwrap();
+        assert!(!renamed.exists());
+        assert_eq!(inverse.label(), "delete");
+        let again = inverse.revert().unwrap();
+        assert!(renamed.exists());
+        assert!(matches!(again, UndoEntry::Added { .. }));
+        assert!(UndoEntry::Deleted { path: renamed.clone(), entry: original }.revert().is_err());
+    }
+
+    #[test]
+    fn importable_file_needs_name_and_exec() {
+        let dir = tempdir().unwrap();
+        let good = dir.path().join("good.desktop");
+        fs::write(&good, "[Desktop Entry]\nType=Application\nName=Good\nExec=good --tray\n").unwrap();
+        let entry = read_importable_file(&good).unwrap();
+        assert_eq!((entry.name.as_str(), entry.command.as_str()), ("Good", "good --tray"));
+        let no_exec = dir.path().join("no-exec.desktop");
+        fs::write(&no_exec, "[Desktop Entry]\nType=Application\nName=Nothing\n").unwrap();
+        assert!(read_importable_file(&no_exec).is_err());
+        let no_name = dir.path().join("no-name.desktop");
+        fs::write(&no_name, "[Desktop Entry]\nName=\nExec=x\n").unwrap();
+        assert!(read_importable_file(&no_name).is_err());
+        let handler = dir.path().join("handler.json");
+        fs::write(&handler, r#"{"id":"sync","name":"Sync","commandLine":"sync --tray","delaySeconds":3}"#).unwrap();
+        let entry = read_importable_file(&handler).unwrap();
+        assert_eq!((entry.name.as_str(), entry.command.as_str(), entry.delay_seconds()), ("Sync", "sync --tray", 3));
+        let nix = dir.path().join("sync.nix");
+        fs::write(&nix, entry.to_nix_home_manager_module()).unwrap();
+        let back = read_importable_file(&nix).unwrap();
+        assert_eq!((back.name, back.command, back.enabled), (entry.name, entry.command, entry.enabled));
+
+        assert_eq!(free_desktop_file_path(dir.path(), "good"), dir.path().join("good-2.desktop"));
+        fs::write(dir.path().join("good-2.desktop"), "").unwrap();
+        assert_eq!(free_desktop_file_path(dir.path(), "good"), dir.path().join("good-3.desktop"));
+    }
+
+    #[test]
+    fn icon_is_parsed_and_kept_on_edit() {
+        let mut e = parse_desktop_file_from_bytes(b"[Desktop Entry]\nName=Tray\nIcon= tray-app \nExec=tray\n").unwrap();
+        assert_eq!(e.icon(), Some("tray-app"));
+        assert!(e.extra.is_empty());
+        let edited = apply_edit(&e, "Tray 2", "tray --hidden");
+        assert_eq!(edited.icon(), Some("tray-app"));
+        assert!(write_desktop_entry_to_string(&edited).contains("\nIcon=tray-app\n"));
+        e.icon = Some("  ".into());
+        assert_eq!(e.icon(), None);
+        e.icon = None;
+        assert_eq!(e.icon(), None);
+        assert!(!write_desktop_entry_to_string(&e).contains("Icon="));
+    }
+
+    #[test]
+    fn try_exec_is_parsed_and_written_before_exec() {
+        let e = parse_desktop_file_from_bytes(b"[Desktop Entry]\nName=Tray\nExec=tray --hidden\nTryExec=tray\n").unwrap();
+        assert_eq!(e.try_exec(), Some("tray"));
+        assert!(e.extra.is_empty());
+        let text = e.to_desktop_string();
+        assert!(text.contains("\nTryExec=tray\nExec=tray --hidden\n"));
+        assert_eq!(parse_desktop_file_from_bytes(text.as_bytes()).unwrap().try_exec, e.try_exec);
+    }
+
+    #[test]
+    fn terminal_key_round_trips() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("script.desktop");
+        fs::write(&path, "[Desktop Entry]\nType=Application\nName=Script\nExec=./run.sh\nTerminal=true\n").unwrap();
+        let mut e = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+        assert!(e.terminal);
+        assert!(e.extra.iter().all(|(k, _)| k != TERMINAL_KEY));
+        write_desktop_entry(&e, &path).unwrap();
+        let back = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+        assert!(back.terminal);
+        assert_eq!(fs::read_to_string(&path).unwrap().matches("Terminal=").count(), 1);
+        assert!(fs::read_to_string(&path).unwrap().contains("\nTerminal=true\n"));
+
+        e.terminal = false;
+        assert!(write_desktop_entry_to_string(&e).contains("\nTerminal=false\n"));
+        let plain = entry("Plain", "plain", true, StartupSource::UserAutostart);
+        assert!(!plain.terminal);
+        assert!(plain.to_desktop_string().contains("\nTerminal=false\n"));
+    }
+
+    #[test]
+    fn show_in_lists_edit_and_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("panel.desktop");
+        fs::write(&path, "[Desktop Entry]\nType=Application\nName=Panel\nExec=panel\nOnlyShowIn=GNOME;XFCE;\n").unwrap();
+        let mut e = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+        assert_eq!(e.desktop_list(ONLY_SHOW_IN_KEY), vec!["GNOME", "XFCE"]);
+        assert!(e.desktop_list(NOT_SHOW_IN_KEY).is_empty());
+
+        e.add_desktop_list_value(ONLY_SHOW_IN_KEY, "MATE").unwrap();
+        e.add_desktop_list_value(ONLY_SHOW_IN_KEY, "GNOME").unwrap();
+        assert!(e.add_desktop_list_value(ONLY_SHOW_IN_KEY, "Two Words").is_err());
+        assert!(e.add_desktop_list_value(NOT_SHOW_IN_KEY, "A;B").is_err());
+        e.add_desktop_list_value(NOT_SHOW_IN_KEY, "KDE").unwrap();
+        assert!(e.remove_desktop_list_value(ONLY_SHOW_IN_KEY, "XFCE"));
+        assert!(!e.remove_desktop_list_value(ONLY_SHOW_IN_KEY, "XFCE"));
+        write_desktop_entry(&e, &path).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.contains("\nOnlyShowIn=GNOME;MATE;\n"), "{text}");
+        assert!(text.contains("\nNotShowIn=KDE;\n"), "{text}");
+
+        let mut back = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+        assert!(!back.extra.iter().any(|(k, _)| k == ONLY_SHOW_IN_KEY || k == NOT_SHOW_IN_KEY));
+        assert!(back.remove_desktop_list_value(NOT_SHOW_IN_KEY, "KDE"));
+        assert!(back.not_show_in.is_empty());
+        assert!(!back.to_desktop_string().contains(NOT_SHOW_IN_KEY));
+    }
 
-    content.append(&name_label);
-    content.append(&name_entry);
-    content.append(&cmd_label);
-    content.append(&cmd_entry);
+    #[test]
+    fn autostart_delay_round_trips() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("late.desktop");
+        fs::write(&path, "[Desktop Entry]\nType=Application\nName=Late\nExec=late\nX-GNOME-Autostart-Delay=30\n").unwrap();
+        let mut e = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+        assert_eq!(e.delay_seconds(), 30);
 
-    dialog.connect_response({
-        let state = state.clone();
-        let original_path = entry.path.clone();
-        move |dlg, resp| {
-            if resp == ResponseType::Ok {
-                let new_name = name_entry.text().to_string();
-                let new_cmd = cmd_entry.text().to_string();
-                if new_name.trim().is_empty() || new_cmd.trim().is_empty() {
-                    state
-                        .status_bar
-                        .set_text("Name and command cannot be empty");
-                    dlg.close();
-                    return;
-                }
-                let res = edit_user_entry(&entry, &new_name, &new_cmd, original_path.as_ref());
-                if let Err(err) = res {
-                    state
-                        .status_bar
-                        .set_text(&format!("Failed to save: {err:#}"));
-                } else if let Err(err) = refresh_entries(&state) {
-                    state
-                        .status_bar
-                        .set_text(&format!("Failed to refresh after edit: {err:#}"));
-                } else {
-                    state.status_bar.set_text("Saved entry");
-                }
-            }
-            dlg.close();
-        }
-    });
+        e.set_delay_seconds(90);
+        write_desktop_entry(&e, &path).unwrap();
+        let mut back = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+        assert_eq!(back.delay_seconds(), 90);
 
-    dialog.show();
-    Ok(())
-}
+        back.set_delay_seconds(0);
+        write_desktop_entry(&back, &path).unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains(AUTOSTART_DELAY_KEY));
+        assert_eq!(parse_desktop_file(&path, StartupSource::UserAutostart).unwrap().delay_seconds(), 0);
 
-fn show_filter_dialog(state: &AppState) -> Result<()> {
-    let parent = state
-        .list_box
-        .root()
-        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
-    let dialog = Dialog::with_buttons(
-        Some("Filter entries"),
-        parent.as_ref(),
-        gtk4::DialogFlags::MODAL,
-        &[("Cancel", ResponseType::Cancel), ("Apply", ResponseType::Ok)],
-    );
+        // An unparsable value is kept as written until the delay is set.
+        fs::write(&path, "[Desktop Entry]\nType=Application\nName=Late\nExec=late\nX-GNOME-Autostart-Delay=soon\n").unwrap();
+        let mut odd = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+        assert_eq!((odd.delay_seconds, odd.delay_seconds()), (None, 0));
+        assert!(odd.to_desktop_string().contains("X-GNOME-Autostart-Delay=soon\n"));
+        odd.set_delay_seconds(5);
+        assert!(odd.to_desktop_string().ends_with("Hidden=false\nX-GNOME-Autostart-Delay=5\n"));
+    }
 
-    let content = dialog.content_area();
-    content.set_spacing(8);
-    let current = *state.filter.borrow();
-    let enabled_cb = CheckButton::with_label("Show enabled");
-    enabled_cb.set_active(current.show_enabled);
-    let disabled_cb = CheckButton::with_label("Show disabled");
-    disabled_cb.set_active(current.show_disabled);
-    let user_cb = CheckButton::with_label("Show user entries");
-    user_cb.set_active(current.show_user);
-    let system_cb = CheckButton::with_label("Show system entries");
-    system_cb.set_active(current.show_system);
+    #[test]
+    fn comment_and_generic_name_survive_edit_and_save() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("browser.desktop");
+        fs::write(
+            &path,
+            "[Desktop Entry]\nType=Application\nName=Browser\nGenericName=Web Browser\nComment=Opens the web\nExec=browser\n",
+        )
+        .unwrap();
+        let e = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
+        assert_eq!((e.comment(), e.generic_name()), (Some("Opens the web"), Some("Web Browser")));
 
-    content.append(&enabled_cb);
-    content.append(&disabled_cb);
-    content.append(&user_cb);
-    content.append(&system_cb);
+        let renamed = renamed_entry_path(&path, &e.name, "Browser");
+        write_desktop_entry(&apply_edit(&e, "Browser", "browser --new-window"), &renamed).unwrap();
+        let back = parse_desktop_file(&renamed, StartupSource::UserAutostart).unwrap();
+        assert_eq!(back.command, "browser --new-window");
+        assert_eq!((back.comment(), back.generic_name()), (Some("Opens the web"), Some("Web Browser")));
 
-    dialog.connect_response({
-        let state = state.clone();
-        move |dlg, resp| {
-            if resp == ResponseType::Ok {
-                let mut filter = state.filter.borrow_mut();
-                filter.show_enabled = enabled_cb.is_active();
-                filter.show_disabled = disabled_cb.is_active();
-                filter.show_user = user_cb.is_active();
-                filter.show_system = system_cb.is_active();
-                drop(filter);
-                rebuild_list(&state);
-                update_detail(&state);
-                state.status_bar.set_text("Filter applied");
-            }
-            dlg.close();
+        let mut changed = back.clone();
+        changed.comment = Some(" Browses ".into());
+        changed.generic_name = None;
+        write_desktop_entry(&changed, &renamed).unwrap();
+        let back = parse_desktop_file(&renamed, StartupSource::UserAutostart).unwrap();
+        assert_eq!((back.comment(), back.generic_name()), (Some("Browses"), None));
+        assert!(!back.extra.iter().any(|(k, _)| k == "Comment" || k == "GenericName"));
+    }
+
+    #[test]
+    fn undo_stack_caps_and_drops_redo_on_new_change() {
+        let toggle = |n: usize| UndoEntry::Toggled { path: PathBuf::from(format!("{n}.desktop")), was_enabled: true };
+        let mut stack = UndoStack::default();
+        for n in 0..UNDO_STACK_LEN + 5 {
+            stack.push(toggle(n));
         }
-    });
+        assert_eq!(stack.done.len(), UNDO_STACK_LEN);
+        assert_eq!(stack.pop_undo().unwrap().paths(), vec![Path::new("24.desktop")]);
+        stack.push_redo(toggle(99));
+        assert_eq!(stack.next_undo().unwrap().paths(), vec![Path::new("23.desktop")]);
+        stack.push(toggle(100));
+        assert!(stack.pop_redo().is_none());
+    }
 
-    dialog.show();
-    Ok(())
-}
+    #[test]
+    fn with_rollback_restores_original_after_failed_write() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("app.desktop");
+        let backup = dir.path().join("backup").join("app.desktop.bak");
+        fs::write(&path, "original").unwrap();
+        let err = with_rollback(&path, &backup, || {
+            fs::write(&path, "half-writ").unwrap();
+            bail!("disk full")
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("disk full"), "{err}");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original");
+        assert!(!backup.exists());
 
-fn show_sort_dialog(state: &AppState) -> Result<()> {
-    let parent = state
-        .list_box
-        .root()
-        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
-    let dialog = Dialog::with_buttons(
-        Some("Sort entries"),
-        parent.as_ref(),
-        gtk4::DialogFlags::MODAL,
-        &[("Cancel", ResponseType::Cancel), ("Apply", ResponseType::Ok)],
-    );
+        let e = entry("App", "app", true, StartupSource::UserAutostart);
+        write_atomic_with_rollback(&e, &path, &backup).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), e.to_desktop_string());
+        assert!(!backup.exists());
+    }
 
-    let content = dialog.content_area();
-    content.set_spacing(8);
-    let current = state.sort.get();
+    #[test]
+    fn write_atomic_with_rollback_keeps_file_in_read_only_dir() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempdir().unwrap();
+        let locked_dir = dir.path().join("autostart");
+        fs::create_dir(&locked_dir).unwrap();
+        let path = locked_dir.join("app.desktop");
+        fs::write(&path, "original").unwrap();
+        fs::set_permissions(&locked_dir, fs::Permissions::from_mode(0o555)).unwrap();
+        // Root ignores directory permissions, so there is no failure to roll back from.
+        if fs::File::create(locked_dir.join("probe")).is_ok() {
+            return;
+        }
+        let backup = dir.path().join("app.desktop.bak");
+        let e = entry("App", "app", false, StartupSource::UserAutostart);
+        assert!(write_atomic_with_rollback(&e, &path, &backup).is_err());
+        fs::set_permissions(&locked_dir, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original");
+        assert!(!backup.exists());
+    }
 
-    let name_asc = CheckButton::with_label("Name (A→Z)");
-    name_asc.set_group(None::<&CheckButton>);
-    name_asc.set_active(matches!(current, SortKey::NameAsc));
+    #[test]
+    fn comment_out_entry_hides_the_group_and_round_trips() {
+        let mut e = entry("Tray", "tray --quiet", false, StartupSource::UserAutostart);
+        e.path = Some(PathBuf::from("/home/u/.config/autostart/tray.desktop"));
+        e.preamble = vec!["# keep me".into()];
+        e.entry_comments = vec!["# inside".into()];
+        e.comment = Some("Tray icon".into());
+        e.other_groups = vec![vec!["[Desktop Action Quit]".into(), "Exec=tray --quit".into()]];
+        let original = e.to_desktop_string();
 
-    let name_desc = CheckButton::with_label("Name (Z→A)");
-    name_desc.set_group(Some(&name_asc));
-    name_desc.set_active(matches!(current, SortKey::NameDesc));
+        e.comment_out_entry();
+        assert!(e.is_commented_out());
+        let text = e.to_desktop_string();
+        assert!(text.starts_with(COMMENTED_OUT_MARKER), "{text}");
+        assert!(text.lines().skip(1).all(|l| l.is_empty() || l.starts_with('#')), "{text}");
+        assert!(text.contains("#[Desktop Entry]\n"), "{text}");
+        e.comment_out_entry();
+        assert_eq!(e.to_desktop_string(), text);
 
-    let status = CheckButton::with_label("Status (enabled first)");
-    status.set_group(Some(&name_asc));
-    status.set_active(matches!(current, SortKey::StatusEnabledFirst));
+        let loaded = parse_desktop_file_from_bytes(text.as_bytes()).unwrap();
+        assert!(loaded.is_commented_out());
+        assert_eq!(loaded.name, "Tray");
+        assert_eq!(loaded.status_label(), "commented out");
+        assert_eq!(loaded.to_desktop_string(), text);
 
-    let source_user = CheckButton::with_label("Source (user first)");
-    source_user.set_group(Some(&name_asc));
-    source_user.set_active(matches!(current, SortKey::SourceUserFirst));
+        e.uncomment_entry();
+        assert!(!e.is_commented_out());
+        let reread = parse_desktop_file_from_bytes(original.as_bytes()).unwrap();
+        assert_eq!(e.to_desktop_string(), reread.to_desktop_string());
+        assert_eq!(e.source, StartupSource::UserAutostart);
+        assert!(e.path.is_some());
+        assert_eq!(e.status_label(), "disabled");
+    }
 
-    let source_system = CheckButton::with_label("Source (system first)");
-    source_system.set_group(Some(&name_asc));
-    source_system.set_active(matches!(current, SortKey::SourceSystemFirst));
+    #[test]
+    fn find_orphaned_xdg_data_apps_in_needs_autostart_flag_and_no_user_file() {
+        let apps = tempdir().unwrap();
+        let autostart = tempdir().unwrap();
+        let app = |name: &str, extra: &str| {
+            fs::write(
+                apps.path().join(name),
+                format!("[Desktop Entry]\nType=Application\nName={name}\nExec=app\n{extra}"),
+            )
+            .unwrap();
+        };
+        app("orphan.desktop", "X-GNOME-Autostart-enabled = true\n");
+        app("registered.desktop", "X-GNOME-Autostart-enabled=true\n");
+        app("plain.desktop", "");
+        app("off.desktop", "X-GNOME-Autostart-enabled=false\n");
+        fs::write(apps.path().join("notes.txt"), "X-GNOME-Autostart-enabled=true\n").unwrap();
+        fs::write(autostart.path().join("registered.desktop"), "").unwrap();
+        assert_eq!(
+            find_orphaned_xdg_data_apps_in(apps.path(), autostart.path()).unwrap(),
+            vec![apps.path().join("orphan.desktop")]
+        );
+        let missing = apps.path().join("missing");
+        assert!(find_orphaned_xdg_data_apps_in(&missing, autostart.path()).unwrap().is_empty());
+    }
 
-    content.append(&name_asc);
-    content.append(&name_desc);
-    content.append(&status);
-    content.append(&source_user);
-    content.append(&source_system);
+    #[test]
+    fn write_desktop_entry_batch_writes_everything() {
+        let dir = tempdir().unwrap();
+        let a = entry("A", "a", true, StartupSource::UserAutostart);
+        let b = entry("B", "b", false, StartupSource::UserAutostart);
+        let batch = vec![(a.clone(), dir.path().join("a.desktop")), (b.clone(), dir.path().join("b.desktop"))];
+        let written = write_desktop_entry_batch(&batch).unwrap();
+        assert_eq!(written, vec![dir.path().join("a.desktop"), dir.path().join("b.desktop")]);
+        assert_eq!(fs::read_to_string(&written[0]).unwrap(), a.to_desktop_string());
+        assert_eq!(fs::read_to_string(&written[1]).unwrap(), b.to_desktop_string());
+        assert!(write_desktop_entry_batch(&[]).unwrap().is_empty());
+    }
 
-    dialog.connect_response({
-        let state = state.clone();
-        move |dlg, resp| {
-            if resp == ResponseType::Ok {
-                let new_sort = if name_asc.is_active() {
-                    SortKey::NameAsc
-                } else if name_desc.is_active() {
-                    SortKey::NameDesc
-                } else if status.is_active() {
-                    SortKey::StatusEnabledFirst
-                } else if source_user.is_active() {
-                    SortKey::SourceUserFirst
-                } else if source_system.is_active() {
-                    SortKey::SourceSystemFirst
-                } else {
-                    state.sort.get()
-                };
-                state.sort.set(new_sort);
-                rebuild_list(&state);
-                state.status_bar.set_text("Sort applied");
-            }
-            dlg.close();
-        }
-    });
+    #[test]
+    fn write_desktop_entry_batch_undoes_earlier_renames_on_failure() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.desktop"), "old").unwrap();
+        // Renaming a file over a non-empty directory fails.
+        let blocker = dir.path().join("c.desktop");
+        fs::create_dir(&blocker).unwrap();
+        fs::write(blocker.join("keep"), "").unwrap();
+        let batch = vec![
+            (entry("A", "a", true, StartupSource::UserAutostart), dir.path().join("a.desktop")),
+            (entry("B", "b", true, StartupSource::UserAutostart), dir.path().join("b.desktop")),
+            (entry("C", "c", true, StartupSource::UserAutostart), blocker.clone()),
+        ];
+        let err = write_desktop_entry_batch(&batch).unwrap_err();
+        assert!(format!("{err:#}").contains("c.desktop"), "{err:#}");
+        assert_eq!(fs::read_to_string(dir.path().join("a.desktop")).unwrap(), "old");
+        assert!(!dir.path().join("b.desktop").exists());
+        let mut left: Vec<_> = fs::read_dir(dir.path()).unwrap().map(|e| e.unwrap().file_name()).collect();
+        left.sort();
+        assert_eq!(left, vec!["a.desktop", "c.desktop"]);
+    }
 
-    dialog.show();
-    Ok(())
-}
+    #[test]
+    fn add_localized_name_validates_and_replaces() {
+        let mut e = entry("Clock", "clock", true, StartupSource::UserAutostart);
+        e.add_localized_name("de", "Uhr").unwrap();
+        e.add_localized_name("pt_BR", "Relógio").unwrap();
+        e.add_localized_name("de", "Wanduhr").unwrap();
+        assert_eq!(
+            e.localized_names,
+            vec![("de".to_string(), "Wanduhr".to_string()), ("pt_BR".to_string(), "Relógio".to_string())]
+        );
+        for locale in ["sr@latin", "es_419", "en_US.UTF-8"] {
+            e.add_localized_name(locale, "x").unwrap();
+        }
+        for locale in ["", "D", "deu_de", "de-DE", "de_", "de@", "de.", "../x"] {
+            assert!(e.add_localized_name(locale, "x").is_err(), "{locale}");
+        }
+        assert!(e.add_localized_name("fr", "").is_err());
+        assert!(e.add_localized_name("fr", "  ").is_err());
+        assert!(e.add_localized_name("fr", "a\nb").is_err());
+        assert!(!e.localized_names.iter().any(|(l, _)| l == "fr"));
+    }
 
-fn show_about_dialog(state: &AppState) -> Result<()> {
-    let parent = state
-        .list_box
-        .root()
-        .and_then(|w| w.downcast::<ApplicationWindow>().ok());
-    let dialog = Dialog::with_buttons(
-        Some("About Universal Startup Manager"),
-        parent.as_ref(),
-        gtk4::DialogFlags::MODAL,
-        &[("Close", ResponseType::Close)],
-    );
-    dialog.set_accessible_role(AccessibleRole::Dialog);
-    dialog.update_property(&[gtk4::accessible::Property::Label(
-        "About Universal Startup Manager",
-    )]);
+    #[test]
+    fn remove_localized_name_reports_whether_it_existed() {
+        let mut e = entry("Clock", "clock", true, StartupSource::UserAutostart);
+        e.add_localized_name("de", "Uhr").unwrap();
+        e.add_localized_name("fr", "Horloge").unwrap();
+        assert!(e.remove_localized_name("de"));
+        assert!(!e.remove_localized_name("de"));
+        assert!(!e.remove_localized_name("nl"));
+        assert_eq!(e.localized_names, vec![("fr".to_string(), "Horloge".to_string())]);
+    }
 
-    let content = dialog.content_area();
-    content.set_spacing(6);
-    let description = Label::new(Some(&format!(
-        "Manage user autostart entries and view system startup items. Version {}",
-        env!("CARGO_PKG_VERSION")
-    )));
-    description.set_wrap(true);
-    content.append(&description);
+    #[test]
+    fn is_daemon_needs_two_signals() {
+        let with = |command: &str, keys: &[(&str, &str)]| {
+            let lines: String = keys.iter().map(|(k, v)| format!("{k}={v}\n")).collect();
+            let mut e = parse_desktop_file_from_bytes(format!("[Desktop Entry]\nName=E\nExec={command}\n{lines}").as_bytes()).unwrap();
+            e.source = StartupSource::UserAutostart;
+            e
+        };
+        let app_keys = [("Icon", "firefox"), ("GenericName", "Web Browser")];
+        // No signals.
+        assert!(!with("firefox", &app_keys).is_daemon());
+        // One signal each.
+        assert!(!with("/usr/bin/gnome-keyring-daemon --start", &app_keys).is_daemon());
+        assert!(!with("firefox", &[("GenericName", "Web Browser")]).is_daemon());
+        assert!(!with("firefox", &[("Icon", "firefox")]).is_daemon());
+        // Two or three signals.
+        assert!(with("firefox", &[]).is_daemon());
+        assert!(with("firefox", &[("Icon", " "), ("GenericName", "")]).is_daemon());
+        assert!(with("/usr/libexec/polkit-gnome-authentication-agent-1", &[("Icon", "polkit")]).is_daemon());
+        assert!(with("gnome-keyring-daemon --start", &[]).is_daemon());
+        assert!(!with("serverless-ui", &[("Icon", "x")]).is_daemon());
 
-    let close_button = dialog
-        .widget_for_response(ResponseType::Close)
-        .and_then(|w| w.downcast::<Button>().ok());
-    if let Some(close_button) = close_button {
-        close_button.update_property(&[gtk4::accessible::Property::Label(
-            "Close about dialog",
-        )]);
+        let entries = vec![with("firefox", &app_keys), with("ssh-agent", &[]), with("app", &app_keys)];
+        let daemons = FilterState {
+            daemons_only: true,
+            ..FilterState::default()
+        };
+        assert_eq!(apply_filter(&entries, &daemons), vec![1]);
+        assert_eq!(sort_indices(&entries, vec![0, 1, 2], SortKey::DaemonsFirst), vec![1, 0, 2]);
     }
-    dialog.connect_response(|dlg, _| {
-        dlg.close();
-    });
-    dialog.present();
-    Ok(())
-}
 
-fn load_entries() -> Result<Vec<StartupEntry>> {
-    let mut entries = Vec::new();
-    entries.extend(load_autostart_dir(
-        user_autostart_dir().as_ref(),
-        StartupSource::UserAutostart,
-    )?);
-    entries.extend(load_autostart_dir(
-        system_autostart_dir().as_ref(),
-        StartupSource::SystemAutostart,
-    )?);
-    Ok(entries)
-}
+    #[test]
+    fn reload_system_entries_from_keeps_other_entries_and_selection() {
+        let dir = tempdir().unwrap();
+        let write = |file: &str, name: &str| {
+            fs::write(dir.path().join(file), format!("[Desktop Entry]\nType=Application\nName={name}\nExec={file}\n"))
+                .unwrap();
+        };
+        write("a.desktop", "A new");
+        let mut stale = entry("A old", "a", true, StartupSource::SystemAutostart);
+        stale.path = Some(dir.path().join("a.desktop"));
+        let mut gone = entry("Gone", "gone", true, StartupSource::SystemAutostart);
+        gone.path = Some(dir.path().join("gone.desktop"));
+        let mut user = entry("Mine", "mine", false, StartupSource::UserAutostart);
+        user.extra.push(("X-Kept".into(), "1".into()));
+        let openbox = entry("tint2", "tint2", true, StartupSource::OpenboxAutostart);
+        let base = vec![user.clone(), stale, gone, openbox.clone()];
 
-fn user_autostart_dir() -> PathBuf {
-    let mut base = dirs::config_dir().unwrap_or_else(|| PathBuf::from("~/.config"));
-    base.push("autostart");
-    base
-}
+        // The directory event: a package added b.desktop and removed gone.desktop.
+        write("b.desktop", "B");
+        let dirs = [dir.path().to_path_buf()];
+        let mut entries = base.clone();
+        let (selected, totals) = reload_system_entries_from(&mut entries, &dirs, None, Some(3)).unwrap();
+        assert_eq!(totals, vec![(StartupSource::SystemAutostart, 2)]);
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Mine", "A new", "B", "tint2"]);
+        assert_eq!(entries[0].extra, user.extra);
+        assert!(!entries[0].enabled);
+        assert_eq!(entries[3].source, openbox.source);
+        assert_eq!(selected, Some(3));
 
-fn system_autostart_dir() -> PathBuf {
-    PathBuf::from("/etc/xdg/autostart")
-}
+        let mut entries = base.clone();
+        assert_eq!(reload_system_entries_from(&mut entries, &dirs, None, Some(1)).unwrap().0, Some(1));
+        let mut entries = base.clone();
+        assert_eq!(reload_system_entries_from(&mut entries, &dirs, None, Some(2)).unwrap().0, None);
+        let mut entries = base;
+        assert_eq!(reload_system_entries_from(&mut entries, &dirs, None, Some(0)).unwrap().0, Some(0));
+    }
 
-fn load_autostart_dir(dir: &Path, source: StartupSource) -> Result<Vec<StartupEntry>> {
-    let mut entries = Vec::new();
-    if !dir.exists() {
-        return Ok(entries);
+    #[test]
+    fn user_entries_differ_only_on_file_changes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.desktop");
+        fs::write(&path, "[Desktop Entry]\nType=Application\nName=A\nExec=a\n").unwrap();
+        let loaded = load_autostart_dir(dir.path(), StartupSource::UserAutostart).unwrap();
+        let mut entries = loaded.clone();
+        entries.insert(0, entry("Panel", "panel", true, StartupSource::SystemAutostart));
+        assert!(!user_entries_differ(&entries, &loaded));
+
+        fs::write(&path, "[Desktop Entry]\nType=Application\nName=A\nExec=a --changed\n").unwrap();
+        assert!(user_entries_differ(&entries, &load_autostart_dir(dir.path(), StartupSource::UserAutostart).unwrap()));
+        fs::remove_file(&path).unwrap();
+        assert!(user_entries_differ(&entries, &load_autostart_dir(dir.path(), StartupSource::UserAutostart).unwrap()));
     }
 
-    for entry in fs::read_dir(dir).with_context(|| format!("reading dir {dir:?}"))? {
-        let entry = entry?;
-        let path = entry.path();
-        if path.extension().and_then(|s| s.to_str()) != Some("desktop") {
-            continue;
+    #[test]
+    fn same_entry_is_found_after_a_re_sort() {
+        let sway = PathBuf::from("/home/u/.config/sway/config");
+        let mut entries = vec![
+            entry("waybar", "waybar", true, StartupSource::SwayConfig),
+            entry("mako", "mako", true, StartupSource::SwayConfig),
+            entry("zeal", "zeal", false, StartupSource::UserAutostart),
+        ];
+        for e in &mut entries[..2] {
+            e.path = Some(sway.clone());
         }
-        match parse_desktop_file(&path, source.clone()) {
-            Ok(item) => entries.push(item),
-            Err(err) => eprintln!("Skipping {:?}: {err:?}", path),
+        let keep = entries[1].clone();
+        let visible = sort_indices(&entries, vec![0, 1, 2], SortKey::NameDesc);
+        let position = visible.iter().position(|&idx| is_same_entry(&entries[idx], &keep));
+        assert_eq!(position, Some(2));
+        assert!(!is_same_entry(&entries[0], &keep));
+        entries[1].name = "mako --renamed".into();
+        assert!(!visible.iter().any(|&idx| is_same_entry(&entries[idx], &keep)));
+    }
+
+    #[test]
+    fn system_autostart_dirs_follow_xdg_config_dirs() {
+        let etc = PathBuf::from("/etc/xdg/autostart");
+        assert_eq!(system_autostart_dirs_from(None), vec![etc.clone()]);
+        assert_eq!(
+            system_autostart_dirs_from(Some("/opt/xdg::relative:/etc/xdg/:/opt/xdg")),
+            vec![PathBuf::from("/opt/xdg/autostart"), etc]
+        );
+
+        // An earlier dir's file hides a later one of the same name, as in the spec.
+        let config_dirs = [tempdir().unwrap(), tempdir().unwrap()];
+        let value = config_dirs.iter().map(|d| d.path().to_string_lossy().into_owned()).collect::<Vec<_>>().join(":");
+        let dirs = system_autostart_dirs_from(Some(&value));
+        assert_eq!(dirs.len(), 3);
+        for (dir, names) in dirs.iter().zip([&["a", "b"][..], &["b", "c"][..]]) {
+            fs::create_dir_all(dir).unwrap();
+            for name in names {
+                fs::write(dir.join(format!("{name}.desktop")), format!("[Desktop Entry]\nName={name}\nExec={name}\n"))
+                    .unwrap();
+            }
         }
+        let mut entries = Vec::new();
+        reload_system_entries_from(&mut entries, &dirs[..2], None, None).unwrap();
+        let paths: Vec<_> = entries.iter().filter_map(|e| e.path.clone()).collect();
+        assert_eq!(paths, vec![dirs[0].join("a.desktop"), dirs[0].join("b.desktop"), dirs[1].join("c.desktop")]);
+    }
+
+    #[test]
+    fn validate_entry_path_in_rejects_other_dirs_and_symlinks() {
+        let base = tempdir().unwrap();
+        let other = tempdir().unwrap();
+        let inside = base.path().join("app.desktop");
+        assert_eq!(validate_entry_path_in(base.path(), "applications dir", &inside).unwrap(), inside);
+        let err = validate_entry_path_in(base.path(), "applications dir", &other.path().join("app.desktop")).unwrap_err();
+        assert!(err.to_string().contains("outside applications dir"), "{err}");
+        let target = other.path().join("real.desktop");
+        fs::write(&target, "").unwrap();
+        std::os::unix::fs::symlink(&target, base.path().join("link.desktop")).unwrap();
+        assert!(validate_entry_path_in(base.path(), "applications dir", &base.path().join("link.desktop")).is_err());
+    }
+
+    #[test]
+    fn application_launcher_copy_is_enabled_and_recognized() {
+        let dir = tempdir().unwrap();
+        let mut e = entry("App", "app --tray", false, StartupSource::UserAutostart);
+        e.ensure_entry_id();
+        let launcher = application_launcher_copy(&e);
+        assert!(launcher.enabled);
+        assert!(launcher.to_desktop_string().contains("Hidden=false"));
+        let path = dir.path().join("app.desktop");
+        fs::write(&path, launcher.to_desktop_string()).unwrap();
+        assert!(is_application_copy_of(&e, &path));
+        let mut other = entry("Other", "other", true, StartupSource::UserAutostart);
+        other.ensure_entry_id();
+        assert!(!is_application_copy_of(&other, &path));
+        assert!(!is_application_copy_of(&e, &dir.path().join("missing.desktop")));
     }
-    Ok(entries)
-}
 
-fn parse_desktop_file(path: &Path, source: StartupSource) -> Result<StartupEntry> {
-    let content = fs::read_to_string(path)
-        .with_context(|| format!("reading desktop file {path:?}"))?;
+    #[test]
+    fn generate_application_launcher_in_keeps_a_foreign_launcher() {
+        let dir = tempdir().unwrap();
+        let mut e = entry("App", "app --tray", true, StartupSource::UserAutostart);
+        e.ensure_entry_id();
+        let theirs = dir.path().join("app.desktop");
+        fs::write(&theirs, "[Desktop Entry]\nType=Application\nName=Mine\nExec=mine\n").unwrap();
+        assert!(generate_application_launcher_in(dir.path(), &e).is_err());
+        assert!(fs::read_to_string(&theirs).unwrap().contains("Exec=mine"));
+        fs::remove_file(&theirs).unwrap();
+        let path = generate_application_launcher_in(dir.path(), &e).unwrap();
+        assert_eq!(path, theirs);
+        assert!(is_application_copy_of(&e, &path));
+        e.command = "app --tray --quiet".to_string();
+        generate_application_launcher_in(dir.path(), &e).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("--quiet"));
+    }
 
-    let mut name = String::from("Unnamed");
-    let mut command = String::new();
-    let mut enabled = true;
-    let mut extra = Vec::new();
-    let mut localized_names = Vec::new();
-    let mut entry_comments = Vec::new();
-    let mut preamble = Vec::new();
-    let mut other_groups: Vec<Vec<String>> = Vec::new();
+    #[test]
+    fn remove_xdg_data_home_desktop_file_rejects_unsafe_slugs() {
+        for slug in ["", "../app", "a/b", "App"] {
+            assert!(remove_xdg_data_home_desktop_file(slug).is_err(), "{slug}");
+        }
+    }
 
-    let mut current_group: Option<String> = None;
-    let mut current_other: Vec<String> = Vec::new();
+    #[test]
+    fn collect_entry_statistics_counts_each_field() {
+        let mut a = entry("A", "app --tray", true, StartupSource::UserAutostart);
+        a.icon = Some("app".into());
+        a.comment = Some("Tray app".into());
+        let mut b = entry("B", "backup-daemon --interval 60", false, StartupSource::SystemAutostart);
+        b.icon = Some(" ".into());
+        b.comment = Some("Backups".into());
+        let c = entry("C", "c", true, StartupSource::OpenboxAutostart);
+        let d = entry("LANG", "en_US.UTF-8", true, StartupSource::EnvironmentD);
+        let stats = collect_entry_statistics(&[a, b, c, d]);
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.enabled, 3);
+        assert_eq!(stats.disabled, 1);
+        assert_eq!(stats.user, 2);
+        assert_eq!(stats.system, 2);
+        assert_eq!(stats.with_icon, 1);
+        assert_eq!(stats.with_description, 2);
+        assert!((stats.average_command_length - (10 + 27 + 1 + 11) as f32 / 4.0).abs() < f32::EPSILON);
+        assert_eq!(stats.longest_command.as_deref(), Some("backup-daemon --interval 60"));
+        assert_eq!(stats.summary(), "4 total entries, 3 enabled, 1 disabled; 2 user / 2 system");
+    }
 
-    for raw_line in content.lines() {
-        let trimmed = raw_line.trim();
-        if trimmed.starts_with('[') && trimmed.ends_with(']') {
-            // close previous non-entry group buffer
-            if let Some(group) = current_group.take() {
-                if group != "Desktop Entry" && !current_other.is_empty() {
-                    other_groups.push(current_other.clone());
-                } else if group == "Desktop Entry" {
-                    // drop, we rebuild entry
-                }
-                current_other.clear();
-            } else {
-                // preamble ends here
-                if !current_other.is_empty() {
-                    preamble.append(&mut current_other);
-                }
-            }
+    #[test]
+    fn collect_entry_statistics_handles_empty_and_ties() {
+        let empty = collect_entry_statistics(&[]);
+        assert_eq!(empty, EntryStatistics::default());
+        assert_eq!(empty.summary(), "0 total entries, 0 enabled, 0 disabled; 0 user / 0 system");
+        let tie = collect_entry_statistics(&[
+            entry("A", "aa", true, StartupSource::UserAutostart),
+            entry("B", "bb", true, StartupSource::UserAutostart),
+        ]);
+        assert_eq!(tie.longest_command.as_deref(), Some("aa"));
+        assert_eq!(collect_entry_statistics(&[entry("E", "", true, StartupSource::UserAutostart)]).longest_command, None);
+    }
 
-            let group_name = trimmed.trim_matches(&['[', ']'][..]).to_string();
-            let in_entry_group = group_name == "Desktop Entry";
-            current_group = Some(group_name.clone());
-            if !in_entry_group {
-                current_other.push(raw_line.to_string());
-            }
-            continue;
+    #[test]
+    fn validate_all_entries_on_load_reports_only_invalid_entries() {
+        let mut odd_sections = entry("Sections", "app", true, StartupSource::UserAutostart);
+        odd_sections.other_groups = vec![vec!["[Extra]".into(), "Key=v".into()]];
+        let mut with_actions = entry("Actions", "app", true, StartupSource::UserAutostart);
+        with_actions.other_groups = vec![vec!["[Desktop Action New]".into(), "Exec=app --new".into()]];
+        let entries = vec![
+            entry("Fine", "app --tray", true, StartupSource::UserAutostart),
+            entry("", "app", true, StartupSource::UserAutostart),
+            entry("NoExec", " ", true, StartupSource::SystemAutostart),
+            odd_sections,
+            with_actions,
+        ];
+        let results = validate_all_entries_on_load(&entries);
+        let flagged: Vec<usize> = results.iter().map(|(idx, _)| *idx).collect();
+        assert_eq!(flagged, vec![1, 2, 3]);
+        assert_eq!(results[0].1.warnings, vec![LintWarning::new(LintSeverity::Error, "has no name")]);
+        assert_eq!(results[1].1.warnings, vec![LintWarning::new(LintSeverity::Error, "has no command")]);
+        assert_eq!(results[2].1.warnings[0].severity, LintSeverity::Warning);
+        assert!(entries[0].validate().is_valid());
+        assert!(entries[4].validate().is_valid());
+    }
+
+    #[test]
+    fn human_readable_source_and_tooltip_cover_every_variant() {
+        let cases = [
+            (StartupSource::UserAutostart, "User autostart (~/.config/autostart/)"),
+            (StartupSource::SystemAutostart, "System autostart (/etc/xdg/autostart/)"),
+            (StartupSource::ShellProfile, "Shell profile (~/.profile, ~/.bash_profile)"),
+            (StartupSource::EnvironmentD, "Session environment (/etc/environment.d/)"),
+            (StartupSource::OpenboxAutostart, "Openbox autostart (~/.config/openbox/autostart)"),
+            (StartupSource::SystemdTimer, "systemd user session (~/.config/systemd/user/)"),
+            (StartupSource::Runit, "runit user service (~/.config/service/)"),
+            (StartupSource::SwayConfig, "Sway config (~/.config/sway/config)"),
+            (StartupSource::I3Config, "i3 config (~/.config/i3/config)"),
+            (StartupSource::XdgUserDirs, "XDG user directories (~/.config/user-dirs.dirs)"),
+            (StartupSource::XfceAutostart, "Xfce session override (~/.config/autostart/)"),
+            (StartupSource::Hyprland, "Hyprland config (~/.config/hypr/hyprland.conf)"),
+            (StartupSource::KdePlasmaEnv, "Plasma environment script (~/.config/plasma-workspace/env/)"),
+            (StartupSource::Unknown, "Unknown source"),
+        ];
+        let mut tooltips = HashSet::new();
+        for (source, expected) in cases {
+            let e = entry("A", "a", true, source.clone());
+            assert_eq!(e.human_readable_source(), expected);
+            let tooltip = e.source_tooltip();
+            assert!(tooltip.len() > expected.len(), "{source:?}");
+            assert!(tooltips.insert(tooltip), "{source:?} tooltip is not distinct");
         }
+        let user = entry("A", "a", true, StartupSource::UserAutostart);
+        assert!(user.source_tooltip().contains("overrides a system entry"));
+    }
 
-        if let Some(group) = &current_group {
-            if group == "Desktop Entry" {
-                if trimmed.starts_with('#') || trimmed.is_empty() {
-                    entry_comments.push(raw_line.to_string());
-                    continue;
-                }
-                let (key, value) = match raw_line.split_once('=') {
-                    Some(pair) => pair,
-                    None => continue,
-                };
-                let key = key.trim();
-                let value = value.trim();
-                if key == "Name" {
-                    name = value.to_string();
-                } else if let Some(locale) = key.strip_prefix("Name[") {
-                    if let Some(locale_key) = locale.strip_suffix(']') {
-                        localized_names.push((locale_key.to_string(), value.to_string()));
-                    }
-                } else if key == "Exec" {
-                    command = value.to_string();
-                } else if key == "Hidden" {
-                    enabled = value != "true";
-                } else if key == "X-GNOME-Autostart-enabled" {
-                    enabled = value == "true";
-                } else {
-                    extra.push((key.to_string(), value.to_string()));
-                }
-            } else {
-                current_other.push(raw_line.to_string());
-            }
-        } else {
-            preamble.push(raw_line.to_string());
+    #[test]
+    fn delete_all_user_entries_cli_requires_yes() {
+        for args in [vec!["--delete-all-user-entries"], vec!["--delete-all-user-entries", "-y"], vec!["--delete-all-user-entries=no"]] {
+            let args: Vec<String> = args.into_iter().map(String::from).collect();
+            let err = run_cli(&args).unwrap().unwrap_err();
+            assert!(err.to_string().contains("add --yes"), "{err}");
         }
+        assert!(delete_all_confirmation_matches(" DELETE "));
+        assert!(!delete_all_confirmation_matches("delete"));
+        assert!(!delete_all_confirmation_matches(""));
     }
 
-    // Flush last group buffer if it is non-entry.
-    if let Some(group) = current_group {
-        if group != "Desktop Entry" && !current_other.is_empty() {
-            other_groups.push(current_other);
-        } else if group == "Desktop Entry" {
-            // drop, already parsed into fields
+    #[test]
+    fn clear_autostart_dir_keeps_locked_and_other_files() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.desktop"), entry("A", "a", true, StartupSource::UserAutostart).to_desktop_string()).unwrap();
+        fs::write(dir.path().join("b.desktop"), entry("B", "b", false, StartupSource::UserAutostart).to_desktop_string()).unwrap();
+        let mut locked = entry("L", "l", true, StartupSource::UserAutostart);
+        locked.locked = true;
+        fs::write(dir.path().join("l.desktop"), locked.to_desktop_string()).unwrap();
+        fs::write(dir.path().join("notes.txt"), "keep").unwrap();
+        assert_eq!(clear_autostart_dir(dir.path()).unwrap(), 2);
+        assert!(dir.path().join("l.desktop").exists());
+        assert!(dir.path().join("notes.txt").exists());
+        assert!(!dir.path().join("a.desktop").exists());
+        assert_eq!(clear_autostart_dir(&dir.path().join("missing")).unwrap(), 0);
+    }
+
+    #[test]
+    fn file_dialog_action_labels() {
+        assert_eq!(FileDialogAction::Open.accept_label(), "Open");
+        assert_eq!(FileDialogAction::Save.accept_label(), "Save");
+    }
+
+    #[cfg(feature = "file-dialog")]
+    #[test]
+    fn create_file_dialog_sets_title_and_label() {
+        // Needs a display; skip quietly on headless machines.
+        if gtk4::init().is_err() {
+            return;
         }
-    } else if !current_other.is_empty() {
-        preamble.extend(current_other);
+        let dialog = create_file_dialog("Export as SVG", FileDialogAction::Save);
+        assert_eq!(dialog.title(), "Export as SVG");
+        assert_eq!(dialog.accept_label().as_deref(), Some("Save"));
+        assert!(dialog.is_modal());
     }
 
-    Ok(StartupEntry {
-        name,
-        command,
-        enabled,
-        source,
-        path: Some(path.to_path_buf()),
-        extra,
-        localized_names,
-        entry_comments,
-        preamble,
-        other_groups,
-    })
-}
+    #[test]
+    fn gnome_autostart_handler_json_round_trips() {
+        let mut e = entry("Nextcloud", "nextcloud --background", false, StartupSource::UserAutostart);
+        e.path = Some(PathBuf::from("/home/u/.config/autostart/com.nextcloud.desktopclient.nextcloud.desktop"));
+        e.env_vars = vec![("QT_SCALE_FACTOR".into(), "1 5".into())];
+        e.comment = Some("File sync \"client\"".into());
+        e.set_delay_seconds(5);
+        let json = e.to_gnome_autostart_handler_json();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["id"], "com.nextcloud.desktopclient.nextcloud");
+        assert_eq!(value["name"], "Nextcloud");
+        assert_eq!(value["description"], "File sync \"client\"");
+        assert_eq!(value["commandLine"], "env QT_SCALE_FACTOR=\"1 5\" nextcloud --background");
+        assert_eq!(value["autostart"], false);
+        assert_eq!(value["delaySeconds"], 5);
 
-fn write_desktop_entry(entry: &StartupEntry, path: &Path) -> Result<()> {
-    let mut dir = path.parent().unwrap_or_else(|| Path::new(".")).to_path_buf();
-    if dir.as_os_str().is_empty() {
-        dir = PathBuf::from(".");
+        let parsed = from_gnome_autostart_handler_json(&json).unwrap();
+        assert_eq!(parsed.name, e.name);
+        assert_eq!(parsed.command, e.command);
+        assert_eq!(parsed.env_vars, e.env_vars);
+        assert!(!parsed.enabled);
+        assert_eq!(parsed.delay_seconds(), 5);
+        assert_eq!(parsed.comment, e.comment);
+        assert_eq!(parsed.extra, e.extra);
+        let mut again: serde_json::Value = serde_json::from_str(&parsed.to_gnome_autostart_handler_json()).unwrap();
+        assert_eq!(again["id"], "nextcloud");
+        again["id"] = value["id"].clone();
+        assert_eq!(again, value);
     }
-    fs::create_dir_all(&dir).with_context(|| format!("Creating dir {:?}", dir))?;
-    let mut tmp = NamedTempFile::new_in(&dir).with_context(|| format!("Creating temp file in {:?}", dir))?;
-    let tmp_path = tmp.path().to_path_buf();
-    let file = tmp.as_file_mut();
-    let mut lines = Vec::new();
-    lines.extend(entry.preamble.clone());
-    if entry.preamble.last().map(|s| !s.is_empty()).unwrap_or(false) {
-        lines.push(String::new());
+
+    #[test]
+    fn from_gnome_autostart_handler_json_defaults_and_errors() {
+        let parsed = from_gnome_autostart_handler_json(r#"{"id":"a","name":"A","commandLine":"a"}"#).unwrap();
+        assert!(parsed.enabled);
+        assert_eq!(parsed.delay_seconds(), 0);
+        assert!(parsed.extra.is_empty());
+        assert!(from_gnome_autostart_handler_json("{}").is_err());
+        assert!(from_gnome_autostart_handler_json(r#"{"id":"a","name":"","commandLine":"a"}"#).is_err());
+        assert!(from_gnome_autostart_handler_json("not json").is_err());
     }
 
-    lines.push("[Desktop Entry]".to_string());
-    lines.extend(entry.entry_comments.clone());
-    lines.push("Type=Application".to_string());
-    lines.push(format!("Name={}", entry.name));
-    for (locale, value) in entry.localized_names.iter() {
-        lines.push(format!("Name[{locale}]={value}"));
+    #[test]
+    fn filter_state_from_cli_args_parses_specifiers() {
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert_eq!(FilterState::from_cli_args(&args(&[])).unwrap(), FilterState::default());
+        let user_enabled = FilterState::from_cli_args(&args(&["--filter=user,enabled"])).unwrap();
+        assert!(user_enabled.show_user && !user_enabled.show_system);
+        assert!(user_enabled.show_enabled && !user_enabled.show_disabled);
+        let split = FilterState::from_cli_args(&args(&["--filter", "system", "--filter=disabled"])).unwrap();
+        assert!(!split.show_user && split.show_system);
+        assert!(!split.show_enabled && split.show_disabled);
+        let both = FilterState::from_cli_args(&args(&["--filter=user, system"])).unwrap();
+        assert!(both.show_user && both.show_system && both.show_enabled && both.show_disabled);
+        assert!(FilterState::from_cli_args(&args(&["--filter=wine"])).is_err());
+        assert!(FilterState::from_cli_args(&args(&["--filter="])).is_err());
+        assert!(FilterState::from_cli_args(&args(&["--filter"])).is_err());
+        assert!(FilterState::from_cli_args(&args(&["--fliter=user"])).is_err());
+        assert!(FilterState::from_cli_args(&args(&["user"])).is_err());
+        assert!(run_cli(&args(&["--list", "--filter=bogus"])).unwrap().is_err());
+        assert!(run_cli(&args(&["--list", "--verbose"])).unwrap().is_err());
     }
-    lines.push(format!("Exec={}", entry.command));
-    lines.push(format!(
-        "X-GNOME-Autostart-enabled={}",
-        if entry.enabled { "true" } else { "false" }
-    ));
-    lines.push(format!(
-        "Hidden={}",
-        if entry.enabled { "false" } else { "true" }
-    ));
-    let known = ["Name", "Exec", "Hidden", "X-GNOME-Autostart-enabled", "Type"];
-    for (k, v) in entry.extra.iter() {
-        if known.contains(&k.as_str()) || k.starts_with("Name[") {
-            continue;
+
+    #[test]
+    fn cli_list_filter_counts_loaded_entries() {
+        let dir = tempdir().unwrap();
+        for (slug, enabled) in [("one", true), ("two", true), ("three", false)] {
+            let e = entry(slug, slug, enabled, StartupSource::UserAutostart);
+            fs::write(dir.path().join(format!("{slug}.desktop")), e.to_desktop_string()).unwrap();
         }
-        lines.push(format!("{k}={v}"));
+        let mut entries = load_autostart_dir(dir.path(), StartupSource::UserAutostart).unwrap();
+        entries.push(entry("Clock", "clock", true, StartupSource::SystemAutostart));
+        let listed = |list: &[&str]| {
+            let args: Vec<String> = list.iter().map(|s| s.to_string()).collect();
+            let filter = FilterState::from_cli_args(&args)?;
+            let visible: Vec<StartupEntry> =
+                apply_filter(&entries, &filter).into_iter().map(|i| entries[i].clone()).collect();
+            Ok::<_, anyhow::Error>(cli_list(&visible))
+        };
+        assert_eq!(listed(&["--filter=user"]).unwrap().lines().count(), 3);
+        assert_eq!(listed(&["--filter=user,enabled"]).unwrap().lines().count(), 2);
+        assert_eq!(listed(&["--filter", "user,disabled"]).unwrap().lines().count(), 1);
+        assert_eq!(listed(&["--filter=user,enabled,disabled"]).unwrap().lines().count(), 3);
+        let system = listed(&["--filter=system"]).unwrap();
+        assert_eq!(system.lines().count(), 1);
+        assert!(system.lines().all(|line| line.split('\t').nth(1) != Some("user")), "{system}");
+        let err = listed(&["--filter=user,bogus"]).unwrap_err();
+        assert!(format!("{err:#}").contains("bogus"));
     }
 
-    if !entry.other_groups.is_empty() && !lines.last().map(|s| s.is_empty()).unwrap_or(true) {
-        lines.push(String::new());
+    #[test]
+    fn build_autostart_sequence_orders_by_phase_delay_and_name() {
+        let with = |name: &str, enabled: bool, source: StartupSource, extra: &[(&str, &str)]| {
+            let mut e = entry(name, name, enabled, source);
+            e.extra = extra.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            if let Some(seconds) = e.extra_value(AUTOSTART_DELAY_KEY).and_then(|v| v.parse().ok()) {
+                e.set_delay_seconds(seconds);
+            }
+            e
+        };
+        let entries = vec![
+            with("nextcloud", true, StartupSource::UserAutostart, &[(AUTOSTART_DELAY_KEY, "5")]),
+            with("nm-applet", true, StartupSource::SystemAutostart, &[(AUTOSTART_PHASE_KEY, "Panel")]),
+            with("polkit-agent", true, StartupSource::SystemAutostart, &[(AUTOSTART_PHASE_KEY, "Initialization")]),
+            with("Alpha", true, StartupSource::UserAutostart, &[]),
+            with("off", false, StartupSource::UserAutostart, &[]),
+            with("bogus-phase", true, StartupSource::UserAutostart, &[(AUTOSTART_PHASE_KEY, "Later"), (AUTOSTART_DELAY_KEY, "2")]),
+            with("LANG", true, StartupSource::EnvironmentD, &[]),
+        ];
+        let sequence = build_autostart_sequence(&entries);
+        let names: Vec<&str> = sequence.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["polkit-agent", "nm-applet", "Alpha", "bogus-phase", "nextcloud"]);
+        assert_eq!(
+            format_autostart_sequence(&sequence[..3]),
+            "1. polkit-agent (0s)\n2. nm-applet (0s)\n3. Alpha (0s)"
+        );
     }
-    for (i, group) in entry.other_groups.iter().enumerate() {
-        lines.extend(group.clone());
-        if i + 1 != entry.other_groups.len() && !group.last().map(|s| s.is_empty()).unwrap_or(true) {
-            lines.push(String::new());
-        }
+
+    #[test]
+    fn build_autostart_sequence_skips_overridden_system_entries() {
+        let mut system = entry("App", "app", true, StartupSource::SystemAutostart);
+        system.path = Some(PathBuf::from("/etc/xdg/autostart/app.desktop"));
+        let mut user = entry("App (mine)", "app --quiet", true, StartupSource::UserAutostart);
+        user.path = Some(PathBuf::from("/home/u/.config/autostart/app.desktop"));
+        let sequence = build_autostart_sequence(&[system.clone(), user.clone()]);
+        assert_eq!(sequence.len(), 1);
+        assert_eq!(sequence[0].name, "App (mine)");
+        user.enabled = false;
+        assert!(build_autostart_sequence(&[system, user]).is_empty());
     }
 
-    let content = if lines.last().map(|l| l.is_empty()).unwrap_or(false) {
-        lines.join("\n")
-    } else {
-        lines.join("\n") + "\n"
-    };
-    file.write_all(content.as_bytes())
-        .with_context(|| format!("Writing {:?}", tmp_path))?;
-    let _ = file.sync_all();
-    tmp.persist(path)
-        .with_context(|| format!("Replacing {:?}", path))?;
-    Ok(())
-}
+    #[test]
+    fn lint_duplicate_names_groups_case_insensitively() {
+        let entries = vec![
+            entry("Syncthing", "syncthing", true, StartupSource::UserAutostart),
+            entry("syncthing", "syncthing --no-browser", true, StartupSource::UserAutostart),
+            entry("Other", "other", true, StartupSource::UserAutostart),
+        ];
+        assert_eq!(
+            lint_duplicate_names(&entries),
+            vec![LintWarning::new(
+                LintSeverity::Warning,
+                "2 entries share a name: Syncthing (user), syncthing (user)"
+            )]
+        );
+    }
 
-fn edit_user_entry(original: &StartupEntry, new_name: &str, new_cmd: &str, original_path: Option<&PathBuf>) -> Result<()> {
-    let mut updated = original.clone();
-    updated.name = new_name.to_string();
-    updated.command = new_cmd.to_string();
-    let target_path = if let Some(p) = original_path {
-        p.clone()
-    } else {
-        user_autostart_dir().join(format!("{}.desktop", slugify(new_name)))
-    };
-    let target_path = validate_user_entry_path(&target_path)?;
-    write_desktop_entry(&updated, &target_path)?;
-    // If slug/name changed, remove old file to avoid duplicates.
-    if let Some(old_path) = original_path {
-        if old_path != &target_path {
-            if let Ok(old_path) = validate_user_entry_path(old_path) {
-                let _ = fs::remove_file(old_path);
-            }
-        }
+    #[test]
+    fn lint_duplicate_names_covers_user_and_system() {
+        let entries = vec![
+            entry("Tracker", "tracker", true, StartupSource::SystemAutostart),
+            entry("Unique", "u", true, StartupSource::SystemAutostart),
+            entry("Tracker", "tracker --quiet", false, StartupSource::UserAutostart),
+        ];
+        let warnings = lint_duplicate_names(&entries);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.ends_with("Tracker (system), Tracker (user)"));
+        assert!(audit_all(&entries, &[]).contains(&warnings[0]));
     }
-    Ok(())
-}
 
-fn create_user_entry(name: &str, command: &str) -> Result<PathBuf> {
-    if name.trim().is_empty() || command.trim().is_empty() {
-        bail!("Name and command are required");
+    #[test]
+    fn lint_duplicate_names_quiet_for_unique_names() {
+        let entries = vec![
+            entry("A", "a", true, StartupSource::UserAutostart),
+            entry("B", "b", true, StartupSource::SystemAutostart),
+        ];
+        assert!(lint_duplicate_names(&entries).is_empty());
+        assert!(lint_duplicate_names(&[]).is_empty());
     }
-    let dir = user_autostart_dir();
-    fs::create_dir_all(&dir).with_context(|| format!("Creating dir {:?}", dir))?;
-    let file_name = format!("{}.desktop", slugify(name));
-    let path = dir.join(file_name);
-    let path = validate_user_entry_path(&path)?;
-    let entry = StartupEntry {
-        name: name.to_string(),
-        command: command.to_string(),
-        enabled: true,
-        source: StartupSource::UserAutostart,
-        path: Some(path.clone()),
-        extra: Vec::new(),
-        localized_names: Vec::new(),
-        entry_comments: Vec::new(),
-        preamble: Vec::new(),
-        other_groups: Vec::new(),
-    };
-    write_desktop_entry(&entry, &path)?;
-    Ok(path)
-}
 
-fn slugify(name: &str) -> String {
-    let mut out = String::new();
-    for c in name.chars() {
-        if c.is_ascii_alphanumeric() {
-            out.push(c.to_ascii_lowercase());
-        } else if c.is_whitespace() || c == '-' || c == '_' {
-            if !out.ends_with('-') {
-                out.push('-');
-            }
-        }
+    #[test]
+    fn parse_environment_file_strips_quotes_and_skips_noise() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("environment");
+        fs::write(
+            &path,
+            "# system-wide\n\nEDITOR=vim\nPATH=\"/opt/tools/bin:/usr/bin\"\n  LANG='C.UTF-8'\nnot a line\n1BAD=x\nJUNK DEFAULT=y\n",
+        )
+        .unwrap();
+        assert_eq!(
+            parse_environment_file(&path).unwrap(),
+            vec![
+                ("EDITOR".to_string(), "vim".to_string()),
+                ("PATH".to_string(), "/opt/tools/bin:/usr/bin".to_string()),
+                ("LANG".to_string(), "C.UTF-8".to_string()),
+            ]
+        );
+        assert!(parse_environment_file(&dir.path().join("missing")).is_err());
     }
-    if out.is_empty() {
-        "entry".into()
-    } else {
-        out
+
+    #[test]
+    fn audit_try_exec_uses_path_from_environment_file() {
+        let dir = tempdir().unwrap();
+        let bin = dir.path().join("bin");
+        fs::create_dir(&bin).unwrap();
+        fs::write(bin.join("helper"), "").unwrap();
+        let env_file = dir.path().join("environment");
+        fs::write(&env_file, format!("PATH=\"{}:$PATH\"\n", bin.display())).unwrap();
+        let search_path = session_search_path(&env_file);
+        assert_eq!(search_path[0], bin);
+
+        let mut e = entry("Helper", "helper --daemon", true, StartupSource::UserAutostart);
+        assert_eq!(audit_try_exec(&e, &search_path), None);
+        e.try_exec = Some("helper".into());
+        assert_eq!(audit_try_exec(&e, &search_path), None);
+        assert!(audit_try_exec(&e, &session_search_path(&dir.path().join("missing"))).is_some());
+        e.try_exec = Some(bin.join("gone").display().to_string());
+        let warning = audit_try_exec(&e, &search_path).unwrap();
+        assert_eq!(warning.severity, LintSeverity::Warning);
+        assert!(warning.message.contains("gone"));
     }
-}
 
-fn source_label(source: &StartupSource) -> &'static str {
-    match source {
-        StartupSource::UserAutostart => "user",
-        StartupSource::SystemAutostart => "system",
-        StartupSource::ShellProfile => "shell",
-        StartupSource::Unknown => "unknown",
+    #[test]
+    fn missing_binaries_checks_try_exec_then_command() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("present"), "").unwrap();
+        let search_path = vec![dir.path().to_path_buf()];
+        let mut guarded = entry("Guarded", "present --tray", true, StartupSource::UserAutostart);
+        guarded.try_exec = Some("absent-helper".into());
+        let entries = vec![
+            entry("Fine", "present --flag", true, StartupSource::UserAutostart),
+            entry("Gone", "absent-app", true, StartupSource::SystemAutostart),
+            guarded,
+            entry("Abs", &format!("{} -x", dir.path().join("present").display()), true, StartupSource::UserAutostart),
+            entry("EDITOR", "export EDITOR=vim", true, StartupSource::EnvironmentD),
+        ];
+        assert_eq!(entries[2].required_binary().as_deref(), Some("absent-helper"));
+        assert_eq!(entries[4].required_binary(), None);
+        let missing = find_missing_binaries(&entries, &search_path);
+        assert_eq!(missing, vec![(1, "absent-app".to_string()), (2, "absent-helper".to_string())]);
+        let report = missing_binaries_report(&entries, &missing);
+        assert!(report.starts_with("2 of 5 entries"));
+        assert!(report.contains("\nGone (system): absent-app"));
+        assert_eq!(missing_binaries_report(&entries[..1], &[]), "No entry needs a missing program (1 checked).");
     }
-}
 
-fn is_user_owned_path(path: &Path) -> bool {
-    let base = user_autostart_dir();
-    let base_canon = match base.canonicalize() {
-        Ok(path) => path,
-        Err(_) => return false,
-    };
-    let parent = path.parent().unwrap_or_else(|| Path::new("."));
-    let parent_canon = match parent.canonicalize() {
-        Ok(path) => path,
-        Err(_) => return false,
-    };
-    if parent_canon != base_canon {
-        return false;
+    #[test]
+    fn write_multiple_autostart_dirs_writes_one_dir() {
+        let dir = tempdir().unwrap();
+        let e = entry("My App", "myapp", true, StartupSource::UserAutostart);
+        let written = write_multiple_autostart_dirs(&e, &[dir.path().to_path_buf()]).unwrap();
+        let expected = dir.path().canonicalize().unwrap().join("my-app.desktop");
+        assert_eq!(written, vec![expected.clone()]);
+        assert_eq!(read_to_string(&expected).unwrap(), write_desktop_entry_to_string(&e));
     }
-    match fs::symlink_metadata(path) {
-        Ok(meta) => meta.is_file() && !meta.file_type().is_symlink(),
-        Err(_) => false,
+
+    #[test]
+    fn write_multiple_autostart_dirs_writes_every_dir() {
+        let first = tempdir().unwrap();
+        let second = tempdir().unwrap();
+        let mut e = entry("My App", "myapp", true, StartupSource::UserAutostart);
+        e.path = Some(PathBuf::from("/elsewhere/custom.desktop"));
+        let dirs = [first.path().to_path_buf(), second.path().to_path_buf(), first.path().to_path_buf()];
+        let written = write_multiple_autostart_dirs(&e, &dirs).unwrap();
+        assert_eq!(written.len(), 2);
+        for dir in [&first, &second] {
+            let path = dir.path().canonicalize().unwrap().join("custom.desktop");
+            assert!(written.contains(&path));
+            assert_eq!(read_to_string(&path).unwrap(), write_desktop_entry_to_string(&e));
+        }
     }
-}
 
-fn validate_user_entry_path(path: &Path) -> Result<PathBuf> {
-    let base = user_autostart_dir();
-    let base_canon = base
-        .canonicalize()
-        .with_context(|| format!("Resolving {:?}", base))?;
-    let parent = path.parent().unwrap_or_else(|| Path::new("."));
-    let parent_canon = parent
-        .canonicalize()
-        .with_context(|| format!("Resolving {:?}", parent))?;
-    if parent_canon != base_canon {
-        bail!("Entry path is outside user autostart dir");
+    #[test]
+    fn write_multiple_autostart_dirs_writes_nothing_when_one_dir_fails() {
+        let good = tempdir().unwrap();
+        let blocked = tempdir().unwrap();
+        fs::create_dir(blocked.path().join("my-app.desktop")).unwrap();
+        let e = entry("My App", "myapp", true, StartupSource::UserAutostart);
+        let attempts = [
+            vec![good.path().to_path_buf(), blocked.path().to_path_buf()],
+            vec![good.path().to_path_buf(), PathBuf::from("/etc")],
+            vec![good.path().to_path_buf(), good.path().join("missing")],
+        ];
+        for dirs in attempts {
+            assert!(write_multiple_autostart_dirs(&e, &dirs).is_err(), "{dirs:?}");
+            assert!(!good.path().join("my-app.desktop").exists());
+        }
     }
-    if let Ok(meta) = fs::symlink_metadata(path) {
-        if meta.file_type().is_symlink() {
-            bail!("Refusing to operate on symlinked entry");
+
+    #[test]
+    fn is_system_path_allows_var_home() {
+        for path in ["/etc/xdg/autostart", "/usr/share", "/var/lib/autostart", "/var"] {
+            assert!(is_system_path(Path::new(path)), "{path}");
         }
-        if !meta.is_file() {
-            bail!("Entry path is not a regular file");
+        for path in ["/var/home/u/.config/autostart", "/home/u/.config/autostart", "/etcetera", "/tmp/x"] {
+            assert!(!is_system_path(Path::new(path)), "{path}");
         }
     }
-    Ok(path.to_path_buf())
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::tempdir;
-    use std::fs::read_to_string;
 
-    fn entry(name: &str, command: &str, enabled: bool, source: StartupSource) -> StartupEntry {
-        StartupEntry {
-            name: name.to_string(),
-            command: command.to_string(),
-            enabled,
-            source,
-            path: None,
-            extra: Vec::new(),
-            localized_names: Vec::new(),
-            entry_comments: Vec::new(),
-            preamble: Vec::new(),
-            other_groups: Vec::new(),
+    #[test]
+    fn cli_copy_writes_the_entry_into_each_dir() {
+        let first = tempdir().unwrap();
+        let second = tempdir().unwrap();
+        let entries = vec![
+            entry("My App", "myapp", true, StartupSource::UserAutostart),
+            entry("Clock", "clock", true, StartupSource::SystemAutostart),
+        ];
+        let dirs = [first.path().to_path_buf(), second.path().to_path_buf()];
+        let out = cli_copy(&entries, "my app", &dirs).unwrap();
+        assert_eq!(out.lines().count(), 2);
+        for dir in [&first, &second] {
+            assert!(dir.path().join("my-app.desktop").exists());
         }
+        assert!(cli_copy(&entries, "My App", &[]).is_err());
+        assert!(cli_copy(&entries, "Clock", &dirs).is_err());
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(run_cli(&args(&["--copy"])).unwrap().is_err());
     }
 
     #[test]
-    fn slugify_basic() {
-        assert_eq!(slugify("My App"), "my-app");
-        assert_eq!(slugify("App_123"), "app-123");
-        assert_eq!(slugify("$$$"), "entry");
+    fn display_name_with_fallback_walks_the_chain() {
+        let mut e = entry("", "clock", true, StartupSource::UserAutostart);
+        e.path = Some(PathBuf::from("/home/u/.config/autostart/org.example.Clock.desktop"));
+        assert_eq!(display_name_with_fallback(&e, "de_DE.UTF-8"), "org.example.Clock");
+        e.path = None;
+        assert_eq!(display_name_with_fallback(&e, "de_DE.UTF-8"), "");
+
+        e.name = "Clock".into();
+        assert_eq!(display_name_with_fallback(&e, "de_DE.UTF-8"), "Clock");
+
+        e.generic_name = Some(" ".into());
+        assert_eq!(display_name_with_fallback(&e, "de_DE.UTF-8"), "Clock");
+        e.generic_name = Some("World Clock".into());
+        assert_eq!(display_name_with_fallback(&e, "de_DE.UTF-8"), "World Clock");
+
+        e.localized_names.push(("de".into(), "Uhr".into()));
+        assert_eq!(display_name_with_fallback(&e, "de_DE.UTF-8"), "Uhr");
+        assert_eq!(display_name_with_fallback(&e, "fr_FR"), "World Clock");
+        assert_eq!(display_name_with_fallback(&e, "C"), "World Clock");
     }
 
     #[test]
-    fn filter_and_sort() {
-        let entries = vec![
-            entry("B", "/bin/true", true, StartupSource::UserAutostart),
-            entry("A", "/bin/false", false, StartupSource::SystemAutostart),
-            entry("C", "/bin/echo", true, StartupSource::UserAutostart),
+    fn localized_name_for_locale_prefers_the_most_specific_tag() {
+        let mut e = entry("Clock", "clock", true, StartupSource::UserAutostart);
+        e.localized_names = vec![
+            ("sr".into(), "Сат".into()),
+            ("sr@latin".into(), "Sat".into()),
+            ("pt".into(), "Relógio".into()),
+            ("pt_BR".into(), "Relógio BR".into()),
+            ("es".into(), "".into()),
         ];
-        let filter = FilterState { show_enabled: true, show_disabled: false, show_user: true, show_system: true };
-        let filtered = apply_filter(&entries, &filter);
-        assert_eq!(filtered.len(), 2);
-        let sorted = sort_indices(&entries, filtered, SortKey::NameAsc);
-        let names: Vec<_> = sorted.iter().map(|i| entries[*i].name.as_str()).collect();
-        assert_eq!(names, vec!["B", "C"]);
-        let sorted_status = sort_indices(&entries, vec![0,1,2], SortKey::StatusEnabledFirst);
-        assert_eq!(sorted_status[0], 0); // enabled first
+        assert_eq!(e.localized_name_for_locale("sr_RS@latin"), Some("Sat"));
+        assert_eq!(e.localized_name_for_locale("sr_RS.UTF-8"), Some("Сат"));
+        assert_eq!(e.localized_name_for_locale("pt_BR.UTF-8"), Some("Relógio BR"));
+        assert_eq!(e.localized_name_for_locale("pt_PT"), Some("Relógio"));
+        assert_eq!(e.localized_name_for_locale("es_ES"), None);
+        assert_eq!(e.localized_name_for_locale("C"), None);
     }
 
     #[test]
-    fn filter_combined_user_enabled() {
-        let entries = vec![
-            entry("UserEnabled", "/bin/true", true, StartupSource::UserAutostart),
-            entry("UserDisabled", "/bin/true", false, StartupSource::UserAutostart),
-            entry("SystemEnabled", "/bin/true", true, StartupSource::SystemAutostart),
-        ];
-        let filter = FilterState { show_enabled: true, show_disabled: false, show_user: true, show_system: false };
-        let filtered = apply_filter(&entries, &filter);
-        assert_eq!(filtered.len(), 1);
-        assert_eq!(entries[filtered[0]].name, "UserEnabled");
+    fn dockerfile_env_instruction_for_single_exports_only() {
+        let env = |command: &str, source: StartupSource| entry("VAR", command, true, source);
+        assert_eq!(
+            env("export EDITOR=vim", StartupSource::KdePlasmaEnv).to_dockerfile_env_instruction(),
+            Some("ENV EDITOR=vim".to_string())
+        );
+        assert_eq!(
+            env("export GREETING=\"hi $USER\"", StartupSource::EnvironmentD).to_dockerfile_env_instruction(),
+            Some("ENV GREETING=\"hi \\$USER\"".to_string())
+        );
+        assert_eq!(env("export EMPTY=", StartupSource::EnvironmentD).to_dockerfile_env_instruction(), Some("ENV EMPTY=\"\"".to_string()));
+        assert_eq!(env("export A=1\nexport B=2", StartupSource::EnvironmentD).to_dockerfile_env_instruction(), None);
+        assert_eq!(env("nm-applet", StartupSource::EnvironmentD).to_dockerfile_env_instruction(), None);
+        assert_eq!(env("export EDITOR=vim", StartupSource::UserAutostart).to_dockerfile_env_instruction(), None);
+        assert_eq!(env("export EDITOR=vim", StartupSource::EnvironmentD).to_dockerfile_entrypoint_instruction(), None);
     }
 
     #[test]
-    fn sort_localized_names_uses_base_name() {
-        let mut a = entry("Äpple", "/bin/true", true, StartupSource::UserAutostart);
-        a.localized_names.push(("de".into(), "Äpfel".into()));
-        let b = entry("Banana", "/bin/true", true, StartupSource::UserAutostart);
-        let indices = vec![0usize, 1usize];
-        let sorted = sort_indices(&vec![a, b], indices, SortKey::NameAsc);
-        // ASCII compare puts Banana before Äpple; ensure stable deterministic ordering
-        assert_eq!(sorted, vec![1, 0]);
+    fn dockerfile_entrypoint_instruction_uses_exec_form() {
+        let e = entry("Viewer", "viewer --title \"My \\\"Docs\\\"\" %U", true, StartupSource::UserAutostart);
+        assert_eq!(
+            e.to_dockerfile_entrypoint_instruction(),
+            Some(r#"ENTRYPOINT ["viewer", "--title", "My \"Docs\""]"#.to_string())
+        );
+        assert_eq!(ExportFormat::Dockerfile.render(&e), e.to_dockerfile_entrypoint_instruction());
+        assert_eq!(entry("Blank", "  ", true, StartupSource::UserAutostart).to_dockerfile_entrypoint_instruction(), None);
+        assert_eq!(entry("Codes", "%F", true, StartupSource::SystemAutostart).to_dockerfile_entrypoint_instruction(), None);
     }
 
     #[test]
-    fn parse_write_preserves_fields() {
-        let dir = tempdir().unwrap();
-        let path = dir.path().join("test.desktop");
-        let content = "\
-# Preamble comment
+    fn path_traversal_check_keeps_paths_inside_the_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("autostart");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("app.desktop"), "").unwrap();
+        fs::write(root.path().join("outside.desktop"), "").unwrap();
 
-[Desktop Entry]
-# entry comment
-Type=Application
-Name=Sample
-Name[de]=Beispiel
-Exec=/bin/true
-X-GNOME-Autostart-enabled=true
-Hidden=false
-X-Test=1
+        validate_path_traversal_safe_in(&dir, Path::new("app.desktop")).unwrap();
+        validate_path_traversal_safe_in(&dir, Path::new("new.desktop")).unwrap();
+        validate_path_traversal_safe_in(&dir, &dir.join("app.desktop")).unwrap();
 
-[Other]
-Foo=Bar
-";
-        std::fs::write(&path, content).unwrap();
-        let mut entry = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
-        assert_eq!(entry.name, "Sample");
-        assert_eq!(entry.localized_names.len(), 1);
-        assert_eq!(entry.extra.iter().find(|(k, _)| k == "X-Test").map(|(_, v)| v.as_str()), Some("1"));
-        // Modify and write back
-        entry.name = "Sample2".into();
-        entry.command = "/bin/echo hi".into();
-        write_desktop_entry(&entry, &path).unwrap();
-        let written = read_to_string(&path).unwrap();
-        assert!(written.contains("Name=Sample2"));
-        assert!(written.contains("Name[de]=Beispiel"));
-        assert!(written.contains("X-Test=1"));
-        assert!(written.contains("[Other]"));
-        assert!(written.contains("Foo=Bar"));
+        assert!(validate_path_traversal_safe_in(&dir, Path::new("../outside.desktop")).is_err());
+        assert!(validate_path_traversal_safe_in(&dir, Path::new("../../.bashrc")).is_err());
+        assert!(validate_path_traversal_safe_in(&dir, &dir.join("x/../../outside.desktop")).is_err());
+        assert!(validate_path_traversal_safe_in(&dir, Path::new("/etc/passwd")).is_err());
+        assert!(validate_path_traversal_safe_in(&dir, &root.path().join("outside.desktop")).is_err());
+        assert!(validate_path_traversal_safe_in(&dir, &dir).is_err());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.path().join("outside.desktop"), dir.join("link.desktop")).unwrap();
+            std::os::unix::fs::symlink(dir.join("app.desktop"), dir.join("inner.desktop")).unwrap();
+            std::os::unix::fs::symlink(root.path().join("missing"), dir.join("dangling.desktop")).unwrap();
+            assert!(validate_path_traversal_safe_in(&dir, Path::new("link.desktop")).is_err());
+            assert!(validate_path_traversal_safe_in(&dir, Path::new("dangling.desktop")).is_err());
+            validate_path_traversal_safe_in(&dir, Path::new("inner.desktop")).unwrap();
+        }
+
+        let json = r#"[{"name": "Evil", "command": "x", "env_vars": [], "enabled": true, "locked": false,
+            "source": "user", "path": "../../.bashrc", "extra": [], "localized_names": [],
+            "entry_comments": [], "preamble": [], "other_groups": []}]"#;
+        assert!(import_entries_from_json(json).is_err());
     }
 
     #[test]
-    fn parse_ignores_non_entry_groups_for_fields() {
+    fn json_backup_file_round_trips_and_plans_user_files() {
         let dir = tempdir().unwrap();
-        let path = dir.path().join("test.desktop");
-        let content = "\
-[NotDesktop]
-Name=ShouldNotUse
+        let mut full = entry("Syncthing", "syncthing serve", false, StartupSource::UserAutostart);
+        full.env_vars = vec![("STNORESTART".into(), "1".into())];
+        full.locked = true;
+        full.path = Some(user_autostart_dir().join("sync.desktop"));
+        full.icon = Some("syncthing".into());
+        full.set_delay_seconds(5);
+        full.extra = vec![("Categories".into(), "Network;".into())];
+        full.localized_names = vec![("de".into(), "Synchronisierung".into()), ("fr".into(), "Synchro".into())];
+        full.entry_comments = vec!["# tray".into()];
+        full.preamble = vec!["# backed up".into()];
+        full.other_groups = vec![vec!["[Desktop Action Stop]".into(), "Exec=syncthing cli stop".into()]];
+        let unsaved = entry("New Tool", "tool", true, StartupSource::UserAutostart);
+        let system = entry("Clock", "clock", true, StartupSource::SystemAutostart);
+        let backup = dir.path().join("backup.json");
+        export_entries_json(&[full.clone(), unsaved.clone(), system], &backup).unwrap();
+        assert!(fs::read_to_string(&backup).unwrap().contains("\"path\": \"sync.desktop\""));
 
-[Desktop Entry]
-Name=Good
-Exec=/bin/true
-X-GNOME-Autostart-enabled=true
-Hidden=false
-";
-        std::fs::write(&path, content).unwrap();
-        let entry = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
-        assert_eq!(entry.name, "Good");
-        assert_eq!(entry.command, "/bin/true");
+        let imported = import_entries_json(&backup).unwrap();
+        assert_eq!(imported.len(), 3);
+        let back = &imported[0];
+        assert_eq!((back.name.as_str(), back.command.as_str()), (full.name.as_str(), full.command.as_str()));
+        assert_eq!((back.enabled, back.locked, &back.source), (false, true, &StartupSource::UserAutostart));
+        assert_eq!(back.env_vars, full.env_vars);
+        assert_eq!(back.path, full.path);
+        assert_eq!((&back.icon, back.delay_seconds), (&full.icon, full.delay_seconds));
+        assert_eq!(back.extra, full.extra);
+        assert_eq!(back.localized_names, full.localized_names);
+        assert_eq!(back.entry_comments, full.entry_comments);
+        assert_eq!(back.preamble, full.preamble);
+        assert_eq!(back.other_groups, full.other_groups);
+
+        let target = dir.path().join("restore");
+        let plan = plan_json_import(imported, &target);
+        let paths: Vec<_> = plan.iter().map(|(_, path)| path.clone()).collect();
+        assert_eq!(paths, vec![target.join("sync.desktop"), target.join("new-tool.desktop")]);
+        // A system entry with the same content does not hide the user one, and copies go once.
+        let mut shadow = unsaved.clone();
+        shadow.source = StartupSource::SystemAutostart;
+        let plan = plan_json_import(vec![shadow, unsaved.clone(), unsaved.clone()], &target);
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].0.source, StartupSource::UserAutostart);
+        fs::write(&backup, "[{\"name\": \"A\"}]").unwrap();
+        assert!(import_entries_json(&backup).is_err());
     }
 
     #[test]
-    fn parse_preserves_duplicate_unknown_keys_order() {
+    fn exported_entries_match_the_schema_and_import_back() {
+        let mut e = entry("Clock", "clock --utc", false, StartupSource::SystemAutostart);
+        e.env_vars = vec![("TZ".into(), "UTC".into())];
+        e.path = Some(PathBuf::from("/etc/xdg/autostart/clock.desktop"));
+        e.comment = Some("Shows the time".into());
+        e.localized_names = vec![("de".into(), "Uhr".into())];
+        e.entry_comments = vec!["# keep".into()];
+        e.other_groups = vec![vec!["[Desktop Action Quit]".into(), "Exec=clock --quit".into()]];
+        let plain = entry("Plain", "plain", true, StartupSource::UserAutostart);
+        let json = export_entries_to_json(&[e.clone(), plain]);
+        validate_json_against_schema(&json, &generate_entry_json_schema()).unwrap();
+
+        let imported = import_entries_from_json(&json).unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].to_desktop_string(), e.to_desktop_string());
+        assert_eq!(imported[0].source, StartupSource::SystemAutostart);
+        assert_eq!(imported[0].path, e.path);
+        assert_eq!(imported[0].other_groups, e.other_groups);
+        assert_eq!(imported[1].path, None);
+        assert!(imported[1].enabled);
+        for source in StartupSource::ALL {
+            assert_eq!(serde_json::to_value(&source).unwrap(), Value::from(source_label(&source)));
+        }
+    }
+
+    #[test]
+    fn validate_json_against_schema_reports_where_it_fails() {
+        let schema = generate_entry_json_schema();
+        let valid: Value = serde_json::from_str(&export_entries_to_json(&[entry(
+            "A",
+            "a",
+            true,
+            StartupSource::UserAutostart,
+        )]))
+        .unwrap();
+        let broken = |change: &dyn Fn(&mut Value)| {
+            let mut value = valid.clone();
+            change(&mut value);
+            validate_json_against_schema(&value.to_string(), &schema).unwrap_err().to_string()
+        };
+        assert!(broken(&|v| v[0]["enabled"] = Value::from("yes")).contains("$[0].enabled: expected boolean, found string"));
+        assert!(broken(&|v| v[0]["source"] = Value::from("cron")).contains("$[0].source"));
+        assert!(broken(&|v| {
+            v[0].as_object_mut().unwrap().remove("command");
+        })
+        .contains("missing required member \"command\""));
+        assert!(broken(&|v| v[0]["color"] = Value::from("red")).contains("unexpected member \"color\""));
+        assert!(broken(&|v| v[0]["extra"] = serde_json::json!([{ "key": "Icon" }])).contains("$[0].extra[0]"));
+        assert!(broken(&|v| *v = serde_json::json!({})).contains("expected array, found object"));
+        assert!(validate_json_against_schema("[", &schema).is_err());
+        assert!(import_entries_from_json(r#"[{"name": "A"}]"#).is_err());
+        validate_json_against_schema("[]", &schema).unwrap();
+    }
+
+    #[test]
+    fn check_user_autostart_limits_warns_past_either_limit() {
         let dir = tempdir().unwrap();
-        let path = dir.path().join("test.desktop");
-        let content = "\
-[Desktop Entry]
-Name=Sample
-Exec=/bin/true
-X-GNOME-Autostart-enabled=true
-Hidden=false
-X-Test=1
-X-Test=2
-";
-        std::fs::write(&path, content).unwrap();
-        let entry = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
-        assert_eq!(entry.extra.iter().filter(|(k, _)| k == "X-Test").count(), 2);
-        // Writing back should keep last value, but preserve order of extras
-        write_desktop_entry(&entry, &path).unwrap();
-        let written = read_to_string(&path).unwrap();
-        assert!(written.contains("X-Test=1"));
-        assert!(written.contains("X-Test=2"));
+        for slug in ["a", "b", "c"] {
+            fs::write(
+                dir.path().join(format!("{slug}.desktop")),
+                format!("[Desktop Entry]\nType=Application\nName={slug}\nExec={slug}\n"),
+            )
+            .unwrap();
+        }
+        let total: u64 = ["a", "b", "c"]
+            .iter()
+            .map(|slug| fs::metadata(dir.path().join(format!("{slug}.desktop"))).unwrap().len())
+            .sum();
+        let mut entries = load_autostart_dir(dir.path(), StartupSource::UserAutostart).unwrap();
+        entries.push(entry("Clock", "clock", true, StartupSource::SystemAutostart));
+        let check = |size, count| check_user_autostart_limits(&entries, size, count);
+        assert_eq!(check(None, None), None);
+        assert_eq!(check(Some(total), Some(3)), None);
+        assert_eq!(check(None, Some(2)), Some("User autostart has 3 entries (limit 2)".to_string()));
+        assert_eq!(
+            check(Some(10), None),
+            Some(format!("User autostart takes {total} B (limit 10 B)"))
+        );
+        assert_eq!(
+            check(Some(10), Some(1)),
+            Some(format!("User autostart has 3 entries (limit 1) and takes {total} B (limit 10 B)"))
+        );
+        assert_eq!(check_user_autostart_limits(&[], Some(0), Some(0)), None);
     }
 
     #[test]
-    fn parse_preserves_entry_comments_and_preamble() {
+    fn xfce4_autostart_override_round_trips() {
         let dir = tempdir().unwrap();
-        let path = dir.path().join("test.desktop");
-        let content = "\
-# Preamble line 1
+        let e = entry("Notes", "notes --tray", true, StartupSource::SystemAutostart);
+        assert!(!e.xfce_override);
+        assert!(!e.to_desktop_string().contains("X-XFCE-Autostart-Override"));
+        let text = e.to_xfce4_autostart_entry();
+        assert_eq!(text.matches("X-XFCE-Autostart-Override=").count(), 1);
+        assert!(text.contains("X-XFCE-Autostart-Override=true\n"));
 
-[Desktop Entry]
-# comment inside
-Name=Foo
-Exec=/bin/true
-Hidden=false
-X-GNOME-Autostart-enabled=true
+        let path = dir.path().join("notes.desktop");
+        fs::write(&path, &text).unwrap();
+        let parsed = from_xfce4_autostart_entry(&path).unwrap();
+        assert_eq!(parsed.source, StartupSource::XfceAutostart);
+        assert!(parsed.xfce_override);
+        assert!(parsed.extra.iter().all(|(k, _)| k != XFCE_AUTOSTART_OVERRIDE_KEY));
+        assert_eq!(parsed.command, "notes --tray");
+        assert_eq!(parsed.to_xfce4_autostart_entry(), text);
+        assert_eq!(parsed.to_desktop_string(), text);
+
+        fs::write(&path, write_desktop_entry_to_string(&entry("Notes", "notes", true, StartupSource::UserAutostart))).unwrap();
+        assert!(!from_xfce4_autostart_entry(&path).unwrap().xfce_override);
+    }
+
+    #[test]
+    fn debug_dump_lists_state_without_commands() {
+        let mut user = entry("Notes", "notes --token=s3cret", true, StartupSource::UserAutostart);
+        user.path = Some(PathBuf::from("/home/u/.config/autostart/notes.desktop"));
+        let system = entry("Agent", "agent", false, StartupSource::SystemAutostart);
+        let entries = vec![user, system];
+        let warnings = vec![(
+            1,
+            ValidationResult {
+                warnings: vec![LintWarning::new(LintSeverity::Warning, "has no icon")],
+            },
+        )];
+        let history = vec!["Refreshed".to_string(), "Filter applied".to_string()];
+        let dump = DebugDump {
+            gtk_version: "4.14.2".into(),
+            os: "Test OS (linux x86_64)".into(),
+            entries: &entries,
+            filter: FilterState::default(),
+            sort: SortKey::DaemonsFirst,
+            pending_warnings: &warnings,
+            status_history: &history,
+        }
+        .render();
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines[0], "Universal Startup Manager debug dump");
+        assert_eq!(lines[1], format!("Version: {}", env!("CARGO_PKG_VERSION")));
+        assert_eq!(lines[2], "GTK: 4.14.2");
+        assert_eq!(lines[3], "OS: Test OS (linux x86_64)");
+        assert!(lines.contains(&"Sort: DaemonsFirst"));
+        assert!(lines.iter().any(|l| l.starts_with("Filter: FilterState {")));
+        assert!(lines.contains(&"Entries (2):"));
+        assert!(lines.contains(&"  [user] Notes | /home/u/.config/autostart/notes.desktop | enabled | command: 20 chars"));
+        assert!(lines.contains(&"  [system] Agent | (no file) | disabled | command: 5 chars"));
+        assert!(lines.contains(&"Pending warnings (1):"));
+        assert!(lines.contains(&"  Agent: [warning] has no icon"));
+        assert!(dump.ends_with("Recent status messages (2, oldest first):\n  Refreshed\n  Filter applied\n"));
+        assert!(!dump.contains("s3cret"));
+    }
+
+    #[test]
+    fn from_hyprland_config_exec_once_lines_skips_comments_and_other_directives() {
+        let config = "\
+# autostart
+monitor = ,preferred,auto,1
+exec-once = waybar
+exec-once=[workspace 2 silent] env GDK_BACKEND=wayland /usr/bin/firefox --new-window  # browser
+exec = pkill -USR1 waybar
+#exec-once = old-thing
+  exec-once = notify-send \"Score ##1\"
+bind = SUPER, Return, exec, kitty
+exec-once =
 ";
-        std::fs::write(&path, content).unwrap();
-        let entry = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
-        assert!(entry.preamble.iter().any(|l| l.contains("Preamble line 1")));
-        assert!(entry.entry_comments.iter().any(|l| l.contains("comment inside")));
-        write_desktop_entry(&entry, &path).unwrap();
-        let written = read_to_string(&path).unwrap();
-        assert!(written.contains("Preamble line 1"));
-        assert!(written.contains("comment inside"));
+        let entries = from_hyprland_config_exec_once_lines(config);
+        let summary: Vec<(&str, &str)> = entries.iter().map(|e| (e.name.as_str(), e.command.as_str())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("waybar", "waybar"),
+                ("firefox", "/usr/bin/firefox --new-window"),
+                ("notify-send", "notify-send \"Score #1\""),
+            ]
+        );
+        assert_eq!(entries[1].env_vars, vec![("GDK_BACKEND".to_string(), "wayland".to_string())]);
+        assert!(entries.iter().all(|e| e.source == StartupSource::Hyprland && e.enabled));
+        assert_eq!(entries[1].to_hyprland_exec_once(), "exec-once = env GDK_BACKEND=wayland /usr/bin/firefox --new-window");
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("hyprland.conf");
+        fs::write(&path, config).unwrap();
+        let loaded = load_hyprland_entries(&path).unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert!(loaded.iter().all(|e| e.path.as_deref() == Some(path.as_path())));
+        assert!(load_hyprland_entries(&dir.path().join("missing")).is_err());
     }
 
     #[test]
-    fn localized_name_roundtrip_edit() {
+    fn filter_state_is_default_only_when_nothing_is_hidden() {
+        assert!(FilterState::default().is_default());
+        let changes: [fn(&mut FilterState); 6] = [
+            |f| f.show_enabled = false,
+            |f| f.show_disabled = false,
+            |f| f.show_user = false,
+            |f| f.show_system = false,
+            |f| f.wine_only = true,
+            |f| f.daemons_only = true,
+        ];
+        for change in changes {
+            let mut filter = FilterState::default();
+            change(&mut filter);
+            assert!(!filter.is_default(), "{filter:?}");
+        }
+        let mut both = FilterState {
+            wine_only: true,
+            show_system: false,
+            ..FilterState::default()
+        };
+        assert!(!both.is_default());
+        both.wine_only = false;
+        both.show_system = true;
+        assert!(both.is_default());
+    }
+
+    #[test]
+    fn launchd_agent_dict_and_plist() {
+        let mut e = entry("Sync & Backup", "syncer --watch \"My Docs\" %U", false, StartupSource::UserAutostart);
+        e.env_vars = vec![("LANG".into(), "C".into())];
+        let log = launchd_log_path("sync-backup");
+        assert!(log.starts_with("/Users/") && log.ends_with("/Library/Logs/sync-backup.log"), "{log}");
+        let string = |s: &str| PlistValue::String(s.to_string());
+        assert_eq!(
+            e.to_launchd_agent_dict(),
+            PlistValue::Dict(vec![
+                ("Label".into(), string("local.sync-backup")),
+                (
+                    "ProgramArguments".into(),
+                    PlistValue::Array(vec![string("syncer"), string("--watch"), string("My Docs")])
+                ),
+                ("EnvironmentVariables".into(), PlistValue::Dict(vec![("LANG".into(), string("C"))])),
+                ("RunAtLoad".into(), PlistValue::Bool(true)),
+                ("Disabled".into(), PlistValue::Bool(true)),
+                ("KeepAlive".into(), PlistValue::Bool(false)),
+                ("StandardOutPath".into(), string(&log)),
+                ("StandardErrorPath".into(), string(&log)),
+            ])
+        );
+
+        let plist = entry("A&B", "ab", true, StartupSource::UserAutostart).to_launchd_agent_plist();
+        assert!(plist.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE plist "));
+        assert!(plist.contains(
+            "<plist version=\"1.0\">\n<dict>\n\t<key>Label</key>\n\t<string>local.ab</string>\n\
+             \t<key>ProgramArguments</key>\n\t<array>\n\t\t<string>ab</string>\n\t</array>\n\
+             \t<key>RunAtLoad</key>\n\t<true/>\n\t<key>KeepAlive</key>\n\t<false/>\n"
+        ));
+        assert!(!plist.contains("Disabled"));
+        assert!(plist.ends_with("</dict>\n</plist>\n"));
+        let escaped = entry("X", "echo \"a<b\"", true, StartupSource::UserAutostart).to_launchd_agent_plist();
+        assert!(escaped.contains("<string>a&lt;b</string>"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn running_processes_are_found_through_proc() {
+        let exe = fs::read_link("/proc/self/exe").unwrap();
+        let pid = is_binary_running(&exe).unwrap();
+        assert!(Path::new(&format!("/proc/{pid}")).exists());
+        assert_eq!(is_binary_running(Path::new("/nonexistent/binary")), None);
+
+        let entries = vec![
+            entry("Other", "/nonexistent/binary --flag", true, StartupSource::UserAutostart),
+            entry("Self", &format!("{} --flag", quote_exec_arg(&exe.to_string_lossy())), true, StartupSource::UserAutostart),
+            entry("Blank", "", true, StartupSource::UserAutostart),
+        ];
+        assert_eq!(find_entries_matching_running_processes(&entries), vec![(1, pid)]);
+        let cached = running_entries(&entries);
+        let none_running = HashMap::new();
+        assert_eq!(sort_indices_with_running(&entries, vec![0, 1, 2], SortKey::RunningFirst, &none_running), vec![2, 0, 1]);
+        assert_eq!(sort_indices_with_running(&entries, vec![0, 1, 2], SortKey::RunningFirst, &cached), vec![1, 2, 0]);
+        let running_only = FilterState { running_only: true, ..FilterState::default() };
+        assert_eq!(apply_filter(&entries, &running_only), vec![1]);
+        assert!(!running_only.is_default());
+        assert_eq!(sort_indices(&entries, vec![0, 1, 2], SortKey::RunningFirst), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn kde_plasma_autostart_entry_picks_env_script_or_desktop_file() {
+        let mut app = entry("Tray", "tray --hidden", true, StartupSource::UserAutostart);
+        app.env_vars = vec![("QT_SCALE_FACTOR".into(), "2".into())];
+        assert_eq!(app.to_kde_plasma_autostart_entry(), write_desktop_entry_to_string(&app));
+        let odd = entry("Odd", "export NOT=env", true, StartupSource::UserAutostart);
+        assert!(odd.to_kde_plasma_autostart_entry().starts_with("[Desktop Entry]\n"));
+
+        let editor = entry("EDITOR", "export EDITOR=vim", true, StartupSource::EnvironmentD);
+        assert_eq!(editor.to_kde_plasma_autostart_entry(), "#!/bin/sh\nexport EDITOR=vim\n");
+        let greeting = entry("GREETING", "export GREETING='hi $USER'", true, StartupSource::KdePlasmaEnv);
+        assert_eq!(greeting.to_kde_plasma_autostart_entry(), "#!/bin/sh\nexport GREETING=\"hi \\$USER\"\n");
+
         let dir = tempdir().unwrap();
-        let path = dir.path().join("test.desktop");
-        let content = "\
-[Desktop Entry]
-Name=Base
-Name[fr]=BaseFr
-Exec=/bin/true
-X-GNOME-Autostart-enabled=true
-Hidden=false
-";
-        std::fs::write(&path, content).unwrap();
-        let mut entry = parse_desktop_file(&path, StartupSource::UserAutostart).unwrap();
-        assert_eq!(entry.localized_names.len(), 1);
-        entry.name = "NewBase".into();
-        // Simulate editing localized name:
-        entry.localized_names.retain(|(loc, _)| loc != "fr");
-        entry.localized_names.push(("fr".into(), "Nouveau".into()));
-        write_desktop_entry(&entry, &path).unwrap();
-        let written = read_to_string(&path).unwrap();
-        assert!(written.contains("Name=NewBase"));
-        assert!(written.contains("Name[fr]=Nouveau"));
+        fs::write(dir.path().join("20-gtk.sh"), "#!/bin/sh\nexport GTK_USE_PORTAL=1\nif true; then :; fi\n").unwrap();
+        fs::write(dir.path().join("10-path.sh"), "export EDITOR=vim\n").unwrap();
+        fs::write(dir.path().join("notes.txt"), "export IGNORED=1\n").unwrap();
+        let entries = load_kde_plasma_env_entries(dir.path()).unwrap();
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["EDITOR", "GTK_USE_PORTAL"]);
+        assert!(entries.iter().all(|e| e.source == StartupSource::KdePlasmaEnv));
+        assert_eq!(entries[1].to_kde_plasma_autostart_entry(), "#!/bin/sh\nexport GTK_USE_PORTAL=1\n");
+        assert!(load_kde_plasma_env_entries(&dir.path().join("missing")).unwrap().is_empty());
     }
 }